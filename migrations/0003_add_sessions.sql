@@ -0,0 +1,11 @@
+CREATE TABLE sessions (
+    id BIGSERIAL UNIQUE NOT NULL,
+    token VARCHAR(64) PRIMARY KEY,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    user_agent TEXT
+);
+
+CREATE INDEX sessions_username ON sessions (username);
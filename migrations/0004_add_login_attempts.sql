@@ -0,0 +1,9 @@
+CREATE TABLE login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    username TEXT NOT NULL,
+    ip_address TEXT,
+    attempt_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX login_attempts_username ON login_attempts (username, attempt_date);
+CREATE INDEX login_attempts_ip_address ON login_attempts (ip_address, attempt_date);
@@ -0,0 +1,9 @@
+CREATE TYPE impersonation_action AS ENUM ('START', 'STOP');
+
+CREATE TABLE impersonation_log (
+    id BIGSERIAL PRIMARY KEY,
+    admin_username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    target_username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE,
+    action impersonation_action NOT NULL,
+    action_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
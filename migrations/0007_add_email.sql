@@ -0,0 +1,14 @@
+ALTER TABLE users
+    ADD COLUMN email VARCHAR(254),
+    ADD COLUMN email_verified_at TIMESTAMP WITH TIME ZONE;
+
+CREATE UNIQUE INDEX users_email ON users (LOWER(email));
+
+CREATE TABLE email_verification_tokens (
+    token VARCHAR(64) PRIMARY KEY,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    email VARCHAR(254) NOT NULL,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX email_verification_tokens_username ON email_verification_tokens (username);
@@ -0,0 +1,7 @@
+CREATE TABLE password_reset_tokens (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX password_reset_tokens_username ON password_reset_tokens (username);
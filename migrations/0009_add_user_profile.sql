@@ -0,0 +1,8 @@
+ALTER TABLE uploads
+    ALTER COLUMN post_id DROP NOT NULL,
+    ADD COLUMN avatar_username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE,
+    ADD CONSTRAINT uploads_post_or_avatar CHECK ((post_id IS NULL) <> (avatar_username IS NULL));
+
+ALTER TABLE users
+    ADD COLUMN bio TEXT NOT NULL DEFAULT '',
+    ADD COLUMN avatar_upload_id BIGINT REFERENCES uploads (id) ON DELETE SET NULL;
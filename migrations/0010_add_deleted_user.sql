@@ -0,0 +1,2 @@
+INSERT INTO users (username, password_hash, is_active, is_uploader, is_admin)
+VALUES ('deleted', '', FALSE, FALSE, FALSE);
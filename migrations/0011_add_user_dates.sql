@@ -0,0 +1,3 @@
+ALTER TABLE users
+    ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ADD COLUMN last_login_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN storage_quota_bytes BIGINT CHECK(storage_quota_bytes >= 0);
@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN birth_date_changed_at TIMESTAMP WITH TIME ZONE;
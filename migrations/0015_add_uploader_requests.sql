@@ -0,0 +1,13 @@
+CREATE TYPE uploader_request_status AS ENUM ('PENDING', 'APPROVED', 'REJECTED');
+
+CREATE TABLE uploader_requests (
+    id BIGSERIAL PRIMARY KEY,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    motivation TEXT NOT NULL,
+    status uploader_request_status NOT NULL DEFAULT 'PENDING',
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    decided_by VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL,
+    decided_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX uploader_requests_pending_username ON uploader_requests (username) WHERE status = 'PENDING';
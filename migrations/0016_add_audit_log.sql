@@ -0,0 +1,13 @@
+CREATE TYPE audit_action AS ENUM ('BULK_USER_EDIT');
+
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_username VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL,
+    action audit_action NOT NULL,
+    target_type VARCHAR(32) NOT NULL,
+    target_id TEXT,
+    details JSONB NOT NULL DEFAULT '{}',
+    action_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_log_action_date ON audit_log (action_date);
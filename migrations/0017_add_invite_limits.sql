@@ -0,0 +1,6 @@
+ALTER TABLE invite_codes
+    ADD COLUMN created_by VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL,
+    ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ADD COLUMN expires_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN max_uses INTEGER NOT NULL DEFAULT 1 CHECK (max_uses >= 1),
+    ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0 CHECK (use_count >= 0 AND use_count <= max_uses);
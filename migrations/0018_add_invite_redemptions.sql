@@ -0,0 +1,6 @@
+CREATE TABLE invite_redemptions (
+    invite_code VARCHAR(64) REFERENCES invite_codes (invite_code) ON DELETE CASCADE NOT NULL,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    redeemed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (invite_code, username)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE users
+    ADD COLUMN invite_allowance INTEGER NOT NULL DEFAULT 0 CHECK(invite_allowance >= 0);
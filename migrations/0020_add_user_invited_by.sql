@@ -0,0 +1,3 @@
+ALTER TABLE users
+    ADD COLUMN invited_via_code VARCHAR(64),
+    ADD COLUMN invited_by VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL;
@@ -0,0 +1,9 @@
+CREATE TABLE registration_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    invite_code TEXT,
+    ip_address TEXT,
+    attempt_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX registration_attempts_invite_code ON registration_attempts (invite_code, attempt_date);
+CREATE INDEX registration_attempts_ip_address ON registration_attempts (ip_address, attempt_date);
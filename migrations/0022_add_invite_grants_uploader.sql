@@ -0,0 +1,4 @@
+ALTER TABLE invite_codes
+    ADD COLUMN grants_uploader BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TYPE audit_action ADD VALUE 'INVITE_UPLOADER_GRANT';
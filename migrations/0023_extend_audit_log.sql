@@ -0,0 +1,8 @@
+ALTER TYPE audit_action ADD VALUE 'POST_BAN';
+ALTER TYPE audit_action ADD VALUE 'POST_UNBAN';
+ALTER TYPE audit_action ADD VALUE 'USER_EDIT';
+ALTER TYPE audit_action ADD VALUE 'INVITE_ADD';
+ALTER TYPE audit_action ADD VALUE 'INVITE_REMOVE';
+ALTER TYPE audit_action ADD VALUE 'UPLOAD_HIDE';
+
+CREATE INDEX audit_log_actor_username ON audit_log (actor_username);
@@ -0,0 +1,2 @@
+ALTER TABLE posts
+    ADD COLUMN ban_expires_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,2 @@
+ALTER TABLE ban_reasons
+    ADD COLUMN default_text TEXT;
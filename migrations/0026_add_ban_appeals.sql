@@ -0,0 +1,16 @@
+CREATE TYPE ban_appeal_status AS ENUM ('PENDING', 'APPROVED', 'REJECTED');
+
+CREATE TABLE ban_appeals (
+    id BIGSERIAL PRIMARY KEY,
+    post_id BIGINT REFERENCES posts (id) ON DELETE CASCADE NOT NULL,
+    author_username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    message TEXT NOT NULL,
+    status ban_appeal_status NOT NULL DEFAULT 'PENDING',
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    decided_by VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL,
+    decided_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX ban_appeals_pending_post_id ON ban_appeals (post_id) WHERE status = 'PENDING';
+
+CREATE INDEX ban_appeals_author_username ON ban_appeals (author_username);
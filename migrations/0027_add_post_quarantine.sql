@@ -0,0 +1,4 @@
+ALTER TABLE posts
+    ADD COLUMN is_quarantined BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TYPE audit_action ADD VALUE 'POST_QUARANTINE';
@@ -0,0 +1,4 @@
+ALTER TABLE uploads
+    ADD COLUMN sha256 VARCHAR(64);
+
+CREATE INDEX uploads_sha256 ON uploads (sha256);
@@ -0,0 +1,11 @@
+CREATE TABLE content_ratings (
+    id VARCHAR(64) PRIMARY KEY,
+    label TEXT NOT NULL,
+    min_age INTEGER NOT NULL,
+    description TEXT
+);
+
+ALTER TABLE posts
+    ADD COLUMN content_rating_id VARCHAR(64) REFERENCES content_ratings (id) ON DELETE SET NULL;
+
+ALTER TYPE audit_action ADD VALUE 'POST_CONTENT_RATING';
@@ -0,0 +1,6 @@
+CREATE TABLE cleanup_runs (
+    id BIGSERIAL PRIMARY KEY,
+    finished_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    hidden_upload_count BIGINT NOT NULL,
+    cleared_ban_count BIGINT NOT NULL
+);
@@ -0,0 +1,13 @@
+CREATE TYPE api_token_scope AS ENUM ('READ', 'UPLOAD');
+
+CREATE TABLE api_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    label VARCHAR(128) NOT NULL,
+    scope api_token_scope NOT NULL,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_date TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX api_tokens_username ON api_tokens (username);
@@ -0,0 +1,12 @@
+CREATE TABLE idempotency_keys (
+    key VARCHAR(255) NOT NULL,
+    username VARCHAR(64) REFERENCES users (username) ON DELETE CASCADE NOT NULL,
+    endpoint VARCHAR(128) NOT NULL,
+    request_hash VARCHAR(64) NOT NULL,
+    response_status SMALLINT,
+    response_body TEXT,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key, username, endpoint)
+);
+
+CREATE INDEX idempotency_keys_creation_date ON idempotency_keys (creation_date);
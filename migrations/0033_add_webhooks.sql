@@ -0,0 +1,29 @@
+CREATE TYPE webhook_event AS ENUM ('POST_CREATED', 'POST_PUBLISHED', 'POST_BANNED', 'POST_UNBANNED');
+
+CREATE TYPE webhook_delivery_status AS ENUM ('PENDING', 'SUCCEEDED', 'FAILED');
+
+CREATE TABLE webhooks (
+    id BIGSERIAL PRIMARY KEY,
+    url VARCHAR(2048) NOT NULL,
+    secret VARCHAR(256) NOT NULL,
+    event_mask INTEGER NOT NULL,
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id BIGINT REFERENCES webhooks (id) ON DELETE CASCADE NOT NULL,
+    event webhook_event NOT NULL,
+    payload TEXT NOT NULL,
+    status webhook_delivery_status NOT NULL DEFAULT 'PENDING',
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    last_response_status SMALLINT,
+    last_error TEXT,
+    next_attempt_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_attempt_date TIMESTAMP WITH TIME ZONE,
+    creation_date TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id);
+CREATE INDEX webhook_deliveries_pending ON webhook_deliveries (next_attempt_date) WHERE status = 'PENDING';
@@ -0,0 +1,4 @@
+ALTER TABLE posts
+    ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+UPDATE posts SET updated_at = creation_date;
@@ -0,0 +1,4 @@
+ALTER TABLE uploads
+    ADD COLUMN written_size BIGINT NOT NULL DEFAULT 0;
+
+UPDATE uploads SET written_size = size WHERE file_status IN ('PUBLISHING', 'PUBLISHED', 'HIDING', 'HIDDEN');
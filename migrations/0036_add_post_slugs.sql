@@ -0,0 +1,10 @@
+ALTER TABLE posts
+    ADD COLUMN slug VARCHAR(100) UNIQUE;
+
+-- All slugs ever assigned to posts, old ones are kept so that links to them are redirected.
+CREATE TABLE post_slugs (
+    slug VARCHAR(100) PRIMARY KEY,
+    post_id BIGINT REFERENCES posts (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX post_slugs_post_id ON post_slugs (post_id);
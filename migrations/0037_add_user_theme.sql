@@ -0,0 +1,5 @@
+CREATE TYPE user_theme AS ENUM ('LIGHT', 'DARK');
+
+-- NULL if user did not choose theme, then theme from cookie is kept.
+ALTER TABLE users
+    ADD COLUMN theme user_theme;
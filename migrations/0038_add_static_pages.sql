@@ -0,0 +1,7 @@
+CREATE TABLE static_pages (
+    slug VARCHAR(64) PRIMARY KEY,
+    title VARCHAR(128) NOT NULL,
+    body TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_by VARCHAR(64) REFERENCES users (username) ON DELETE SET NULL
+);
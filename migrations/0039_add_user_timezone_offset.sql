@@ -0,0 +1,3 @@
+-- Offset from UTC in minutes, NULL if user did not choose it, then offset from cookie is kept.
+ALTER TABLE users
+    ADD COLUMN timezone_offset INTEGER;
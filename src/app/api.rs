@@ -3,26 +3,56 @@ use std::{borrow::Cow, cmp::max, collections::HashMap};
 use lazy_static::lazy_static;
 use maplit::hashmap;
 use regex::Regex;
-use rocket::{data::ToByteUnit, post, put, serde::json::Json, Data, Either, State};
+use rocket::{
+    data::ToByteUnit, get, head, http::Status, options, patch, post, put, response::status::Custom,
+    serde::json::Json, uri, Data, Either, State,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Pool, Postgres};
-use validator::{Validate, ValidationError};
+use time::OffsetDateTime;
+use tokio::io::AsyncReadExt;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::{
     app::{
         db::{
-            add_post, add_upload, get_upload, try_edit_post_check_exists_and_permission,
-            try_set_upload_status, try_set_upload_status_check_exists, NewPost, NewUpload,
-            PostEdit, UploadStatus, User,
+            add_avatar_upload, add_post, add_post_with_connection, add_upload, check_database,
+            extend_upload_written_size, get_upload, get_upload_written_size,
+            list_posts_with_pagination, set_upload_sha256, set_user_avatar,
+            try_edit_post_check_exists_and_permission, try_get_ban_reason, try_get_post,
+            try_get_public_post, try_get_user_storage_usage, try_set_upload_status,
+            try_set_upload_status_check_exists, NewPost, NewUpload, Post, PostEdit, PostVisibility,
+            RatingIdSet, Upload, UploadFull, UploadMediaKind, UploadStatus, User, WebhookEvent,
+        },
+        events::{EventBus, PostEvent},
+        idempotency::{run_idempotent, IdempotencyKey, StoredResponse},
+        openapi::{build_openapi_document, list_api_operations},
+        rate_limit::RateLimited,
+        shutdown::UploadShutdownState,
+        storage::{
+            allocate_private_file, hash_private_file, publish_file, read_text_preview,
+            unpublish_file, write_private_file, StorageProbeCache, TextPreview,
         },
-        storage::{allocate_private_file, publish_file, unpublish_file, write_private_file},
     },
-    auth::Uploader,
-    utils::{content_range::ContentRange, csrf::HeaderCSRF},
-    UploadConfig,
+    auth::{Admin, Authentication, Uploader},
+    utils::{
+        content_range::ContentRange,
+        csrf::HeaderCSRF,
+        csrf_lib::CsrfToken,
+        file_server::{is_file_access_allowed, OriginalUri},
+        form_extra_validation::IdSet,
+        locale::Locale,
+        origin::AllowedOrigin,
+        pagination::{PageOverflowBehavior, PageParams},
+        signed_token::{SignedTokenKey, FILE_TOKEN_PARAMETER},
+        tus::{TusHeaders, TusResponse, TUS_EXTENSIONS, TUS_PATCH_CONTENT_TYPE, TUS_VERSION},
+    },
+    EmailConfig, PaginationConfig, UploadConfig, UploadStorage,
 };
 
+const AVATAR_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
 lazy_static! {
     static ref EXTENSION_REGEX: Regex = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
 }
@@ -32,12 +62,12 @@ pub struct PostAddRequest {
     #[validate(length(
         min = 1,
         code = "title_is_blank",
-        message = "название не должно быть пустым"
+        message = "validation.title_is_blank"
     ))]
     #[validate(length(
         max = 500,
         code = "title_too_long",
-        message = "название должно быть не длиннее 500 символов"
+        message = "validation.title_too_long"
     ))]
     title: String,
 
@@ -45,13 +75,11 @@ pub struct PostAddRequest {
 
     is_hidden: bool,
 
-    #[validate(range(
-        min = 0,
-        max = 21,
-        message = "минимальный возраст должен быть в диапазоне от 0 до 21 года включительно"
-    ))]
+    #[validate(range(min = 0, max = 21, message = "validation.min_age_out_of_range"))]
     min_age: Option<i32>,
 
+    content_rating_id: Option<String>,
+
     is_pinned: bool,
 }
 
@@ -61,46 +89,444 @@ pub struct PostAddResponseOk {
     url: String,
 }
 
+fn validate_post_add_request(
+    request: &PostAddRequest,
+    content_rating_id_set: &RatingIdSet,
+) -> ValidationErrors {
+    let mut validation_errors = request.validate().err().unwrap_or_default();
+
+    if request
+        .content_rating_id
+        .as_deref()
+        .is_some_and(|content_rating_id| !content_rating_id_set.is_valid_id(content_rating_id))
+    {
+        validation_errors.add(
+            "content_rating_id",
+            ValidationError {
+                code: Cow::from("content_rating_does_not_exist"),
+                message: Some(Cow::from("validation.content_rating_does_not_exist")),
+                params: HashMap::new(),
+            },
+        );
+    }
+
+    validation_errors
+}
+
 #[post("/api/posts/add", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn post_add_post<'b>(
     request: Json<PostAddRequest>,
     pool: &State<Pool<Postgres>>,
     user: User,
+    content_rating_id_set: RatingIdSet,
     _uploader: Uploader,
     _header_csrf: HeaderCSRF,
-) -> Result<Json<PostAddResponseOk>, crate::error::Error> {
-    request.validate()?;
+    idempotency_key: IdempotencyKey,
+    event_bus: &State<EventBus>,
+) -> Result<Either<Json<PostAddResponseOk>, StoredResponse>, crate::error::Error> {
+    let username = user.username.clone();
+    run_idempotent(&idempotency_key, &username, &*request, pool, async {
+        let validation_errors = validate_post_add_request(&request, &content_rating_id_set);
+        if !validation_errors.is_empty() {
+            return Err(crate::error::Error::from(validation_errors));
+        }
 
-    let post = add_post(
-        NewPost {
-            title: &request.title,
-            description: &request.description,
-            is_hidden: request.is_hidden,
-            min_age: request.min_age,
-            is_pinned: request.is_pinned,
-        },
-        user,
-        pool,
-    )
-    .await?;
+        let post = add_post(
+            NewPost {
+                title: &request.title,
+                description: &request.description,
+                is_hidden: request.is_hidden,
+                min_age: request.min_age,
+                content_rating_id: request.content_rating_id.as_deref(),
+                is_pinned: request.is_pinned,
+            },
+            user,
+            pool,
+        )
+        .await?;
+        event_bus.emit(PostEvent::new(
+            WebhookEvent::PostCreated,
+            post.id,
+            (!post.is_hidden).then(|| post.title.clone()),
+        ));
+
+        Ok::<_, crate::error::Error>(Json(PostAddResponseOk {
+            id: post.id,
+            url: post.detail_url().to_string(),
+        }))
+    })
+    .await
+}
+
+/// Result for one post of batch: created post, post which is valid but was not created because
+/// other posts are invalid, or validation errors.
+#[derive(Clone, Debug, Serialize)]
+pub enum PostAddBatchItemResult {
+    Ok(PostAddResponseOk),
+    Valid,
+    ValidationErrors(ValidationErrors),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PostAddBatchResponse {
+    items: Vec<PostAddBatchItemResult>,
+}
+
+/// Create several posts at once. Posts are created only if all of them are valid, otherwise
+/// validation errors are returned for each post.
+#[post("/api/posts/add-batch", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn post_add_batch_post(
+    request: Json<Vec<PostAddRequest>>,
+    pool: &State<Pool<Postgres>>,
+    user: User,
+    content_rating_id_set: RatingIdSet,
+    upload_config: &State<UploadConfig>,
+    _uploader: Uploader,
+    _header_csrf: HeaderCSRF,
+    event_bus: &State<EventBus>,
+    locale: Locale,
+) -> Result<
+    Either<Json<PostAddBatchResponse>, Custom<Json<PostAddBatchResponse>>>,
+    crate::error::Error,
+> {
+    if request.is_empty() || request.len() as u64 > upload_config.max_post_batch_size {
+        let mut validation_errors = ValidationErrors::new();
+        validation_errors.add(
+            "posts",
+            ValidationError {
+                code: Cow::from("batch_size_invalid"),
+                message: None,
+                params: hashmap! {Cow::from("max_size") => json!(upload_config.max_post_batch_size)},
+            },
+        );
+        return Err(validation_errors.into());
+    }
+
+    let item_validation_errors: Vec<ValidationErrors> = request
+        .iter()
+        .map(|post| validate_post_add_request(post, &content_rating_id_set))
+        .collect();
+    if item_validation_errors
+        .iter()
+        .any(|validation_errors| !validation_errors.is_empty())
+    {
+        return Ok(Either::Right(Custom(
+            Status::UnprocessableEntity,
+            Json(PostAddBatchResponse {
+                items: item_validation_errors
+                    .into_iter()
+                    .map(|mut validation_errors| {
+                        if validation_errors.is_empty() {
+                            PostAddBatchItemResult::Valid
+                        } else {
+                            locale.translate_validation_errors(&mut validation_errors);
+                            PostAddBatchItemResult::ValidationErrors(validation_errors)
+                        }
+                    })
+                    .collect(),
+            }),
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    let mut items = Vec::with_capacity(request.len());
+    let mut events = Vec::with_capacity(request.len());
+    for post in request.iter() {
+        let post = add_post_with_connection(
+            NewPost {
+                title: &post.title,
+                description: &post.description,
+                is_hidden: post.is_hidden,
+                min_age: post.min_age,
+                content_rating_id: post.content_rating_id.as_deref(),
+                is_pinned: post.is_pinned,
+            },
+            user.clone(),
+            &mut transaction,
+        )
+        .await?;
+        events.push(PostEvent::new(
+            WebhookEvent::PostCreated,
+            post.id,
+            (!post.is_hidden).then(|| post.title.clone()),
+        ));
+        items.push(PostAddBatchItemResult::Ok(PostAddResponseOk {
+            id: post.id,
+            url: post.detail_url().to_string(),
+        }));
+    }
+    transaction.commit().await?;
+    for event in events {
+        event_bus.emit(event);
+    }
+
+    Ok(Either::Left(Json(PostAddBatchResponse { items })))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UploadResponse {
+    id: i64,
+    extension: Option<String>,
+    size: i64,
+    url: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PostResponse {
+    #[serde(with = "time::serde::rfc3339")]
+    creation_date: OffsetDateTime,
+    title: String,
+    description: String,
+    author_username: String,
+    author_display_name: Option<String>,
+    is_hidden: bool,
+    is_pinned: bool,
+    min_age: Option<i32>,
+    content_rating_id: Option<String>,
+    uploads: Vec<UploadResponse>,
+}
+
+impl PostResponse {
+    fn new(post: Post, storage: &UploadStorage) -> Self {
+        Self {
+            uploads: post
+                .uploads
+                .iter()
+                .map(|upload| UploadResponse {
+                    id: upload.id,
+                    extension: upload.extension.clone(),
+                    size: upload.size,
+                    url: upload.file_url(storage),
+                })
+                .collect(),
+            creation_date: post.creation_date,
+            title: post.title,
+            description: post.description,
+            author_username: post.author_username,
+            author_display_name: post.author_display_name,
+            is_hidden: post.is_hidden,
+            is_pinned: post.is_pinned,
+            min_age: post.min_age,
+            content_rating_id: post.content_rating_id,
+        }
+    }
+}
+
+/// Post as it is visible to user: content is only included for visible posts.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "visibility", rename_all = "snake_case")]
+pub enum PostVisibilityResponse {
+    Visible(PostResponse),
+    Hidden,
+    Banned {
+        reason: Option<String>,
+        reason_text: Option<String>,
+        #[serde(with = "time::serde::rfc3339::option")]
+        expires_at: Option<OffsetDateTime>,
+    },
+    AgeRestricted {
+        min_age: i32,
+    },
+    Quarantined,
+}
+
+impl PostVisibilityResponse {
+    fn new(post_visibility: PostVisibility, storage: &UploadStorage) -> Self {
+        match post_visibility {
+            PostVisibility::Visible(post) => Self::Visible(PostResponse::new(post, storage)),
+            PostVisibility::Hidden => Self::Hidden,
+            PostVisibility::Banned(ban_reason, ban_reason_text, ban_expires_at) => Self::Banned {
+                reason: ban_reason.map(|ban_reason| ban_reason.id),
+                reason_text: ban_reason_text,
+                expires_at: ban_expires_at,
+            },
+            PostVisibility::AgeRestricted(min_age) => Self::AgeRestricted { min_age },
+            PostVisibility::Quarantined => Self::Quarantined,
+        }
+    }
+
+    /// Status of response for post detail, same as for HTML page.
+    fn status(&self) -> Status {
+        match self {
+            Self::Visible(_) => Status::Ok,
+            Self::Hidden | Self::AgeRestricted { .. } | Self::Quarantined => Status::Forbidden,
+            Self::Banned { .. } => Status::UnavailableForLegalReasons,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PostItemResponse {
+    id: i64,
+    #[serde(flatten)]
+    visibility: PostVisibilityResponse,
+}
 
-    Ok(Json(PostAddResponseOk {
-        id: post.id,
-        url: post.detail_url().to_string(),
+#[derive(Clone, Debug, Serialize)]
+pub struct PostsListResponse {
+    items: Vec<PostItemResponse>,
+    page_id: u64,
+    page_size: u64,
+    page_count: u64,
+    total_item_count: u64,
+}
+
+#[get("/api/posts?<page_id>&<page_size>")]
+pub async fn posts_list_api_get(
+    user: Authentication,
+    pool: &State<Pool<Postgres>>,
+    pagination_config: &State<PaginationConfig>,
+    upload_config: &State<UploadConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
+) -> Result<Json<PostsListResponse>, crate::error::Error> {
+    let page_params = PageParams {
+        page_id,
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Error,
+    };
+    page_params.check(pagination_config)?;
+
+    let page = list_posts_with_pagination(pool, page_params, &user).await?;
+
+    Ok(Json(PostsListResponse {
+        items: page
+            .items
+            .into_iter()
+            .map(|post| PostItemResponse {
+                id: post.id,
+                visibility: PostVisibilityResponse::new(
+                    post.check_visible(&user),
+                    &upload_config.storage,
+                ),
+            })
+            .collect(),
+        page_id: page.page_id,
+        page_size: page.page_size,
+        page_count: page.page_count,
+        total_item_count: page.total_item_count,
     }))
 }
 
+#[get("/api/posts/by-id/<id>")]
+pub async fn post_detail_api_get(
+    user: Authentication,
+    pool: &State<Pool<Postgres>>,
+    upload_config: &State<UploadConfig>,
+    id: i64,
+) -> Result<Custom<Json<PostItemResponse>>, crate::error::Error> {
+    let post = try_get_post(id, pool, &user)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let visibility = PostVisibilityResponse::new(post.check_visible(&user), &upload_config.storage);
+    Ok(Custom(
+        visibility.status(),
+        Json(PostItemResponse { id, visibility }),
+    ))
+}
+
+/// Size of embedded post, it is reduced to `maxwidth` and `maxheight` if consumer requests it.
+const OEMBED_DEFAULT_WIDTH: u32 = 640;
+const OEMBED_DEFAULT_HEIGHT: u32 = 360;
+
+const OEMBED_PROVIDER_NAME: &str = "archivanima";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OEmbedResponse {
+    #[serde(rename = "type")]
+    embed_type: String,
+    version: String,
+    title: String,
+    author_name: String,
+    author_url: String,
+    provider_name: String,
+    provider_url: String,
+    html: String,
+    width: u32,
+    height: u32,
+}
+
+/// Get post ID from absolute URL of post detail page on this site.
+pub fn parse_post_url(url: &str, site_url: &str) -> Option<i64> {
+    let path = url.strip_prefix(site_url.trim_end_matches('/'))?;
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let id = path
+        .strip_prefix("/posts/by-id/")?
+        .trim_end_matches('/')
+        .parse()
+        .ok()?;
+    path.trim_end_matches('/')
+        .eq(&uri!(crate::app::views::post_detail_get(id)).to_string())
+        .then_some(id)
+}
+
+/// oEmbed provider endpoint, only posts visible to anonymous users can be embedded.
+#[get("/api/oembed?<url>&<format>&<maxwidth>&<maxheight>")]
+pub async fn oembed_get(
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+    url: &str,
+    format: Option<&str>,
+    maxwidth: Option<u32>,
+    maxheight: Option<u32>,
+) -> Result<Either<Json<OEmbedResponse>, Status>, crate::error::Error> {
+    if format.is_some_and(|format| format != "json") {
+        return Ok(Either::Right(Status::NotImplemented));
+    }
+
+    let site_url = email_config.site_url.trim_end_matches('/');
+    let id = parse_post_url(url, site_url).ok_or(crate::error::Error::DoesNotExist)?;
+    let post = try_get_public_post(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let width = maxwidth.map_or(OEMBED_DEFAULT_WIDTH, |maxwidth| {
+        maxwidth.min(OEMBED_DEFAULT_WIDTH)
+    });
+    let height = maxheight.map_or(OEMBED_DEFAULT_HEIGHT, |maxheight| {
+        maxheight.min(OEMBED_DEFAULT_HEIGHT)
+    });
+    let embed_type = match post.uploads.first().map(Upload::media_kind) {
+        Some(UploadMediaKind::Video) => "video",
+        _ => "rich",
+    };
+    let html = format!(
+        r#"<iframe src="{}{}" width="{}" height="{}" title="{}" frameborder="0" allowfullscreen></iframe>"#,
+        site_url,
+        uri!(crate::app::views::post_embed_get(id)),
+        width,
+        height,
+        html_escape::encode_double_quoted_attribute(&post.title)
+    );
+
+    Ok(Either::Left(Json(OEmbedResponse {
+        embed_type: embed_type.to_string(),
+        version: "1.0".to_string(),
+        author_name: post.author_name().to_string(),
+        author_url: format!("{}{}", site_url, post.author_detail_url()),
+        provider_name: OEMBED_PROVIDER_NAME.to_string(),
+        provider_url: format!("{}/", site_url),
+        title: post.title,
+        html,
+        width,
+        height,
+    })))
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Validate)]
 pub struct PostEditRequest {
     #[validate(length(
         min = 1,
         code = "title_is_blank",
-        message = "название не должно быть пустым"
+        message = "validation.title_is_blank"
     ))]
     #[validate(length(
         max = 500,
         code = "title_too_long",
-        message = "название должно быть не длиннее 500 символов"
+        message = "validation.title_too_long"
     ))]
     title: Option<String>,
 
@@ -108,13 +534,11 @@ pub struct PostEditRequest {
 
     is_hidden: Option<bool>,
 
-    #[validate(range(
-        min = 0,
-        max = 21,
-        message = "минимальный возраст должен быть в диапазоне от 0 до 21 года включительно"
-    ))]
+    #[validate(range(min = 0, max = 21, message = "validation.min_age_out_of_range"))]
     min_age: Option<i32>,
 
+    content_rating_id: Option<String>,
+
     is_pinned: Option<bool>,
 }
 
@@ -122,29 +546,64 @@ pub struct PostEditRequest {
 pub struct PostEditResponseOk {}
 
 #[post("/api/posts/by-id/<id>/edit", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn post_edit_post<'b>(
     id: i64,
     request: Json<PostEditRequest>,
     pool: &State<Pool<Postgres>>,
     user: User,
+    content_rating_id_set: RatingIdSet,
     _uploader: Uploader,
     _header_csrf: HeaderCSRF,
+    event_bus: &State<EventBus>,
 ) -> Result<Json<PostEditResponseOk>, crate::error::Error> {
-    request.validate()?;
+    let mut validation_errors = request.validate().err().unwrap_or_default();
+
+    if request
+        .content_rating_id
+        .as_deref()
+        .is_some_and(|content_rating_id| !content_rating_id_set.is_valid_id(content_rating_id))
+    {
+        validation_errors.add(
+            "content_rating_id",
+            ValidationError {
+                code: Cow::from("content_rating_does_not_exist"),
+                message: Some(Cow::from("validation.content_rating_does_not_exist")),
+                params: HashMap::new(),
+            },
+        );
+    }
 
-    try_edit_post_check_exists_and_permission(
+    if !validation_errors.is_empty() {
+        return Err(validation_errors.into());
+    }
+
+    let is_published = try_edit_post_check_exists_and_permission(
         PostEdit {
             id,
             title: request.title.as_deref(),
             description: request.description.as_deref(),
             is_hidden: request.is_hidden,
             min_age: request.min_age,
+            content_rating_id: request.content_rating_id.as_deref(),
             is_pinned: request.is_pinned,
         },
         &user,
         pool,
     )
     .await?;
+    if is_published {
+        let title = match &request.title {
+            Some(title) => title.clone(),
+            None => {
+                try_get_post(id, pool, &Authentication::Authenticated(user.clone()))
+                    .await?
+                    .ok_or(crate::error::Error::DoesNotExist)?
+                    .title
+            }
+        };
+        event_bus.emit(PostEvent::new(WebhookEvent::PostPublished, id, Some(title)));
+    }
 
     Ok(Json(PostEditResponseOk {}))
 }
@@ -165,10 +624,16 @@ pub struct UploadAddResponseOk {
     id: i64,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UploadAddResponseQuotaExceeded {
+    remaining_bytes: i64,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct UploadByChunkResponseOk {}
 
 #[post("/api/uploads/add", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_add_post<'r, 'a, 'b>(
     request: Json<UploadAddRequest<'r>>,
     pool: &'a State<Pool<Postgres>>,
@@ -176,9 +641,122 @@ pub async fn upload_add_post<'r, 'a, 'b>(
     upload_config: &'b State<UploadConfig>,
     _uploader: Uploader,
     _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
+    idempotency_key: IdempotencyKey,
+) -> Result<
+    Either<
+        Either<Json<UploadAddResponseOk>, Custom<Json<UploadAddResponseQuotaExceeded>>>,
+        StoredResponse,
+    >,
+    crate::error::Error,
+> {
+    let username = user.username.clone();
+    run_idempotent(&idempotency_key, &username, &*request, pool, async {
+        let mut validation_errors = request.validate().err().unwrap_or_default();
+
+        if request.size == 0 {
+            validation_errors.add(
+                "size",
+                ValidationError {
+                    code: Cow::from("size_is_zero"),
+                    message: None,
+                    params: HashMap::new(),
+                },
+            );
+        } else if (request.size > upload_config.max_file_size) || (request.size > i64::MAX as u64) {
+            validation_errors.add(
+                "size",
+                ValidationError {
+                    code: Cow::from("size_too_large"),
+                    message: None,
+                    params: hashmap! {Cow::from("max_size") => json!(max(request.size, i64::MAX as u64))},
+                },
+            );
+        }
+        let size = request.size as i64;
+
+        let remaining_bytes = try_get_user_storage_usage(&user.username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?
+            .remaining_bytes(upload_config.default_storage_quota_bytes);
+        if size > remaining_bytes {
+            return Ok(Either::Right(Custom(
+                Status::UnprocessableEntity,
+                Json(UploadAddResponseQuotaExceeded { remaining_bytes }),
+            )));
+        }
+
+        let upload = allocate_upload(
+            NewUpload {
+                extension: request.extension,
+                size,
+                post_id: request.post_id,
+            },
+            user,
+            pool,
+            &upload_config.storage,
+        )
+        .await?;
+
+        Ok::<_, crate::error::Error>(Either::Left(Json(UploadAddResponseOk { id: upload.id })))
+    })
+    .await
+}
+
+/// Add upload to post, allocate its file and mark it as ready for writing.
+async fn allocate_upload(
+    new_upload: NewUpload<'_>,
+    user: User,
+    pool: &Pool<Postgres>,
+    storage: &UploadStorage,
+) -> Result<Upload, crate::error::Error> {
+    let (extension, size) = (new_upload.extension, new_upload.size);
+    let upload = add_upload(new_upload, user, pool).await?;
+
+    allocate_private_file(upload.id, extension, size as u64, storage).await?;
+
+    try_set_upload_status(upload.id, UploadStatus::Allocated, pool)
+        .await?
+        .unwrap();
+
+    Ok(upload)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+pub struct AvatarUploadAddRequest<'r> {
+    size: u64,
+
+    #[validate(length(max = 32, code = "extension_too_long"))]
+    #[validate(regex(path = "EXTENSION_REGEX", code = "extension_invalid_chars"))]
+    extension: Option<&'r str>,
+}
+
+#[post("/api/users/me/avatar/add", data = "<request>")]
+pub async fn avatar_upload_add_post<'r, 'a, 'b>(
+    request: Json<AvatarUploadAddRequest<'r>>,
+    pool: &'a State<Pool<Postgres>>,
+    user: User,
+    upload_config: &'b State<UploadConfig>,
+    _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
 ) -> Result<Json<UploadAddResponseOk>, crate::error::Error> {
     let mut validation_errors = request.validate().err().unwrap_or_default();
 
+    if !request.extension.is_some_and(|extension| {
+        AVATAR_EXTENSIONS
+            .iter()
+            .any(|allowed_extension| allowed_extension.eq_ignore_ascii_case(extension))
+    }) {
+        validation_errors.add(
+            "extension",
+            ValidationError {
+                code: Cow::from("extension_not_image"),
+                message: None,
+                params: HashMap::new(),
+            },
+        );
+    }
+
     if request.size == 0 {
         validation_errors.add(
             "size",
@@ -188,28 +766,22 @@ pub async fn upload_add_post<'r, 'a, 'b>(
                 params: HashMap::new(),
             },
         );
-    } else if (request.size > upload_config.max_file_size) || (request.size > i64::MAX as u64) {
+    } else if request.size > upload_config.max_avatar_size {
         validation_errors.add(
             "size",
             ValidationError {
                 code: Cow::from("size_too_large"),
                 message: None,
-                params: hashmap! {Cow::from("max_size") => json!(max(request.size, i64::MAX as u64))},
+                params: hashmap! {Cow::from("max_size") => json!(upload_config.max_avatar_size)},
             },
         );
     }
-    let size = request.size as i64;
 
-    let upload = add_upload(
-        NewUpload {
-            extension: request.extension,
-            size,
-            post_id: request.post_id,
-        },
-        user,
-        pool,
-    )
-    .await?;
+    if !validation_errors.is_empty() {
+        return Err(validation_errors.into());
+    }
+
+    let upload = add_avatar_upload(request.extension, request.size as i64, &user, pool).await?;
 
     allocate_private_file(
         upload.id,
@@ -234,40 +806,68 @@ pub async fn upload_upload_by_chunk_put<'r, 'a, 'b>(
     pool: &'a State<Pool<Postgres>>,
     user: User,
     upload_config: &'b State<UploadConfig>,
-    _uploader: Uploader,
     content_range: ContentRange,
+    upload_shutdown_state: &'b State<UploadShutdownState>,
     _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
 ) -> Result<Json<UploadByChunkResponseOk>, crate::error::Error> {
-    let upload = get_upload(id, pool).await?;
+    let _write_guard = upload_shutdown_state
+        .begin_write(id)
+        .ok_or(crate::error::Error::ShuttingDown)?;
 
-    if upload.post_author_username != user.username {
-        return Err(crate::error::Error::AccessDenied);
-    }
+    let upload = get_upload(id, pool).await?;
 
-    try_set_upload_status_check_exists(id, UploadStatus::Writing, pool).await?;
+    check_upload_owner(&upload, &user)?;
 
-    let (start_pos, end_post) = match content_range {
+    let (start_pos, end_pos) = match content_range {
         ContentRange(Either::Left(bytes)) => {
             if bytes.complete_length != (upload.size as u64) {
-                try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
                 return Err(crate::error::Error::InvalidContentRange);
             }
             (bytes.first_byte, bytes.last_byte)
         }
         ContentRange(Either::Right(unbound)) => (unbound.first_byte, unbound.last_byte),
     };
-    let length = end_post + 1 - start_pos;
-    {
-        let mut data_stream = data.open(length.bytes());
-        write_private_file(
-            id,
-            upload.extension.as_deref(),
-            &mut data_stream,
-            start_pos,
-            &upload_config.storage,
-        )
-        .await?;
+    if (end_pos < start_pos) || (end_pos >= upload.size as u64) {
+        return Err(crate::error::Error::InvalidContentRange);
     }
+    let length = end_pos + 1 - start_pos;
+    if length > upload_config.max_file_size {
+        return Err(crate::error::Error::UploadChunkTooLarge);
+    }
+
+    try_set_upload_status_check_exists(id, UploadStatus::Writing, None, pool).await?;
+
+    // Stream is limited to one byte more than declared length, so that longer body is detected
+    // without writing data out of range.
+    let mut data_stream = data.open((length + 1).bytes());
+    let write_result = write_private_file(
+        id,
+        upload.extension.as_deref(),
+        &mut (&mut data_stream).take(length),
+        start_pos,
+        &upload_config.storage,
+    )
+    .await;
+    let written_size = match write_result {
+        Ok(written_size) => written_size,
+        Err(err) => {
+            try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
+            return Err(err.into());
+        }
+    };
+    let has_extra_data = !matches!(data_stream.read(&mut [0u8; 1]).await, Ok(0));
+    if (written_size != length) || has_extra_data {
+        try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
+        return Err(crate::error::Error::UploadChunkSizeMismatch);
+    }
+    extend_upload_written_size(
+        id,
+        start_pos as i64,
+        (start_pos + written_size) as i64,
+        pool,
+    )
+    .await?;
 
     try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
 
@@ -281,22 +881,51 @@ pub async fn upload_finalize_post<'r, 'a, 'b>(
     pool: &'a State<Pool<Postgres>>,
     user: User,
     upload_config: &'b State<UploadConfig>,
-    _uploader: Uploader,
     _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
 ) -> Result<Json<UploadByChunkResponseOk>, crate::error::Error> {
     let upload = get_upload(id, pool).await?;
 
-    if upload.post_author_username != user.username {
-        return Err(crate::error::Error::AccessDenied);
-    }
+    check_upload_owner(&upload, &user)?;
+
+    finalize_upload(&upload, &user, pool, &upload_config.storage).await?;
+
+    Ok(Json(UploadByChunkResponseOk {}))
+}
+
+/// Hash and publish upload file after all data is written. Finalized avatar upload replaces
+/// previous avatar of user.
+async fn finalize_upload(
+    upload: &UploadFull,
+    user: &User,
+    pool: &Pool<Postgres>,
+    storage: &UploadStorage,
+) -> Result<(), crate::error::Error> {
+    let id = upload.id;
 
-    try_set_upload_status_check_exists(id, UploadStatus::Publishing, pool).await?;
+    try_set_upload_status_check_exists(id, UploadStatus::Publishing, None, pool).await?;
 
-    publish_file(id, upload.extension.as_deref(), &upload_config.storage).await?;
+    let sha256 = hash_private_file(id, upload.extension.as_deref(), storage).await?;
+    set_upload_sha256(id, &sha256, pool).await?;
+
+    publish_file(id, upload.extension.as_deref(), storage).await?;
 
     try_set_upload_status(id, UploadStatus::Published, pool).await?;
 
-    Ok(Json(UploadByChunkResponseOk {}))
+    if upload.post_id.is_none() {
+        if let Some(old_avatar_id) = set_user_avatar(&user.username, id, pool).await? {
+            let old_avatar = get_upload(old_avatar_id, pool).await?;
+            if try_set_upload_status(old_avatar_id, UploadStatus::Hiding, pool)
+                .await?
+                .is_some()
+            {
+                unpublish_file(old_avatar_id, old_avatar.extension.as_deref(), storage).await?;
+                try_set_upload_status(old_avatar_id, UploadStatus::Hidden, pool).await?;
+            }
+        }
+    }
+
+    Ok(())
 }
 
 #[post("/api/uploads/by-id/<id>/remove")]
@@ -308,14 +937,22 @@ pub async fn upload_hide_post<'r, 'a, 'b>(
     upload_config: &'b State<UploadConfig>,
     _uploader: Uploader,
     _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
 ) -> Result<Json<UploadByChunkResponseOk>, crate::error::Error> {
     let upload = get_upload(id, pool).await?;
 
-    if upload.post_author_username != user.username {
+    let is_moderation = upload.owner_username != user.username;
+    if upload.post_id.is_none() || (is_moderation && !user.is_admin) {
         return Err(crate::error::Error::AccessDenied);
     }
 
-    try_set_upload_status_check_exists(id, UploadStatus::Hiding, pool).await?;
+    try_set_upload_status_check_exists(
+        id,
+        UploadStatus::Hiding,
+        is_moderation.then_some(user.username.as_str()),
+        pool,
+    )
+    .await?;
 
     unpublish_file(id, upload.extension.as_deref(), &upload_config.storage).await?;
 
@@ -323,3 +960,356 @@ pub async fn upload_hide_post<'r, 'a, 'b>(
 
     Ok(Json(UploadByChunkResponseOk {}))
 }
+
+#[get("/api/uploads/by-id/<id>/preview")]
+pub async fn upload_preview_get(
+    id: i64,
+    user: Authentication,
+    pool: &State<Pool<Postgres>>,
+    upload_config: &State<UploadConfig>,
+) -> Result<Json<TextPreview>, crate::error::Error> {
+    let upload = get_upload(id, pool).await?;
+    let post_id = upload.post_id.ok_or(crate::error::Error::DoesNotExist)?;
+
+    let post = match try_get_post(post_id, pool, &user)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?
+        .check_visible(&user)
+    {
+        PostVisibility::Visible(post) => post,
+        _ => return Err(crate::error::Error::AccessDenied),
+    };
+    // Post contains only published uploads.
+    let upload = post
+        .uploads
+        .into_iter()
+        .find(|post_upload| post_upload.id == id)
+        .filter(Upload::is_text)
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    Ok(Json(
+        read_text_preview(id, upload.extension.as_deref(), &upload_config.storage).await?,
+    ))
+}
+
+#[options("/api/tus")]
+pub async fn tus_options(upload_config: &State<UploadConfig>) -> TusResponse {
+    TusResponse::new(Status::NoContent)
+        .header("Tus-Version", TUS_VERSION)
+        .header("Tus-Extension", TUS_EXTENSIONS)
+        .header("Tus-Max-Size", upload_config.max_file_size)
+}
+
+/// Get file extension from tus `filename` metadata, invalid extensions are ignored.
+fn get_tus_upload_extension(tus_headers: &TusHeaders) -> Option<&str> {
+    tus_headers
+        .upload_metadata
+        .get("filename")
+        .and_then(|filename| filename.rsplit_once('.'))
+        .map(|(_, extension)| extension)
+        .filter(|extension| extension.len() <= 32 && EXTENSION_REGEX.is_match(extension))
+}
+
+/// Create upload with tus protocol. Post is set by `post_id` metadata, file extension is taken from
+/// `filename` metadata.
+#[post("/api/tus")]
+pub async fn tus_create_post(
+    tus_headers: TusHeaders,
+    pool: &State<Pool<Postgres>>,
+    user: User,
+    upload_config: &State<UploadConfig>,
+    _uploader: Uploader,
+    _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
+) -> Result<TusResponse, crate::error::Error> {
+    if !tus_headers.is_version_supported() {
+        return Ok(TusResponse::version_not_supported());
+    }
+    let size = match tus_headers.upload_length {
+        Some(size) if size > 0 => size,
+        _ => return Ok(TusResponse::new(Status::BadRequest)),
+    };
+    if (size > upload_config.max_file_size) || (size > i64::MAX as u64) {
+        return Ok(TusResponse::new(Status::PayloadTooLarge));
+    }
+    let post_id = match tus_headers
+        .upload_metadata
+        .get("post_id")
+        .and_then(|post_id| post_id.parse().ok())
+    {
+        Some(post_id) => post_id,
+        None => return Ok(TusResponse::new(Status::BadRequest)),
+    };
+
+    let remaining_bytes = try_get_user_storage_usage(&user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?
+        .remaining_bytes(upload_config.default_storage_quota_bytes);
+    if size as i64 > remaining_bytes {
+        return Ok(TusResponse::new(Status::PayloadTooLarge));
+    }
+
+    let upload = allocate_upload(
+        NewUpload {
+            extension: get_tus_upload_extension(&tus_headers),
+            size: size as i64,
+            post_id,
+        },
+        user,
+        pool,
+        &upload_config.storage,
+    )
+    .await?;
+
+    Ok(TusResponse::new(Status::Created).header("Location", uri!(tus_head(upload.id))))
+}
+
+#[head("/api/tus/<id>")]
+pub async fn tus_head(
+    id: i64,
+    tus_headers: TusHeaders,
+    pool: &State<Pool<Postgres>>,
+    user: User,
+) -> Result<TusResponse, crate::error::Error> {
+    if !tus_headers.is_version_supported() {
+        return Ok(TusResponse::version_not_supported());
+    }
+
+    let upload = get_upload(id, pool).await?;
+
+    check_upload_owner(&upload, &user)?;
+
+    let written_size = get_upload_written_size(id, pool).await?;
+
+    Ok(TusResponse::new(Status::Ok)
+        .header("Upload-Offset", written_size)
+        .header("Upload-Length", upload.size)
+        .header("Cache-Control", "no-store"))
+}
+
+/// Append data to upload with tus protocol, upload is finalized when all data is written.
+#[patch("/api/tus/<id>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn tus_patch<'r>(
+    id: i64,
+    data: Data<'r>,
+    tus_headers: TusHeaders,
+    pool: &State<Pool<Postgres>>,
+    user: User,
+    upload_config: &State<UploadConfig>,
+    upload_shutdown_state: &State<UploadShutdownState>,
+    _header_csrf: HeaderCSRF,
+    _allowed_origin: AllowedOrigin,
+) -> Result<TusResponse, crate::error::Error> {
+    if !tus_headers.is_version_supported() {
+        return Ok(TusResponse::version_not_supported());
+    }
+    if tus_headers
+        .content_type
+        .as_deref()
+        .and_then(|content_type| content_type.split(';').next())
+        .map(str::trim)
+        != Some(TUS_PATCH_CONTENT_TYPE)
+    {
+        return Ok(TusResponse::new(Status::UnsupportedMediaType));
+    }
+    let offset = match tus_headers.upload_offset {
+        Some(offset) => offset,
+        None => return Ok(TusResponse::new(Status::BadRequest)),
+    };
+
+    let _write_guard = upload_shutdown_state
+        .begin_write(id)
+        .ok_or(crate::error::Error::ShuttingDown)?;
+
+    let upload = get_upload(id, pool).await?;
+
+    check_upload_owner(&upload, &user)?;
+
+    if offset != get_upload_written_size(id, pool).await? as u64 {
+        return Ok(TusResponse::new(Status::Conflict));
+    }
+    if try_set_upload_status_check_exists(id, UploadStatus::Writing, None, pool)
+        .await?
+        .is_none()
+    {
+        return Ok(TusResponse::new(Status::Conflict));
+    }
+
+    let write_result = {
+        let mut data_stream = data.open((upload.size as u64 - offset).bytes());
+        write_private_file(
+            id,
+            upload.extension.as_deref(),
+            &mut data_stream,
+            offset,
+            &upload_config.storage,
+        )
+        .await
+    };
+    // Interrupted requests are expected with resumable uploads, so upload is made writable again
+    // even if data was not written.
+    let written_size = match write_result {
+        Ok(written_size) => written_size,
+        Err(err) => {
+            try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
+            return Err(err.into());
+        }
+    };
+    let new_offset =
+        extend_upload_written_size(id, offset as i64, (offset + written_size) as i64, pool).await?;
+
+    try_set_upload_status(id, UploadStatus::Allocated, pool).await?;
+
+    if new_offset == upload.size {
+        finalize_upload(&upload, &user, pool, &upload_config.storage).await?;
+    }
+
+    Ok(TusResponse::new(Status::NoContent).header("Upload-Offset", new_offset))
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BanReasonResponseOk {
+    id: String,
+    description: Option<String>,
+    default_text: Option<String>,
+}
+
+#[get("/api/ban-reasons/by-id/<id>")]
+pub async fn ban_reason_get(
+    id: &str,
+    pool: &State<Pool<Postgres>>,
+    _admin: Admin,
+) -> Result<Json<BanReasonResponseOk>, crate::error::Error> {
+    let ban_reason = try_get_ban_reason(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    Ok(Json(BanReasonResponseOk {
+        id: ban_reason.id,
+        description: ban_reason.description,
+        default_text: ban_reason.default_text,
+    }))
+}
+
+fn check_upload_owner(upload: &UploadFull, user: &User) -> Result<(), crate::error::Error> {
+    if (upload.owner_username != user.username) || (upload.post_id.is_some() && !user.is_uploader())
+    {
+        Err(crate::error::Error::AccessDenied)
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthResponse {
+    status: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReadinessResponse {
+    status: String,
+    failing_dependency: Option<String>,
+}
+
+#[get("/healthz")]
+pub async fn healthz_get() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+    })
+}
+
+/// Check access to media file for proxy which serves media itself (see `auth_request` in
+/// `nginx-conf/default.conf`), in the same way as media route of application does.
+#[get("/media-access")]
+pub async fn media_access_get(
+    original_uri: OriginalUri,
+    pool: &State<Pool<Postgres>>,
+    upload_config: &State<UploadConfig>,
+    signed_token_key: &State<SignedTokenKey>,
+) -> Status {
+    if upload_config.signed_url_lifetime.is_none() {
+        return Status::NoContent;
+    }
+    let base_url = match &upload_config.storage {
+        UploadStorage::FileSystem { base_url, .. } => base_url,
+    };
+    let path = original_uri.0.path();
+    let file_name = match path.as_str().strip_prefix(base_url.as_str()) {
+        Some(file_name) if !file_name.is_empty() && !file_name.contains('/') => file_name,
+        _ => return Status::Forbidden,
+    };
+    let token = original_uri.0.query().and_then(|query| {
+        query
+            .segments()
+            .find(|(name, _)| *name == FILE_TOKEN_PARAMETER)
+            .map(|(_, value)| value)
+    });
+
+    if is_file_access_allowed(signed_token_key, token, file_name, Some(pool)).await {
+        Status::NoContent
+    } else {
+        Status::Forbidden
+    }
+}
+
+#[get("/readyz")]
+pub async fn readyz_get(
+    pool: &State<Pool<Postgres>>,
+    upload_config: &State<UploadConfig>,
+    storage_probe_cache: &State<StorageProbeCache>,
+) -> Custom<Json<ReadinessResponse>> {
+    let failing_dependency = if let Err(err) = check_database(pool).await {
+        log::warn!("Database check failed: {:?}", err);
+        Some("database")
+    } else if !storage_probe_cache
+        .is_writable(&upload_config.storage)
+        .await
+    {
+        Some("storage")
+    } else {
+        None
+    };
+
+    match failing_dependency {
+        Some(failing_dependency) => Custom(
+            Status::ServiceUnavailable,
+            Json(ReadinessResponse {
+                status: "unavailable".to_string(),
+                failing_dependency: Some(failing_dependency.to_string()),
+            }),
+        ),
+        None => Custom(
+            Status::Ok,
+            Json(ReadinessResponse {
+                status: "ok".to_string(),
+                failing_dependency: None,
+            }),
+        ),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CsrfTokenResponse {
+    csrf_token: String,
+}
+
+/// Token for `X-CSRF-Token` header, so that scripts using session cookie do not have to load HTML
+/// page to get it. Requests with API token do not need it, see [`HeaderCSRF`].
+#[get("/api/csrf")]
+pub async fn csrf_token_get(csrf_token: CsrfToken) -> Json<CsrfTokenResponse> {
+    Json(CsrfTokenResponse {
+        csrf_token: csrf_token.authenticity_token(),
+    })
+}
+
+#[get("/api/openapi.json")]
+pub async fn openapi_get() -> Json<serde_json::Value> {
+    Json(build_openapi_document(&list_api_operations()))
+}
+
+/// Requests rejected by rate limit fairing are rerouted here.
+#[get("/api/rate-limited")]
+pub async fn rate_limited_get(rate_limited: RateLimited) -> Result<(), crate::error::Error> {
+    Err(crate::error::Error::RateLimited(rate_limited.retry_after))
+}
@@ -0,0 +1,438 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::ErrorKind,
+    path::Path,
+};
+
+use async_zip::{tokio::write::ZipFileWriter, Compression, ZipEntryBuilder};
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+use tokio::{
+    fs::{create_dir_all, File},
+    io::{duplex, AsyncWrite, DuplexStream},
+};
+use tokio_stream::StreamExt;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use crate::{
+    app::{
+        db::{
+            add_post, add_upload, extend_upload_written_size, list_posts_with_pagination,
+            set_upload_sha256, try_ban_post_check_exists,
+            try_edit_post_check_exists_and_permission, try_get_ban_reason, try_get_content_rating,
+            try_set_post_quarantined_check_exists, try_set_upload_status, NewPost, NewUpload, Post,
+            PostEdit, UploadStatus, User,
+        },
+        storage::{
+            allocate_private_file, copy_public_file, get_file_name, hash_private_file,
+            open_public_file, publish_file, write_private_file,
+        },
+    },
+    auth::Authentication,
+    utils::{
+        page_stream::iterate_pages,
+        pagination::{Page, PageParams},
+    },
+    UploadStorage,
+};
+
+const EXPORT_MANIFEST_FILE_NAME: &str = "manifest.json";
+const IMPORT_MANIFEST_FILE_NAME: &str = "import-manifest.json";
+const POSTS_DIRECTORY_NAME: &str = "posts";
+const FILES_DIRECTORY_NAME: &str = "files";
+const POST_ARCHIVE_METADATA_FILE_NAME: &str = "post.json";
+
+/// Size of buffer between task writing post archive and response.
+const POST_ARCHIVE_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedBan {
+    pub ban_reason_id: Option<String>,
+    pub ban_reason_text: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub ban_expires_at: Option<OffsetDateTime>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedUpload {
+    pub id: i64,
+    pub extension: Option<String>,
+    pub size: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedPost {
+    pub id: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub creation_date: OffsetDateTime,
+    pub title: String,
+    pub description: String,
+    pub author_username: String,
+    pub is_hidden: bool,
+    pub is_pinned: bool,
+    pub is_quarantined: bool,
+    pub min_age: Option<i32>,
+    pub content_rating_id: Option<String>,
+    pub ban: Option<ExportedBan>,
+    pub uploads: Vec<ExportedUpload>,
+}
+
+impl From<Post> for ExportedPost {
+    fn from(value: Post) -> Self {
+        Self {
+            id: value.id,
+            creation_date: value.creation_date,
+            title: value.title,
+            description: value.description,
+            author_username: value.author_username,
+            is_hidden: value.is_hidden,
+            is_pinned: value.is_pinned,
+            is_quarantined: value.is_quarantined,
+            min_age: value.min_age,
+            content_rating_id: value.content_rating_id,
+            ban: value.ban.map(
+                |(ban_reason, ban_reason_text, ban_expires_at)| ExportedBan {
+                    ban_reason_id: ban_reason.map(|ban_reason| ban_reason.id),
+                    ban_reason_text,
+                    ban_expires_at,
+                },
+            ),
+            uploads: value
+                .uploads
+                .into_iter()
+                .map(|upload| ExportedUpload {
+                    id: upload.id,
+                    extension: upload.extension,
+                    size: upload.size,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// IDs of posts which are already written to export directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub post_ids: BTreeSet<i64>,
+}
+
+/// Mapping of exported post and upload IDs to IDs of imported ones.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportManifest {
+    pub post_ids: BTreeMap<i64, i64>,
+    pub upload_ids: BTreeMap<i64, i64>,
+}
+
+async fn load_manifest<T: Default + DeserializeOwned>(
+    path: &Path,
+) -> Result<T, crate::error::Error> {
+    match tokio::fs::read(path).await {
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn save_manifest<T: Serialize>(path: &Path, manifest: &T) -> Result<(), crate::error::Error> {
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec_pretty(manifest)?).await?;
+    tokio::fs::rename(temp_path, path).await?;
+    Ok(())
+}
+
+/// Export all posts as JSON files with their published files to `output` directory. Posts
+/// already listed in manifest are skipped, so interrupted export can be resumed. Returns number
+/// of exported posts.
+pub async fn export_posts(
+    output: &Path,
+    page_size: u64,
+    storage: &UploadStorage,
+    pool: &Pool<Postgres>,
+) -> Result<u64, crate::error::Error> {
+    let posts_path = output.join(POSTS_DIRECTORY_NAME);
+    let files_path = output.join(FILES_DIRECTORY_NAME);
+    create_dir_all(&posts_path).await?;
+    create_dir_all(&files_path).await?;
+
+    let manifest_path = output.join(EXPORT_MANIFEST_FILE_NAME);
+    let mut manifest: ExportManifest = load_manifest(&manifest_path).await?;
+
+    let authentication = Authentication::system();
+    let mut stream = Box::pin(iterate_pages(
+        page_size,
+        Box::pin(async |page_params| {
+            list_posts_with_pagination(pool, page_params, &authentication).await
+        }),
+    ));
+    let mut exported_count = 0;
+    while let Some(page) = stream.next().await {
+        for post in page?.items {
+            if manifest.post_ids.contains(&post.id) {
+                continue;
+            }
+
+            info!("Exporting post {}", post.id);
+            for upload in post.uploads.iter() {
+                let file_name = get_file_name(upload.id, upload.extension.as_deref());
+                copy_public_file(
+                    upload.id,
+                    upload.extension.as_deref(),
+                    &files_path.join(file_name),
+                    storage,
+                )
+                .await?;
+            }
+            let exported_post = ExportedPost::from(post);
+            tokio::fs::write(
+                posts_path.join(format!("{}.json", exported_post.id)),
+                serde_json::to_vec_pretty(&exported_post)?,
+            )
+            .await?;
+
+            manifest.post_ids.insert(exported_post.id);
+            save_manifest(&manifest_path, &manifest).await?;
+            exported_count += 1;
+        }
+    }
+
+    Ok(exported_count)
+}
+
+async fn load_exported_posts_page(
+    input: &Path,
+    post_ids: &[i64],
+    page_params: PageParams,
+) -> Result<Page<ExportedPost>, crate::error::Error> {
+    let page_count = (post_ids.len() as u64).div_ceil(page_params.page_size);
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let mut items = Vec::new();
+    for post_id in post_ids.iter().skip(offset as usize).take(limit as usize) {
+        let data = tokio::fs::read(
+            input
+                .join(POSTS_DIRECTORY_NAME)
+                .join(format!("{}.json", post_id)),
+        )
+        .await?;
+        items.push(serde_json::from_slice(&data)?);
+    }
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count: post_ids.len() as u64,
+        page_count,
+    })
+}
+
+async fn import_post(
+    input: &Path,
+    exported_post: &ExportedPost,
+    author: &User,
+    manifest: &mut ImportManifest,
+    storage: &UploadStorage,
+    pool: &Pool<Postgres>,
+) -> Result<i64, crate::error::Error> {
+    let content_rating_id = match &exported_post.content_rating_id {
+        Some(content_rating_id) => try_get_content_rating(content_rating_id, pool)
+            .await?
+            .map(|content_rating| content_rating.id),
+        None => None,
+    };
+    let post = add_post(
+        NewPost {
+            title: &exported_post.title,
+            description: &exported_post.description,
+            is_hidden: true,
+            min_age: exported_post.min_age,
+            content_rating_id: content_rating_id.as_deref(),
+            is_pinned: exported_post.is_pinned,
+        },
+        author.clone(),
+        pool,
+    )
+    .await?;
+
+    for exported_upload in exported_post.uploads.iter() {
+        let extension = exported_upload.extension.as_deref();
+        let upload = add_upload(
+            NewUpload {
+                extension,
+                size: exported_upload.size,
+                post_id: post.id,
+            },
+            author.clone(),
+            pool,
+        )
+        .await?;
+
+        allocate_private_file(upload.id, extension, exported_upload.size as u64, storage).await?;
+        try_set_upload_status(upload.id, UploadStatus::Allocated, pool).await?;
+        try_set_upload_status(upload.id, UploadStatus::Writing, pool).await?;
+        let mut file = File::open(
+            input
+                .join(FILES_DIRECTORY_NAME)
+                .join(get_file_name(exported_upload.id, extension)),
+        )
+        .await?;
+        let written_size = write_private_file(upload.id, extension, &mut file, 0, storage).await?;
+        extend_upload_written_size(upload.id, 0, written_size as i64, pool).await?;
+        try_set_upload_status(upload.id, UploadStatus::Allocated, pool).await?;
+
+        try_set_upload_status(upload.id, UploadStatus::Publishing, pool).await?;
+        let sha256 = hash_private_file(upload.id, extension, storage).await?;
+        set_upload_sha256(upload.id, &sha256, pool).await?;
+        publish_file(upload.id, extension, storage).await?;
+        try_set_upload_status(upload.id, UploadStatus::Published, pool).await?;
+
+        manifest.upload_ids.insert(exported_upload.id, upload.id);
+    }
+
+    if let Some(ban) = &exported_post.ban {
+        let ban_reason_id = match &ban.ban_reason_id {
+            Some(ban_reason_id) => try_get_ban_reason(ban_reason_id, pool)
+                .await?
+                .map(|ban_reason| ban_reason.id),
+            None => None,
+        };
+        try_ban_post_check_exists(
+            post.id,
+            ban_reason_id,
+            ban.ban_reason_text.clone(),
+            ban.ban_expires_at,
+            &author.username,
+            pool,
+        )
+        .await?;
+    }
+    if exported_post.is_quarantined {
+        try_set_post_quarantined_check_exists(post.id, true, &author.username, pool).await?;
+    }
+    if !exported_post.is_hidden {
+        // Post is created hidden so that it is not shown before all files are published.
+        try_edit_post_check_exists_and_permission(
+            PostEdit {
+                id: post.id,
+                title: None,
+                description: None,
+                is_hidden: Some(false),
+                min_age: exported_post.min_age,
+                content_rating_id: content_rating_id.as_deref(),
+                is_pinned: None,
+            },
+            author,
+            pool,
+        )
+        .await?;
+    }
+
+    Ok(post.id)
+}
+
+/// Import posts from directory created by [`export_posts`] on behalf of `author`, assigning new
+/// IDs. Mapping of exported IDs to new ones is saved to import manifest in `input` directory, and
+/// already imported posts are skipped. Returns number of imported posts.
+pub async fn import_posts(
+    input: &Path,
+    author: &User,
+    page_size: u64,
+    storage: &UploadStorage,
+    pool: &Pool<Postgres>,
+) -> Result<u64, crate::error::Error> {
+    let export_manifest: ExportManifest =
+        load_manifest(&input.join(EXPORT_MANIFEST_FILE_NAME)).await?;
+    let post_ids: Vec<i64> = export_manifest.post_ids.into_iter().collect();
+
+    let manifest_path = input.join(IMPORT_MANIFEST_FILE_NAME);
+    let mut manifest: ImportManifest = load_manifest(&manifest_path).await?;
+
+    let mut stream = Box::pin(iterate_pages(
+        page_size,
+        Box::pin(async |page_params| load_exported_posts_page(input, &post_ids, page_params).await),
+    ));
+    let mut imported_count = 0;
+    while let Some(page) = stream.next().await {
+        for exported_post in page?.items {
+            if manifest.post_ids.contains_key(&exported_post.id) {
+                continue;
+            }
+
+            info!("Importing post {}", exported_post.id);
+            let post_id =
+                import_post(input, &exported_post, author, &mut manifest, storage, pool).await?;
+
+            manifest.post_ids.insert(exported_post.id, post_id);
+            save_manifest(&manifest_path, &manifest).await?;
+            imported_count += 1;
+        }
+    }
+
+    Ok(imported_count)
+}
+
+/// Get total size of files in post archive.
+pub fn get_post_archive_size(post: &Post) -> u64 {
+    post.uploads
+        .iter()
+        .map(|upload| upload.size.max(0) as u64)
+        .sum()
+}
+
+/// Write ZIP archive with post metadata in `post.json` and its published files in `files`
+/// directory. Files are stored without compression and copied by chunks, so that archive is not
+/// kept in memory.
+pub async fn write_post_archive<W>(
+    post: Post,
+    storage: &UploadStorage,
+    writer: W,
+) -> Result<(), crate::error::Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let exported_post = ExportedPost::from(post);
+    let mut zip_writer = ZipFileWriter::with_tokio(writer);
+
+    zip_writer
+        .write_entry_whole(
+            ZipEntryBuilder::new(POST_ARCHIVE_METADATA_FILE_NAME.into(), Compression::Stored),
+            &serde_json::to_vec_pretty(&exported_post)?,
+        )
+        .await?;
+
+    for upload in exported_post.uploads.iter() {
+        let extension = upload.extension.as_deref();
+        let file = open_public_file(upload.id, extension, storage).await?;
+        let mut entry_writer = zip_writer
+            .write_entry_stream(ZipEntryBuilder::new(
+                format!(
+                    "{}/{}",
+                    FILES_DIRECTORY_NAME,
+                    get_file_name(upload.id, extension)
+                )
+                .into(),
+                Compression::Stored,
+            ))
+            .await?;
+        futures_lite::io::copy(file.compat(), &mut entry_writer).await?;
+        entry_writer.close().await?;
+    }
+
+    zip_writer.close().await?;
+    Ok(())
+}
+
+/// Start writing post archive in background task, returns reader of archive. If writing fails,
+/// error is logged and archive is truncated, because response status is already sent.
+pub fn stream_post_archive(post: Post, storage: UploadStorage) -> DuplexStream {
+    let (writer, reader) = duplex(POST_ARCHIVE_BUFFER_SIZE);
+    tokio::spawn(async move {
+        let post_id = post.id;
+        if let Err(err) = write_post_archive(post, &storage, writer).await {
+            warn!("Failed to write archive of post {}: {:?}", post_id, err);
+        }
+    });
+    reader
+}
@@ -1,30 +1,49 @@
-use std::{collections::HashSet, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
-use argon2::{password_hash::SaltString, Argon2, PasswordHasher, PasswordVerifier};
+use argon2::{
+    password_hash::SaltString, Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier,
+};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
 use log::debug;
-use rand::thread_rng;
+use rand::{distributions::Standard, thread_rng, Rng};
 use rocket::{
     async_trait,
     form::FromFormField,
     http::{uri::Origin, Status},
     request::{self, FromRequest},
     time::OffsetDateTime,
-    uri, Request, State,
+    uri, Request, State, UriDisplayQuery,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::types::PgInterval, Pool, Postgres};
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::types::PgInterval, PgConnection, Pool, Postgres};
 
 use crate::{
-    app::storage::get_file_url,
+    app::{
+        metrics::METRICS,
+        storage::{get_file_url, unpublish_file},
+    },
     auth::Authentication,
     utils::{
         form_extra_validation::IdSet,
         iter_group::IntoGroupLinkedHashMap,
         pagination::{Page, PageParams},
+        slug::{is_slug_variant, make_unique_slug, slugify},
+        theme::Theme,
     },
-    UploadStorage,
+    PasswordHashConfig, UploadStorage,
 };
 
+pub const DELETED_USERNAME: &str = "deleted";
+
+/// Salt and output of password hash which is checked for missing users, see
+/// [`check_dummy_password`].
+const DUMMY_PASSWORD_HASH_SALT: &str = "YXJjaGl2YW5pbWEtZHVtbXk";
+const DUMMY_PASSWORD_HASH_OUTPUT: &str = "AjEqwHH4CGMAiA9ONO+7T98QYtLAEB+OGMfiwIRvKbc";
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NewUser<'a> {
     pub username: &'a str,
@@ -33,6 +52,7 @@ pub struct NewUser<'a> {
     pub is_admin: bool,
     pub is_uploader: bool,
     pub birth_date: Option<OffsetDateTime>,
+    pub email: Option<&'a str>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -43,6 +63,31 @@ pub struct UserFull {
     pub is_uploader: bool,
     pub password_hash: String,
     pub birth_date: Option<OffsetDateTime>,
+    pub must_change_password: bool,
+    pub created_at: OffsetDateTime,
+    pub last_login_at: Option<OffsetDateTime>,
+    pub display_name: Option<String>,
+}
+
+/// Check password against fixed hash with current hashing parameters when user does not exist, so
+/// that failed login takes same time for missing and existing users. Result is always `false`.
+pub fn check_dummy_password(
+    password: &str,
+    password_hash_config: &PasswordHashConfig,
+) -> Result<bool, crate::error::Error> {
+    let hash_string = format!(
+        "$argon2id$v=19$m={},t={},p={}${}${}",
+        password_hash_config.memory_cost,
+        password_hash_config.iterations,
+        password_hash_config.parallelism,
+        DUMMY_PASSWORD_HASH_SALT,
+        DUMMY_PASSWORD_HASH_OUTPUT
+    );
+    let hash = argon2::PasswordHash::new(&hash_string)?;
+    let argon2 = Argon2::default();
+    // Result is ignored, it is only computed to spend time.
+    let _ = argon2.verify_password(password.as_bytes(), &hash);
+    Ok(false)
 }
 
 impl UserFull {
@@ -54,6 +99,20 @@ impl UserFull {
             Err(_) => Ok(false),
         }
     }
+
+    pub fn needs_rehash(
+        &self,
+        password_hash_config: &PasswordHashConfig,
+    ) -> Result<bool, crate::error::Error> {
+        let hash = argon2::PasswordHash::new(&self.password_hash)?;
+        if Algorithm::try_from(hash.algorithm)? != Algorithm::Argon2id {
+            return Ok(true);
+        }
+        let params = Params::try_from(&hash)?;
+        Ok(params.m_cost() < password_hash_config.memory_cost
+            || params.t_cost() < password_hash_config.iterations
+            || params.p_cost() < password_hash_config.parallelism)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -63,6 +122,10 @@ pub struct User {
     pub is_admin: bool,
     pub is_uploader: bool,
     pub birth_date: Option<OffsetDateTime>,
+    pub must_change_password: bool,
+    pub created_at: OffsetDateTime,
+    pub last_login_at: Option<OffsetDateTime>,
+    pub display_name: Option<String>,
 }
 
 impl User {
@@ -77,6 +140,10 @@ impl User {
     pub fn is_uploader(&self) -> bool {
         self.is_uploader || self.is_admin
     }
+
+    pub fn display_name_or_username(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.username)
+    }
 }
 
 impl From<UserFull> for User {
@@ -87,11 +154,17 @@ impl From<UserFull> for User {
             is_admin: value.is_admin,
             is_uploader: value.is_uploader,
             birth_date: value.birth_date,
+            must_change_password: value.must_change_password,
+            created_at: value.created_at,
+            last_login_at: value.last_login_at,
+            display_name: value.display_name,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, FromFormField)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, FromFormField, UriDisplayQuery,
+)]
 pub enum UserStatus {
     #[field(value = "banned")]
     Banned,
@@ -106,10 +179,10 @@ pub enum UserStatus {
 impl UserStatus {
     pub fn get_options() -> Vec<(String, String)> {
         vec![
-            ("banned".to_string(), "забанен".to_string()),
-            ("user".to_string(), "обычный".to_string()),
-            ("uploader".to_string(), "загружающий".to_string()),
-            ("admin".to_string(), "администратор".to_string()),
+            ("banned".to_string(), "user_status.banned".to_string()),
+            ("user".to_string(), "user_status.user".to_string()),
+            ("uploader".to_string(), "user_status.uploader".to_string()),
+            ("admin".to_string(), "user_status.admin".to_string()),
         ]
     }
 
@@ -124,6 +197,111 @@ impl UserStatus {
     }
 }
 
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, FromFormField, UriDisplayQuery,
+)]
+pub enum UserSort {
+    #[field(value = "username")]
+    Username,
+    #[field(value = "status")]
+    Status,
+    #[field(value = "created_at")]
+    CreatedAt,
+    #[field(value = "last_login")]
+    LastLogin,
+}
+
+impl UserSort {
+    pub fn get_options() -> Vec<(String, String)> {
+        vec![
+            ("username".to_string(), "user_sort.username".to_string()),
+            ("status".to_string(), "user_sort.status".to_string()),
+            ("created_at".to_string(), "user_sort.created_at".to_string()),
+            ("last_login".to_string(), "user_sort.last_login".to_string()),
+        ]
+    }
+
+    pub fn get_option(self) -> String {
+        match self {
+            UserSort::Username => "username",
+            UserSort::Status => "status",
+            UserSort::CreatedAt => "created_at",
+            UserSort::LastLogin => "last_login",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, FromFormField)]
+pub enum BulkUserAction {
+    #[field(value = "ban")]
+    Ban,
+    #[field(value = "unban")]
+    Unban,
+    #[field(value = "make_uploader")]
+    MakeUploader,
+    #[field(value = "revoke_uploader")]
+    RevokeUploader,
+}
+
+impl BulkUserAction {
+    pub fn get_options() -> Vec<(String, String)> {
+        vec![
+            ("ban".to_string(), "bulk_user_action.ban".to_string()),
+            ("unban".to_string(), "bulk_user_action.unban".to_string()),
+            (
+                "make_uploader".to_string(),
+                "bulk_user_action.make_uploader".to_string(),
+            ),
+            (
+                "revoke_uploader".to_string(),
+                "bulk_user_action.revoke_uploader".to_string(),
+            ),
+        ]
+    }
+
+    pub fn get_option(self) -> String {
+        match self {
+            BulkUserAction::Ban => "ban",
+            BulkUserAction::Unban => "unban",
+            BulkUserAction::MakeUploader => "make_uploader",
+            BulkUserAction::RevokeUploader => "revoke_uploader",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, FromFormField)]
+pub enum AccountDeletionMode {
+    #[field(value = "anonymize")]
+    Anonymize,
+    #[field(value = "delete")]
+    Delete,
+}
+
+impl AccountDeletionMode {
+    pub fn get_options() -> Vec<(String, String)> {
+        vec![
+            (
+                "anonymize".to_string(),
+                "account_deletion_mode.anonymize".to_string(),
+            ),
+            (
+                "delete".to_string(),
+                "account_deletion_mode.delete".to_string(),
+            ),
+        ]
+    }
+
+    pub fn get_option(self) -> String {
+        match self {
+            AccountDeletionMode::Anonymize => "anonymize",
+            AccountDeletionMode::Delete => "delete",
+        }
+        .to_string()
+    }
+}
+
 impl From<User> for UserStatus {
     fn from(value: User) -> Self {
         if !value.is_active {
@@ -141,11 +319,14 @@ impl From<User> for UserStatus {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UsernameAndInviteCheckError {
     UserAlreadyExists,
+    EmailAlreadyExists,
     InvalidInviteCode,
+    ExpiredInviteCode,
 }
 
 pub async fn try_add_user_check_username(
     new_user: NewUser<'_>,
+    password_hash_config: &PasswordHashConfig,
     pool: &Pool<Postgres>,
 ) -> Result<Option<()>, crate::error::Error> {
     let mut transaction = pool.begin().await?;
@@ -176,7 +357,7 @@ WHERE
         let mut rng = thread_rng();
         salt = SaltString::generate(&mut rng);
     };
-    let argon2 = Argon2::default();
+    let argon2 = password_hash_config.get_hasher()?;
     let password_hash = argon2
         .hash_password(new_user.password.as_bytes(), &salt)?
         .to_string();
@@ -184,15 +365,17 @@ WHERE
     sqlx::query!(
         r#"
 INSERT INTO
-    users (username, password_hash, is_active, is_admin, is_uploader)
+    users (username, password_hash, is_active, is_admin, is_uploader, birth_date, email, created_at)
 VALUES
-    ($1, $2, $3, $4, $5)
+    ($1, $2, $3, $4, $5, $6, $7, NOW())
             "#,
         new_user.username,
         password_hash,
         new_user.is_active,
         new_user.is_admin,
-        new_user.is_uploader
+        new_user.is_uploader,
+        new_user.birth_date,
+        new_user.email
     )
     .execute(&mut *transaction)
     .await?;
@@ -205,6 +388,7 @@ VALUES
 pub async fn try_add_user_check_username_and_invite<'a>(
     new_user: NewUser<'a>,
     invite_code: &'a str,
+    password_hash_config: &PasswordHashConfig,
     pool: &Pool<Postgres>,
 ) -> Result<Result<(), UsernameAndInviteCheckError>, crate::error::Error> {
     let mut transaction = pool.begin().await?;
@@ -230,31 +414,62 @@ WHERE
         return Ok(Err(UsernameAndInviteCheckError::UserAlreadyExists));
     }
 
-    let is_valid_code = sqlx::query!(
+    if let Some(email) = new_user.email {
+        if is_email_taken(email, None, &mut transaction).await? {
+            transaction.commit().await?;
+
+            return Ok(Err(UsernameAndInviteCheckError::EmailAlreadyExists));
+        }
+    }
+
+    let used_invite = sqlx::query!(
         r#"
-SELECT
-    invite_code
-FROM
+UPDATE
     invite_codes
+SET
+    use_count = use_count + 1
 WHERE
     invite_code = $1
+    AND use_count < max_uses
+    AND (expires_at IS NULL OR expires_at > NOW())
+RETURNING
+    invite_code, created_by, grants_uploader
     "#,
         invite_code
     )
     .fetch_optional(&mut *transaction)
-    .await?
-    .is_some();
+    .await?;
 
-    if !is_valid_code {
-        return Ok(Err(UsernameAndInviteCheckError::InvalidInviteCode));
-    }
+    let Some(used_invite) = used_invite else {
+        let is_expired = sqlx::query!(
+            r#"
+SELECT
+    invite_code
+FROM
+    invite_codes
+WHERE
+    invite_code = $1
+    AND expires_at <= NOW()
+        "#,
+            invite_code
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .is_some();
+
+        return Ok(Err(if is_expired {
+            UsernameAndInviteCheckError::ExpiredInviteCode
+        } else {
+            UsernameAndInviteCheckError::InvalidInviteCode
+        }));
+    };
 
     let salt;
     {
         let mut rng = thread_rng();
         salt = SaltString::generate(&mut rng);
     };
-    let argon2 = Argon2::default();
+    let argon2 = password_hash_config.get_hasher()?;
     let password_hash = argon2
         .hash_password(new_user.password.as_bytes(), &salt)?
         .to_string();
@@ -262,30 +477,52 @@ WHERE
     sqlx::query!(
         r#"
 INSERT INTO
-    users (username, password_hash, is_active, is_admin, is_uploader, birth_date)
+    users (
+        username, password_hash, is_active, is_admin, is_uploader, birth_date, email, created_at,
+        invited_via_code, invited_by
+    )
 VALUES
-    ($1, $2, $3, $4, $5, $6)
+    ($1, $2, $3, $4, $5, $6, $7, NOW(), $8, $9)
             "#,
         new_user.username,
         password_hash,
         new_user.is_active,
         new_user.is_admin,
-        new_user.is_uploader,
-        new_user.birth_date
+        new_user.is_uploader || used_invite.grants_uploader,
+        new_user.birth_date,
+        new_user.email,
+        used_invite.invite_code,
+        used_invite.created_by
     )
     .execute(&mut *transaction)
     .await?;
 
+    if used_invite.grants_uploader && !new_user.is_uploader {
+        add_audit_log_entry(
+            new_user.username,
+            AuditAction::InviteUploaderGrant,
+            "user",
+            Some(new_user.username),
+            &serde_json::json!({
+                "invite_code": used_invite.invite_code,
+                "invite_created_by": used_invite.created_by,
+            }),
+            &mut transaction,
+        )
+        .await?;
+    }
+
     sqlx::query!(
         r#"
-DELETE FROM
-    invite_codes
-WHERE
-    invite_code = $1
+INSERT INTO
+    invite_redemptions (invite_code, username)
+VALUES
+    ($1, $2)
     "#,
-        invite_code
+        invite_code,
+        new_user.username
     )
-    .execute(pool)
+    .execute(&mut *transaction)
     .await?;
 
     transaction.commit().await?;
@@ -300,7 +537,8 @@ pub async fn try_get_user_full(
     let result = sqlx::query!(
         r#"
 SELECT
-    username, is_active, is_admin, is_uploader, password_hash, birth_date
+    username, is_active, is_admin, is_uploader, password_hash, birth_date, must_change_password,
+    created_at, last_login_at, display_name
 FROM
     users
 WHERE
@@ -318,6 +556,10 @@ WHERE
         is_uploader: user_data.is_uploader,
         password_hash: user_data.password_hash,
         birth_date: user_data.birth_date,
+        must_change_password: user_data.must_change_password,
+        created_at: user_data.created_at,
+        last_login_at: user_data.last_login_at,
+        display_name: user_data.display_name,
     }))
 }
 
@@ -328,7 +570,8 @@ pub async fn try_get_user(
     let result = sqlx::query!(
         r#"
 SELECT
-    username, is_active, is_admin, is_uploader, birth_date
+    username, is_active, is_admin, is_uploader, birth_date, must_change_password,
+    created_at, last_login_at, display_name
 FROM
     users
 WHERE
@@ -345,14 +588,21 @@ WHERE
         is_admin: user_data.is_admin,
         is_uploader: user_data.is_uploader,
         birth_date: user_data.birth_date,
+        must_change_password: user_data.must_change_password,
+        created_at: user_data.created_at,
+        last_login_at: user_data.last_login_at,
+        display_name: user_data.display_name,
     }))
 }
 
 pub async fn try_edit_user_check_exists(
     username: &str,
     status: UserStatus,
+    actor_username: &str,
     pool: &Pool<Postgres>,
 ) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
     let already_exists = sqlx::query!(
         r#"
 SELECT
@@ -364,11 +614,13 @@ WHERE
         "#,
         username
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *transaction)
     .await?
     .is_some();
 
     if !already_exists {
+        transaction.commit().await?;
+
         return Ok(None);
     }
 
@@ -386,340 +638,2891 @@ WHERE
         status == UserStatus::Uploader,
         status == UserStatus::Admin,
     )
-    .execute(pool)
+    .execute(&mut *transaction)
     .await?;
 
-    Ok(Some(()))
-}
-
-pub async fn change_user_password(
-    username: &str,
-    new_password: &str,
-    pool: &Pool<Postgres>,
-) -> Result<(), crate::error::Error> {
-    let salt;
-    {
-        let mut rng = thread_rng();
-        salt = SaltString::generate(&mut rng);
-    };
-    let argon2 = Argon2::default();
-    let password_hash = argon2
-        .hash_password(new_password.as_bytes(), &salt)?
-        .to_string();
-
-    sqlx::query!(
-        r#"
-UPDATE
-    users
-SET
-    password_hash = $2
-WHERE
-    username = $1
-        "#,
-        username,
-        password_hash
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::UserEdit,
+        "user",
+        Some(username),
+        &serde_json::json!({
+            "status": status.get_option(),
+        }),
+        &mut transaction,
     )
-    .execute(pool)
     .await?;
 
-    Ok(())
+    transaction.commit().await?;
+
+    if status == UserStatus::Banned {
+        remove_user_sessions(username, None, pool).await?;
+    }
+
+    Ok(Some(()))
 }
 
-pub async fn try_add_invite_check_exists(
-    invite_code: &str,
+pub async fn delete_user_account(
+    username: &str,
+    mode: AccountDeletionMode,
     pool: &Pool<Postgres>,
+    storage: &UploadStorage,
 ) -> Result<Option<()>, crate::error::Error> {
+    if username == DELETED_USERNAME {
+        return Ok(None);
+    }
+
     let mut transaction = pool.begin().await?;
 
     let already_exists = sqlx::query!(
         r#"
 SELECT
-    invite_code
+    username
 FROM
-    invite_codes
+    users
 WHERE
-    invite_code = $1
+    username = $1
+FOR UPDATE
         "#,
-        invite_code
+        username
     )
     .fetch_optional(&mut *transaction)
     .await?
     .is_some();
 
-    if already_exists {
+    if !already_exists {
         transaction.commit().await?;
 
         return Ok(None);
     }
 
-    sqlx::query!(
-        r#"
-INSERT INTO
-    invite_codes (invite_code)
-VALUES
-    ($1)
+    let hidden_post_uploads: Vec<(i64, Option<String>)> = if mode == AccountDeletionMode::Delete {
+        sqlx::query!(
+            r#"
+UPDATE
+    posts
+SET
+    is_hidden = TRUE, is_pinned = FALSE, updated_at = CURRENT_TIMESTAMP
+WHERE
+    author_username = $1
             "#,
-        invite_code
-    )
-    .execute(&mut *transaction)
-    .await?;
-
-    transaction.commit().await?;
-
-    Ok(Some(()))
-}
+            username
+        )
+        .execute(&mut *transaction)
+        .await?;
 
-pub async fn try_remove_invite_check_exists(
-    invite_code: &str,
-    pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+UPDATE
+    uploads
+SET
+    file_status = 'HIDING'
+WHERE
+    file_status = 'PUBLISHED'
+    AND post_id IN (
+        SELECT
+            id
+        FROM
+            posts
+        WHERE
+            author_username = $1
+    )
+RETURNING
+    id, extension
+            "#,
+            username
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .map(|record| (record.id, record.extension))
+        .collect()
+    } else {
+        vec![]
+    };
 
-    let already_exists = sqlx::query!(
+    let avatar_uploads: Vec<(i64, Option<String>)> = sqlx::query!(
         r#"
 SELECT
-    invite_code
+    id, extension
 FROM
-    invite_codes
+    uploads
 WHERE
-    invite_code = $1
+    avatar_username = $1
         "#,
-        invite_code
+        username
     )
-    .fetch_optional(&mut *transaction)
+    .fetch_all(&mut *transaction)
     .await?
-    .is_some();
+    .into_iter()
+    .map(|record| (record.id, record.extension))
+    .collect();
 
-    if !already_exists {
-        transaction.commit().await?;
+    sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    author_username = $2, updated_at = CURRENT_TIMESTAMP
+WHERE
+    author_username = $1
+        "#,
+        username,
+        DELETED_USERNAME
+    )
+    .execute(&mut *transaction)
+    .await?;
 
-        return Ok(None);
-    }
+    sqlx::query!(
+        r#"
+DELETE FROM
+    login_attempts
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
 
     sqlx::query!(
         r#"
 DELETE FROM
-    invite_codes
+    users
 WHERE
-    invite_code = $1
-            "#,
-        invite_code
+    username = $1
+        "#,
+        username
     )
     .execute(&mut *transaction)
     .await?;
 
     transaction.commit().await?;
 
-    Ok(Some(()))
-}
-
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct BanReason {
-    pub id: String,
-    pub description: Option<String>,
-}
-
-impl BanReason {
-    pub fn edit_url(&self) -> Origin {
-        uri!(crate::app::views::ban_reason_edit_get(&self.id))
+    for (id, extension) in hidden_post_uploads.iter().chain(avatar_uploads.iter()) {
+        unpublish_file(*id, extension.as_deref(), storage).await?;
     }
-}
+    set_uploads_hidden(
+        pool,
+        hidden_post_uploads.into_iter().map(|(id, _)| id).collect(),
+    )
+    .await?;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct BanReasonIdSet {
-    pub ids_set: HashSet<String>,
-    pub option_list: Vec<(String, String)>,
+    Ok(Some(()))
 }
 
-impl IdSet for BanReasonIdSet {
-    fn get_option_list(&self) -> Vec<(String, String)> {
-        self.option_list.clone()
-    }
+pub async fn change_user_password(
+    username: &str,
+    new_password: &str,
+    must_change_password: bool,
+    password_hash_config: &PasswordHashConfig,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    let salt;
+    {
+        let mut rng = thread_rng();
+        salt = SaltString::generate(&mut rng);
+    };
+    let argon2 = password_hash_config.get_hasher()?;
+    let password_hash = argon2
+        .hash_password(new_password.as_bytes(), &salt)?
+        .to_string();
 
-    fn is_valid_id(&self, id: &str) -> bool {
-        self.ids_set.contains(id)
-    }
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    password_hash = $2, must_change_password = $3
+WHERE
+    username = $1
+        "#,
+        username,
+        password_hash,
+        must_change_password
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
 }
 
-#[async_trait]
-impl<'r> FromRequest<'r> for BanReasonIdSet {
-    type Error = crate::error::Error;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserStorageUsage {
+    pub usage_bytes: i64,
+    pub storage_quota_bytes: Option<i64>,
+}
 
-    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let result: &request::Outcome<Self, Self::Error> = req
-            .local_cache_async(async {
-                let pool_state_result: request::Outcome<&State<Pool<Postgres>>, ()> =
-                    req.guard().await;
-                match pool_state_result {
-                    request::Outcome::Success(pool_state) => {
-                        match list_ban_reasons(pool_state).await {
-                            Ok(ban_reasons) => request::Outcome::Success(BanReasonIdSet {
-                                ids_set: HashSet::from_iter(
-                                    ban_reasons.iter().map(|ban_reason| &ban_reason.id).cloned(),
-                                ),
-                                option_list: ban_reasons
-                                    .iter()
-                                    .map(|ban_reason| {
-                                        (
-                                            ban_reason.id.clone(),
-                                            if let Some(description) = &ban_reason.description {
-                                                format!("{}: {}", ban_reason.id, description)
-                                            } else {
-                                                ban_reason.id.clone()
-                                            },
-                                        )
-                                    })
-                                    .collect(),
-                            }),
-                            Err(err) => request::Outcome::Error((Status::InternalServerError, err)),
-                        }
-                    }
-                    request::Outcome::Error((status, ())) => {
-                        request::Outcome::Error((status, crate::error::Error::PoolNotFound))
-                    }
-                    request::Outcome::Forward(status) => request::Outcome::Forward(status),
-                }
-            })
-            .await;
+impl UserStorageUsage {
+    pub fn quota_bytes(&self, default_quota_bytes: u64) -> i64 {
+        self.storage_quota_bytes
+            .unwrap_or(default_quota_bytes.try_into().unwrap_or(i64::MAX))
+    }
 
-        result.clone()
+    pub fn remaining_bytes(&self, default_quota_bytes: u64) -> i64 {
+        (self.quota_bytes(default_quota_bytes) - self.usage_bytes).max(0)
     }
 }
 
-pub async fn list_ban_reasons(
+pub async fn try_get_user_storage_usage(
+    username: &str,
     pool: &Pool<Postgres>,
-) -> Result<Vec<BanReason>, crate::error::Error> {
-    let result = sqlx::query!(
+) -> Result<Option<UserStorageUsage>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        UserStorageUsage,
         r#"
 SELECT
-    id, description
+    (
+        SELECT
+            COALESCE(SUM(uploads.size), 0)::BIGINT
+        FROM
+            uploads
+            LEFT JOIN posts
+                ON posts.id = uploads.post_id
+        WHERE
+            (
+                posts.author_username = users.username
+                OR uploads.avatar_username = users.username
+            )
+            AND uploads.file_status NOT IN ('HIDDEN', 'MISSING')
+    ) AS "usage_bytes!",
+    storage_quota_bytes
 FROM
-    ban_reasons
-ORDER BY
-    id
-        "#
+    users
+WHERE
+    username = $1
+        "#,
+        username
     )
-    .fetch_all(pool)
-    .await?
-    .iter()
-    .map(|record| BanReason {
-        id: record.id.clone(),
-        description: record.description.clone(),
-    })
-    .collect();
-
-    Ok(result)
+    .fetch_optional(pool)
+    .await?)
 }
 
-pub async fn try_get_ban_reason(
-    id: &str,
+pub async fn set_user_storage_quota(
+    username: &str,
+    storage_quota_bytes: Option<i64>,
     pool: &Pool<Postgres>,
-) -> Result<Option<BanReason>, crate::error::Error> {
-    let result = sqlx::query!(
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
         r#"
-SELECT
-    description
-FROM
-    ban_reasons
+UPDATE
+    users
+SET
+    storage_quota_bytes = $2
 WHERE
-    id = $1
+    username = $1
         "#,
-        id
+        username,
+        storage_quota_bytes
     )
-    .fetch_optional(pool)
-    .await?
-    .map(|record| BanReason {
-        id: id.to_owned(),
-        description: record.description,
-    });
+    .execute(pool)
+    .await?;
 
-    Ok(result)
+    Ok(())
 }
 
-pub async fn try_add_ban_reason_check_exists(
-    ban_reason: BanReason,
+pub async fn set_user_last_login(
+    username: &str,
     pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let mut transaction = pool.begin().await?;
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    last_login_at = NOW()
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .execute(pool)
+    .await?;
 
-    let already_exists = sqlx::query!(
+    Ok(())
+}
+
+pub async fn set_user_must_change_password(
+    username: &str,
+    must_change_password: bool,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
         r#"
-SELECT
-    id
-FROM
-    ban_reasons
+UPDATE
+    users
+SET
+    must_change_password = $2
 WHERE
-    id = $1
+    username = $1
         "#,
-        ban_reason.id
+        username,
+        must_change_password
     )
-    .fetch_optional(&mut *transaction)
-    .await?
-    .is_some();
+    .execute(pool)
+    .await?;
 
-    if already_exists {
-        transaction.commit().await?;
+    Ok(())
+}
 
-        return Ok(None);
-    }
+fn generate_token() -> String {
+    let token_bytes: Vec<u8> = thread_rng().sample_iter(Standard).take(32).collect();
+    BASE64_URL_SAFE_NO_PAD.encode(&token_bytes[..])
+}
+
+pub fn generate_random_password() -> String {
+    let password_bytes: Vec<u8> = thread_rng().sample_iter(Standard).take(18).collect();
+    BASE64_URL_SAFE_NO_PAD.encode(&password_bytes[..])
+}
+
+pub async fn add_session(
+    username: &str,
+    user_agent: Option<&str>,
+    lifetime: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<String, crate::error::Error> {
+    let token = generate_token();
+    let lifetime: PgInterval = lifetime.try_into()?;
 
     sqlx::query!(
         r#"
 INSERT INTO
-    ban_reasons (id, description)
+    sessions (token, username, user_agent, expires_at)
 VALUES
-    ($1, $2)
-            "#,
-        ban_reason.id,
-        ban_reason.description
+    ($1, $2, $3, NOW() + $4)
+        "#,
+        token,
+        username,
+        user_agent,
+        lifetime
     )
-    .execute(&mut *transaction)
+    .execute(pool)
     .await?;
 
-    transaction.commit().await?;
-
-    Ok(Some(()))
+    Ok(token)
 }
 
-pub async fn try_edit_ban_reason_check_exists(
-    ban_reason: BanReason,
+pub async fn try_get_user_by_session(
+    token: &str,
     pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let already_exists = sqlx::query!(
+) -> Result<Option<User>, crate::error::Error> {
+    let result = sqlx::query!(
         r#"
-SELECT
-    id
+UPDATE
+    sessions
+SET
+    last_seen_date = NOW()
 FROM
-    ban_reasons
+    users
 WHERE
-    id = $1
+    sessions.token = $1
+    AND sessions.expires_at > NOW()
+    AND sessions.username = users.username
+RETURNING
+    users.username, is_active, is_admin, is_uploader, birth_date, must_change_password,
+    created_at, last_login_at, display_name
         "#,
-        ban_reason.id
+        token
     )
     .fetch_optional(pool)
-    .await?
-    .is_some();
+    .await?;
 
-    if !already_exists {
-        return Ok(None);
-    }
+    Ok(result.map(|user_data| User {
+        username: user_data.username,
+        is_active: user_data.is_active,
+        is_admin: user_data.is_admin,
+        is_uploader: user_data.is_uploader,
+        birth_date: user_data.birth_date,
+        must_change_password: user_data.must_change_password,
+        created_at: user_data.created_at,
+        last_login_at: user_data.last_login_at,
+        display_name: user_data.display_name,
+    }))
+}
 
+pub async fn remove_session(token: &str, pool: &Pool<Postgres>) -> Result<(), crate::error::Error> {
     sqlx::query!(
         r#"
-UPDATE
-    ban_reasons
-SET
-    description = $2
+DELETE FROM
+    sessions
 WHERE
-    id = $1
-            "#,
-        ban_reason.id,
-        ban_reason.description
+    token = $1
+        "#,
+        token
     )
     .execute(pool)
     .await?;
 
-    Ok(Some(()))
+    Ok(())
+}
+
+/// Remove sessions which are expired, they are not used for authentication anyway.
+pub async fn remove_expired_sessions(pool: &Pool<Postgres>) -> Result<u64, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+DELETE FROM
+    sessions
+WHERE
+    expires_at <= NOW()
+        "#
+    )
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+pub async fn remove_user_sessions(
+    username: &str,
+    except_token: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+DELETE FROM
+    sessions
+WHERE
+    username = $1
+    AND ($2::TEXT IS NULL OR token != $2)
+        "#,
+        username,
+        except_token
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn is_login_locked(
+    username: &str,
+    ip_address: Option<&str>,
+    max_attempts: u32,
+    window: Duration,
+    lockout_duration: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let window: PgInterval = window.try_into()?;
+    let lockout_duration: PgInterval = lockout_duration.try_into()?;
+
+    let result = sqlx::query!(
+        r#"
+SELECT
+    (
+        COUNT(id) >= $3
+        AND AGE(CURRENT_TIMESTAMP, MAX(attempt_date)) < $5
+    ) AS "is_locked!"
+FROM
+    login_attempts
+WHERE
+    (
+        username = $1
+        OR ($2::TEXT IS NOT NULL AND ip_address = $2)
+    )
+    AND AGE(CURRENT_TIMESTAMP, attempt_date) < $4
+        "#,
+        username,
+        ip_address,
+        max_attempts as i64,
+        window,
+        lockout_duration
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(result.is_locked)
+}
+
+pub async fn add_failed_login_attempt(
+    username: &str,
+    ip_address: Option<&str>,
+    window: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    let window: PgInterval = window.try_into()?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    login_attempts
+WHERE
+    AGE(CURRENT_TIMESTAMP, attempt_date) >= $1
+        "#,
+        window
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    login_attempts (username, ip_address)
+VALUES
+    ($1, $2)
+        "#,
+        username,
+        ip_address
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+pub async fn clear_login_attempts(
+    username: &str,
+    ip_address: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+DELETE FROM
+    login_attempts
+WHERE
+    username = $1
+    OR ($2::TEXT IS NOT NULL AND ip_address = $2)
+        "#,
+        username,
+        ip_address
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Check if registration is locked for invite code (if it is used) or for IP address. Attempts
+/// without invite code are counted for IP address only.
+pub async fn is_registration_locked(
+    invite_code: Option<&str>,
+    ip_address: Option<&str>,
+    max_attempts: u32,
+    window: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let window: PgInterval = window.try_into()?;
+
+    let result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id) >= $3 AS "is_locked!"
+FROM
+    registration_attempts
+WHERE
+    (
+        ($1::TEXT IS NOT NULL AND invite_code = $1)
+        OR ($2::TEXT IS NOT NULL AND ip_address = $2)
+    )
+    AND AGE(CURRENT_TIMESTAMP, attempt_date) < $4
+        "#,
+        invite_code,
+        ip_address,
+        max_attempts as i64,
+        window
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(result.is_locked)
+}
+
+pub async fn add_failed_registration_attempt(
+    invite_code: Option<&str>,
+    ip_address: Option<&str>,
+    window: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    let window: PgInterval = window.try_into()?;
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    registration_attempts
+WHERE
+    AGE(CURRENT_TIMESTAMP, attempt_date) >= $1
+        "#,
+        window
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    registration_attempts (invite_code, ip_address)
+VALUES
+    ($1, $2)
+        "#,
+        invite_code,
+        ip_address
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+pub async fn is_email_available(
+    email: &str,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+    let is_taken = is_email_taken(email, None, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(!is_taken)
+}
+
+async fn is_email_taken(
+    email: &str,
+    except_username: Option<&str>,
+    transaction: &mut sqlx::Transaction<'_, Postgres>,
+) -> Result<bool, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    username
+FROM
+    users
+WHERE
+    LOWER(email) = LOWER($1)
+    AND ($2::VARCHAR IS NULL OR username <> $2)
+        "#,
+        email,
+        except_username
+    )
+    .fetch_optional(&mut **transaction)
+    .await?
+    .is_some())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserEmail {
+    pub email: Option<String>,
+    pub email_verified_at: Option<OffsetDateTime>,
+}
+
+impl UserEmail {
+    pub fn is_verified(&self) -> bool {
+        self.email.is_some() && self.email_verified_at.is_some()
+    }
+}
+
+pub async fn try_get_user_email(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<UserEmail>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        UserEmail,
+        r#"
+SELECT
+    email, email_verified_at
+FROM
+    users
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn try_set_user_email_check_unique(
+    username: &str,
+    email: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    if let Some(email) = email {
+        if is_email_taken(email, Some(username), &mut transaction).await? {
+            transaction.commit().await?;
+
+            return Ok(None);
+        }
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    email = $2,
+    email_verified_at = CASE
+        WHEN LOWER(email) = LOWER($2::VARCHAR) THEN email_verified_at
+        ELSE NULL
+    END
+WHERE
+    username = $1
+        "#,
+        username,
+        email
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    email_verification_tokens
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn add_email_verification_token(
+    username: &str,
+    email: &str,
+    pool: &Pool<Postgres>,
+) -> Result<String, crate::error::Error> {
+    let token = generate_token();
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    email_verification_tokens (token, username, email)
+VALUES
+    ($1, $2, $3)
+        "#,
+        token,
+        username,
+        email
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+pub async fn try_verify_email(
+    token: &str,
+    lifetime: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<Option<String>, crate::error::Error> {
+    let lifetime: PgInterval = lifetime.try_into()?;
+
+    let mut transaction = pool.begin().await?;
+
+    let token_data = sqlx::query!(
+        r#"
+DELETE FROM
+    email_verification_tokens
+WHERE
+    token = $1
+RETURNING
+    username, email, AGE(CURRENT_TIMESTAMP, creation_date) < $2 AS "is_valid!"
+        "#,
+        token,
+        lifetime
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let result = match token_data {
+        Some(token_data) if token_data.is_valid => sqlx::query!(
+            r#"
+UPDATE
+    users
+SET
+    email_verified_at = NOW()
+WHERE
+    username = $1
+    AND email = $2
+RETURNING
+    username
+            "#,
+            token_data.username,
+            token_data.email
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .map(|record| record.username),
+        _ => None,
+    };
+
+    transaction.commit().await?;
+
+    Ok(result)
+}
+
+fn hash_token(token: &str) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+pub async fn try_get_verified_email_for_password_reset(
+    username_or_email: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<(String, String)>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    username, email AS "email!"
+FROM
+    users
+WHERE
+    (username = $1 OR LOWER(email) = LOWER($1))
+    AND email IS NOT NULL
+    AND email_verified_at IS NOT NULL
+    AND is_active
+        "#,
+        username_or_email
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| (record.username, record.email)))
+}
+
+pub async fn add_password_reset_token(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<String, crate::error::Error> {
+    let token = generate_token();
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    password_reset_tokens
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    password_reset_tokens (token_hash, username)
+VALUES
+    ($1, $2)
+        "#,
+        hash_token(&token),
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(token)
+}
+
+pub async fn is_password_reset_token_valid(
+    token: &str,
+    lifetime: Duration,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let lifetime: PgInterval = lifetime.try_into()?;
+
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    username
+FROM
+    password_reset_tokens
+WHERE
+    token_hash = $1
+    AND AGE(CURRENT_TIMESTAMP, creation_date) < $2
+        "#,
+        hash_token(token),
+        lifetime
+    )
+    .fetch_optional(pool)
+    .await?
+    .is_some())
+}
+
+pub async fn try_reset_password_with_token(
+    token: &str,
+    new_password: &str,
+    lifetime: Duration,
+    password_hash_config: &PasswordHashConfig,
+    pool: &Pool<Postgres>,
+) -> Result<Option<String>, crate::error::Error> {
+    let lifetime: PgInterval = lifetime.try_into()?;
+
+    let mut transaction = pool.begin().await?;
+
+    let token_data = sqlx::query!(
+        r#"
+DELETE FROM
+    password_reset_tokens
+WHERE
+    token_hash = $1
+RETURNING
+    username, AGE(CURRENT_TIMESTAMP, creation_date) < $2 AS "is_valid!"
+        "#,
+        hash_token(token),
+        lifetime
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let username = match token_data {
+        Some(token_data) if token_data.is_valid => token_data.username,
+        _ => {
+            transaction.commit().await?;
+
+            return Ok(None);
+        }
+    };
+
+    let salt;
+    {
+        let mut rng = thread_rng();
+        salt = SaltString::generate(&mut rng);
+    };
+    let argon2 = password_hash_config.get_hasher()?;
+    let password_hash = argon2
+        .hash_password(new_password.as_bytes(), &salt)?
+        .to_string();
+
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    password_hash = $2, must_change_password = false
+WHERE
+    username = $1
+        "#,
+        username,
+        password_hash
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    sessions
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(username))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserProfile {
+    pub bio: String,
+    pub avatar: Option<Upload>,
+    pub public_post_count: i64,
+}
+
+pub async fn try_get_user_profile(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<UserProfile>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    users.bio,
+    uploads.id AS "avatar_id?",
+    uploads.extension AS "avatar_extension?",
+    uploads.size AS "avatar_size?",
+    uploads.creation_date AS "avatar_creation_date?",
+    uploads.file_status AS "avatar_file_status?: UploadStatus",
+    (
+        SELECT
+            COUNT(posts.id)
+        FROM
+            posts
+        WHERE
+            posts.author_username = users.username
+            AND NOT posts.is_hidden
+            AND NOT posts.is_quarantined
+            AND NOT (
+                posts.is_banned
+                AND (
+                    posts.ban_expires_at IS NULL
+                    OR posts.ban_expires_at > CURRENT_TIMESTAMP
+                )
+            )
+    ) AS "public_post_count!"
+FROM
+    users
+    LEFT JOIN uploads
+        ON uploads.id = users.avatar_upload_id
+        AND uploads.file_status = 'PUBLISHED'
+WHERE
+    users.username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.map(|record| UserProfile {
+        bio: record.bio,
+        avatar: match (
+            record.avatar_id,
+            record.avatar_size,
+            record.avatar_creation_date,
+            record.avatar_file_status,
+        ) {
+            (Some(id), Some(size), Some(creation_date), Some(file_status)) => Some(Upload {
+                id,
+                extension: record.avatar_extension,
+                size,
+                creation_date,
+                file_status,
+            }),
+            _ => None,
+        },
+        public_post_count: record.public_post_count,
+    }))
+}
+
+pub async fn set_user_bio(
+    username: &str,
+    bio: &str,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    bio = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        bio
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get theme chosen by user, `None` if it is not chosen or user does not exist.
+pub async fn get_user_theme(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<Theme>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    theme AS "theme: Theme"
+FROM
+    users
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.and_then(|row| row.theme))
+}
+
+pub async fn set_user_theme(
+    username: &str,
+    theme: Theme,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    theme = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        theme as _
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_user_timezone_offset(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<i32>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    timezone_offset
+FROM
+    users
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.and_then(|row| row.timezone_offset))
+}
+
+pub async fn set_user_timezone_offset(
+    username: &str,
+    timezone_offset: Option<i32>,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    timezone_offset = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        timezone_offset
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn try_change_user_birth_date_once(
+    username: &str,
+    birth_date: Option<OffsetDateTime>,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    birth_date = $2,
+    birth_date_changed_at = NOW()
+WHERE
+    username = $1
+    AND birth_date_changed_at IS NULL
+RETURNING
+    username
+        "#,
+        username,
+        birth_date
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|_| ()))
+}
+
+pub async fn set_user_birth_date(
+    username: &str,
+    birth_date: Option<OffsetDateTime>,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    birth_date = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        birth_date
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_user_display_name(
+    username: &str,
+    display_name: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    display_name = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        display_name
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn bulk_edit_users(
+    usernames: &[String],
+    action: BulkUserAction,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<u64, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let changed_usernames: Vec<String> = sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    is_active = CASE
+        WHEN $2 = 'ban' THEN FALSE
+        WHEN $2 = 'unban' THEN TRUE
+        ELSE is_active
+    END,
+    is_uploader = CASE
+        WHEN $2 = 'make_uploader' THEN TRUE
+        WHEN $2 = 'revoke_uploader' THEN FALSE
+        ELSE is_uploader
+    END
+WHERE
+    username = ANY($1)
+    AND username <> $3
+    AND NOT is_admin
+    AND (
+        CASE
+            WHEN $2 = 'ban' THEN is_active
+            WHEN $2 = 'unban' THEN NOT is_active
+            WHEN $2 = 'make_uploader' THEN NOT is_uploader
+            WHEN $2 = 'revoke_uploader' THEN is_uploader
+        END
+    )
+RETURNING
+    username
+        "#,
+        usernames,
+        action.get_option(),
+        DELETED_USERNAME
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|record| record.username)
+    .collect();
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::BulkUserEdit,
+        "user",
+        None,
+        &serde_json::json!({
+            "action": action.get_option(),
+            "usernames": changed_usernames,
+        }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    if action == BulkUserAction::Ban {
+        for username in &changed_usernames {
+            remove_user_sessions(username, None, pool).await?;
+        }
+    }
+
+    Ok(changed_usernames.len() as u64)
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    sqlx::Type,
+    Serialize,
+    Deserialize,
+    FromFormField,
+    UriDisplayQuery,
+)]
+#[sqlx(type_name = "audit_action")]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditAction {
+    #[field(value = "bulk_user_edit")]
+    BulkUserEdit,
+    #[field(value = "invite_uploader_grant")]
+    InviteUploaderGrant,
+    #[field(value = "post_ban")]
+    PostBan,
+    #[field(value = "post_unban")]
+    PostUnban,
+    #[field(value = "user_edit")]
+    UserEdit,
+    #[field(value = "invite_add")]
+    InviteAdd,
+    #[field(value = "invite_remove")]
+    InviteRemove,
+    #[field(value = "upload_hide")]
+    UploadHide,
+    #[field(value = "post_quarantine")]
+    PostQuarantine,
+    #[field(value = "post_content_rating")]
+    PostContentRating,
+}
+
+impl AuditAction {
+    pub fn get_options() -> Vec<(String, String)> {
+        [
+            AuditAction::BulkUserEdit,
+            AuditAction::InviteUploaderGrant,
+            AuditAction::PostBan,
+            AuditAction::PostUnban,
+            AuditAction::UserEdit,
+            AuditAction::InviteAdd,
+            AuditAction::InviteRemove,
+            AuditAction::UploadHide,
+            AuditAction::PostQuarantine,
+            AuditAction::PostContentRating,
+        ]
+        .into_iter()
+        .map(|action| (action.get_option(), action.get_verbose_name().to_string()))
+        .collect()
+    }
+
+    pub fn get_option(self) -> String {
+        match self {
+            AuditAction::BulkUserEdit => "bulk_user_edit",
+            AuditAction::InviteUploaderGrant => "invite_uploader_grant",
+            AuditAction::PostBan => "post_ban",
+            AuditAction::PostUnban => "post_unban",
+            AuditAction::UserEdit => "user_edit",
+            AuditAction::InviteAdd => "invite_add",
+            AuditAction::InviteRemove => "invite_remove",
+            AuditAction::UploadHide => "upload_hide",
+            AuditAction::PostQuarantine => "post_quarantine",
+            AuditAction::PostContentRating => "post_content_rating",
+        }
+        .to_string()
+    }
+
+    /// Message key of audit action name.
+    pub fn get_verbose_name(self) -> &'static str {
+        match self {
+            AuditAction::BulkUserEdit => "audit_action.bulk_user_edit",
+            AuditAction::InviteUploaderGrant => "audit_action.invite_uploader_grant",
+            AuditAction::PostBan => "audit_action.post_ban",
+            AuditAction::PostUnban => "audit_action.post_unban",
+            AuditAction::UserEdit => "audit_action.user_edit",
+            AuditAction::InviteAdd => "audit_action.invite_add",
+            AuditAction::InviteRemove => "audit_action.invite_remove",
+            AuditAction::UploadHide => "audit_action.upload_hide",
+            AuditAction::PostQuarantine => "audit_action.post_quarantine",
+            AuditAction::PostContentRating => "audit_action.post_content_rating",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub actor_username: Option<String>,
+    pub action: AuditAction,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub details: String,
+    pub action_date: OffsetDateTime,
+}
+
+pub async fn list_audit_log_with_pagination(
+    pool: &Pool<Postgres>,
+    page_params: PageParams,
+    actor_username: Option<&str>,
+    action: Option<AuditAction>,
+) -> Result<Page<AuditLogEntry>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id)
+FROM
+    audit_log
+WHERE
+    ($1::TEXT IS NULL OR actor_username = $1)
+    AND ($2::audit_action IS NULL OR action = $2)
+        "#,
+        actor_username,
+        action as _
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let items = sqlx::query_as!(
+        AuditLogEntry,
+        r#"
+SELECT
+    id, actor_username, action AS "action: AuditAction", target_type, target_id,
+    details::TEXT AS "details!", action_date
+FROM
+    audit_log
+WHERE
+    ($3::TEXT IS NULL OR actor_username = $3)
+    AND ($4::audit_action IS NULL OR action = $4)
+ORDER BY
+    action_date DESC, id DESC
+LIMIT $2
+OFFSET $1
+        "#,
+        offset,
+        limit,
+        actor_username,
+        action as _
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn add_audit_log_entry(
+    actor_username: &str,
+    action: AuditAction,
+    target_type: &str,
+    target_id: Option<&str>,
+    details: &serde_json::Value,
+    connection: &mut PgConnection,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+INSERT INTO
+    audit_log (actor_username, action, target_type, target_id, details)
+VALUES
+    ($1, $2, $3, $4, CAST($5::TEXT AS JSONB))
+        "#,
+        actor_username,
+        action as _,
+        target_type,
+        target_id,
+        details.to_string()
+    )
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "uploader_request_status")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum UploaderRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploaderRequest {
+    pub id: i64,
+    pub username: String,
+    pub motivation: String,
+    pub status: UploaderRequestStatus,
+    pub creation_date: OffsetDateTime,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<OffsetDateTime>,
+}
+
+pub async fn try_add_uploader_request_check_pending(
+    username: &str,
+    motivation: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    uploader_requests
+WHERE
+    username = $1
+    AND status = 'PENDING'
+        "#,
+        username
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    uploader_requests (username, motivation)
+VALUES
+    ($1, $2)
+        "#,
+        username,
+        motivation
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn list_pending_uploader_requests(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<UploaderRequest>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        UploaderRequest,
+        r#"
+SELECT
+    id, username, motivation, status AS "status: UploaderRequestStatus", creation_date,
+    decided_by, decided_at
+FROM
+    uploader_requests
+WHERE
+    status = 'PENDING'
+ORDER BY
+    creation_date, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn try_decide_uploader_request_check_pending(
+    id: i64,
+    status: UploaderRequestStatus,
+    decided_by: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<String>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+UPDATE
+    uploader_requests
+SET
+    status = $2, decided_by = $3, decided_at = NOW()
+WHERE
+    id = $1
+    AND status = 'PENDING'
+RETURNING
+    username
+        "#,
+        id,
+        status as _,
+        decided_by
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| record.username))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "impersonation_action")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum ImpersonationAction {
+    Start,
+    Stop,
+}
+
+pub async fn add_impersonation_log_entry(
+    admin_username: &str,
+    target_username: Option<&str>,
+    action: ImpersonationAction,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+INSERT INTO
+    impersonation_log (admin_username, target_username, action)
+VALUES
+    ($1, $2, $3)
+        "#,
+        admin_username,
+        target_username,
+        action as _
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub id: i64,
+    pub creation_date: OffsetDateTime,
+    pub last_seen_date: OffsetDateTime,
+    pub user_agent: Option<String>,
+    pub is_current: bool,
+}
+
+pub async fn list_sessions_for_user(
+    username: &str,
+    current_token: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<Session>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    id, creation_date, last_seen_date, user_agent,
+    ($2::TEXT IS NOT NULL AND token = $2) AS "is_current!"
+FROM
+    sessions
+WHERE
+    username = $1
+    AND expires_at > NOW()
+ORDER BY
+    last_seen_date DESC, id DESC
+        "#,
+        username,
+        current_token
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| Session {
+        id: record.id,
+        creation_date: record.creation_date,
+        last_seen_date: record.last_seen_date,
+        user_agent: record.user_agent,
+        is_current: record.is_current,
+    })
+    .collect())
+}
+
+pub async fn try_revoke_session_check_owner(
+    id: i64,
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+DELETE FROM
+    sessions
+WHERE
+    id = $1
+    AND username = $2
+RETURNING
+    id
+        "#,
+        id,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.map(|_| ()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, FromFormField)]
+#[sqlx(type_name = "api_token_scope")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum ApiTokenScope {
+    #[field(value = "read")]
+    Read,
+    #[field(value = "upload")]
+    Upload,
+}
+
+impl ApiTokenScope {
+    pub fn get_options() -> Vec<(String, String)> {
+        [ApiTokenScope::Read, ApiTokenScope::Upload]
+            .into_iter()
+            .map(|scope| (scope.get_option(), scope.get_verbose_name().to_string()))
+            .collect()
+    }
+
+    pub fn get_option(self) -> String {
+        match self {
+            ApiTokenScope::Read => "read",
+            ApiTokenScope::Upload => "upload",
+        }
+        .to_string()
+    }
+
+    /// Message key of API token scope name.
+    pub fn get_verbose_name(self) -> &'static str {
+        match self {
+            ApiTokenScope::Read => "api_token_scope.read",
+            ApiTokenScope::Upload => "api_token_scope.upload",
+        }
+    }
+
+    /// Whether requests which change data (not `GET` or `HEAD`) are allowed with this scope.
+    pub fn allows_write(self) -> bool {
+        matches!(self, ApiTokenScope::Upload)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiToken {
+    pub id: i64,
+    pub label: String,
+    pub scope: ApiTokenScope,
+    pub creation_date: OffsetDateTime,
+    pub last_used_date: Option<OffsetDateTime>,
+}
+
+/// Create API token for user, returns plaintext token, only its hash is stored.
+pub async fn add_api_token(
+    username: &str,
+    label: &str,
+    scope: ApiTokenScope,
+    pool: &Pool<Postgres>,
+) -> Result<String, crate::error::Error> {
+    let token = generate_token();
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    api_tokens (token_hash, username, label, scope)
+VALUES
+    ($1, $2, $3, $4)
+        "#,
+        hash_token(&token),
+        username,
+        label,
+        scope as _
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+pub async fn try_get_user_by_api_token(
+    token: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<(User, ApiTokenScope)>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+UPDATE
+    api_tokens
+SET
+    last_used_date = NOW()
+FROM
+    users
+WHERE
+    api_tokens.token_hash = $1
+    AND api_tokens.username = users.username
+RETURNING
+    users.username, is_active, is_admin, is_uploader, birth_date, must_change_password,
+    created_at, last_login_at, display_name, scope AS "scope: ApiTokenScope"
+        "#,
+        hash_token(token)
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.map(|user_data| {
+        (
+            User {
+                username: user_data.username,
+                is_active: user_data.is_active,
+                is_admin: user_data.is_admin,
+                is_uploader: user_data.is_uploader,
+                birth_date: user_data.birth_date,
+                must_change_password: user_data.must_change_password,
+                created_at: user_data.created_at,
+                last_login_at: user_data.last_login_at,
+                display_name: user_data.display_name,
+            },
+            user_data.scope,
+        )
+    }))
+}
+
+pub async fn list_api_tokens_for_user(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<ApiToken>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    id, label, scope AS "scope: ApiTokenScope", creation_date, last_used_date
+FROM
+    api_tokens
+WHERE
+    username = $1
+ORDER BY
+    id DESC
+        "#,
+        username
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| ApiToken {
+        id: record.id,
+        label: record.label,
+        scope: record.scope,
+        creation_date: record.creation_date,
+        last_used_date: record.last_used_date,
+    })
+    .collect())
+}
+
+pub async fn try_revoke_api_token_check_owner(
+    id: i64,
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+DELETE FROM
+    api_tokens
+WHERE
+    id = $1
+    AND username = $2
+RETURNING
+    id
+        "#,
+        id,
+        username
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.map(|_| ()))
+}
+
+pub async fn try_add_invite_check_exists(
+    invite_code: &str,
+    created_by: Option<&str>,
+    expires_at: Option<OffsetDateTime>,
+    max_uses: i32,
+    grants_uploader: bool,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    invite_code
+FROM
+    invite_codes
+WHERE
+    invite_code = $1
+        "#,
+        invite_code
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    invite_codes (invite_code, created_by, created_at, expires_at, max_uses, grants_uploader)
+VALUES
+    ($1, $2, NOW(), $3, $4, $5)
+            "#,
+        invite_code,
+        created_by,
+        expires_at,
+        max_uses,
+        grants_uploader
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if let Some(created_by) = created_by {
+        add_audit_log_entry(
+            created_by,
+            AuditAction::InviteAdd,
+            "invite",
+            None,
+            &serde_json::json!({
+                "max_uses": max_uses,
+                "grants_uploader": grants_uploader,
+            }),
+            &mut transaction,
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+const RANDOM_INVITE_CODE_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const RANDOM_INVITE_CODE_LENGTH: usize = 24;
+
+fn generate_random_invite_code() -> String {
+    let mut rng = thread_rng();
+    (0..RANDOM_INVITE_CODE_LENGTH)
+        .map(|_| {
+            RANDOM_INVITE_CODE_ALPHABET[rng.gen_range(0..RANDOM_INVITE_CODE_ALPHABET.len())] as char
+        })
+        .collect()
+}
+
+/// Create `count` random base32 invite codes and return them in plaintext (this is the only
+/// place where they can be obtained).
+pub async fn add_random_invites(
+    count: u32,
+    created_by: Option<&str>,
+    expires_at: Option<OffsetDateTime>,
+    max_uses: i32,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<String>, crate::error::Error> {
+    let invite_codes: Vec<String> = (0..count).map(|_| generate_random_invite_code()).collect();
+
+    let mut transaction = pool.begin().await?;
+
+    for invite_code in invite_codes.iter() {
+        sqlx::query!(
+            r#"
+INSERT INTO
+    invite_codes (invite_code, created_by, created_at, expires_at, max_uses)
+VALUES
+    ($1, $2, NOW(), $3, $4)
+            "#,
+            invite_code,
+            created_by,
+            expires_at,
+            max_uses
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    if let Some(created_by) = created_by {
+        add_audit_log_entry(
+            created_by,
+            AuditAction::InviteAdd,
+            "invite",
+            None,
+            &serde_json::json!({
+                "count": count,
+                "max_uses": max_uses,
+            }),
+            &mut transaction,
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(invite_codes)
+}
+
+/// User data visible only to admins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserAdminDetails {
+    pub invited_via_code: Option<String>,
+    pub invited_by: Option<String>,
+    pub invited_users: Vec<String>,
+}
+
+pub async fn get_user_admin_details(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<UserAdminDetails>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        UserAdminDetails,
+        r#"
+SELECT
+    invited_via_code,
+    invited_by,
+    ARRAY(
+        SELECT
+            invited_users.username
+        FROM
+            users AS invited_users
+        WHERE
+            invited_users.invited_by = users.username
+        ORDER BY
+            invited_users.created_at ASC
+    ) AS "invited_users!"
+FROM
+    users
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn try_get_user_invite_allowance(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<i32>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    invite_allowance
+FROM
+    users
+WHERE
+    username = $1
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|row| row.invite_allowance))
+}
+
+pub async fn set_user_invite_allowance(
+    username: &str,
+    invite_allowance: i32,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    invite_allowance = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        invite_allowance
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Create single-use random invite code on behalf of user, spending one invite from their
+/// allowance. Returns `None` if allowance is exhausted.
+pub async fn try_add_user_invite_check_allowance(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<String>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let allowance_spent = sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    invite_allowance = invite_allowance - 1
+WHERE
+    username = $1
+    AND invite_allowance > 0
+RETURNING
+    username
+        "#,
+        username
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !allowance_spent {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    let invite_code = generate_random_invite_code();
+    sqlx::query!(
+        r#"
+INSERT INTO
+    invite_codes (invite_code, created_by, created_at, max_uses)
+VALUES
+    ($1, $2, NOW(), 1)
+        "#,
+        invite_code,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(invite_code))
+}
+
+/// Remove invite codes created by user that were never redeemed. Returns number of removed codes.
+pub async fn remove_unredeemed_invites_by_creator(
+    username: &str,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<u64, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let removed_count = sqlx::query!(
+        r#"
+DELETE FROM
+    invite_codes
+WHERE
+    created_by = $1
+    AND use_count = 0
+        "#,
+        username
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    if removed_count > 0 {
+        add_audit_log_entry(
+            actor_username,
+            AuditAction::InviteRemove,
+            "user",
+            Some(username),
+            &serde_json::json!({
+                "count": removed_count,
+            }),
+            &mut transaction,
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(removed_count)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invite {
+    pub invite_code: String,
+    pub created_by: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub expires_at: Option<OffsetDateTime>,
+    pub max_uses: i32,
+    pub use_count: i32,
+    pub grants_uploader: bool,
+    pub redeemed_by: Vec<String>,
+}
+
+impl Invite {
+    pub fn masked_code(&self) -> String {
+        let visible: String = self.invite_code.chars().take(2).collect();
+        visible + "••••••"
+    }
+
+    pub fn remaining_uses(&self) -> i32 {
+        (self.max_uses - self.use_count).max(0)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc())
+    }
+}
+
+pub async fn try_get_invite(
+    invite_code: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<Invite>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        Invite,
+        r#"
+SELECT
+    invite_codes.invite_code, created_by, created_at, expires_at, max_uses, use_count,
+    grants_uploader,
+    COALESCE(
+        ARRAY_AGG(invite_redemptions.username ORDER BY invite_redemptions.redeemed_at)
+            FILTER (WHERE invite_redemptions.username IS NOT NULL),
+        ARRAY[]::VARCHAR[]
+    ) AS "redeemed_by!"
+FROM
+    invite_codes
+    LEFT JOIN invite_redemptions
+        ON invite_codes.invite_code = invite_redemptions.invite_code
+WHERE
+    invite_codes.invite_code = $1
+GROUP BY
+    invite_codes.invite_code
+        "#,
+        invite_code
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn list_invites_with_pagination(
+    pool: &Pool<Postgres>,
+    page_params: PageParams,
+) -> Result<Page<Invite>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(invite_code)
+FROM
+    invite_codes
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let items = sqlx::query_as!(
+        Invite,
+        r#"
+SELECT
+    invite_codes.invite_code, created_by, created_at, expires_at, max_uses, use_count,
+    grants_uploader,
+    COALESCE(
+        ARRAY_AGG(invite_redemptions.username ORDER BY invite_redemptions.redeemed_at)
+            FILTER (WHERE invite_redemptions.username IS NOT NULL),
+        ARRAY[]::VARCHAR[]
+    ) AS "redeemed_by!"
+FROM
+    invite_codes
+    LEFT JOIN invite_redemptions
+        ON invite_codes.invite_code = invite_redemptions.invite_code
+GROUP BY
+    invite_codes.invite_code
+ORDER BY
+    created_at DESC, invite_codes.invite_code
+LIMIT $2
+OFFSET $1
+        "#,
+        offset,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn try_remove_invite_check_exists(
+    invite_code: &str,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    invite_code
+FROM
+    invite_codes
+WHERE
+    invite_code = $1
+        "#,
+        invite_code
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    invite_codes
+WHERE
+    invite_code = $1
+            "#,
+        invite_code
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::InviteRemove,
+        "invite",
+        None,
+        &serde_json::json!({}),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanReason {
+    pub id: String,
+    pub description: Option<String>,
+    pub default_text: Option<String>,
+}
+
+impl BanReason {
+    pub fn edit_url(&self) -> Origin {
+        uri!(crate::app::views::ban_reason_edit_get(&self.id))
+    }
+
+    pub fn remove_url(&self) -> Origin {
+        uri!(crate::app::views::ban_reason_remove_get(&self.id))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanReasonIdSet {
+    pub ids_set: HashSet<String>,
+    pub option_list: Vec<(String, String)>,
+}
+
+impl IdSet for BanReasonIdSet {
+    fn get_option_list(&self) -> Vec<(String, String)> {
+        self.option_list.clone()
+    }
+
+    fn is_valid_id(&self, id: &str) -> bool {
+        self.ids_set.contains(id)
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for BanReasonIdSet {
+    type Error = crate::error::Error;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let result: &request::Outcome<Self, Self::Error> = req
+            .local_cache_async(async {
+                let pool_state_result: request::Outcome<&State<Pool<Postgres>>, ()> =
+                    req.guard().await;
+                match pool_state_result {
+                    request::Outcome::Success(pool_state) => {
+                        match list_ban_reasons(pool_state).await {
+                            Ok(ban_reasons) => request::Outcome::Success(BanReasonIdSet {
+                                ids_set: HashSet::from_iter(
+                                    ban_reasons.iter().map(|ban_reason| &ban_reason.id).cloned(),
+                                ),
+                                option_list: ban_reasons
+                                    .iter()
+                                    .map(|ban_reason| {
+                                        (
+                                            ban_reason.id.clone(),
+                                            if let Some(description) = &ban_reason.description {
+                                                format!("{}: {}", ban_reason.id, description)
+                                            } else {
+                                                ban_reason.id.clone()
+                                            },
+                                        )
+                                    })
+                                    .collect(),
+                            }),
+                            Err(err) => request::Outcome::Error((Status::InternalServerError, err)),
+                        }
+                    }
+                    request::Outcome::Error((status, ())) => {
+                        request::Outcome::Error((status, crate::error::Error::PoolNotFound))
+                    }
+                    request::Outcome::Forward(status) => request::Outcome::Forward(status),
+                }
+            })
+            .await;
+
+        result.clone()
+    }
+}
+
+pub async fn list_ban_reasons(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<BanReason>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    id, description, default_text
+FROM
+    ban_reasons
+ORDER BY
+    id
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|record| BanReason {
+        id: record.id.clone(),
+        description: record.description.clone(),
+        default_text: record.default_text.clone(),
+    })
+    .collect();
+
+    Ok(result)
+}
+
+pub async fn try_get_ban_reason(
+    id: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<BanReason>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    description, default_text
+FROM
+    ban_reasons
+WHERE
+    id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| BanReason {
+        id: id.to_owned(),
+        description: record.description,
+        default_text: record.default_text,
+    });
+
+    Ok(result)
+}
+
+pub async fn try_add_ban_reason_check_exists(
+    ban_reason: BanReason,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    ban_reasons
+WHERE
+    id = $1
+        "#,
+        ban_reason.id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    ban_reasons (id, description, default_text)
+VALUES
+    ($1, $2, $3)
+            "#,
+        ban_reason.id,
+        ban_reason.description,
+        ban_reason.default_text
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn try_edit_ban_reason_check_exists(
+    ban_reason: BanReason,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    ban_reasons
+WHERE
+    id = $1
+        "#,
+        ban_reason.id
+    )
+    .fetch_optional(pool)
+    .await?
+    .is_some();
+
+    if !already_exists {
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    ban_reasons
+SET
+    description = $2,
+    default_text = $3
+WHERE
+    id = $1
+            "#,
+        ban_reason.id,
+        ban_reason.description,
+        ban_reason.default_text
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(Some(()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BanReasonRemoveResult {
+    Removed,
+    ReferencedByPosts(i64),
+}
+
+/// Remove ban reason. If posts reference it, either refuse (returning their count) or, when
+/// `detach_posts` is set, clear `ban_reason_id` on these posts keeping their ban text.
+pub async fn try_remove_ban_reason_check_exists(
+    id: &str,
+    detach_posts: bool,
+    pool: &Pool<Postgres>,
+) -> Result<Option<BanReasonRemoveResult>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    ban_reasons
+WHERE
+    id = $1
+FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    if detach_posts {
+        sqlx::query!(
+            r#"
+UPDATE
+    posts
+SET
+    ban_reason_id = NULL, updated_at = CURRENT_TIMESTAMP
+WHERE
+    ban_reason_id = $1
+            "#,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+    } else {
+        let post_count = sqlx::query!(
+            r#"
+SELECT
+    COUNT(id) AS "post_count!"
+FROM
+    posts
+WHERE
+    ban_reason_id = $1
+            "#,
+            id
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .post_count;
+
+        if post_count > 0 {
+            transaction.commit().await?;
+
+            return Ok(Some(BanReasonRemoveResult::ReferencedByPosts(post_count)));
+        }
+    }
+
+    sqlx::query!(
+        r#"
+DELETE FROM
+    ban_reasons
+WHERE
+    id = $1
+        "#,
+        id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(BanReasonRemoveResult::Removed))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentRating {
+    pub id: String,
+    pub label: String,
+    pub min_age: i32,
+    pub description: Option<String>,
+}
+
+impl ContentRating {
+    pub fn edit_url(&self) -> Origin {
+        uri!(crate::app::views::content_rating_edit_get(&self.id))
+    }
+
+    pub fn get_option_name(&self) -> String {
+        format!("{} ({}+)", self.label, self.min_age)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RatingIdSet {
+    pub ids_set: HashSet<String>,
+    pub option_list: Vec<(String, String)>,
+}
+
+impl RatingIdSet {
+    pub fn new(content_ratings: &[ContentRating]) -> Self {
+        RatingIdSet {
+            ids_set: HashSet::from_iter(
+                content_ratings
+                    .iter()
+                    .map(|content_rating| &content_rating.id)
+                    .cloned(),
+            ),
+            option_list: content_ratings
+                .iter()
+                .map(|content_rating| (content_rating.id.clone(), content_rating.get_option_name()))
+                .collect(),
+        }
+    }
+}
+
+impl IdSet for RatingIdSet {
+    fn get_option_list(&self) -> Vec<(String, String)> {
+        self.option_list.clone()
+    }
+
+    fn is_valid_id(&self, id: &str) -> bool {
+        self.ids_set.contains(id)
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RatingIdSet {
+    type Error = crate::error::Error;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let result: &request::Outcome<Self, Self::Error> = req
+            .local_cache_async(async {
+                let pool_state_result: request::Outcome<&State<Pool<Postgres>>, ()> =
+                    req.guard().await;
+                match pool_state_result {
+                    request::Outcome::Success(pool_state) => {
+                        match list_content_ratings(pool_state).await {
+                            Ok(content_ratings) => {
+                                request::Outcome::Success(RatingIdSet::new(&content_ratings))
+                            }
+                            Err(err) => request::Outcome::Error((Status::InternalServerError, err)),
+                        }
+                    }
+                    request::Outcome::Error((status, ())) => {
+                        request::Outcome::Error((status, crate::error::Error::PoolNotFound))
+                    }
+                    request::Outcome::Forward(status) => request::Outcome::Forward(status),
+                }
+            })
+            .await;
+
+        result.clone()
+    }
+}
+
+pub async fn list_content_ratings(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<ContentRating>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    id, label, min_age, description
+FROM
+    content_ratings
+ORDER BY
+    min_age, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| ContentRating {
+        id: record.id,
+        label: record.label,
+        min_age: record.min_age,
+        description: record.description,
+    })
+    .collect();
+
+    Ok(result)
+}
+
+pub async fn try_get_content_rating(
+    id: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<ContentRating>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    label, min_age, description
+FROM
+    content_ratings
+WHERE
+    id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| ContentRating {
+        id: id.to_owned(),
+        label: record.label,
+        min_age: record.min_age,
+        description: record.description,
+    });
+
+    Ok(result)
+}
+
+pub async fn try_add_content_rating_check_exists(
+    content_rating: ContentRating,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    content_ratings
+WHERE
+    id = $1
+        "#,
+        content_rating.id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    content_ratings (id, label, min_age, description)
+VALUES
+    ($1, $2, $3, $4)
+            "#,
+        content_rating.id,
+        content_rating.label,
+        content_rating.min_age,
+        content_rating.description
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn try_edit_content_rating_check_exists(
+    content_rating: ContentRating,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let exists = sqlx::query!(
+        r#"
+UPDATE
+    content_ratings
+SET
+    label = $2,
+    min_age = $3,
+    description = $4
+WHERE
+    id = $1
+RETURNING
+    id
+            "#,
+        content_rating.id,
+        content_rating.label,
+        content_rating.min_age,
+        content_rating.description
+    )
+    .fetch_optional(pool)
+    .await?
+    .is_some();
+
+    if !exists {
+        return Ok(None);
+    }
+
+    Ok(Some(()))
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -728,221 +3531,2404 @@ pub struct NewPost<'a> {
     pub description: &'a str,
     pub is_hidden: bool,
     pub min_age: Option<i32>,
+    pub content_rating_id: Option<&'a str>,
+    pub is_pinned: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Post {
+    pub id: i64,
+    pub creation_date: OffsetDateTime,
+    pub title: String,
+    pub description: String,
+    pub author_username: String,
+    pub author_display_name: Option<String>,
+    pub is_hidden: bool,
+    pub is_quarantined: bool,
+    pub ban: Option<(Option<BanReason>, Option<String>, Option<OffsetDateTime>)>,
+    pub uploads: Vec<Upload>,
+    pub min_age: Option<i32>,
+    pub content_rating_id: Option<String>,
+    pub is_age_restricted: bool,
     pub is_pinned: bool,
+    pub updated_at: OffsetDateTime,
+    pub slug: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum PostVisibility {
+    Visible(Post),
+    Hidden,
+    Banned(Option<BanReason>, Option<String>, Option<OffsetDateTime>),
+    AgeRestricted(i32),
+    Quarantined,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostEdit<'r> {
+    pub id: i64,
+    pub title: Option<&'r str>,
+    pub description: Option<&'r str>,
+    pub is_hidden: Option<bool>,
+    pub min_age: Option<i32>,
+    pub content_rating_id: Option<&'r str>,
+    pub is_pinned: Option<bool>,
+}
+
+impl Post {
+    pub fn detail_url(&self) -> Origin {
+        match &self.slug {
+            Some(slug) => uri!(crate::app::views::post_by_slug_get(slug)),
+            None => uri!(crate::app::views::post_detail_get(self.id)),
+        }
+    }
+
+    pub fn author_detail_url(&self) -> Origin {
+        uri!(crate::app::views::user_detail_get(&self.author_username))
+    }
+
+    pub fn has_content_rating(&self, content_rating_id: &str) -> bool {
+        self.content_rating_id.as_deref() == Some(content_rating_id)
+    }
+
+    pub fn author_name(&self) -> &str {
+        self.author_display_name
+            .as_deref()
+            .unwrap_or(&self.author_username)
+    }
+
+    pub fn check_visible(self, user: &Authentication) -> PostVisibility {
+        if user.is_admin() {
+            PostVisibility::Visible(self)
+        } else {
+            match &self.ban {
+                Some((ban_reason, ban_reason_text, ban_expires_at)) => PostVisibility::Banned(
+                    ban_reason.clone(),
+                    ban_reason_text.clone(),
+                    *ban_expires_at,
+                ),
+                None => {
+                    if self.is_quarantined {
+                        PostVisibility::Quarantined
+                    } else if self.is_hidden {
+                        if Some(&self.author_username) == user.username().as_ref() {
+                            PostVisibility::Visible(self)
+                        } else {
+                            PostVisibility::Hidden
+                        }
+                    } else if self.is_age_restricted {
+                        if Some(&self.author_username) == user.username().as_ref() {
+                            PostVisibility::Visible(self)
+                        } else {
+                            PostVisibility::AgeRestricted(self.min_age.unwrap_or(0))
+                        }
+                    } else {
+                        PostVisibility::Visible(self)
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn can_edit(&self, user: &Authentication) -> bool {
+        match user {
+            Authentication::Authenticated(user_real)
+            | Authentication::Impersonated(user_real, _) => {
+                user_real.username == self.author_username
+            }
+            Authentication::Banned(_) => false,
+            Authentication::Anonymous => false,
+        }
+    }
+
+    pub fn can_edit_by_user(&self, user: &User) -> bool {
+        user.username == self.author_username
+    }
+
+    pub fn can_ban(&self, user: &Authentication) -> bool {
+        user.is_admin()
+    }
+
+    pub fn can_unban(&self, user: &Authentication) -> bool {
+        user.is_admin() && self.ban.is_some()
+    }
+
+    pub fn can_quarantine(&self, user: &Authentication) -> bool {
+        user.is_admin()
+    }
+
+    pub fn can_set_content_rating(&self, user: &Authentication) -> bool {
+        user.is_admin()
+    }
+
+    pub fn can_appeal_ban(&self, user: &Authentication) -> bool {
+        self.ban.is_some() && self.can_edit(user)
+    }
+}
+
+pub async fn list_posts_with_pagination(
+    pool: &Pool<Postgres>,
+    page_params: PageParams,
+    user: &Authentication,
+) -> Result<Page<Post>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id), MAX(id)
+FROM
+    posts
+WHERE
+    NOT is_quarantined
+    OR $1
+    OR author_username = $2
+        "#,
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_one(pool)
+    .await?;
+    let max_id = count_query_result.max.unwrap_or(0) as u64;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = max_id.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let group_by = sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.creation_date, title, posts.description AS post_description, author_username,
+    is_hidden, (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS "is_banned!",
+    ban_reason_id, ban_reason_text, ban_expires_at, ban_reasons.description AS ban_reason_description,
+    ban_reasons.default_text AS ban_reason_default_text,
+    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
+    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
+    COALESCE(content_ratings.min_age, posts.min_age) AS min_age, content_rating_id,
+    is_age_restricted($3, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age)) AS is_age_restricted,
+    is_pinned, is_quarantined, users.display_name AS author_display_name, posts.updated_at, posts.slug
+FROM
+    posts
+    INNER JOIN users
+        ON posts.author_username = users.username
+    LEFT JOIN ban_reasons
+        ON posts.ban_reason_id = ban_reasons.id
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+    LEFT JOIN uploads
+        ON posts.id = uploads.post_id
+        AND file_status = 'PUBLISHED'
+WHERE
+    posts.id > $2
+    AND posts.id <= ($1 + $2)
+    AND (NOT is_quarantined OR $4 OR author_username = $5)
+ORDER BY
+    posts.id, uploads.id
+        "#,
+        limit,
+        offset,
+        user.birth_date(),
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (
+        (
+            record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
+            record.is_banned, record.ban_reason_id, (record.ban_reason_description, record.ban_reason_default_text), record.ban_reason_text, record.min_age,
+            (record.is_age_restricted, record.is_pinned, record.author_display_name, record.ban_expires_at, record.is_quarantined, record.content_rating_id, record.updated_at, record.slug)
+        ),
+        match record.upload_id {
+            Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
+            None => None
+        }
+    ))
+    .into_group_linked_map();
+
+    let items: Vec<Post> = group_by
+        .into_iter()
+        .map(
+            |(
+                (
+                    post_id,
+                    creation_date,
+                    title,
+                    post_description,
+                    author_username,
+                    is_hidden,
+                    is_banned,
+                    ban_reason_id,
+                    (ban_reason_description, ban_reason_default_text),
+                    ban_reason_text,
+                    min_age,
+                    (
+                        is_age_restricted,
+                        is_pinned,
+                        author_display_name,
+                        ban_expires_at,
+                        is_quarantined,
+                        content_rating_id,
+                        updated_at,
+                        slug,
+                    ),
+                ),
+                upload_records,
+            )| Post {
+                id: post_id,
+                creation_date,
+                title,
+                description: post_description,
+                author_username,
+                author_display_name,
+                is_hidden,
+                is_quarantined,
+                ban: if is_banned {
+                    Some((
+                        ban_reason_id.map(|ban_reason_id| BanReason {
+                            id: ban_reason_id,
+                            description: ban_reason_description,
+                            default_text: ban_reason_default_text,
+                        }),
+                        ban_reason_text,
+                        ban_expires_at,
+                    ))
+                } else {
+                    None
+                },
+                uploads: upload_records
+                    .into_iter()
+                    .flatten()
+                    .map(
+                        |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
+                            id: upload_id,
+                            extension,
+                            size: size.unwrap(),
+                            creation_date: upload_creation_date.unwrap(),
+                            file_status: file_status.unwrap(),
+                        },
+                    )
+                    .collect(),
+                min_age,
+                content_rating_id,
+                is_age_restricted: is_age_restricted.unwrap(),
+                is_pinned,
+                updated_at,
+                slug,
+            },
+        )
+        .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn search_posts_with_pagination(
+    pool: &Pool<Postgres>,
+    query: Option<&str>,
+    page_params: PageParams,
+    user: &Authentication,
+) -> Result<Page<Post>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id)
+FROM
+    posts, to_tsquery($1) query
+WHERE
+    query @@ document_tsvector
+    AND (NOT is_quarantined OR $2 OR author_username = $3)
+        "#,
+        query,
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let group_by = sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.creation_date, title,
+    posts.description AS post_description, author_username,
+    is_hidden, (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS "is_banned!",
+    ban_reason_id, ban_reason_text, ban_expires_at, ban_reasons.description AS ban_reason_description,
+    ban_reasons.default_text AS ban_reason_default_text,
+    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
+    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
+    COALESCE(content_ratings.min_age, posts.min_age) AS min_age, content_rating_id,
+    is_age_restricted($3, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age)) AS is_age_restricted,
+    is_pinned, is_quarantined, users.display_name AS author_display_name, posts.updated_at, posts.slug
+FROM
+    (
+        SELECT
+            id, creation_date, title, description, author_username,
+            is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_expires_at, min_age, content_rating_id, is_quarantined,
+            ts_rank(document_tsvector, query) AS rank,
+            is_pinned, updated_at, slug
+        FROM
+            posts, to_tsquery($4) query
+        WHERE
+            query @@ document_tsvector
+            AND (NOT is_quarantined OR $5 OR author_username = $6)
+        ORDER BY
+            rank DESC, id ASC
+        LIMIT
+            $1
+        OFFSET
+            $2
+    ) posts
+    INNER JOIN users
+        ON posts.author_username = users.username
+    LEFT JOIN ban_reasons
+        ON posts.ban_reason_id = ban_reasons.id
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+    LEFT JOIN uploads
+        ON posts.id = uploads.post_id
+        AND file_status = 'PUBLISHED'
+ORDER BY
+    rank DESC, posts.id ASC, uploads.id ASC
+        "#,
+        limit,
+        offset,
+        user.birth_date(),
+        query,
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record|
+        (
+            (
+                record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
+                record.is_banned, record.ban_reason_id, (record.ban_reason_description, record.ban_reason_default_text), record.ban_reason_text, record.min_age,
+                (record.is_age_restricted, record.is_pinned, record.author_display_name, record.ban_expires_at, record.is_quarantined, record.content_rating_id, record.updated_at, record.slug)
+            ),
+            match record.upload_id {
+                Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
+                None => None
+            }
+        )
+    )
+    .into_group_linked_map();
+
+    let items: Vec<Post> = group_by
+        .into_iter()
+        .map(
+            |(
+                (
+                    post_id,
+                    creation_date,
+                    title,
+                    post_description,
+                    author_username,
+                    is_hidden,
+                    is_banned,
+                    ban_reason_id,
+                    (ban_reason_description, ban_reason_default_text),
+                    ban_reason_text,
+                    min_age,
+                    (
+                        is_age_restricted,
+                        is_pinned,
+                        author_display_name,
+                        ban_expires_at,
+                        is_quarantined,
+                        content_rating_id,
+                        updated_at,
+                        slug,
+                    ),
+                ),
+                upload_records,
+            )| Post {
+                id: post_id,
+                creation_date,
+                title,
+                description: post_description,
+                author_username,
+                author_display_name,
+                is_hidden,
+                is_quarantined,
+                ban: if is_banned {
+                    Some((
+                        ban_reason_id.map(|ban_reason_id| BanReason {
+                            id: ban_reason_id,
+                            description: ban_reason_description,
+                            default_text: ban_reason_default_text,
+                        }),
+                        ban_reason_text,
+                        ban_expires_at,
+                    ))
+                } else {
+                    None
+                },
+                uploads: upload_records
+                    .into_iter()
+                    .flatten()
+                    .map(
+                        |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
+                            id: upload_id,
+                            extension,
+                            size: size.unwrap(),
+                            creation_date: upload_creation_date.unwrap(),
+                            file_status: file_status.unwrap(),
+                        },
+                    )
+                    .collect(),
+                min_age,
+                content_rating_id,
+                is_age_restricted: is_age_restricted.unwrap(),
+                is_pinned,
+                updated_at,
+                slug,
+            },
+        )
+        .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn try_get_post(
+    id: i64,
+    pool: &Pool<Postgres>,
+    user: &Authentication,
+) -> Result<Option<Post>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.creation_date, title, posts.description AS post_description, author_username,
+    is_hidden, (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS "is_banned!",
+    ban_reason_id, ban_reason_text, ban_expires_at, ban_reasons.description AS ban_reason_description,
+    ban_reasons.default_text AS ban_reason_default_text,
+    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
+    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
+    COALESCE(content_ratings.min_age, posts.min_age) AS min_age, content_rating_id,
+    is_age_restricted($2, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age)) AS is_age_restricted,
+    is_pinned, is_quarantined, users.display_name AS author_display_name, posts.updated_at, posts.slug
+FROM
+    posts
+    INNER JOIN users
+        ON posts.author_username = users.username
+    LEFT JOIN ban_reasons
+        ON posts.ban_reason_id = ban_reasons.id
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+    LEFT JOIN uploads
+    ON posts.id = uploads.post_id
+    AND file_status = 'PUBLISHED'
+WHERE
+    posts.id = $1
+            "#,
+            id,
+            user.birth_date()
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (
+        (
+            record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
+            record.is_banned, record.ban_reason_id, (record.ban_reason_description, record.ban_reason_default_text), record.ban_reason_text,
+            record.min_age, (record.is_age_restricted, record.is_pinned, record.author_display_name, record.ban_expires_at, record.is_quarantined, record.content_rating_id, record.updated_at, record.slug)
+        ),
+        match record.upload_id {
+            Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
+            None => None
+        }
+    ))
+    .into_group_linked_map();
+
+    let mut result_iter = result.into_iter();
+
+    Ok(result_iter.next().map(
+        |(
+            (
+                post_id,
+                creation_date,
+                title,
+                post_description,
+                author_username,
+                is_hidden,
+                is_banned,
+                ban_reason_id,
+                (ban_reason_description, ban_reason_default_text),
+                ban_reason_text,
+                min_age,
+                (
+                    is_age_restricted,
+                    is_pinned,
+                    author_display_name,
+                    ban_expires_at,
+                    is_quarantined,
+                    content_rating_id,
+                    updated_at,
+                    slug,
+                ),
+            ),
+            upload_records,
+        )| Post {
+            id: post_id,
+            creation_date,
+            title,
+            description: post_description,
+            author_username,
+            author_display_name,
+            is_hidden,
+            is_quarantined,
+            ban: if is_banned {
+                Some((
+                    ban_reason_id.map(|ban_reason_id| BanReason {
+                        id: ban_reason_id,
+                        description: ban_reason_description,
+                        default_text: ban_reason_default_text,
+                    }),
+                    ban_reason_text,
+                    ban_expires_at,
+                ))
+            } else {
+                None
+            },
+            uploads: upload_records
+                .into_iter()
+                .flatten()
+                .map(
+                    |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
+                        id: upload_id,
+                        extension,
+                        size: size.unwrap(),
+                        creation_date: upload_creation_date.unwrap(),
+                        file_status: file_status.unwrap(),
+                    },
+                )
+                .collect(),
+            min_age,
+            content_rating_id,
+            is_age_restricted: is_age_restricted.unwrap(),
+            is_pinned,
+            updated_at,
+            slug,
+        },
+    ))
+}
+
+/// Get post if it is visible to anonymous users.
+pub async fn try_get_public_post(
+    id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Option<Post>, crate::error::Error> {
+    Ok(
+        match try_get_post(id, pool, &Authentication::Anonymous)
+            .await?
+            .map(|post| post.check_visible(&Authentication::Anonymous))
+        {
+            Some(PostVisibility::Visible(post)) => Some(post),
+            _ => None,
+        },
+    )
+}
+
+pub async fn add_post(
+    post: NewPost<'_>,
+    user: User,
+    pool: &Pool<Postgres>,
+) -> Result<Post, crate::error::Error> {
+    let mut connection = pool.acquire().await?;
+    add_post_with_connection(post, user, &mut connection).await
+}
+
+/// Same as [`add_post`], but can be used inside transaction.
+pub async fn add_post_with_connection(
+    post: NewPost<'_>,
+    user: User,
+    connection: &mut PgConnection,
+) -> Result<Post, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+INSERT INTO
+    posts (title, description, is_hidden, is_banned, author_username, min_age, document_tsvector, is_pinned, content_rating_id)
+VALUES
+    ($1, $2, $3, $4, $5, $6, TO_TSVECTOR($1 || ' ' || COALESCE($2, '')), $7, $8)
+RETURNING id, creation_date, updated_at
+            "#,
+        post.title,
+        post.description,
+        post.is_hidden,
+        false,
+        user.username,
+        post.min_age,
+        post.is_pinned,
+        post.content_rating_id,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+
+    let slug = update_post_slug(result.id, post.title, None, connection).await?;
+
+    Ok(Post {
+        id: result.id,
+        creation_date: result.creation_date,
+        title: post.title.to_string(),
+        description: post.description.to_string(),
+        author_username: user.username,
+        author_display_name: user.display_name,
+        is_hidden: post.is_hidden,
+        is_quarantined: false,
+        ban: None,
+        uploads: vec![],
+        min_age: post.min_age,
+        content_rating_id: post.content_rating_id.map(str::to_string),
+        is_age_restricted: false,
+        is_pinned: post.is_pinned,
+        updated_at: result.updated_at,
+        slug,
+    })
+}
+
+/// Slugs which can not be used, because they are paths of other pages under `/posts/`.
+const RESERVED_POST_SLUGS: [&str; 4] = ["add", "by-id", "pinned", "search"];
+
+/// Assign slug generated from title to post, unless its current slug is already generated from
+/// the same title. Old slugs of post are kept, so that they can be redirected. Slug of post is not
+/// changed if title has no characters usable in slug.
+async fn update_post_slug(
+    id: i64,
+    title: &str,
+    current_slug: Option<&str>,
+    connection: &mut PgConnection,
+) -> Result<Option<String>, crate::error::Error> {
+    let base_slug = slugify(title);
+    if base_slug.is_empty() {
+        return Ok(current_slug.map(str::to_string));
+    }
+    if current_slug.is_some_and(|current_slug| is_slug_variant(current_slug, &base_slug)) {
+        return Ok(current_slug.map(str::to_string));
+    }
+
+    loop {
+        let taken_slugs: HashSet<String> = sqlx::query!(
+            r#"
+SELECT
+    slug
+FROM
+    post_slugs
+WHERE
+    (slug = $1 OR slug LIKE $1 || '-%')
+    AND post_id <> $2
+            "#,
+            base_slug,
+            id
+        )
+        .fetch_all(&mut *connection)
+        .await?
+        .into_iter()
+        .map(|record| record.slug)
+        .collect();
+        let slug = make_unique_slug(&base_slug, |slug| {
+            taken_slugs.contains(slug) || RESERVED_POST_SLUGS.contains(&slug)
+        });
+
+        // Slug may be taken by concurrent request, then next one is tried.
+        let owner_id = sqlx::query!(
+            r#"
+WITH inserted AS (
+    INSERT INTO
+        post_slugs (slug, post_id)
+    VALUES
+        ($1, $2)
+    ON CONFLICT (slug) DO NOTHING
+    RETURNING post_id
+)
+SELECT post_id AS "post_id!" FROM inserted
+UNION ALL
+SELECT post_id FROM post_slugs WHERE slug = $1
+            "#,
+            slug,
+            id
+        )
+        .fetch_one(&mut *connection)
+        .await?
+        .post_id;
+        if owner_id != id {
+            continue;
+        }
+
+        sqlx::query!(
+            r#"
+UPDATE
+    posts
+SET
+    slug = $2
+WHERE
+    id = $1
+            "#,
+            id,
+            slug
+        )
+        .execute(&mut *connection)
+        .await?;
+
+        return Ok(Some(slug));
+    }
+}
+
+/// Find post by current or old slug. Returns post ID and current slug of post.
+pub async fn try_get_post_id_by_slug(
+    slug: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<(i64, Option<String>)>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.slug
+FROM
+    post_slugs
+    INNER JOIN posts
+        ON posts.id = post_slugs.post_id
+WHERE
+    post_slugs.slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| (record.id, record.slug)))
+}
+
+/// Edit post, returns `true` if hidden post was published.
+pub async fn try_edit_post_check_exists_and_permission<'r>(
+    post: PostEdit<'r>,
+    user: &User,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let record = sqlx::query!(
+        r#"
+SELECT
+    id, author_username, title, description, is_hidden, is_pinned, slug
+FROM
+    posts
+WHERE
+    id = $1
+        "#,
+        post.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let record = record.ok_or(crate::error::Error::DoesNotExist)?;
+
+    if record.author_username != user.username {
+        return Err(crate::error::Error::AccessDenied);
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    title = $2, description = $3, is_hidden = $4, min_age = $5, is_pinned = $6, content_rating_id = $7,
+    document_tsvector = TO_TSVECTOR($2 || ' ' || COALESCE($3, '')), updated_at = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+            "#,
+        post.id,
+        post.title.unwrap_or(&record.title),
+        post.description.unwrap_or(&record.description),
+        post.is_hidden.unwrap_or(record.is_hidden),
+        post.min_age,
+        post.is_pinned.unwrap_or(record.is_pinned),
+        post.content_rating_id,
+    )
+    .execute(pool)
+    .await?;
+
+    if let Some(title) = post.title {
+        let mut connection = pool.acquire().await?;
+        update_post_slug(post.id, title, record.slug.as_deref(), &mut connection).await?;
+    }
+
+    Ok(record.is_hidden && post.is_hidden == Some(false))
+}
+
+pub async fn try_ban_post_check_exists(
+    post_id: i64,
+    ban_reason_id: Option<String>,
+    ban_reason_text: Option<String>,
+    ban_expires_at: Option<OffsetDateTime>,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let post_already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    posts
+WHERE
+    id = $1
+        "#,
+        post_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !post_already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_banned = TRUE, ban_reason_id = $2, ban_reason_text = $3, ban_expires_at = $4,
+    updated_at = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+        "#,
+        post_id,
+        ban_reason_id,
+        ban_reason_text,
+        ban_expires_at
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostBan,
+        "post",
+        Some(&post_id.to_string()),
+        &serde_json::json!({
+            "ban_reason_id": ban_reason_id,
+            "ban_reason_text": ban_reason_text,
+            "ban_expires_at": ban_expires_at.map(|ban_expires_at| ban_expires_at.to_string()),
+        }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+/// Ban all not yet banned posts of user, return number of affected posts.
+pub async fn ban_all_posts_by_author(
+    username: &str,
+    ban_reason_id: Option<String>,
+    ban_reason_text: Option<String>,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<u64, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+SELECT
+    username
+FROM
+    users
+WHERE
+    username = $1
+FOR UPDATE
+        "#,
+        username
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let affected_count = sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_banned = TRUE, ban_reason_id = $2, ban_reason_text = $3, ban_expires_at = NULL,
+    updated_at = CURRENT_TIMESTAMP
+WHERE
+    author_username = $1
+    AND NOT (
+        is_banned
+        AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)
+    )
+        "#,
+        username,
+        ban_reason_id,
+        ban_reason_text
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostBan,
+        "user",
+        Some(username),
+        &serde_json::json!({
+            "ban_reason_id": ban_reason_id,
+            "ban_reason_text": ban_reason_text,
+            "post_count": affected_count,
+        }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(affected_count)
+}
+
+pub async fn try_unban_post_check_exists(
+    post_id: i64,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let post_already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    posts
+WHERE
+    id = $1
+        "#,
+        post_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !post_already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_banned = FALSE, ban_reason_id = NULL, ban_reason_text = NULL, ban_expires_at = NULL,
+    updated_at = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+        "#,
+        post_id,
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostUnban,
+        "post",
+        Some(&post_id.to_string()),
+        &serde_json::json!({}),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn try_set_post_quarantined_check_exists(
+    post_id: i64,
+    is_quarantined: bool,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let post_exists = sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_quarantined = $2, updated_at = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+RETURNING
+    id
+        "#,
+        post_id,
+        is_quarantined
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !post_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostQuarantine,
+        "post",
+        Some(&post_id.to_string()),
+        &serde_json::json!({ "is_quarantined": is_quarantined }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn try_set_post_content_rating_check_exists(
+    post_id: i64,
+    content_rating_id: Option<&str>,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let post_exists = sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    content_rating_id = $2, updated_at = CURRENT_TIMESTAMP
+WHERE
+    id = $1
+RETURNING
+    id
+        "#,
+        post_id,
+        content_rating_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if !post_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostContentRating,
+        "post",
+        Some(&post_id.to_string()),
+        &serde_json::json!({ "content_rating_id": content_rating_id }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+/// Physically clear expired post bans (they are already ignored when posts are loaded). Returns
+/// number of affected posts.
+pub async fn clear_expired_post_bans(pool: &Pool<Postgres>) -> Result<u64, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_banned = FALSE, ban_reason_id = NULL, ban_reason_text = NULL, ban_expires_at = NULL,
+    updated_at = CURRENT_TIMESTAMP
+WHERE
+    is_banned
+    AND ban_expires_at <= CURRENT_TIMESTAMP
+        "#
+    )
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "ban_appeal_status")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum BanAppealStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl BanAppealStatus {
+    /// Message key of ban appeal status name.
+    pub fn get_verbose_name(&self) -> &'static str {
+        match self {
+            Self::Pending => "ban_appeal_status.pending",
+            Self::Approved => "ban_appeal_status.approved",
+            Self::Rejected => "ban_appeal_status.rejected",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BanAppeal {
+    pub id: i64,
+    pub post_id: i64,
+    pub author_username: String,
+    pub message: String,
+    pub status: BanAppealStatus,
+    pub creation_date: OffsetDateTime,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<OffsetDateTime>,
+}
+
+impl BanAppeal {
+    pub fn post_detail_url(&self) -> Origin {
+        uri!(crate::app::views::post_detail_get(self.post_id))
+    }
+}
+
+/// Add appeal for banned post. Returns `None` if post already has appeal that is not decided yet.
+pub async fn try_add_ban_appeal_check_pending(
+    post_id: i64,
+    author_username: &str,
+    message: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    posts
+WHERE
+    id = $1
+FOR UPDATE
+        "#,
+        post_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let already_exists = sqlx::query!(
+        r#"
+SELECT
+    id
+FROM
+    ban_appeals
+WHERE
+    post_id = $1
+    AND status = 'PENDING'
+        "#,
+        post_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    if already_exists {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+INSERT INTO
+    ban_appeals (post_id, author_username, message)
+VALUES
+    ($1, $2, $3)
+        "#,
+        post_id,
+        author_username,
+        message
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(()))
+}
+
+pub async fn list_pending_ban_appeals(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<BanAppeal>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        BanAppeal,
+        r#"
+SELECT
+    id, post_id, author_username, message, status AS "status: BanAppealStatus", creation_date,
+    decided_by, decided_at
+FROM
+    ban_appeals
+WHERE
+    status = 'PENDING'
+ORDER BY
+    creation_date, id
+        "#
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn list_ban_appeals_by_author(
+    author_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<BanAppeal>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        BanAppeal,
+        r#"
+SELECT
+    id, post_id, author_username, message, status AS "status: BanAppealStatus", creation_date,
+    decided_by, decided_at
+FROM
+    ban_appeals
+WHERE
+    author_username = $1
+ORDER BY
+    creation_date DESC, id DESC
+        "#,
+        author_username
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn try_get_last_ban_appeal(
+    post_id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Option<BanAppeal>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        BanAppeal,
+        r#"
+SELECT
+    id, post_id, author_username, message, status AS "status: BanAppealStatus", creation_date,
+    decided_by, decided_at
+FROM
+    ban_appeals
+WHERE
+    post_id = $1
+ORDER BY
+    creation_date DESC, id DESC
+LIMIT 1
+        "#,
+        post_id
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Set status of pending appeal. Returns ID of appealed post or `None` if there is no pending
+/// appeal with such ID.
+pub async fn try_decide_ban_appeal_check_pending(
+    id: i64,
+    status: BanAppealStatus,
+    decided_by: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<i64>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+UPDATE
+    ban_appeals
+SET
+    status = $2, decided_by = $3, decided_at = NOW()
+WHERE
+    id = $1
+    AND status = 'PENDING'
+RETURNING
+    post_id
+        "#,
+        id,
+        status as _,
+        decided_by
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| record.post_id))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "upload_status")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum UploadStatus {
+    Initialized,
+    Allocated,
+    Writing,
+    Publishing,
+    Published,
+    Hiding,
+    Hidden,
+    Missing,
+}
+
+impl UploadStatus {
+    /// Message key of upload status name.
+    pub fn get_verbose_name(&self) -> &'static str {
+        match self {
+            UploadStatus::Initialized => "upload_status.initialized",
+            UploadStatus::Allocated => "upload_status.allocated",
+            UploadStatus::Writing => "upload_status.writing",
+            UploadStatus::Publishing => "upload_status.publishing",
+            UploadStatus::Published => "upload_status.published",
+            UploadStatus::Hiding => "upload_status.hiding",
+            UploadStatus::Hidden => "upload_status.hidden",
+            UploadStatus::Missing => "upload_status.missing",
+        }
+    }
+
+    pub fn can_transition_to(&self, new_status: &UploadStatus) -> bool {
+        match new_status {
+            UploadStatus::Initialized => false,
+            UploadStatus::Allocated => {
+                self == &UploadStatus::Initialized || self == &UploadStatus::Writing
+            }
+            UploadStatus::Writing => self == &UploadStatus::Allocated,
+            UploadStatus::Publishing => {
+                self == &UploadStatus::Allocated || self == &UploadStatus::Hidden
+            }
+            UploadStatus::Published => self == &UploadStatus::Publishing,
+            UploadStatus::Hiding => self == &UploadStatus::Published,
+            UploadStatus::Hidden => self == &UploadStatus::Hiding,
+            UploadStatus::Missing => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewUpload<'a> {
+    pub extension: Option<&'a str>,
+    pub size: i64,
+    pub post_id: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Upload {
+    pub id: i64,
+    pub extension: Option<String>,
+    pub size: i64,
+    pub creation_date: OffsetDateTime,
+    pub file_status: UploadStatus,
+}
+
+impl Upload {
+    pub fn file_url(&self, storage: &UploadStorage) -> String {
+        get_file_url(self.id, self.extension.as_deref(), storage)
+    }
+
+    /// URL of image shown in post grid. Thumbnails are not generated yet, so it is URL of file
+    /// itself.
+    pub fn thumbnail_url(&self, storage: &UploadStorage) -> String {
+        self.file_url(storage)
+    }
+
+    pub fn media_kind(&self) -> UploadMediaKind {
+        match self.extension.as_deref().map(str::to_lowercase).as_deref() {
+            Some(extension) if IMAGE_EXTENSIONS.contains(&extension) => UploadMediaKind::Image,
+            Some("mp4" | "webm" | "ogv" | "mov") => UploadMediaKind::Video,
+            Some("mp3" | "ogg" | "oga" | "opus" | "wav" | "flac" | "m4a") => UploadMediaKind::Audio,
+            Some(extension) if TEXT_EXTENSIONS.contains(&extension) => UploadMediaKind::Text,
+            _ => UploadMediaKind::Other,
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.media_kind() == UploadMediaKind::Image
+    }
+
+    pub fn is_video(&self) -> bool {
+        self.media_kind() == UploadMediaKind::Video
+    }
+
+    pub fn is_audio(&self) -> bool {
+        self.media_kind() == UploadMediaKind::Audio
+    }
+
+    pub fn is_text(&self) -> bool {
+        self.media_kind() == UploadMediaKind::Text
+    }
+}
+
+/// Extensions of uploads which are shown as images, in lowercase.
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Extensions of uploads which are previewed as plain text, in lowercase.
+pub const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "csv", "log", "json"];
+
+/// Kind of uploaded file, it is determined by extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadMediaKind {
+    Image,
+    Video,
+    Audio,
+    Text,
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadFull {
+    pub id: i64,
+    pub extension: Option<String>,
+    pub size: i64,
+    pub creation_date: OffsetDateTime,
+    pub file_status: UploadStatus,
+    pub post_id: Option<i64>,
+    pub owner_username: String,
+}
+
+pub async fn get_upload(id: i64, pool: &Pool<Postgres>) -> Result<UploadFull, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    file_status AS "file_status: UploadStatus", extension, uploads.creation_date, size, post_id,
+    COALESCE(posts.author_username, uploads.avatar_username) AS "owner_username!"
+FROM
+    uploads
+    LEFT JOIN posts
+        ON posts.id = uploads.post_id
+WHERE
+    uploads.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match result {
+        None => Err(crate::error::Error::DoesNotExist),
+        Some(record) => Ok(UploadFull {
+            id,
+            extension: record.extension,
+            size: record.size,
+            creation_date: record.creation_date,
+            file_status: record.file_status,
+            post_id: record.post_id,
+            owner_username: record.owner_username,
+        }),
+    }
+}
+
+/// Check if upload belongs to post with age restriction, files of such posts are served only by
+/// signed URLs.
+pub async fn is_upload_age_restricted(
+    id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<bool, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+SELECT
+    posts.min_age IS NOT NULL AS "is_age_restricted!"
+FROM
+    uploads
+    INNER JOIN posts
+        ON posts.id = uploads.post_id
+WHERE
+    uploads.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.is_some_and(|record| record.is_age_restricted))
+}
+
+/// Get first published image upload of each post, keyed by post ID. Posts without images are
+/// absent from result.
+pub async fn list_post_cover_uploads(
+    post_ids: &[i64],
+    pool: &Pool<Postgres>,
+) -> Result<HashMap<i64, Upload>, crate::error::Error> {
+    if post_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let image_extensions: Vec<String> = IMAGE_EXTENSIONS
+        .iter()
+        .map(|extension| extension.to_string())
+        .collect();
+
+    Ok(sqlx::query!(
+        r#"
+SELECT DISTINCT ON (post_id)
+    post_id AS "post_id!", id, extension, creation_date, size,
+    file_status AS "file_status: UploadStatus"
+FROM
+    uploads
+WHERE
+    post_id = ANY($1)
+    AND file_status = 'PUBLISHED'
+    AND LOWER(extension) = ANY($2)
+ORDER BY
+    post_id, id
+        "#,
+        post_ids,
+        &image_extensions
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| {
+        (
+            record.post_id,
+            Upload {
+                id: record.id,
+                extension: record.extension,
+                size: record.size,
+                creation_date: record.creation_date,
+                file_status: record.file_status,
+            },
+        )
+    })
+    .collect())
+}
+
+pub async fn add_upload(
+    upload: NewUpload<'_>,
+    user: User,
+    pool: &Pool<Postgres>,
+) -> Result<Upload, crate::error::Error> {
+    let record = sqlx::query!(
+        r#"
+SELECT
+    id, author_username
+FROM
+    posts
+WHERE
+    id = $1
+        "#,
+        upload.post_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let author_username = match record {
+        Some(record_real) => Ok(record_real.author_username),
+        None => Err(crate::error::Error::DoesNotExist),
+    }?;
+
+    if author_username != user.username {
+        return Err(crate::error::Error::AccessDenied);
+    }
+
+    let result = sqlx::query!(
+        r#"
+INSERT INTO
+    uploads (extension, size, file_status, post_id)
+VALUES
+    ($1, $2, $3, $4)
+RETURNING id, creation_date
+            "#,
+        upload.extension,
+        upload.size,
+        UploadStatus::Initialized as _,
+        upload.post_id,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Upload {
+        id: result.id,
+        extension: upload.extension.map(|x| x.to_string()),
+        size: upload.size,
+        creation_date: result.creation_date,
+        file_status: UploadStatus::Initialized,
+    })
+}
+
+pub async fn add_avatar_upload(
+    extension: Option<&str>,
+    size: i64,
+    user: &User,
+    pool: &Pool<Postgres>,
+) -> Result<Upload, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+INSERT INTO
+    uploads (extension, size, file_status, avatar_username)
+VALUES
+    ($1, $2, $3, $4)
+RETURNING id, creation_date
+            "#,
+        extension,
+        size,
+        UploadStatus::Initialized as _,
+        user.username,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Upload {
+        id: result.id,
+        extension: extension.map(|x| x.to_string()),
+        size,
+        creation_date: result.creation_date,
+        file_status: UploadStatus::Initialized,
+    })
+}
+
+pub async fn set_user_avatar(
+    username: &str,
+    upload_id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Option<i64>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let old_avatar_upload_id = sqlx::query!(
+        r#"
+SELECT
+    avatar_upload_id
+FROM
+    users
+WHERE
+    username = $1
+FOR UPDATE
+        "#,
+        username
+    )
+    .fetch_one(&mut *transaction)
+    .await?
+    .avatar_upload_id;
+
+    sqlx::query!(
+        r#"
+UPDATE
+    users
+SET
+    avatar_upload_id = $2
+WHERE
+    username = $1
+        "#,
+        username,
+        upload_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(old_avatar_upload_id.filter(|old_id| *old_id != upload_id))
+}
+
+pub async fn set_upload_sha256(
+    id: i64,
+    sha256: &str,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    uploads
+SET
+    sha256 = $2
+WHERE
+    id = $1
+        "#,
+        id,
+        sha256
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get size of contiguous part of upload file written from its start.
+pub async fn get_upload_written_size(
+    id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<i64, crate::error::Error> {
+    let record = sqlx::query!(
+        r#"
+SELECT
+    written_size
+FROM
+    uploads
+WHERE
+    id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match record {
+        None => Err(crate::error::Error::DoesNotExist),
+        Some(record) => Ok(record.written_size),
+    }
+}
+
+/// Record that range from `start_pos` (inclusive) to `end_pos` (exclusive) of upload file is
+/// written. Written size is only extended if range adjoins already written part, because later
+/// parts may be written before earlier ones. Returns new written size.
+pub async fn extend_upload_written_size(
+    id: i64,
+    start_pos: i64,
+    end_pos: i64,
+    pool: &Pool<Postgres>,
+) -> Result<i64, crate::error::Error> {
+    let record = sqlx::query!(
+        r#"
+UPDATE
+    uploads
+SET
+    written_size = CASE
+        WHEN written_size >= $2 THEN GREATEST(written_size, $3)
+        ELSE written_size
+    END
+WHERE
+    id = $1
+RETURNING written_size
+        "#,
+        id,
+        start_pos,
+        end_pos
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match record {
+        None => Err(crate::error::Error::DoesNotExist),
+        Some(record) => Ok(record.written_size),
+    }
+}
+
+/// List IDs of posts that are neither hidden nor banned and have upload with the same hash as
+/// given upload. Returns `None` if upload does not exist or its hash is unknown.
+pub async fn list_post_ids_by_upload_hash(
+    upload_id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Option<Vec<i64>>, crate::error::Error> {
+    let sha256 = sqlx::query!(
+        r#"
+SELECT
+    sha256
+FROM
+    uploads
+WHERE
+    id = $1
+        "#,
+        upload_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .and_then(|record| record.sha256);
+
+    let sha256 = match sha256 {
+        Some(sha256) => sha256,
+        None => return Ok(None),
+    };
+
+    Ok(Some(
+        sqlx::query!(
+            r#"
+SELECT DISTINCT
+    posts.id
+FROM
+    posts
+    INNER JOIN uploads
+        ON posts.id = uploads.post_id
+WHERE
+    uploads.sha256 = $1
+    AND NOT posts.is_hidden
+    AND NOT (
+        posts.is_banned
+        AND (posts.ban_expires_at IS NULL OR posts.ban_expires_at > CURRENT_TIMESTAMP)
+    )
+ORDER BY
+    posts.id
+            "#,
+            sha256
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|record| record.id)
+        .collect(),
+    ))
+}
+
+/// Ban all posts that are neither hidden nor banned and have upload with the same hash as given
+/// upload. Returns IDs of banned posts or `None` if upload does not exist or its hash is unknown.
+pub async fn ban_posts_by_upload_hash(
+    upload_id: i64,
+    ban_reason_id: Option<String>,
+    ban_reason_text: Option<String>,
+    actor_username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<Vec<i64>>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let sha256 = sqlx::query!(
+        r#"
+SELECT
+    sha256
+FROM
+    uploads
+WHERE
+    id = $1
+        "#,
+        upload_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .and_then(|record| record.sha256);
+
+    let sha256 = match sha256 {
+        Some(sha256) => sha256,
+        None => {
+            transaction.commit().await?;
+
+            return Ok(None);
+        }
+    };
+
+    let mut post_ids: Vec<i64> = sqlx::query!(
+        r#"
+UPDATE
+    posts
+SET
+    is_banned = TRUE, ban_reason_id = $2, ban_reason_text = $3, ban_expires_at = NULL,
+    updated_at = CURRENT_TIMESTAMP
+WHERE
+    id IN (
+        SELECT
+            post_id
+        FROM
+            uploads
+        WHERE
+            sha256 = $1
+    )
+    AND NOT is_hidden
+    AND NOT (
+        is_banned
+        AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)
+    )
+RETURNING
+    id
+        "#,
+        sha256,
+        ban_reason_id,
+        ban_reason_text
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|record| record.id)
+    .collect();
+    post_ids.sort_unstable();
+
+    add_audit_log_entry(
+        actor_username,
+        AuditAction::PostBan,
+        "upload",
+        Some(&upload_id.to_string()),
+        &serde_json::json!({
+            "ban_reason_id": ban_reason_id,
+            "ban_reason_text": ban_reason_text,
+            "sha256": sha256,
+            "post_ids": post_ids,
+        }),
+        &mut transaction,
+    )
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(Some(post_ids))
+}
+
+pub async fn try_set_upload_status(
+    id: i64,
+    new_status: UploadStatus,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let file_status = sqlx::query!(
+        r#"
+SELECT
+    file_status AS "file_status: UploadStatus"
+FROM
+    uploads
+WHERE
+    id = $1
+        "#,
+        id
+    )
+    .fetch_one(&mut *transaction)
+    .await?
+    .file_status;
+
+    debug!(
+        "Trying to transition upload {} from {:?} to {:?}",
+        id, file_status, new_status
+    );
+    if !file_status.can_transition_to(&new_status) {
+        transaction.commit().await?;
+
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+UPDATE
+    uploads
+SET
+    file_status = $2
+WHERE
+    id = $1
+            "#,
+        id,
+        new_status.clone() as _
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    METRICS.record_upload_status_transition(&file_status, &new_status);
+
+    Ok(Some(()))
+}
+
+/// Change upload status if transition is possible. If `moderator_username` is set (moderator hides
+/// upload of other user), the change is recorded in audit log.
+pub async fn try_set_upload_status_check_exists(
+    id: i64,
+    new_status: UploadStatus,
+    moderator_username: Option<&str>,
+    pool: &Pool<Postgres>,
+) -> Result<Option<UploadFull>, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let result = sqlx::query!(
+        r#"
+SELECT
+    file_status AS "file_status: UploadStatus", extension, uploads.creation_date, size, post_id,
+    COALESCE(posts.author_username, uploads.avatar_username) AS "owner_username!"
+FROM
+    uploads
+    LEFT JOIN posts
+        ON posts.id = uploads.post_id
+WHERE
+    uploads.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    match result {
+        None => Err(crate::error::Error::DoesNotExist),
+        Some(record) => {
+            if !record.file_status.can_transition_to(&new_status) {
+                Ok(None)
+            } else {
+                sqlx::query!(
+                    r#"
+UPDATE
+    uploads
+SET
+    file_status = $2
+WHERE
+    id = $1
+            "#,
+                    id,
+                    new_status.clone() as _
+                )
+                .execute(&mut *transaction)
+                .await?;
+
+                if let Some(moderator_username) = moderator_username {
+                    add_audit_log_entry(
+                        moderator_username,
+                        AuditAction::UploadHide,
+                        "upload",
+                        Some(&id.to_string()),
+                        &serde_json::json!({
+                            "owner_username": record.owner_username,
+                            "status": format!("{:?}", new_status),
+                        }),
+                        &mut transaction,
+                    )
+                    .await?;
+                }
+
+                transaction.commit().await?;
+                METRICS.record_upload_status_transition(&record.file_status, &new_status);
+
+                Ok(Some(UploadFull {
+                    id,
+                    extension: record.extension,
+                    size: record.size,
+                    creation_date: record.creation_date,
+                    file_status: record.file_status,
+                    post_id: record.post_id,
+                    owner_username: record.owner_username,
+                }))
+            }
+        }
+    }
+}
+
+pub async fn list_old_in_progress_uploads_and_set_hiding(
+    pool: &Pool<Postgres>,
+    page_params: PageParams,
+    max_age: Duration,
+) -> Result<Page<Upload>, crate::error::Error> {
+    let max_age: PgInterval = max_age.try_into()?;
+
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id)
+FROM
+    uploads
+WHERE
+    file_status NOT IN ('PUBLISHED', 'HIDDEN', 'MISSING')
+    AND (
+        AGE(CURRENT_TIMESTAMP, creation_date) > $1
+        OR file_status = 'HIDING'
+    )
+        "#,
+        max_age
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let items = sqlx::query!(
+        r#"
+UPDATE
+    uploads
+SET
+    file_status = 'HIDING'
+WHERE
+    id IN (
+        SELECT
+            id 
+        FROM
+            uploads
+        WHERE
+            file_status NOT IN ('PUBLISHED', 'HIDDEN', 'MISSING')
+            AND (
+                AGE(CURRENT_TIMESTAMP, creation_date) > $3
+                OR file_status = 'HIDING'
+            )
+        ORDER BY
+            id
+        LIMIT
+            $1
+        OFFSET
+            $2
+    )
+RETURNING
+    id, extension, creation_date, size, file_status AS "file_status: UploadStatus"
+        "#,
+        limit,
+        offset,
+        max_age
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| Upload {
+        id: record.id,
+        extension: record.extension,
+        size: record.size,
+        creation_date: record.creation_date,
+        file_status: record.file_status,
+    })
+    .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn set_uploads_hidden(
+    pool: &Pool<Postgres>,
+    ids: Vec<i64>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+UPDATE
+    uploads
+SET
+    file_status = 'HIDDEN'
+WHERE
+    id = ANY($1::BIGINT[])
+        "#,
+        ids.as_slice()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Check that database is reachable.
+pub async fn check_database(pool: &Pool<Postgres>) -> Result<(), crate::error::Error> {
+    sqlx::query!("SELECT 1 AS one").fetch_one(pool).await?;
+
+    Ok(())
+}
+
+pub async fn add_cleanup_run(
+    hidden_upload_count: u64,
+    cleared_ban_count: u64,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    sqlx::query!(
+        r#"
+INSERT INTO
+    cleanup_runs (hidden_upload_count, cleared_ban_count)
+VALUES
+    ($1, $2)
+        "#,
+        hidden_upload_count as i64,
+        cleared_ban_count as i64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_last_cleanup_run_date(
+    pool: &Pool<Postgres>,
+) -> Result<Option<OffsetDateTime>, crate::error::Error> {
+    Ok(sqlx::query!(
+        r#"
+SELECT
+    MAX(finished_at) AS last_finished_at
+FROM
+    cleanup_runs
+        "#
+    )
+    .fetch_one(pool)
+    .await?
+    .last_finished_at)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Post {
+pub struct DashboardPost {
     pub id: i64,
-    pub creation_date: OffsetDateTime,
     pub title: String,
-    pub description: String,
     pub author_username: String,
-    pub is_hidden: bool,
-    pub ban: Option<(Option<BanReason>, Option<String>)>,
-    pub uploads: Vec<Upload>,
-    pub min_age: Option<i32>,
-    pub is_age_restricted: bool,
-    pub is_pinned: bool,
+    pub creation_date: OffsetDateTime,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum PostVisibility {
-    Visible(Post),
-    Hidden,
-    Banned(Option<BanReason>, Option<String>),
-    AgeRestricted(i32),
+impl DashboardPost {
+    pub fn detail_url(&self) -> Origin {
+        uri!(crate::app::views::post_detail_get(self.id))
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct PostEdit<'r> {
-    pub id: i64,
-    pub title: Option<&'r str>,
-    pub description: Option<&'r str>,
-    pub is_hidden: Option<bool>,
-    pub min_age: Option<i32>,
-    pub is_pinned: Option<bool>,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DashboardUser {
+    pub username: String,
+    pub created_at: OffsetDateTime,
 }
 
-impl Post {
+impl DashboardUser {
     pub fn detail_url(&self) -> Origin {
-        uri!(crate::app::views::post_detail_get(self.id))
+        uri!(crate::app::views::user_detail_get(&self.username))
     }
+}
 
-    pub fn author_detail_url(&self) -> Origin {
-        uri!(crate::app::views::user_detail_get(&self.author_username))
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UploadStatusStats {
+    pub file_status: UploadStatus,
+    pub count: i64,
+    pub total_size: i64,
+}
+
+/// Numbers of users, posts and uploads by their state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StorageStats {
+    pub banned_user_count: i64,
+    pub regular_user_count: i64,
+    pub uploader_count: i64,
+    pub admin_count: i64,
+    pub visible_post_count: i64,
+    pub hidden_post_count: i64,
+    pub quarantined_post_count: i64,
+    pub banned_post_count: i64,
+    pub uploads: Vec<UploadStatusStats>,
+}
+
+impl StorageStats {
+    pub fn user_count(&self) -> i64 {
+        self.banned_user_count + self.regular_user_count + self.uploader_count + self.admin_count
     }
 
-    pub fn check_visible(self, user: &Authentication) -> PostVisibility {
-        if user.is_admin() {
-            PostVisibility::Visible(self)
-        } else {
-            match &self.ban {
-                Some((ban_reason, ban_reason_text)) => {
-                    PostVisibility::Banned(ban_reason.clone(), ban_reason_text.clone())
-                }
-                None => {
-                    if self.is_hidden {
-                        if Some(&self.author_username) == user.username().as_ref() {
-                            PostVisibility::Visible(self)
-                        } else {
-                            PostVisibility::Hidden
-                        }
-                    } else if self.is_age_restricted {
-                        if Some(&self.author_username) == user.username().as_ref() {
-                            PostVisibility::Visible(self)
-                        } else {
-                            PostVisibility::AgeRestricted(self.min_age.unwrap_or(0))
-                        }
-                    } else {
-                        PostVisibility::Visible(self)
-                    }
-                }
-            }
-        }
+    pub fn post_count(&self) -> i64 {
+        self.visible_post_count
+            + self.hidden_post_count
+            + self.quarantined_post_count
+            + self.banned_post_count
     }
 
-    pub fn can_edit(&self, user: &Authentication) -> bool {
-        match user {
-            Authentication::Authenticated(user_real) => user_real.username == self.author_username,
-            Authentication::Banned(_) => false,
-            Authentication::Anonymous => false,
-        }
+    pub fn upload_count(&self) -> i64 {
+        self.uploads.iter().map(|item| item.count).sum()
     }
 
-    pub fn can_edit_by_user(&self, user: &User) -> bool {
-        user.username == self.author_username
+    pub fn total_upload_size(&self) -> i64 {
+        self.uploads.iter().map(|item| item.total_size).sum()
     }
+}
+
+/// Get storage statistics. Post is counted by most severe state: banned, then quarantined, then
+/// hidden.
+pub async fn get_storage_stats(pool: &Pool<Postgres>) -> Result<StorageStats, crate::error::Error> {
+    let user_counts = sqlx::query!(
+        r#"
+SELECT
+    COUNT(*) FILTER (WHERE NOT is_active) AS "banned_user_count!",
+    COUNT(*) FILTER (WHERE is_active AND NOT is_admin AND NOT is_uploader) AS "regular_user_count!",
+    COUNT(*) FILTER (WHERE is_active AND NOT is_admin AND is_uploader) AS "uploader_count!",
+    COUNT(*) FILTER (WHERE is_active AND is_admin) AS "admin_count!"
+FROM
+    users
+WHERE
+    username <> $1
+        "#,
+        DELETED_USERNAME
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let post_counts = sqlx::query!(
+        r#"
+WITH post_states AS (
+    SELECT
+        (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS is_banned,
+        is_quarantined, is_hidden
+    FROM
+        posts
+)
+SELECT
+    COUNT(*) FILTER (WHERE NOT is_banned AND NOT is_quarantined AND NOT is_hidden) AS "visible_post_count!",
+    COUNT(*) FILTER (WHERE NOT is_banned AND NOT is_quarantined AND is_hidden) AS "hidden_post_count!",
+    COUNT(*) FILTER (WHERE NOT is_banned AND is_quarantined) AS "quarantined_post_count!",
+    COUNT(*) FILTER (WHERE is_banned) AS "banned_post_count!"
+FROM
+    post_states
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
 
-    pub fn can_ban(&self, user: &Authentication) -> bool {
-        user.is_admin()
-    }
+    let uploads = sqlx::query_as!(
+        UploadStatusStats,
+        r#"
+SELECT
+    file_status AS "file_status: UploadStatus", COUNT(*) AS "count!",
+    COALESCE(SUM(size), 0)::BIGINT AS "total_size!"
+FROM
+    uploads
+GROUP BY
+    file_status
+ORDER BY
+    file_status
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
 
-    pub fn can_unban(&self, user: &Authentication) -> bool {
-        user.is_admin() && self.ban.is_some()
-    }
+    Ok(StorageStats {
+        banned_user_count: user_counts.banned_user_count,
+        regular_user_count: user_counts.regular_user_count,
+        uploader_count: user_counts.uploader_count,
+        admin_count: user_counts.admin_count,
+        visible_post_count: post_counts.visible_post_count,
+        hidden_post_count: post_counts.hidden_post_count,
+        quarantined_post_count: post_counts.quarantined_post_count,
+        banned_post_count: post_counts.banned_post_count,
+        uploads,
+    })
 }
 
-pub async fn list_posts_with_pagination(
+pub async fn list_published_uploads_with_pagination(
     pool: &Pool<Postgres>,
     page_params: PageParams,
-    user: &Authentication,
-) -> Result<Page<Post>, crate::error::Error> {
+) -> Result<Page<Upload>, crate::error::Error> {
     let count_query_result = sqlx::query!(
         r#"
 SELECT
-    COUNT(id), MAX(id)
+    COUNT(id)
 FROM
-    posts
+    uploads
+WHERE
+    file_status = 'PUBLISHED'
         "#
     )
     .fetch_one(pool)
     .await?;
-    let max_id = count_query_result.max.unwrap_or(0) as u64;
+
     let total_item_count = count_query_result.count.unwrap_or(0) as u64;
 
-    let page_count = max_id.div_ceil(page_params.page_size);
+    let page_count = total_item_count.div_ceil(page_params.page_size);
 
     let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
 
-    let group_by = sqlx::query!(
+    let items = sqlx::query_as!(
+        Upload,
         r#"
 SELECT
-    posts.id, posts.creation_date, title, posts.description AS post_description, author_username,
-    is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_reasons.description AS ban_reason_description,
-    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
-    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
-    min_age, is_age_restricted($3, CURRENT_TIMESTAMP, min_age) AS is_age_restricted,
-    is_pinned
+    id, extension, creation_date, size, file_status AS "file_status: UploadStatus"
 FROM
-    posts
-    LEFT JOIN ban_reasons
-        ON posts.ban_reason_id = ban_reasons.id
-    LEFT JOIN uploads
-        ON posts.id = uploads.post_id
-        AND file_status = 'PUBLISHED'
+    uploads
 WHERE
-    posts.id > $2
-    AND posts.id <= ($1 + $2)
+    file_status = 'PUBLISHED'
 ORDER BY
-    posts.id, uploads.id
+    id
+LIMIT
+    $1
+OFFSET
+    $2
         "#,
         limit,
-        offset,
-        user.birth_date()
+        offset
     )
     .fetch_all(pool)
-    .await?
-    .into_iter()
-    .map(|record| (
-        (
-            record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
-            record.is_banned, record.ban_reason_id, record.ban_reason_description, record.ban_reason_text, record.min_age,
-            (record.is_age_restricted, record.is_pinned)
-        ),
-        match record.upload_id {
-            Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
-            None => None
-        }
-    ))
-    .into_group_linked_map();
-
-    let items: Vec<Post> = group_by
-        .into_iter()
-        .map(
-            |(
-                (
-                    post_id,
-                    creation_date,
-                    title,
-                    post_description,
-                    author_username,
-                    is_hidden,
-                    is_banned,
-                    ban_reason_id,
-                    ban_reason_description,
-                    ban_reason_text,
-                    min_age,
-                    (is_age_restricted, is_pinned),
-                ),
-                upload_records,
-            )| Post {
-                id: post_id,
-                creation_date,
-                title,
-                description: post_description,
-                author_username,
-                is_hidden,
-                ban: if is_banned {
-                    Some((
-                        ban_reason_id.map(|ban_reason_id| BanReason {
-                            id: ban_reason_id,
-                            description: ban_reason_description,
-                        }),
-                        ban_reason_text,
-                    ))
-                } else {
-                    None
-                },
-                uploads: upload_records
-                    .into_iter()
-                    .flatten()
-                    .map(
-                        |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
-                            id: upload_id,
-                            extension,
-                            size: size.unwrap(),
-                            creation_date: upload_creation_date.unwrap(),
-                            file_status: file_status.unwrap(),
-                        },
-                    )
-                    .collect(),
-                min_age,
-                is_age_restricted: is_age_restricted.unwrap(),
-                is_pinned,
-            },
-        )
-        .collect();
+    .await?;
 
     Ok(Page {
         items,
@@ -953,22 +5939,164 @@ ORDER BY
     })
 }
 
-pub async fn search_posts_with_pagination(
+/// Aggregate numbers and recent activity for moderation dashboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdminDashboardStats {
+    pub post_count: i64,
+    pub user_count: i64,
+    pub pending_upload_count: i64,
+    pub banned_post_count: i64,
+    pub pending_ban_appeal_count: i64,
+    pub pending_uploader_request_count: i64,
+    pub failed_webhook_delivery_count: i64,
+    pub storage_usage_bytes: i64,
+    pub storage_stats: StorageStats,
+    pub last_cleanup_at: Option<OffsetDateTime>,
+    pub recent_posts: Vec<DashboardPost>,
+    pub recent_users: Vec<DashboardUser>,
+}
+
+const ADMIN_DASHBOARD_RECENT_COUNT: i64 = 10;
+
+pub async fn get_admin_dashboard_stats(
+    pool: &Pool<Postgres>,
+) -> Result<AdminDashboardStats, crate::error::Error> {
+    let counts = sqlx::query!(
+        r#"
+SELECT
+    (SELECT COUNT(*) FROM posts) AS "post_count!",
+    (SELECT COUNT(*) FROM users WHERE username <> $1) AS "user_count!",
+    (
+        SELECT
+            COUNT(*)
+        FROM
+            uploads
+        WHERE
+            file_status IN ('INITIALIZED', 'ALLOCATED', 'WRITING', 'PUBLISHING')
+    ) AS "pending_upload_count!",
+    (
+        SELECT
+            COUNT(*)
+        FROM
+            posts
+        WHERE
+            is_banned
+            AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)
+    ) AS "banned_post_count!",
+    (SELECT COUNT(*) FROM ban_appeals WHERE status = 'PENDING') AS "pending_ban_appeal_count!",
+    (
+        SELECT
+            COUNT(*)
+        FROM
+            uploader_requests
+        WHERE
+            status = 'PENDING'
+    ) AS "pending_uploader_request_count!",
+    (
+        SELECT
+            COUNT(*)
+        FROM
+            webhook_deliveries
+        WHERE
+            status = 'FAILED'
+    ) AS "failed_webhook_delivery_count!",
+    (
+        SELECT
+            COALESCE(SUM(size), 0)::BIGINT
+        FROM
+            uploads
+        WHERE
+            file_status NOT IN ('HIDDEN', 'MISSING')
+    ) AS "storage_usage_bytes!"
+        "#,
+        DELETED_USERNAME
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let recent_posts = sqlx::query_as!(
+        DashboardPost,
+        r#"
+SELECT
+    id, title, author_username, creation_date
+FROM
+    posts
+ORDER BY
+    id DESC
+LIMIT
+    $1
+        "#,
+        ADMIN_DASHBOARD_RECENT_COUNT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let recent_users = sqlx::query_as!(
+        DashboardUser,
+        r#"
+SELECT
+    username, created_at
+FROM
+    users
+WHERE
+    username <> $1
+ORDER BY
+    created_at DESC, username
+LIMIT
+    $2
+        "#,
+        DELETED_USERNAME,
+        ADMIN_DASHBOARD_RECENT_COUNT
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(AdminDashboardStats {
+        post_count: counts.post_count,
+        user_count: counts.user_count,
+        pending_upload_count: counts.pending_upload_count,
+        banned_post_count: counts.banned_post_count,
+        pending_ban_appeal_count: counts.pending_ban_appeal_count,
+        pending_uploader_request_count: counts.pending_uploader_request_count,
+        failed_webhook_delivery_count: counts.failed_webhook_delivery_count,
+        storage_usage_bytes: counts.storage_usage_bytes,
+        storage_stats: get_storage_stats(pool).await?,
+        last_cleanup_at: get_last_cleanup_run_date(pool).await?,
+        recent_posts,
+        recent_users,
+    })
+}
+
+pub async fn list_users_with_pagination(
     pool: &Pool<Postgres>,
-    query: Option<&str>,
     page_params: PageParams,
-    user: &Authentication,
-) -> Result<Page<Post>, crate::error::Error> {
+    sort: UserSort,
+    status: Option<UserStatus>,
+) -> Result<Page<User>, crate::error::Error> {
+    let status = status.map(UserStatus::get_option);
+
     let count_query_result = sqlx::query!(
         r#"
 SELECT
-    COUNT(id)
+    COUNT(username)
 FROM
-    posts, to_tsquery($1) query
+    users
 WHERE
-    query @@ document_tsvector
+    username <> $2
+    AND (
+        $1::TEXT IS NULL
+        OR (
+            CASE
+                WHEN NOT is_active THEN 'banned'
+                WHEN is_admin THEN 'admin'
+                WHEN is_uploader THEN 'uploader'
+                ELSE 'user'
+            END
+        ) = $1
+    )
         "#,
-        query
+        status,
+        DELETED_USERNAME
     )
     .fetch_one(pool)
     .await?;
@@ -978,63 +6106,160 @@ WHERE
 
     let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
 
-    let group_by = sqlx::query!(
+    let items = sqlx::query!(
         r#"
 SELECT
-    posts.id, posts.creation_date, title,
-    posts.description AS post_description, author_username,
-    is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_reasons.description AS ban_reason_description,
-    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
-    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
-    min_age, is_age_restricted($3, CURRENT_TIMESTAMP, min_age) AS is_age_restricted,
-    is_pinned
+    username, is_active, is_admin, is_uploader, password_hash, birth_date, must_change_password,
+    created_at, last_login_at, display_name
 FROM
-    (
-        SELECT
-            id, creation_date, title, description, author_username,
-            is_hidden, is_banned, ban_reason_id, ban_reason_text, min_age,
-            ts_rank(document_tsvector, query) AS rank,
-            is_pinned
-        FROM
-            posts, to_tsquery($4) query
-        WHERE
-            query @@ document_tsvector
-        ORDER BY
-            rank DESC, id ASC
-        LIMIT
-            $1
-        OFFSET
-            $2
-    ) posts
-    LEFT JOIN ban_reasons
-        ON posts.ban_reason_id = ban_reasons.id
-    LEFT JOIN uploads
-        ON posts.id = uploads.post_id
-        AND file_status = 'PUBLISHED'
+    users
+WHERE
+    username <> $5
+    AND (
+        $3::TEXT IS NULL
+        OR (
+            CASE
+                WHEN NOT is_active THEN 'banned'
+                WHEN is_admin THEN 'admin'
+                WHEN is_uploader THEN 'uploader'
+                ELSE 'user'
+            END
+        ) = $3
+    )
+ORDER BY
+    CASE
+        WHEN $4 = 'status' THEN (
+            CASE
+                WHEN NOT is_active THEN 0
+                WHEN is_admin THEN 3
+                WHEN is_uploader THEN 2
+                ELSE 1
+            END
+        )
+    END,
+    CASE
+        WHEN $4 = 'created_at' THEN created_at
+    END DESC,
+    CASE
+        WHEN $4 = 'last_login' THEN last_login_at
+    END DESC NULLS LAST,
+    username
+LIMIT $2
+OFFSET $1
+    "#,
+        offset,
+        limit,
+        status,
+        sort.get_option(),
+        DELETED_USERNAME
+    )
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|user_data| User {
+        username: user_data.username.clone(),
+        is_active: user_data.is_active,
+        is_admin: user_data.is_admin,
+        is_uploader: user_data.is_uploader,
+        birth_date: user_data.birth_date,
+        must_change_password: user_data.must_change_password,
+        created_at: user_data.created_at,
+        last_login_at: user_data.last_login_at,
+        display_name: user_data.display_name.clone(),
+    })
+    .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn list_latest_pinned_posts(
+    pool: &Pool<Postgres>,
+    limit: u64,
+    user: &Authentication,
+) -> Result<(Vec<Post>, u64), crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(id)
+FROM
+    posts
+WHERE
+    is_pinned
+    AND (NOT is_quarantined OR $1 OR author_username = $2)
+        "#,
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let group_by = sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.creation_date, title,
+    posts.description AS post_description, author_username,
+    is_hidden, (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS "is_banned!",
+    ban_reason_id, ban_reason_text, ban_expires_at, ban_reasons.description AS ban_reason_description,
+    ban_reasons.default_text AS ban_reason_default_text,
+    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
+    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
+    COALESCE(content_ratings.min_age, posts.min_age) AS min_age, content_rating_id,
+    is_age_restricted($2, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age)) AS is_age_restricted,
+    is_pinned, is_quarantined, users.display_name AS author_display_name, posts.updated_at, posts.slug
+FROM
+(
+    SELECT
+        id, creation_date, title, description, author_username,
+        is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_expires_at, min_age, content_rating_id, is_quarantined,
+        is_pinned, updated_at, slug
+    FROM
+        posts
+    WHERE
+        is_pinned
+        AND (NOT is_quarantined OR $3 OR author_username = $4)
+    ORDER BY
+        id DESC
+    LIMIT
+        $1
+) posts
+INNER JOIN users
+    ON posts.author_username = users.username
+LEFT JOIN ban_reasons
+    ON posts.ban_reason_id = ban_reasons.id
+LEFT JOIN content_ratings
+    ON posts.content_rating_id = content_ratings.id
+LEFT JOIN uploads
+    ON posts.id = uploads.post_id
+    AND file_status = 'PUBLISHED'
 ORDER BY
-    rank DESC, posts.id ASC, uploads.id ASC
+    posts.id DESC, uploads.id ASC
         "#,
-        limit,
-        offset,
+        limit as i64,
         user.birth_date(),
-        query
+        user.is_admin(),
+        user.username()
     )
     .fetch_all(pool)
     .await?
     .into_iter()
-    .map(|record|
+    .map(|record| (
         (
-            (
-                record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
-                record.is_banned, record.ban_reason_id, record.ban_reason_description, record.ban_reason_text, record.min_age,
-                (record.is_age_restricted, record.is_pinned)
-            ),
-            match record.upload_id {
-                Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
-                None => None
-            }
-        )
-    )
+            record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
+            record.is_banned, record.ban_reason_id, (record.ban_reason_description, record.ban_reason_default_text), record.ban_reason_text, record.min_age,
+            (record.is_age_restricted, record.is_pinned, record.author_display_name, record.ban_expires_at, record.is_quarantined, record.content_rating_id, record.updated_at, record.slug)
+        ),
+        match record.upload_id {
+            Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
+            None => None
+        }
+    ))
     .into_group_linked_map();
 
     let items: Vec<Post> = group_by
@@ -1050,10 +6275,19 @@ ORDER BY
                     is_hidden,
                     is_banned,
                     ban_reason_id,
-                    ban_reason_description,
+                    (ban_reason_description, ban_reason_default_text),
                     ban_reason_text,
                     min_age,
-                    (is_age_restricted, is_pinned),
+                    (
+                        is_age_restricted,
+                        is_pinned,
+                        author_display_name,
+                        ban_expires_at,
+                        is_quarantined,
+                        content_rating_id,
+                        updated_at,
+                        slug,
+                    ),
                 ),
                 upload_records,
             )| Post {
@@ -1062,14 +6296,18 @@ ORDER BY
                 title,
                 description: post_description,
                 author_username,
+                author_display_name,
                 is_hidden,
+                is_quarantined,
                 ban: if is_banned {
                     Some((
                         ban_reason_id.map(|ban_reason_id| BanReason {
                             id: ban_reason_id,
                             description: ban_reason_description,
+                            default_text: ban_reason_default_text,
                         }),
                         ban_reason_text,
+                        ban_expires_at,
                     ))
                 } else {
                     None
@@ -1088,12 +6326,135 @@ ORDER BY
                     )
                     .collect(),
                 min_age,
+                content_rating_id,
                 is_age_restricted: is_age_restricted.unwrap(),
                 is_pinned,
+                updated_at,
+                slug,
             },
         )
         .collect();
 
+    Ok((items, total_item_count))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FeedPost {
+    pub id: i64,
+    pub title: String,
+    pub description: String,
+    pub author_username: String,
+    pub author_display_name: Option<String>,
+    pub creation_date: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+impl FeedPost {
+    pub fn detail_url(&self) -> Origin {
+        uri!(crate::app::views::post_detail_get(self.id))
+    }
+
+    pub fn author_name(&self) -> &str {
+        self.author_display_name
+            .as_deref()
+            .unwrap_or(&self.author_username)
+    }
+}
+
+/// Latest posts which are visible to anonymous users, feeds are not authenticated, so hidden,
+/// banned, quarantined and age-restricted posts are not listed.
+pub async fn list_public_posts_for_feed(
+    limit: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<FeedPost>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        FeedPost,
+        r#"
+SELECT
+    posts.id, title, COALESCE(posts.description, '') AS "description!", author_username,
+    users.display_name AS author_display_name, posts.creation_date, posts.updated_at
+FROM
+    posts
+    INNER JOIN users
+        ON posts.author_username = users.username
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+WHERE
+    NOT is_hidden
+    AND NOT is_quarantined
+    AND NOT (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP))
+    AND NOT is_age_restricted(NULL, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age))
+ORDER BY
+    posts.id DESC
+LIMIT
+    $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Post entry of sitemap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SitemapPost {
+    pub id: i64,
+    pub updated_at: OffsetDateTime,
+}
+
+/// List IDs and update dates of posts which are visible to anonymous users, for sitemap.
+pub async fn list_public_post_ids_and_dates(
+    page_params: PageParams,
+    pool: &Pool<Postgres>,
+) -> Result<Page<SitemapPost>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(posts.id)
+FROM
+    posts
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+WHERE
+    NOT is_hidden
+    AND NOT is_quarantined
+    AND NOT (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP))
+    AND NOT is_age_restricted(NULL, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age))
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let items = sqlx::query_as!(
+        SitemapPost,
+        r#"
+SELECT
+    posts.id, posts.updated_at
+FROM
+    posts
+    LEFT JOIN content_ratings
+        ON posts.content_rating_id = content_ratings.id
+WHERE
+    NOT is_hidden
+    AND NOT is_quarantined
+    AND NOT (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP))
+    AND NOT is_age_restricted(NULL, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age))
+ORDER BY
+    posts.id
+LIMIT $2
+OFFSET $1
+        "#,
+        offset,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
     Ok(Page {
         items,
         page_id,
@@ -1103,32 +6464,138 @@ ORDER BY
     })
 }
 
-pub async fn try_get_post(
-    id: i64,
+/// List usernames of active users, for sitemap.
+pub async fn list_public_usernames(
+    page_params: PageParams,
+    pool: &Pool<Postgres>,
+) -> Result<Page<String>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
+        r#"
+SELECT
+    COUNT(username)
+FROM
+    users
+WHERE
+    is_active
+    AND username <> $1
+        "#,
+        DELETED_USERNAME
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let items = sqlx::query!(
+        r#"
+SELECT
+    username
+FROM
+    users
+WHERE
+    is_active
+    AND username <> $1
+ORDER BY
+    username
+LIMIT $3
+OFFSET $2
+        "#,
+        DELETED_USERNAME,
+        offset,
+        limit
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| record.username)
+    .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
+}
+
+pub async fn list_pinned_posts_with_pagination(
     pool: &Pool<Postgres>,
+    page_params: PageParams,
     user: &Authentication,
-) -> Result<Option<Post>, crate::error::Error> {
-    let result = sqlx::query!(
+) -> Result<Page<Post>, crate::error::Error> {
+    let count_query_result = sqlx::query!(
         r#"
 SELECT
-    posts.id, posts.creation_date, title, posts.description AS post_description, author_username,
-    is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_reasons.description AS ban_reason_description,
+    COUNT(id)
+FROM
+    posts
+WHERE
+    is_pinned
+    AND (NOT is_quarantined OR $1 OR author_username = $2)
+        "#,
+        user.is_admin(),
+        user.username()
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
+
+    let page_count = total_item_count.div_ceil(page_params.page_size);
+
+    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+
+    let group_by = sqlx::query!(
+        r#"
+SELECT
+    posts.id, posts.creation_date, title,
+    posts.description AS post_description, author_username,
+    is_hidden, (is_banned AND (ban_expires_at IS NULL OR ban_expires_at > CURRENT_TIMESTAMP)) AS "is_banned!",
+    ban_reason_id, ban_reason_text, ban_expires_at, ban_reasons.description AS ban_reason_description,
+    ban_reasons.default_text AS ban_reason_default_text,
     uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
     uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
-    min_age, is_age_restricted($2, CURRENT_TIMESTAMP, min_age) AS is_age_restricted,
-    is_pinned
+    COALESCE(content_ratings.min_age, posts.min_age) AS min_age, content_rating_id,
+    is_age_restricted($3, CURRENT_TIMESTAMP, COALESCE(content_ratings.min_age, posts.min_age)) AS is_age_restricted,
+    is_pinned, is_quarantined, users.display_name AS author_display_name, posts.updated_at, posts.slug
 FROM
-    posts
-    LEFT JOIN ban_reasons
-        ON posts.ban_reason_id = ban_reasons.id
-    LEFT JOIN uploads
+(
+    SELECT
+        id, creation_date, title, description, author_username,
+        is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_expires_at, min_age, content_rating_id, is_quarantined,
+        is_pinned, updated_at, slug
+    FROM
+        posts
+    WHERE
+        is_pinned
+        AND (NOT is_quarantined OR $4 OR author_username = $5)
+    ORDER BY
+        id DESC
+    LIMIT
+        $1
+    OFFSET
+        $2
+) posts
+INNER JOIN users
+    ON posts.author_username = users.username
+LEFT JOIN ban_reasons
+    ON posts.ban_reason_id = ban_reasons.id
+LEFT JOIN content_ratings
+    ON posts.content_rating_id = content_ratings.id
+LEFT JOIN uploads
     ON posts.id = uploads.post_id
     AND file_status = 'PUBLISHED'
-WHERE
-    posts.id = $1
-            "#,
-            id,
-            user.birth_date()
+ORDER BY
+    posts.id DESC, uploads.id ASC
+        "#,
+        limit,
+        offset,
+        user.birth_date(),
+        user.is_admin(),
+        user.username()
     )
     .fetch_all(pool)
     .await?
@@ -1136,8 +6603,8 @@ WHERE
     .map(|record| (
         (
             record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
-            record.is_banned, record.ban_reason_id, record.ban_reason_description, record.ban_reason_text,
-            record.min_age, (record.is_age_restricted, record.is_pinned)
+            record.is_banned, record.ban_reason_id, (record.ban_reason_description, record.ban_reason_default_text), record.ban_reason_text, record.min_age,
+            (record.is_age_restricted, record.is_pinned, record.author_display_name, record.ban_expires_at, record.is_quarantined, record.content_rating_id, record.updated_at, record.slug)
         ),
         match record.upload_id {
             Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
@@ -1146,143 +6613,211 @@ WHERE
     ))
     .into_group_linked_map();
 
-    let mut result_iter = result.into_iter();
-
-    Ok(result_iter.next().map(
-        |(
-            (
-                post_id,
+    let items: Vec<Post> = group_by
+        .into_iter()
+        .map(
+            |(
+                (
+                    post_id,
+                    creation_date,
+                    title,
+                    post_description,
+                    author_username,
+                    is_hidden,
+                    is_banned,
+                    ban_reason_id,
+                    (ban_reason_description, ban_reason_default_text),
+                    ban_reason_text,
+                    min_age,
+                    (
+                        is_age_restricted,
+                        is_pinned,
+                        author_display_name,
+                        ban_expires_at,
+                        is_quarantined,
+                        content_rating_id,
+                        updated_at,
+                        slug,
+                    ),
+                ),
+                upload_records,
+            )| Post {
+                id: post_id,
                 creation_date,
                 title,
-                post_description,
+                description: post_description,
                 author_username,
+                author_display_name,
                 is_hidden,
-                is_banned,
-                ban_reason_id,
-                ban_reason_description,
-                ban_reason_text,
+                is_quarantined,
+                ban: if is_banned {
+                    Some((
+                        ban_reason_id.map(|ban_reason_id| BanReason {
+                            id: ban_reason_id,
+                            description: ban_reason_description,
+                            default_text: ban_reason_default_text,
+                        }),
+                        ban_reason_text,
+                        ban_expires_at,
+                    ))
+                } else {
+                    None
+                },
+                uploads: upload_records
+                    .into_iter()
+                    .flatten()
+                    .map(
+                        |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
+                            id: upload_id,
+                            extension,
+                            size: size.unwrap(),
+                            creation_date: upload_creation_date.unwrap(),
+                            file_status: file_status.unwrap(),
+                        },
+                    )
+                    .collect(),
                 min_age,
-                (is_age_restricted, is_pinned),
-            ),
-            upload_records,
-        )| Post {
-            id: post_id,
-            creation_date,
-            title,
-            description: post_description,
-            author_username,
-            is_hidden,
-            ban: if is_banned {
-                Some((
-                    ban_reason_id.map(|ban_reason_id| BanReason {
-                        id: ban_reason_id,
-                        description: ban_reason_description,
-                    }),
-                    ban_reason_text,
-                ))
-            } else {
-                None
+                content_rating_id,
+                is_age_restricted: is_age_restricted.unwrap(),
+                is_pinned,
+                updated_at,
+                slug,
             },
-            uploads: upload_records
-                .into_iter()
-                .flatten()
-                .map(
-                    |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
-                        id: upload_id,
-                        extension,
-                        size: size.unwrap(),
-                        creation_date: upload_creation_date.unwrap(),
-                        file_status: file_status.unwrap(),
-                    },
-                )
-                .collect(),
-            min_age,
-            is_age_restricted: is_age_restricted.unwrap(),
-            is_pinned,
-        },
-    ))
+        )
+        .collect();
+
+    Ok(Page {
+        items,
+        page_id,
+        page_size: page_params.page_size,
+        total_item_count,
+        page_count,
+    })
 }
 
-pub async fn add_post(
-    post: NewPost<'_>,
-    user: User,
+/// Result of reserving idempotency key before executing request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdempotencyKeyReservation {
+    /// Key was not used before, request should be executed.
+    Reserved,
+    /// Request with this key was already completed, its response should be replayed.
+    Completed {
+        response_status: u16,
+        response_body: String,
+    },
+    /// Key was used with different request or request with this key is still in progress.
+    Conflict,
+}
+
+/// Reserve idempotency key for user and endpoint. Keys created before `expires_before` are
+/// replaced.
+pub async fn try_reserve_idempotency_key(
+    key: &str,
+    username: &str,
+    endpoint: &str,
+    request_hash: &str,
+    expires_before: OffsetDateTime,
     pool: &Pool<Postgres>,
-) -> Result<Post, crate::error::Error> {
-    let result = sqlx::query!(
+) -> Result<IdempotencyKeyReservation, crate::error::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
         r#"
-INSERT INTO
-    posts (title, description, is_hidden, is_banned, author_username, min_age, document_tsvector, is_pinned)
-VALUES
-    ($1, $2, $3, $4, $5, $6, TO_TSVECTOR($1 || ' ' || COALESCE($2, '')), $7)
-RETURNING id, creation_date
-            "#,
-        post.title,
-        post.description,
-        post.is_hidden,
-        false,
-        user.username,
-        post.min_age,
-        post.is_pinned,
+DELETE FROM
+    idempotency_keys
+WHERE
+    key = $1
+    AND username = $2
+    AND endpoint = $3
+    AND creation_date < $4
+        "#,
+        key,
+        username,
+        endpoint,
+        expires_before
     )
-    .fetch_one(pool)
+    .execute(&mut *transaction)
     .await?;
 
-    Ok(Post {
-        id: result.id,
-        creation_date: result.creation_date,
-        title: post.title.to_string(),
-        description: post.description.to_string(),
-        author_username: user.username,
-        is_hidden: post.is_hidden,
-        ban: None,
-        uploads: vec![],
-        min_age: post.min_age,
-        is_age_restricted: false,
-        is_pinned: post.is_pinned,
-    })
-}
-
-pub async fn try_edit_post_check_exists_and_permission<'r>(
-    post: PostEdit<'r>,
-    user: &User,
-    pool: &Pool<Postgres>,
-) -> Result<(), crate::error::Error> {
-    let record = sqlx::query!(
+    let is_reserved = sqlx::query!(
         r#"
+INSERT INTO
+    idempotency_keys (key, username, endpoint, request_hash)
+VALUES
+    ($1, $2, $3, $4)
+ON CONFLICT DO NOTHING
+RETURNING
+    key
+        "#,
+        key,
+        username,
+        endpoint,
+        request_hash
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .is_some();
+
+    let result = if is_reserved {
+        IdempotencyKeyReservation::Reserved
+    } else {
+        let record = sqlx::query!(
+            r#"
 SELECT
-    id, author_username, title, description, is_hidden, is_pinned
+    request_hash, response_status, response_body
 FROM
-    posts
+    idempotency_keys
 WHERE
-    id = $1
-        "#,
-        post.id
-    )
-    .fetch_optional(pool)
-    .await?;
+    key = $1
+    AND username = $2
+    AND endpoint = $3
+            "#,
+            key,
+            username,
+            endpoint
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        match (record.response_status, record.response_body) {
+            (Some(response_status), Some(response_body)) if record.request_hash == request_hash => {
+                IdempotencyKeyReservation::Completed {
+                    response_status: response_status as u16,
+                    response_body,
+                }
+            }
+            _ => IdempotencyKeyReservation::Conflict,
+        }
+    };
 
-    let record = record.ok_or(crate::error::Error::DoesNotExist)?;
+    transaction.commit().await?;
 
-    if record.author_username != user.username {
-        return Err(crate::error::Error::AccessDenied);
-    }
+    Ok(result)
+}
 
+pub async fn set_idempotency_key_response(
+    key: &str,
+    username: &str,
+    endpoint: &str,
+    response_status: u16,
+    response_body: &str,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
     sqlx::query!(
         r#"
 UPDATE
-    posts
+    idempotency_keys
 SET
-    title = $2, description = $3, is_hidden = $4, min_age = $5, is_pinned = $6,
-    document_tsvector = TO_TSVECTOR($2 || ' ' || COALESCE($3, ''))
+    response_status = $4, response_body = $5
 WHERE
-    id = $1
-            "#,
-        post.id,
-        post.title.unwrap_or(&record.title),
-        post.description.unwrap_or(&record.description),
-        post.is_hidden.unwrap_or(record.is_hidden),
-        post.min_age,
-        post.is_pinned.unwrap_or(record.is_pinned),
+    key = $1
+    AND username = $2
+    AND endpoint = $3
+        "#,
+        key,
+        username,
+        endpoint,
+        response_status as i16,
+        response_body
     )
     .execute(pool)
     .await?;
@@ -1290,450 +6825,374 @@ WHERE
     Ok(())
 }
 
-pub async fn try_ban_post_check_exists(
-    post_id: i64,
-    ban_reason_id: Option<String>,
-    ban_reason_text: Option<String>,
+/// Release reserved idempotency key, so that failed request can be retried with the same key.
+pub async fn remove_idempotency_key(
+    key: &str,
+    username: &str,
+    endpoint: &str,
     pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let post_already_exists = sqlx::query!(
-        r#"
-SELECT
-    id
-FROM
-    posts
-WHERE
-    id = $1
-        "#,
-        post_id
-    )
-    .fetch_optional(pool)
-    .await?
-    .is_some();
-
-    if !post_already_exists {
-        return Ok(None);
-    }
-
+) -> Result<(), crate::error::Error> {
     sqlx::query!(
         r#"
-UPDATE
-    posts
-SET
-    is_banned = TRUE, ban_reason_id = $2, ban_reason_text = $3
+DELETE FROM
+    idempotency_keys
 WHERE
-    id = $1
+    key = $1
+    AND username = $2
+    AND endpoint = $3
         "#,
-        post_id,
-        ban_reason_id,
-        ban_reason_text
+        key,
+        username,
+        endpoint
     )
-    .fetch_optional(pool)
+    .execute(pool)
     .await?;
 
-    Ok(Some(()))
+    Ok(())
 }
 
-pub async fn try_unban_post_check_exists(
-    post_id: i64,
+pub async fn remove_expired_idempotency_keys(
+    expires_before: OffsetDateTime,
     pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let post_already_exists = sqlx::query!(
+) -> Result<u64, crate::error::Error> {
+    Ok(sqlx::query!(
         r#"
-SELECT
-    id
-FROM
-    posts
+DELETE FROM
+    idempotency_keys
 WHERE
-    id = $1
+    creation_date < $1
         "#,
-        post_id
+        expires_before
     )
-    .fetch_optional(pool)
+    .execute(pool)
     .await?
-    .is_some();
+    .rows_affected())
+}
 
-    if !post_already_exists {
-        return Ok(None);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "webhook_event")]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "snake_case")]
+// Variant names are event names sent to webhooks.
+#[allow(clippy::enum_variant_names)]
+pub enum WebhookEvent {
+    PostCreated,
+    PostPublished,
+    PostBanned,
+    PostUnbanned,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::PostCreated,
+        WebhookEvent::PostPublished,
+        WebhookEvent::PostBanned,
+        WebhookEvent::PostUnbanned,
+    ];
+
+    /// Bit of event in webhook event mask.
+    pub fn bit(self) -> i32 {
+        match self {
+            WebhookEvent::PostCreated => 1,
+            WebhookEvent::PostPublished => 1 << 1,
+            WebhookEvent::PostBanned => 1 << 2,
+            WebhookEvent::PostUnbanned => 1 << 3,
+        }
     }
 
-    sqlx::query!(
-        r#"
-UPDATE
-    posts
-SET
-    is_banned = FALSE, ban_reason_id = NULL, ban_reason_text = NULL
-WHERE
-    id = $1
-        "#,
-        post_id,
-    )
-    .fetch_optional(pool)
-    .await?;
+    pub fn get_option(self) -> String {
+        match self {
+            WebhookEvent::PostCreated => "post_created",
+            WebhookEvent::PostPublished => "post_published",
+            WebhookEvent::PostBanned => "post_banned",
+            WebhookEvent::PostUnbanned => "post_unbanned",
+        }
+        .to_string()
+    }
 
-    Ok(Some(()))
+    /// Message key of webhook event name.
+    pub fn get_verbose_name(self) -> &'static str {
+        match self {
+            WebhookEvent::PostCreated => "webhook_event.post_created",
+            WebhookEvent::PostPublished => "webhook_event.post_published",
+            WebhookEvent::PostBanned => "webhook_event.post_banned",
+            WebhookEvent::PostUnbanned => "webhook_event.post_unbanned",
+        }
+    }
+
+    pub fn get_mask(events: &[WebhookEvent]) -> i32 {
+        events.iter().fold(0, |mask, event| mask | event.bit())
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
-#[sqlx(type_name = "upload_status")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "webhook_delivery_status")]
 #[sqlx(rename_all = "UPPERCASE")]
-pub enum UploadStatus {
-    Initialized,
-    Allocated,
-    Writing,
-    Publishing,
-    Published,
-    Hiding,
-    Hidden,
-    Missing,
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
 }
 
-impl UploadStatus {
-    pub fn can_transition_to(&self, new_status: &UploadStatus) -> bool {
-        match new_status {
-            UploadStatus::Initialized => false,
-            UploadStatus::Allocated => {
-                self == &UploadStatus::Initialized || self == &UploadStatus::Writing
-            }
-            UploadStatus::Writing => self == &UploadStatus::Allocated,
-            UploadStatus::Publishing => {
-                self == &UploadStatus::Allocated || self == &UploadStatus::Hidden
-            }
-            UploadStatus::Published => self == &UploadStatus::Publishing,
-            UploadStatus::Hiding => self == &UploadStatus::Published,
-            UploadStatus::Hidden => self == &UploadStatus::Hiding,
-            UploadStatus::Missing => true,
+impl WebhookDeliveryStatus {
+    /// Message key of webhook delivery status name.
+    pub fn get_verbose_name(self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "webhook_delivery_status.pending",
+            WebhookDeliveryStatus::Succeeded => "webhook_delivery_status.succeeded",
+            WebhookDeliveryStatus::Failed => "webhook_delivery_status.failed",
         }
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct NewUpload<'a> {
-    pub extension: Option<&'a str>,
-    pub size: i64,
-    pub post_id: i64,
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Upload {
+pub struct Webhook {
     pub id: i64,
-    pub extension: Option<String>,
-    pub size: i64,
+    pub url: String,
+    pub secret: String,
+    pub event_mask: i32,
+    pub is_enabled: bool,
     pub creation_date: OffsetDateTime,
-    pub file_status: UploadStatus,
 }
 
-impl Upload {
-    pub fn file_url(&self, storage: &UploadStorage) -> String {
-        get_file_url(self.id, self.extension.as_deref(), storage)
+impl Webhook {
+    pub fn handles(&self, event: WebhookEvent) -> bool {
+        self.event_mask & event.bit() != 0
+    }
+
+    pub fn events(&self) -> Vec<WebhookEvent> {
+        WebhookEvent::ALL
+            .into_iter()
+            .filter(|event| self.handles(*event))
+            .collect()
+    }
+
+    pub fn deliveries_url(&self) -> Origin {
+        uri!(crate::app::views::webhook_deliveries_get(
+            self.id,
+            None as Option<u64>,
+            None as Option<u64>
+        ))
+    }
+
+    pub fn toggle_url(&self) -> Origin {
+        uri!(crate::app::views::webhook_toggle_post(self.id))
+    }
+
+    pub fn remove_url(&self) -> Origin {
+        uri!(crate::app::views::webhook_remove_post(self.id))
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct UploadFull {
+pub struct WebhookDelivery {
     pub id: i64,
-    pub extension: Option<String>,
-    pub size: i64,
+    pub webhook_id: i64,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempt_count: i32,
+    pub last_response_status: Option<i16>,
+    pub last_error: Option<String>,
+    pub next_attempt_date: OffsetDateTime,
+    pub last_attempt_date: Option<OffsetDateTime>,
     pub creation_date: OffsetDateTime,
-    pub file_status: UploadStatus,
-    pub post_id: i64,
-    pub post_author_username: String,
 }
 
-pub async fn get_upload(id: i64, pool: &Pool<Postgres>) -> Result<UploadFull, crate::error::Error> {
-    let result = sqlx::query!(
-        r#"
-SELECT
-    file_status AS "file_status: UploadStatus", extension, uploads.creation_date, size, post_id, posts.author_username
-FROM
-    uploads
-    JOIN posts
-        ON posts.id = uploads.post_id
-WHERE
-    uploads.id = $1
-        "#,
-        id
-    )
-    .fetch_optional(pool)
-    .await?;
-
-    match result {
-        None => Err(crate::error::Error::DoesNotExist),
-        Some(record) => Ok(UploadFull {
-            id,
-            extension: record.extension,
-            size: record.size,
-            creation_date: record.creation_date,
-            file_status: record.file_status,
-            post_id: record.post_id,
-            post_author_username: record.author_username,
-        }),
-    }
+/// Pending delivery with URL and secret of its webhook.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookDeliveryTask {
+    pub id: i64,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub attempt_count: i32,
+    pub url: String,
+    pub secret: String,
 }
 
-pub async fn add_upload(
-    upload: NewUpload<'_>,
-    user: User,
+pub async fn add_webhook(
+    url: &str,
+    secret: &str,
+    event_mask: i32,
     pool: &Pool<Postgres>,
-) -> Result<Upload, crate::error::Error> {
-    let record = sqlx::query!(
-        r#"
-SELECT
-    id, author_username
-FROM
-    posts
-WHERE
-    id = $1
-        "#,
-        upload.post_id
-    )
-    .fetch_optional(pool)
-    .await?;
-
-    let author_username = match record {
-        Some(record_real) => Ok(record_real.author_username),
-        None => Err(crate::error::Error::DoesNotExist),
-    }?;
-
-    if author_username != user.username {
-        return Err(crate::error::Error::AccessDenied);
-    }
-
-    let result = sqlx::query!(
+) -> Result<i64, crate::error::Error> {
+    Ok(sqlx::query!(
         r#"
 INSERT INTO
-    uploads (extension, size, file_status, post_id)
+    webhooks (url, secret, event_mask)
 VALUES
-    ($1, $2, $3, $4)
-RETURNING id, creation_date
-            "#,
-        upload.extension,
-        upload.size,
-        UploadStatus::Initialized as _,
-        upload.post_id,
+    ($1, $2, $3)
+RETURNING
+    id
+        "#,
+        url,
+        secret,
+        event_mask
     )
     .fetch_one(pool)
-    .await?;
+    .await?
+    .id)
+}
 
-    Ok(Upload {
-        id: result.id,
-        extension: upload.extension.map(|x| x.to_string()),
-        size: upload.size,
-        creation_date: result.creation_date,
-        file_status: UploadStatus::Initialized,
-    })
+pub async fn list_webhooks(pool: &Pool<Postgres>) -> Result<Vec<Webhook>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        Webhook,
+        r#"
+SELECT
+    id, url, secret, event_mask, is_enabled, creation_date
+FROM
+    webhooks
+ORDER BY
+    id
+        "#
+    )
+    .fetch_all(pool)
+    .await?)
 }
 
-pub async fn try_set_upload_status(
+pub async fn try_get_webhook(
     id: i64,
-    new_status: UploadStatus,
     pool: &Pool<Postgres>,
-) -> Result<Option<()>, crate::error::Error> {
-    let mut transaction = pool.begin().await?;
-
-    let file_status = sqlx::query!(
+) -> Result<Option<Webhook>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        Webhook,
         r#"
 SELECT
-    file_status AS "file_status: UploadStatus"
+    id, url, secret, event_mask, is_enabled, creation_date
 FROM
-    uploads
+    webhooks
 WHERE
     id = $1
         "#,
         id
     )
-    .fetch_one(&mut *transaction)
-    .await?
-    .file_status;
-
-    debug!(
-        "Trying to transition upload {} from {:?} to {:?}",
-        id, file_status, new_status
-    );
-    if !file_status.can_transition_to(&new_status) {
-        transaction.commit().await?;
-
-        return Ok(None);
-    }
+    .fetch_optional(pool)
+    .await?)
+}
 
-    sqlx::query!(
+/// Enable or disable webhook, returns its new state.
+pub async fn try_toggle_webhook_check_exists(
+    id: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Option<bool>, crate::error::Error> {
+    Ok(sqlx::query!(
         r#"
 UPDATE
-    uploads
+    webhooks
 SET
-    file_status = $2
+    is_enabled = NOT is_enabled
 WHERE
     id = $1
-            "#,
-        id,
-        new_status as _
+RETURNING
+    is_enabled
+        "#,
+        id
     )
-    .execute(&mut *transaction)
-    .await?;
-
-    transaction.commit().await?;
-
-    Ok(Some(()))
+    .fetch_optional(pool)
+    .await?
+    .map(|record| record.is_enabled))
 }
 
-pub async fn try_set_upload_status_check_exists(
+pub async fn try_remove_webhook_check_exists(
     id: i64,
-    new_status: UploadStatus,
     pool: &Pool<Postgres>,
-) -> Result<Option<UploadFull>, crate::error::Error> {
-    let mut transaction = pool.begin().await?;
-
+) -> Result<Option<()>, crate::error::Error> {
     let result = sqlx::query!(
         r#"
-SELECT
-    file_status AS "file_status: UploadStatus", extension, uploads.creation_date, size, post_id, posts.author_username
-FROM
-    uploads
-    JOIN posts
-        ON posts.id = uploads.post_id
+DELETE FROM
+    webhooks
 WHERE
-    uploads.id = $1
+    id = $1
         "#,
         id
     )
-    .fetch_optional(&mut *transaction)
+    .execute(pool)
     .await?;
 
-    match result {
-        None => Err(crate::error::Error::DoesNotExist),
-        Some(record) => {
-            if !record.file_status.can_transition_to(&new_status) {
-                Ok(None)
-            } else {
-                sqlx::query!(
-                    r#"
-UPDATE
-    uploads
-SET
-    file_status = $2
-WHERE
-    id = $1
-            "#,
-                    id,
-                    new_status as _
-                )
-                .execute(&mut *transaction)
-                .await?;
-
-                transaction.commit().await?;
-
-                Ok(Some(UploadFull {
-                    id,
-                    extension: record.extension,
-                    size: record.size,
-                    creation_date: record.creation_date,
-                    file_status: record.file_status,
-                    post_id: record.post_id,
-                    post_author_username: record.author_username,
-                }))
-            }
-        }
-    }
+    Ok((result.rows_affected() > 0).then_some(()))
 }
 
-pub async fn list_old_in_progress_uploads_and_set_hiding(
+/// Queue delivery of event payload to all enabled webhooks subscribed to it, returns number of
+/// queued deliveries.
+pub async fn add_webhook_deliveries(
+    event: WebhookEvent,
+    payload: &str,
     pool: &Pool<Postgres>,
-    page_params: PageParams,
-    max_age: Duration,
-) -> Result<Page<Upload>, crate::error::Error> {
-    let max_age: PgInterval = max_age.try_into()?;
-
-    let count_query_result = sqlx::query!(
+) -> Result<u64, crate::error::Error> {
+    Ok(sqlx::query!(
         r#"
+INSERT INTO
+    webhook_deliveries (webhook_id, event, payload)
 SELECT
-    COUNT(id)
-FROM
-    uploads
-WHERE
-    file_status NOT IN ('PUBLISHED', 'HIDDEN', 'MISSING')
-    AND (
-        AGE(CURRENT_TIMESTAMP, creation_date) > $1
-        OR file_status = 'HIDING'
-    )
-        "#,
-        max_age
-    )
-    .fetch_one(pool)
-    .await?;
-
-    let total_item_count = count_query_result.count.unwrap_or(0) as u64;
-
-    let page_count = total_item_count.div_ceil(page_params.page_size);
-
-    let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
+    id, $1, $2
+FROM
+    webhooks
+WHERE
+    is_enabled
+    AND (event_mask & $3) <> 0
+        "#,
+        event as _,
+        payload,
+        event.bit()
+    )
+    .execute(pool)
+    .await?
+    .rows_affected())
+}
 
-    let items = sqlx::query!(
+/// List pending deliveries of enabled webhooks which should be attempted at `now`.
+pub async fn list_due_webhook_deliveries(
+    now: OffsetDateTime,
+    limit: i64,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<WebhookDeliveryTask>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        WebhookDeliveryTask,
         r#"
-UPDATE
-    uploads
-SET
-    file_status = 'HIDING'
+SELECT
+    webhook_deliveries.id, event AS "event: WebhookEvent", payload, attempt_count, url, secret
+FROM
+    webhook_deliveries
+    INNER JOIN webhooks ON webhooks.id = webhook_deliveries.webhook_id
 WHERE
-    id IN (
-        SELECT
-            id 
-        FROM
-            uploads
-        WHERE
-            file_status NOT IN ('PUBLISHED', 'HIDDEN', 'MISSING')
-            AND (
-                AGE(CURRENT_TIMESTAMP, creation_date) > $3
-                OR file_status = 'HIDING'
-            )
-        ORDER BY
-            id
-        LIMIT
-            $1
-        OFFSET
-            $2
-    )
-RETURNING
-    id, extension, creation_date, size, file_status AS "file_status: UploadStatus"
+    status = 'PENDING'
+    AND next_attempt_date <= $1
+    AND webhooks.is_enabled
+ORDER BY
+    next_attempt_date, webhook_deliveries.id
+LIMIT $2
         "#,
-        limit,
-        offset,
-        max_age
+        now,
+        limit
     )
     .fetch_all(pool)
-    .await?
-    .into_iter()
-    .map(|record| Upload {
-        id: record.id,
-        extension: record.extension,
-        size: record.size,
-        creation_date: record.creation_date,
-        file_status: record.file_status,
-    })
-    .collect();
-
-    Ok(Page {
-        items,
-        page_id,
-        page_size: page_params.page_size,
-        total_item_count,
-        page_count,
-    })
+    .await?)
 }
 
-pub async fn set_uploads_hidden(
+/// Save result of delivery attempt, `next_attempt_date` is only used for pending deliveries.
+pub async fn set_webhook_delivery_result(
+    id: i64,
+    status: WebhookDeliveryStatus,
+    response_status: Option<u16>,
+    error: Option<&str>,
+    next_attempt_date: OffsetDateTime,
     pool: &Pool<Postgres>,
-    ids: Vec<i64>,
 ) -> Result<(), crate::error::Error> {
     sqlx::query!(
         r#"
 UPDATE
-    uploads
+    webhook_deliveries
 SET
-    file_status = 'HIDDEN'
+    status = $2, attempt_count = attempt_count + 1, last_response_status = $3, last_error = $4,
+    next_attempt_date = $5, last_attempt_date = NOW()
 WHERE
-    id = ANY($1::BIGINT[])
+    id = $1
         "#,
-        ids.as_slice()
+        id,
+        status as _,
+        response_status.map(|response_status| response_status as i16),
+        error,
+        next_attempt_date
     )
     .execute(pool)
     .await?;
@@ -1741,17 +7200,21 @@ WHERE
     Ok(())
 }
 
-pub async fn list_users_with_pagination(
-    pool: &Pool<Postgres>,
+pub async fn list_webhook_deliveries_with_pagination(
+    webhook_id: i64,
     page_params: PageParams,
-) -> Result<Page<User>, crate::error::Error> {
+    pool: &Pool<Postgres>,
+) -> Result<Page<WebhookDelivery>, crate::error::Error> {
     let count_query_result = sqlx::query!(
         r#"
 SELECT
-    COUNT(username)
+    COUNT(id)
 FROM
-    users
-        "#
+    webhook_deliveries
+WHERE
+    webhook_id = $1
+        "#,
+        webhook_id
     )
     .fetch_one(pool)
     .await?;
@@ -1761,31 +7224,28 @@ FROM
 
     let (limit, offset, page_id) = page_params.get_limit_offset_and_page_id(page_count)?;
 
-    let items = sqlx::query!(
+    let items = sqlx::query_as!(
+        WebhookDelivery,
         r#"
 SELECT
-    username, is_active, is_admin, is_uploader, password_hash, birth_date
+    id, webhook_id, event AS "event: WebhookEvent", payload,
+    status AS "status: WebhookDeliveryStatus", attempt_count, last_response_status, last_error,
+    next_attempt_date, last_attempt_date, creation_date
 FROM
-    users
+    webhook_deliveries
+WHERE
+    webhook_id = $3
 ORDER BY
-    username
+    creation_date DESC, id DESC
 LIMIT $2
 OFFSET $1
-    "#,
+        "#,
         offset,
-        limit
+        limit,
+        webhook_id
     )
     .fetch_all(pool)
-    .await?
-    .iter()
-    .map(|user_data| User {
-        username: user_data.username.clone(),
-        is_active: user_data.is_active,
-        is_admin: user_data.is_admin,
-        is_uploader: user_data.is_uploader,
-        birth_date: user_data.birth_date,
-    })
-    .collect();
+    .await?;
 
     Ok(Page {
         items,
@@ -1796,119 +7256,140 @@ OFFSET $1
     })
 }
 
-pub async fn list_latest_pinned_posts(
+/// Page with text in Markdown editable by admins, like rules or contacts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaticPage {
+    pub slug: String,
+    pub title: String,
+    pub body: String,
+    pub updated_at: OffsetDateTime,
+    pub updated_by: Option<String>,
+}
+
+impl StaticPage {
+    pub fn detail_url(&self) -> Origin {
+        uri!(crate::app::views::static_page_get(&self.slug))
+    }
+
+    pub fn edit_url(&self) -> Origin {
+        uri!(crate::app::views::static_page_edit_get(&self.slug))
+    }
+
+    pub fn remove_url(&self) -> Origin {
+        uri!(crate::app::views::static_page_remove_get(&self.slug))
+    }
+}
+
+pub async fn list_static_pages(
     pool: &Pool<Postgres>,
-    limit: u64,
-    user: &Authentication,
-) -> Result<Vec<Post>, crate::error::Error> {
-    let group_by = sqlx::query!(
+) -> Result<Vec<StaticPage>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        StaticPage,
         r#"
 SELECT
-    posts.id, posts.creation_date, title,
-    posts.description AS post_description, author_username,
-    is_hidden, is_banned, ban_reason_id, ban_reason_text, ban_reasons.description AS ban_reason_description,
-    uploads.id AS "upload_id?", uploads.extension AS "upload_extension?", uploads.creation_date AS "upload_creation_date?",
-    uploads.size AS "size?", uploads.file_status AS "file_status?: UploadStatus",
-    min_age, is_age_restricted($2, CURRENT_TIMESTAMP, min_age) AS is_age_restricted,
-    is_pinned
+    slug, title, body, updated_at, updated_by
 FROM
-(
-    SELECT
-        id, creation_date, title, description, author_username,
-        is_hidden, is_banned, ban_reason_id, ban_reason_text, min_age,
-        is_pinned
-    FROM
-        posts
-    WHERE
-        is_pinned
-    ORDER BY
-        id DESC
-    LIMIT
-        $1
-) posts
-LEFT JOIN ban_reasons
-    ON posts.ban_reason_id = ban_reasons.id
-LEFT JOIN uploads
-    ON posts.id = uploads.post_id
-    AND file_status = 'PUBLISHED'
+    static_pages
 ORDER BY
-    posts.id DESC, uploads.id ASC
-        "#,
-        limit as i64,
-        user.birth_date()
+    slug
+        "#
     )
     .fetch_all(pool)
-    .await?
-    .into_iter()
-    .map(|record| (
-        (
-            record.id, record.creation_date, record.title, record.post_description, record.author_username, record.is_hidden,
-            record.is_banned, record.ban_reason_id, record.ban_reason_description, record.ban_reason_text, record.min_age,
-            (record.is_age_restricted, record.is_pinned)
-        ),
-        match record.upload_id {
-            Some(upload_id) => Some((upload_id, record.upload_extension, record.upload_creation_date, record.size, record.file_status)),
-            None => None
-        }
-    ))
-    .into_group_linked_map();
+    .await?)
+}
 
-    let items: Vec<Post> = group_by
-        .into_iter()
-        .map(
-            |(
-                (
-                    post_id,
-                    creation_date,
-                    title,
-                    post_description,
-                    author_username,
-                    is_hidden,
-                    is_banned,
-                    ban_reason_id,
-                    ban_reason_description,
-                    ban_reason_text,
-                    min_age,
-                    (is_age_restricted, is_pinned),
-                ),
-                upload_records,
-            )| Post {
-                id: post_id,
-                creation_date,
-                title,
-                description: post_description,
-                author_username,
-                is_hidden,
-                ban: if is_banned {
-                    Some((
-                        ban_reason_id.map(|ban_reason_id| BanReason {
-                            id: ban_reason_id,
-                            description: ban_reason_description,
-                        }),
-                        ban_reason_text,
-                    ))
-                } else {
-                    None
-                },
-                uploads: upload_records
-                    .into_iter()
-                    .flatten()
-                    .map(
-                        |(upload_id, extension, upload_creation_date, size, file_status)| Upload {
-                            id: upload_id,
-                            extension,
-                            size: size.unwrap(),
-                            creation_date: upload_creation_date.unwrap(),
-                            file_status: file_status.unwrap(),
-                        },
-                    )
-                    .collect(),
-                min_age,
-                is_age_restricted: is_age_restricted.unwrap(),
-                is_pinned,
-            },
-        )
-        .collect();
+pub async fn try_get_static_page(
+    slug: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<StaticPage>, crate::error::Error> {
+    Ok(sqlx::query_as!(
+        StaticPage,
+        r#"
+SELECT
+    slug, title, body, updated_at, updated_by
+FROM
+    static_pages
+WHERE
+    slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Add page, returns `None` if page with this slug already exists.
+pub async fn try_add_static_page_check_exists(
+    slug: &str,
+    title: &str,
+    body: &str,
+    updated_by: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+INSERT INTO
+    static_pages (slug, title, body, updated_by)
+VALUES
+    ($1, $2, $3, $4)
+ON CONFLICT (slug) DO NOTHING
+        "#,
+        slug,
+        title,
+        body,
+        updated_by
+    )
+    .execute(pool)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then_some(()))
+}
+
+pub async fn try_edit_static_page_check_exists(
+    slug: &str,
+    title: &str,
+    body: &str,
+    updated_by: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+UPDATE
+    static_pages
+SET
+    title = $2,
+    body = $3,
+    updated_at = NOW(),
+    updated_by = $4
+WHERE
+    slug = $1
+        "#,
+        slug,
+        title,
+        body,
+        updated_by
+    )
+    .execute(pool)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then_some(()))
+}
+
+pub async fn try_remove_static_page_check_exists(
+    slug: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<()>, crate::error::Error> {
+    let result = sqlx::query!(
+        r#"
+DELETE FROM
+    static_pages
+WHERE
+    slug = $1
+        "#,
+        slug
+    )
+    .execute(pool)
+    .await?;
 
-    Ok(items)
+    Ok((result.rows_affected() > 0).then_some(()))
 }
@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use rocket::{time::OffsetDateTime, uri, Build, Rocket};
+use serde::Serialize;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::app::db::WebhookEvent;
+
+/// Change of post, emitted by routes after it is saved to database.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PostEvent {
+    pub event: WebhookEvent,
+    pub post_id: i64,
+    pub post_url: String,
+    /// Title is only sent for events after which post is visible.
+    pub title: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub date: OffsetDateTime,
+}
+
+impl PostEvent {
+    pub fn new(event: WebhookEvent, post_id: i64, title: Option<String>) -> Self {
+        Self {
+            event,
+            post_id,
+            post_url: uri!(crate::app::views::post_detail_get(post_id)).to_string(),
+            title,
+            date: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Check if post is visible after event, i.e. it was created not hidden or published.
+    pub fn is_publication(&self) -> bool {
+        match self.event {
+            WebhookEvent::PostCreated => self.title.is_some(),
+            WebhookEvent::PostPublished => true,
+            WebhookEvent::PostBanned | WebhookEvent::PostUnbanned => false,
+        }
+    }
+}
+
+/// Sender of post events to background subscribers, so that routes do not wait for their
+/// delivery and no external requests are made while database transaction is open.
+#[derive(Clone, Debug, Default)]
+pub struct EventBus {
+    senders: Arc<Mutex<Vec<UnboundedSender<PostEvent>>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> UnboundedReceiver<PostEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.senders.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn emit(&self, event: PostEvent) {
+        self.senders.lock().unwrap().retain(|sender| {
+            if let Err(err) = sender.send(event.clone()) {
+                warn!("Event {:?} is dropped: subscriber is stopped", err.0.event);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// Subscribe to [`EventBus`] managed by `rocket`, it is created if it is not managed yet, so that
+/// fairings which use it can be attached in any order.
+pub fn subscribe_to_event_bus(
+    rocket: Rocket<Build>,
+) -> (Rocket<Build>, UnboundedReceiver<PostEvent>) {
+    match rocket.state::<EventBus>().map(EventBus::subscribe) {
+        Some(receiver) => (rocket, receiver),
+        None => {
+            let event_bus = EventBus::default();
+            let receiver = event_bus.subscribe();
+            (rocket.manage(event_bus), receiver)
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::{future::Future, time::Duration};
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use rocket::{
+    async_trait,
+    http::{ContentType, Status},
+    request::{self, FromRequest},
+    response::{self, status::Custom, Responder},
+    serde::json::Json,
+    Either, Request, Response,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+
+use crate::app::db::{
+    remove_idempotency_key, set_idempotency_key_response, try_reserve_idempotency_key,
+    IdempotencyKeyReservation,
+};
+
+/// Time during which responses are replayed for repeated requests with the same key.
+pub const IDEMPOTENCY_KEY_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const IDEMPOTENCY_KEY_MAX_LENGTH: usize = 255;
+
+/// Value of optional `Idempotency-Key` header with name of route it was sent to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdempotencyKey {
+    pub key: Option<String>,
+    pub endpoint: String,
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = crate::error::Error;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let endpoint = req
+            .route()
+            .and_then(|route| route.name.as_deref())
+            .unwrap_or_default()
+            .to_string();
+        match req.headers().get_one(IDEMPOTENCY_KEY_HEADER) {
+            Some(key) if key.is_empty() || key.len() > IDEMPOTENCY_KEY_MAX_LENGTH => {
+                request::Outcome::Error((
+                    Status::BadRequest,
+                    crate::error::Error::Misc("invalid idempotency key".to_string()),
+                ))
+            }
+            key => request::Outcome::Success(Self {
+                key: key.map(str::to_string),
+                endpoint,
+            }),
+        }
+    }
+}
+
+/// JSON response saved for idempotency key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: Status,
+    pub body: String,
+}
+
+impl<'r> Responder<'r, 'static> for StoredResponse {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .status(self.status)
+            .header(ContentType::JSON)
+            .sized_body(self.body.len(), std::io::Cursor::new(self.body))
+            .ok()
+    }
+}
+
+/// Responses which can be saved and replayed for repeated requests.
+pub trait ToStoredResponse {
+    fn to_stored_response(&self) -> Result<StoredResponse, crate::error::Error>;
+}
+
+impl<T: Serialize> ToStoredResponse for Json<T> {
+    fn to_stored_response(&self) -> Result<StoredResponse, crate::error::Error> {
+        Ok(StoredResponse {
+            status: Status::Ok,
+            body: serde_json::to_string(&self.0)?,
+        })
+    }
+}
+
+impl<T: Serialize> ToStoredResponse for Custom<Json<T>> {
+    fn to_stored_response(&self) -> Result<StoredResponse, crate::error::Error> {
+        Ok(StoredResponse {
+            status: self.0,
+            body: serde_json::to_string(&(self.1).0)?,
+        })
+    }
+}
+
+impl<L: ToStoredResponse, R: ToStoredResponse> ToStoredResponse for Either<L, R> {
+    fn to_stored_response(&self) -> Result<StoredResponse, crate::error::Error> {
+        match self {
+            Either::Left(left) => left.to_stored_response(),
+            Either::Right(right) => right.to_stored_response(),
+        }
+    }
+}
+
+/// Execute request `handler` once for idempotency key: response is saved and replayed for
+/// repeated requests with the same key and body, reusing key with different body is rejected.
+/// Failed requests are not saved, so they can be retried with the same key. Requests without key
+/// are executed as usual.
+pub async fn run_idempotent<R, F, B>(
+    idempotency_key: &IdempotencyKey,
+    username: &str,
+    request_body: &B,
+    pool: &Pool<Postgres>,
+    handler: F,
+) -> Result<Either<R, StoredResponse>, crate::error::Error>
+where
+    R: ToStoredResponse,
+    F: Future<Output = Result<R, crate::error::Error>>,
+    B: Serialize,
+{
+    let key = match &idempotency_key.key {
+        Some(key) => key,
+        None => return Ok(Either::Left(handler.await?)),
+    };
+    let endpoint = &idempotency_key.endpoint;
+    let request_hash =
+        BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(serde_json::to_vec(request_body)?));
+
+    match try_reserve_idempotency_key(
+        key,
+        username,
+        endpoint,
+        &request_hash,
+        OffsetDateTime::now_utc() - IDEMPOTENCY_KEY_LIFETIME,
+        pool,
+    )
+    .await?
+    {
+        IdempotencyKeyReservation::Reserved => {}
+        IdempotencyKeyReservation::Completed {
+            response_status,
+            response_body,
+        } => {
+            return Ok(Either::Right(StoredResponse {
+                status: Status::new(response_status),
+                body: response_body,
+            }))
+        }
+        IdempotencyKeyReservation::Conflict => {
+            return Err(crate::error::Error::IdempotencyKeyConflict)
+        }
+    }
+
+    match handler.await {
+        Ok(response) => {
+            let stored_response = response.to_stored_response()?;
+            set_idempotency_key_response(
+                key,
+                username,
+                endpoint,
+                stored_response.status.code,
+                &stored_response.body,
+                pool,
+            )
+            .await?;
+            Ok(Either::Left(response))
+        }
+        Err(err) => {
+            remove_idempotency_key(key, username, endpoint, pool).await?;
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use rocket::{
+    async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    http::Status,
+    request::{self, FromRequest},
+    Build, Data, Request, Response, Rocket,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    app::{db::UploadStatus, rate_limit::get_client_ip},
+    error, MetricsConfig,
+};
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::new().expect("metrics should be registered once");
+}
+
+/// Application metrics in Prometheus format. Metrics are global, because they are updated from
+/// code which has no access to Rocket state (like cleanup scheduler).
+pub struct Metrics {
+    pub registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub upload_bytes_written: IntCounter,
+    pub upload_status_transitions: IntCounterVec,
+    pub cleanup_runs: IntCounter,
+    pub cleanup_removed_files: IntCounter,
+    pub db_pool_connections: IntGauge,
+    pub db_pool_idle_connections: IntGauge,
+    pub db_pool_max_connections: IntGauge,
+    pub search_duration: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("archivanima".to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests"),
+            &["route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Duration of HTTP request handling",
+            ),
+            &["route"],
+        )?;
+        let upload_bytes_written = IntCounter::new(
+            "upload_bytes_written_total",
+            "Number of bytes written to private upload files",
+        )?;
+        let upload_status_transitions = IntCounterVec::new(
+            Opts::new(
+                "upload_status_transitions_total",
+                "Number of upload status changes",
+            ),
+            &["from", "to"],
+        )?;
+        let cleanup_runs = IntCounter::new("cleanup_runs_total", "Number of cleanup runs")?;
+        let cleanup_removed_files = IntCounter::new(
+            "cleanup_removed_files_total",
+            "Number of stale uploads hidden by cleanup",
+        )?;
+        let db_pool_connections =
+            IntGauge::new("db_pool_connections", "Number of open database connections")?;
+        let db_pool_idle_connections = IntGauge::new(
+            "db_pool_idle_connections",
+            "Number of idle database connections",
+        )?;
+        let db_pool_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Maximum number of database connections",
+        )?;
+        let search_duration = Histogram::with_opts(HistogramOpts::new(
+            "search_duration_seconds",
+            "Duration of post search queries",
+        ))?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(upload_bytes_written.clone()))?;
+        registry.register(Box::new(upload_status_transitions.clone()))?;
+        registry.register(Box::new(cleanup_runs.clone()))?;
+        registry.register(Box::new(cleanup_removed_files.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(db_pool_idle_connections.clone()))?;
+        registry.register(Box::new(db_pool_max_connections.clone()))?;
+        registry.register(Box::new(search_duration.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            upload_bytes_written,
+            upload_status_transitions,
+            cleanup_runs,
+            cleanup_removed_files,
+            db_pool_connections,
+            db_pool_idle_connections,
+            db_pool_max_connections,
+            search_duration,
+        })
+    }
+
+    pub fn record_upload_status_transition(&self, from: &UploadStatus, to: &UploadStatus) {
+        let from = format!("{:?}", from).to_lowercase();
+        let to = format!("{:?}", to).to_lowercase();
+        self.upload_status_transitions
+            .with_label_values(&[from.as_str(), to.as_str()])
+            .inc();
+    }
+
+    /// Update database pool gauges, they are set when metrics are requested.
+    pub fn update_pool_metrics(&self, pool: &Pool<Postgres>) {
+        self.db_pool_connections.set(pool.size().into());
+        self.db_pool_idle_connections
+            .set(pool.num_idle().try_into().unwrap_or(i64::MAX));
+        self.db_pool_max_connections
+            .set(pool.options().get_max_connections().into());
+    }
+}
+
+/// Encode metrics from registry in Prometheus text format.
+pub fn encode_metrics(registry: &Registry) -> Result<String, error::Error> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+    String::from_utf8(buffer).map_err(|err| error::Error::Misc(err.to_string()))
+}
+
+/// Guard for metrics endpoint, see [`MetricsConfig`].
+#[derive(Clone, Copy, Debug)]
+pub struct MetricsAccess;
+
+#[async_trait]
+impl<'r> FromRequest<'r> for MetricsAccess {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let config = match req.rocket().state::<MetricsConfig>() {
+            Some(config) => config,
+            None => return request::Outcome::Error((Status::Forbidden, ())),
+        };
+        let bearer_token = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        // Without trusted proxy address of proxied request is address of proxy itself.
+        let ip = if config.trust_proxy || !req.headers().contains("X-Forwarded-For") {
+            Some(get_client_ip(req, config.trust_proxy))
+        } else {
+            None
+        };
+
+        if config.is_allowed(ip, bearer_token) {
+            request::Outcome::Success(Self)
+        } else {
+            request::Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RequestStartTime(Instant);
+
+/// Fairing which counts HTTP requests by route and response status and measures their duration.
+/// Requests which did not match any route are counted with `unmatched` route.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsFairing {
+    config: MetricsConfig,
+}
+
+impl MetricsFairing {
+    pub fn new(config: MetricsConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fairing for MetricsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Metrics",
+            kind: Kind::Ignite | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket
+            .manage(METRICS.registry.clone())
+            .manage(self.config.clone()))
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStartTime(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let start_time = req.local_cache(|| RequestStartTime(Instant::now())).0;
+        let status = res.status().code.to_string();
+        let route = req
+            .route()
+            .map(|route| route.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+
+        METRICS
+            .http_requests
+            .with_label_values(&[route.as_str(), status.as_str()])
+            .inc();
+        METRICS
+            .http_request_duration
+            .with_label_values(&[route.as_str()])
+            .observe(start_time.elapsed().as_secs_f64());
+    }
+}
@@ -1,8 +1,20 @@
 #[allow(non_snake_case)]
 pub mod api;
+pub mod backup;
 pub mod db;
+pub mod events;
+pub mod idempotency;
+pub mod metrics;
+pub mod openapi;
+pub mod page_meta;
+pub mod rate_limit;
+pub mod security_headers;
+pub mod shutdown;
+pub mod sitemap;
 pub mod storage;
+pub mod telegram;
 pub mod templates;
 #[allow(non_snake_case)]
 #[allow(clippy::redundant_locals)]
 pub mod views;
+pub mod webhooks;
@@ -0,0 +1,634 @@
+use rocket::http::Method;
+use serde_json::{json, Map, Value};
+
+use crate::utils::tus::TUS_VERSION;
+
+/// Routes under `/api` which are not called by clients directly.
+#[cfg(test)]
+pub const UNDOCUMENTED_API_PATHS: [&str; 1] = ["/api/rate-limited"];
+
+/// Description of API route. It is maintained by hand, so test checks that every mounted `/api`
+/// route is described and every described route is mounted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiOperation {
+    pub method: Method,
+    pub path: &'static str,
+    pub summary: &'static str,
+    pub parameters: Vec<Value>,
+    pub request_body: Option<Value>,
+    pub response: Value,
+    /// Responses with other statuses which are not [`crate::error::ErrorResponse`].
+    pub other_responses: Vec<(u16, Value)>,
+    pub requires_authentication: bool,
+}
+
+impl ApiOperation {
+    /// Path in OpenAPI format, with `{name}` instead of `<name>` for parameters.
+    pub fn openapi_path(&self) -> String {
+        rocket_path_to_openapi(self.path)
+    }
+}
+
+pub fn rocket_path_to_openapi(path: &str) -> String {
+    path.replace('<', "{").replace('>', "}")
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "content": { "application/json": { "schema": schema } } })
+}
+
+fn path_id_parameter(description: &str) -> Value {
+    json!({
+        "name": "id",
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "integer", "format": "int64" }
+    })
+}
+
+fn tus_resumable_parameter() -> Value {
+    json!({
+        "name": "Tus-Resumable",
+        "in": "header",
+        "required": true,
+        "schema": { "type": "string", "enum": [TUS_VERSION] }
+    })
+}
+
+fn idempotency_key_parameter() -> Value {
+    json!({
+        "name": "Idempotency-Key",
+        "in": "header",
+        "required": false,
+        "description": "repeated requests with the same key and body get saved response for 24 hours",
+        "schema": { "type": "string", "minLength": 1, "maxLength": 255 }
+    })
+}
+
+pub fn list_api_operations() -> Vec<ApiOperation> {
+    vec![
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/openapi.json",
+            summary: "Get this OpenAPI document",
+            parameters: vec![],
+            request_body: None,
+            response: json!({ "type": "object" }),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/csrf",
+            summary: "Get token for X-CSRF-Token header, it changes after login",
+            parameters: vec![],
+            request_body: None,
+            response: schema_ref("CsrfTokenResponse"),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/posts",
+            summary: "List posts, content is only included for posts visible to user",
+            parameters: vec![
+                json!({
+                    "name": "page_id",
+                    "in": "query",
+                    "required": false,
+                    "description": "page number starting from 0, last page by default",
+                    "schema": { "type": "integer", "minimum": 0 }
+                }),
+                json!({
+                    "name": "page_size",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer", "minimum": 1 }
+                }),
+            ],
+            request_body: None,
+            response: schema_ref("PostsListResponse"),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/posts/by-id/<id>",
+            summary: "Get post, content is only included if post is visible to user",
+            parameters: vec![path_id_parameter("post ID")],
+            request_body: None,
+            response: schema_ref("PostItemResponse"),
+            other_responses: vec![
+                (403, schema_ref("PostItemResponse")),
+                (451, schema_ref("PostItemResponse")),
+            ],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/oembed",
+            summary: "Get oEmbed description of post, only public posts can be embedded",
+            parameters: vec![
+                json!({
+                    "name": "url",
+                    "in": "query",
+                    "required": true,
+                    "description": "absolute URL of post page",
+                    "schema": { "type": "string" }
+                }),
+                json!({
+                    "name": "format",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "string", "enum": ["json"] }
+                }),
+                json!({
+                    "name": "maxwidth",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer", "minimum": 0 }
+                }),
+                json!({
+                    "name": "maxheight",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer", "minimum": 0 }
+                }),
+            ],
+            request_body: None,
+            response: schema_ref("OEmbedResponse"),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/posts/add",
+            summary: "Create post",
+            parameters: vec![idempotency_key_parameter()],
+            request_body: Some(schema_ref("PostAddRequest")),
+            response: schema_ref("PostAddResponseOk"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/posts/add-batch",
+            summary: "Create several posts, either all of them or none",
+            parameters: vec![],
+            request_body: Some(json!({
+                "type": "array",
+                "items": schema_ref("PostAddRequest")
+            })),
+            response: schema_ref("PostAddBatchResponse"),
+            other_responses: vec![(422, schema_ref("PostAddBatchResponse"))],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/posts/by-id/<id>/edit",
+            summary: "Edit post, missing fields are not changed",
+            parameters: vec![path_id_parameter("post ID")],
+            request_body: Some(schema_ref("PostEditRequest")),
+            response: schema_ref("EmptyResponse"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/uploads/add",
+            summary: "Allocate file upload for post",
+            parameters: vec![idempotency_key_parameter()],
+            request_body: Some(schema_ref("UploadAddRequest")),
+            response: schema_ref("UploadAddResponseOk"),
+            other_responses: vec![(422, schema_ref("UploadAddResponseQuotaExceeded"))],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Put,
+            path: "/api/uploads/by-id/<id>/upload-by-chunk",
+            summary: "Write chunk of file, position is set by Content-Range header, body size must match it",
+            parameters: vec![
+                path_id_parameter("upload ID"),
+                json!({
+                    "name": "Content-Range",
+                    "in": "header",
+                    "required": true,
+                    "schema": { "type": "string", "example": "bytes 0-1023/4096" }
+                }),
+            ],
+            request_body: Some(json!({ "type": "string", "format": "binary" })),
+            response: schema_ref("EmptyResponse"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/uploads/by-id/<id>/finalize",
+            summary: "Publish file after all chunks are written",
+            parameters: vec![path_id_parameter("upload ID")],
+            request_body: None,
+            response: schema_ref("EmptyResponse"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/uploads/by-id/<id>/remove",
+            summary: "Hide published file",
+            parameters: vec![path_id_parameter("upload ID")],
+            request_body: None,
+            response: schema_ref("EmptyResponse"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/uploads/by-id/<id>/preview",
+            summary: "Get beginning of published text file attached to visible post",
+            parameters: vec![path_id_parameter("upload ID")],
+            request_body: None,
+            response: schema_ref("TextPreview"),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Options,
+            path: "/api/tus",
+            summary:
+                "Get supported tus protocol version, extensions and maximum file size in headers",
+            parameters: vec![],
+            request_body: None,
+            response: json!({}),
+            other_responses: vec![],
+            requires_authentication: false,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/tus",
+            summary: "Create upload with tus protocol, its URL is returned in Location header",
+            parameters: vec![
+                tus_resumable_parameter(),
+                json!({
+                    "name": "Upload-Length",
+                    "in": "header",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int64", "minimum": 1 }
+                }),
+                json!({
+                    "name": "Upload-Metadata",
+                    "in": "header",
+                    "required": true,
+                    "description": "Base64-encoded `post_id` and optional `filename`",
+                    "schema": { "type": "string", "example": "post_id MQ==,filename ZmlsZS50eHQ=" }
+                }),
+            ],
+            request_body: None,
+            response: json!({}),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Head,
+            path: "/api/tus/<id>",
+            summary: "Get number of written bytes of upload in Upload-Offset header",
+            parameters: vec![path_id_parameter("upload ID"), tus_resumable_parameter()],
+            request_body: None,
+            response: json!({}),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Patch,
+            path: "/api/tus/<id>",
+            summary:
+                "Append data to upload with tus protocol, file is published when it is complete",
+            parameters: vec![
+                path_id_parameter("upload ID"),
+                tus_resumable_parameter(),
+                json!({
+                    "name": "Upload-Offset",
+                    "in": "header",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int64", "minimum": 0 }
+                }),
+            ],
+            request_body: Some(json!({ "type": "string", "format": "binary" })),
+            response: json!({}),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Post,
+            path: "/api/users/me/avatar/add",
+            summary: "Allocate avatar upload, it is set as avatar when finalized",
+            parameters: vec![],
+            request_body: Some(schema_ref("AvatarUploadAddRequest")),
+            response: schema_ref("UploadAddResponseOk"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+        ApiOperation {
+            method: Method::Get,
+            path: "/api/ban-reasons/by-id/<id>",
+            summary: "Get ban reason, only for administrators",
+            parameters: vec![json!({
+                "name": "id",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" }
+            })],
+            request_body: None,
+            response: schema_ref("BanReason"),
+            other_responses: vec![],
+            requires_authentication: true,
+        },
+    ]
+}
+
+fn post_add_request_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["title", "description", "is_hidden", "is_pinned"],
+        "properties": {
+            "title": { "type": "string", "minLength": 1, "maxLength": 500 },
+            "description": { "type": "string" },
+            "is_hidden": { "type": "boolean" },
+            "min_age": { "type": "integer", "nullable": true, "minimum": 0, "maximum": 21 },
+            "content_rating_id": { "type": "string", "nullable": true },
+            "is_pinned": { "type": "boolean" }
+        }
+    })
+}
+
+fn post_edit_request_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "title": { "type": "string", "nullable": true, "minLength": 1, "maxLength": 500 },
+            "description": { "type": "string", "nullable": true },
+            "is_hidden": { "type": "boolean", "nullable": true },
+            "min_age": { "type": "integer", "nullable": true, "minimum": 0, "maximum": 21 },
+            "content_rating_id": { "type": "string", "nullable": true },
+            "is_pinned": { "type": "boolean", "nullable": true }
+        }
+    })
+}
+
+fn post_item_response_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "post content is only included if `visibility` is `visible`, `banned` posts include ban reason and `age_restricted` posts include minimal age",
+        "required": ["id", "visibility"],
+        "properties": {
+            "id": { "type": "integer", "format": "int64" },
+            "visibility": {
+                "type": "string",
+                "enum": ["visible", "hidden", "banned", "age_restricted", "quarantined"]
+            },
+            "creation_date": { "type": "string", "format": "date-time" },
+            "title": { "type": "string" },
+            "description": { "type": "string" },
+            "author_username": { "type": "string" },
+            "author_display_name": { "type": "string", "nullable": true },
+            "is_hidden": { "type": "boolean" },
+            "is_pinned": { "type": "boolean" },
+            "min_age": { "type": "integer", "nullable": true },
+            "content_rating_id": { "type": "string", "nullable": true },
+            "uploads": { "type": "array", "items": schema_ref("Upload") },
+            "reason": { "type": "string", "nullable": true },
+            "reason_text": { "type": "string", "nullable": true },
+            "expires_at": { "type": "string", "format": "date-time", "nullable": true }
+        }
+    })
+}
+
+fn error_response_schema() -> Value {
+    json!({
+        "oneOf": [
+            {
+                "type": "string",
+                "enum": [
+                    "Misc", "Sqlx", "PasswordHash", "PoolNotFound", "Rocket", "AccessDenied",
+                    "PasswordChangeRequired", "DoesNotExist", "InvalidPagination",
+                    "PageDoesNotExist", "IO", "InvalidUploadState", "InvalidContentRange", "Mail",
+                    "ShuttingDown", "RateLimited", "IdempotencyKeyConflict", "Webhook", "Telegram",
+                    "ArchiveTooLarge", "UploadChunkTooLarge", "UploadChunkSizeMismatch", "Unknown"
+                ]
+            },
+            {
+                "type": "object",
+                "required": ["ValidationErrors"],
+                "properties": { "ValidationErrors": schema_ref("ValidationErrors") }
+            }
+        ]
+    })
+}
+
+fn component_schemas() -> Value {
+    json!({
+        "PostAddRequest": post_add_request_schema(),
+        "PostAddResponseOk": {
+            "type": "object",
+            "required": ["id", "url"],
+            "properties": {
+                "id": { "type": "integer", "format": "int64" },
+                "url": { "type": "string" }
+            }
+        },
+        "PostAddBatchResponse": {
+            "type": "object",
+            "description": "results in the same order as posts in request, posts are created only if all of them are valid",
+            "required": ["items"],
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "oneOf": [
+                            {
+                                "type": "object",
+                                "required": ["Ok"],
+                                "properties": { "Ok": schema_ref("PostAddResponseOk") }
+                            },
+                            { "type": "string", "enum": ["Valid"] },
+                            {
+                                "type": "object",
+                                "required": ["ValidationErrors"],
+                                "properties": { "ValidationErrors": schema_ref("ValidationErrors") }
+                            }
+                        ]
+                    }
+                }
+            }
+        },
+        "PostEditRequest": post_edit_request_schema(),
+        "PostItemResponse": post_item_response_schema(),
+        "PostsListResponse": {
+            "type": "object",
+            "required": ["items", "page_id", "page_size", "page_count", "total_item_count"],
+            "properties": {
+                "items": { "type": "array", "items": schema_ref("PostItemResponse") },
+                "page_id": { "type": "integer" },
+                "page_size": { "type": "integer" },
+                "page_count": { "type": "integer" },
+                "total_item_count": { "type": "integer" }
+            }
+        },
+        "OEmbedResponse": {
+            "type": "object",
+            "required": [
+                "type", "version", "title", "author_name", "author_url", "provider_name",
+                "provider_url", "html", "width", "height"
+            ],
+            "properties": {
+                "type": { "type": "string", "enum": ["rich", "video"] },
+                "version": { "type": "string", "enum": ["1.0"] },
+                "title": { "type": "string" },
+                "author_name": { "type": "string" },
+                "author_url": { "type": "string" },
+                "provider_name": { "type": "string" },
+                "provider_url": { "type": "string" },
+                "html": { "type": "string" },
+                "width": { "type": "integer" },
+                "height": { "type": "integer" }
+            }
+        },
+        "Upload": {
+            "type": "object",
+            "required": ["id", "size", "url"],
+            "properties": {
+                "id": { "type": "integer", "format": "int64" },
+                "extension": { "type": "string", "nullable": true },
+                "size": { "type": "integer", "format": "int64" },
+                "url": { "type": "string" }
+            }
+        },
+        "UploadAddRequest": {
+            "type": "object",
+            "required": ["size", "post_id"],
+            "properties": {
+                "size": { "type": "integer", "minimum": 1 },
+                "extension": {
+                    "type": "string",
+                    "nullable": true,
+                    "maxLength": 32,
+                    "pattern": "^[a-zA-Z0-9_]+$"
+                },
+                "post_id": { "type": "integer", "format": "int64" }
+            }
+        },
+        "UploadAddResponseOk": {
+            "type": "object",
+            "required": ["id"],
+            "properties": { "id": { "type": "integer", "format": "int64" } }
+        },
+        "UploadAddResponseQuotaExceeded": {
+            "type": "object",
+            "required": ["remaining_bytes"],
+            "properties": { "remaining_bytes": { "type": "integer", "format": "int64" } }
+        },
+        "TextPreview": {
+            "type": "object",
+            "required": ["content", "is_truncated"],
+            "properties": {
+                "content": { "type": "string" },
+                "is_truncated": { "type": "boolean" }
+            }
+        },
+        "AvatarUploadAddRequest": {
+            "type": "object",
+            "required": ["size", "extension"],
+            "properties": {
+                "size": { "type": "integer", "minimum": 1 },
+                "extension": { "type": "string", "enum": ["png", "jpg", "jpeg", "gif", "webp"] }
+            }
+        },
+        "BanReason": {
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "string" },
+                "description": { "type": "string", "nullable": true },
+                "default_text": { "type": "string", "nullable": true }
+            }
+        },
+        "CsrfTokenResponse": {
+            "type": "object",
+            "required": ["csrf_token"],
+            "properties": { "csrf_token": { "type": "string" } }
+        },
+        "EmptyResponse": { "type": "object" },
+        "ValidationErrors": {
+            "type": "object",
+            "description": "validation errors by field name",
+            "additionalProperties": true
+        },
+        "ErrorResponse": error_response_schema()
+    })
+}
+
+/// Build OpenAPI 3 document for `operations`.
+pub fn build_openapi_document(operations: &[ApiOperation]) -> Value {
+    let mut paths = Map::new();
+    for operation in operations {
+        let mut operation_object = json!({
+            "summary": operation.summary,
+            "parameters": operation.parameters,
+            "responses": {
+                "200": json_content(operation.response.clone()),
+                "default": json_content(schema_ref("ErrorResponse"))
+            }
+        });
+        for (status, schema) in operation.other_responses.iter() {
+            operation_object["responses"][status.to_string()] = json_content(schema.clone());
+        }
+        if let Some(request_body) = &operation.request_body {
+            let content_type = if request_body.get("format") == Some(&json!("binary")) {
+                "application/octet-stream"
+            } else {
+                "application/json"
+            };
+            operation_object["requestBody"] = json!({
+                "required": true,
+                "content": { content_type: { "schema": request_body } }
+            });
+        }
+        if operation.requires_authentication {
+            operation_object["security"] = json!([{ "apiToken": [] }, { "csrfToken": [] }]);
+        }
+
+        let path_object = paths
+            .entry(operation.openapi_path())
+            .or_insert_with(|| json!({}));
+        path_object[operation.method.as_str().to_lowercase()] = operation_object;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "archivanima API",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths,
+        "components": {
+            "schemas": component_schemas(),
+            "securitySchemes": {
+                "apiToken": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "personal API token, tokens with read scope can only be used for GET requests"
+                },
+                "csrfToken": {
+                    "type": "apiKey",
+                    "in": "header",
+                    "name": "X-CSRF-Token",
+                    "description": "CSRF token, required for requests authenticated with session cookie"
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use rocket::uri;
+
+use crate::{
+    app::db::Post,
+    utils::{breadcrumbs::Breadcrumb, locale::Locale, post_layout::PostListLayout},
+};
+
+/// Site name, it is name of root breadcrumb and suffix of every document title.
+pub const SITE_NAME: &str = "archivanima";
+
+lazy_static! {
+    pub static ref BREADCRUMB_ROOT: Breadcrumb = Breadcrumb::new_with_url(
+        SITE_NAME.to_string(),
+        uri!(crate::app::views::index_get()).to_string()
+    );
+    pub static ref BREADCRUMB_POSTS: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.posts",
+        uri!(crate::app::views::posts_list_get(
+            None as Option<u64>,
+            None as Option<u64>,
+            None as Option<PostListLayout>
+        ))
+        .to_string()
+    );
+    pub static ref BREADCRUMB_USERS: Breadcrumb =
+        Breadcrumb::message_without_url("breadcrumb.users");
+}
+
+/// Document title and breadcrumb trail of page.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageMeta {
+    /// Page name in document title. If it is not set, names of breadcrumbs are used.
+    pub title: Option<String>,
+    pub breadcrumbs: Vec<Breadcrumb>,
+}
+
+impl PageMeta {
+    pub fn new(breadcrumbs: Vec<Breadcrumb>) -> Self {
+        Self {
+            title: None,
+            breadcrumbs,
+        }
+    }
+
+    pub fn with_title(self, title: String) -> Self {
+        Self {
+            title: Some(title),
+            ..self
+        }
+    }
+
+    pub fn index() -> Self {
+        Self::new(vec![Breadcrumb::new_without_url(SITE_NAME.to_string())])
+    }
+
+    /// Page right under root, `key` is message key of its name.
+    pub fn section(key: &str) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            Breadcrumb::message_without_url(key),
+        ])
+    }
+
+    /// Page under `parent` page, `key` is message key of its name.
+    pub fn subsection(parent: &Breadcrumb, key: &str) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            parent.clone(),
+            Breadcrumb::message_without_url(key),
+        ])
+    }
+
+    /// Detail page of post which is visible to user.
+    pub fn for_post(post: &Post) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            BREADCRUMB_POSTS.clone(),
+            Breadcrumb::new_without_url(format!("#{}: {}", post.id, post.title)),
+        ])
+        .with_title(post.title.clone())
+    }
+
+    /// Detail page of post which is not visible to user, so only its ID is shown.
+    pub fn for_post_id(post_id: i64) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            BREADCRUMB_POSTS.clone(),
+            Breadcrumb::new_without_url(format!("#{}", post_id)),
+        ])
+    }
+
+    /// Page of action with post, `key` is message key of action name.
+    pub fn for_post_action(post_id: i64, key: &str) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            BREADCRUMB_POSTS.clone(),
+            Breadcrumb::new_with_url(
+                format!("#{}", post_id),
+                uri!(crate::app::views::post_detail_get(post_id)).to_string(),
+            ),
+            Breadcrumb::message_without_url(key),
+        ])
+    }
+
+    pub fn for_user(username: &str) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            BREADCRUMB_USERS.clone(),
+            Breadcrumb::new_without_url(username.to_string()),
+        ])
+    }
+
+    /// Page of action with user, `key` is message key of action name.
+    pub fn for_user_action(username: &str, key: &str) -> Self {
+        Self::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            BREADCRUMB_USERS.clone(),
+            Breadcrumb::new_with_url(
+                username.to_string(),
+                uri!(crate::app::views::user_detail_get(username)).to_string(),
+            ),
+            Breadcrumb::message_without_url(key),
+        ])
+    }
+
+    /// Document title: page name followed by site name, or site name alone for root page.
+    pub fn title(&self, locale: &Locale) -> String {
+        let mut parts: Vec<Cow<'_, str>> = match &self.title {
+            Some(title) => vec![Cow::Borrowed(title)],
+            None => self
+                .breadcrumbs
+                .iter()
+                .skip(1)
+                .rev()
+                .map(|breadcrumb| breadcrumb.name(locale))
+                .collect(),
+        };
+        parts.push(Cow::Borrowed(SITE_NAME));
+        parts.join(" — ")
+    }
+}
+
+impl From<Vec<Breadcrumb>> for PageMeta {
+    fn from(breadcrumbs: Vec<Breadcrumb>) -> Self {
+        Self::new(breadcrumbs)
+    }
+}
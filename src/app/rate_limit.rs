@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rocket::{
+    async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    http::{Method, Status},
+    request::{self, FromRequest},
+    uri, Build, Data, Orbit, Request, Rocket,
+};
+use tokio::time::{sleep, Instant};
+
+use crate::{auth::get_session_token, AuthConfig, RateLimitBucketConfig, RateLimitConfig};
+
+/// Interval of removing buckets which are full again, so that memory is not wasted on clients
+/// which stopped sending requests.
+const RATE_LIMIT_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RateLimitGroup {
+    Uploads,
+    Posts,
+    Search,
+}
+
+impl RateLimitGroup {
+    pub fn from_path(path: &str) -> Option<Self> {
+        if path.starts_with("/api/uploads/") || path.starts_with("/api/tus") {
+            Some(Self::Uploads)
+        } else if path.starts_with("/api/posts/") {
+            Some(Self::Posts)
+        } else if path == "/posts/search" {
+            Some(Self::Search)
+        } else {
+            None
+        }
+    }
+}
+
+/// Clients with session cookie are limited by session, other ones (including ones using API
+/// tokens) by IP address. Session is not checked in database: fairing runs before routing, so
+/// [`crate::auth::Authentication`] guard can not be resolved yet (it depends on route), and private
+/// cookie can not be forged anyway.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Session(String),
+    Ip(IpAddr),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, config: &RateLimitBucketConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() / config.refill_interval.as_secs_f64())
+            .min(config.capacity as f64);
+        self.updated_at = now;
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(RateLimitGroup, RateLimitKey), TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_bucket_config(&self, group: RateLimitGroup) -> Option<RateLimitBucketConfig> {
+        match group {
+            RateLimitGroup::Uploads => self.config.uploads,
+            RateLimitGroup::Posts => self.config.posts,
+            RateLimitGroup::Search => self.config.search,
+        }
+    }
+
+    /// Take one request from bucket, returns time after which request can be retried if bucket is
+    /// empty.
+    pub fn check(
+        &self,
+        group: RateLimitGroup,
+        key: RateLimitKey,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let bucket_config = match self.get_bucket_config(group) {
+            Some(bucket_config) => bucket_config,
+            None => return Ok(()),
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry((group, key)).or_insert(TokenBucket {
+            tokens: bucket_config.capacity as f64,
+            updated_at: now,
+        });
+        bucket.refill(&bucket_config, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(bucket_config.refill_interval.mul_f64(1.0 - bucket.tokens))
+        }
+    }
+
+    /// Remove buckets which are full, they are same as missing ones.
+    pub fn prune(&self, now: Instant) {
+        self.buckets.lock().unwrap().retain(|(group, _), bucket| {
+            match self.get_bucket_config(*group) {
+                Some(bucket_config) => {
+                    bucket.refill(&bucket_config, now);
+                    bucket.tokens < bucket_config.capacity as f64
+                }
+                None => false,
+            }
+        });
+    }
+
+    #[cfg(test)]
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+}
+
+/// Get client IP address, if `trust_proxy` is set, last address from `X-Forwarded-For` header is
+/// used: it is appended by trusted proxy, while preceding addresses are sent by client.
+pub fn get_client_ip(req: &Request<'_>, trust_proxy: bool) -> IpAddr {
+    let forwarded_ip = if trust_proxy {
+        req.headers()
+            .get("X-Forwarded-For")
+            .last()
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|value| value.trim().parse().ok())
+    } else {
+        None
+    };
+    forwarded_ip
+        .or_else(|| req.remote().map(|remote| remote.ip()))
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/// Number of seconds after which request rejected by [`RateLimitFairing`] can be retried.
+#[derive(Clone, Copy, Debug, Default)]
+struct RateLimitExceeded(Option<u64>);
+
+/// Guard for route which rejected requests are rerouted to.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimited {
+    pub retry_after: u64,
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RateLimited {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.local_cache(RateLimitExceeded::default).0 {
+            Some(retry_after) => request::Outcome::Success(Self { retry_after }),
+            None => request::Outcome::Forward(Status::NotFound),
+        }
+    }
+}
+
+/// Fairing which limits request rate for route groups. Fairings can not respond to requests, so
+/// rejected requests are rerouted to [`crate::app::api::rate_limited_get`].
+#[derive(Clone, Debug, Default)]
+pub struct RateLimitFairing {
+    config: RateLimitConfig,
+}
+
+impl RateLimitFairing {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Fairing for RateLimitFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rate limit",
+            kind: Kind::Ignite | Kind::Liftoff | Kind::Request,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket.manage(Arc::new(RateLimiter::new(self.config.clone()))))
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        if let Some(rate_limiter) = rocket.state::<Arc<RateLimiter>>() {
+            let rate_limiter = rate_limiter.clone();
+            let mut shutdown = rocket.shutdown();
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = sleep(RATE_LIMIT_PRUNE_INTERVAL) => {
+                            rate_limiter.prune(Instant::now());
+                        }
+                        _ = &mut shutdown => break,
+                    }
+                }
+            });
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let group = match RateLimitGroup::from_path(req.uri().path().as_str()) {
+            Some(group) => group,
+            None => return,
+        };
+        let rate_limiter = match req.rocket().state::<Arc<RateLimiter>>() {
+            Some(rate_limiter) => rate_limiter.clone(),
+            None => return,
+        };
+
+        let session_token = req
+            .rocket()
+            .state::<AuthConfig>()
+            .and_then(|auth_config| get_session_token(req.cookies(), auth_config));
+        let key = match session_token {
+            Some(session_token) => RateLimitKey::Session(session_token),
+            None => RateLimitKey::Ip(get_client_ip(req, self.config.trust_proxy)),
+        };
+
+        if let Err(retry_after) = rate_limiter.check(group, key, Instant::now()) {
+            req.local_cache(|| RateLimitExceeded(Some(retry_after.as_secs_f64().ceil() as u64)));
+            req.set_method(Method::Get);
+            req.set_uri(uri!(crate::app::api::rate_limited_get()));
+        }
+    }
+}
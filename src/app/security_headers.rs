@@ -0,0 +1,136 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+use rand::{distributions::Standard, thread_rng, Rng};
+use rocket::{
+    async_trait,
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    Request, Response,
+};
+
+use crate::SecurityHeadersConfig;
+
+/// Names of routes which can be shown in frames on other sites.
+const FRAMEABLE_ROUTES: [&str; 1] = ["post_embed_get"];
+
+/// Placeholder for nonce of inline scripts in `Content-Security-Policy`.
+pub const CSP_NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// Random value which allows inline scripts of single response, it is generated once per request
+/// and shared by templates and response header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CspNonce(String);
+
+impl CspNonce {
+    fn generate() -> Self {
+        let nonce_bytes: Vec<u8> = thread_rng().sample_iter(Standard).take(16).collect();
+        Self(BASE64_STANDARD.encode(&nonce_bytes[..]))
+    }
+}
+
+pub fn get_csp_nonce(req: &Request<'_>) -> String {
+    req.local_cache(CspNonce::generate).0.clone()
+}
+
+fn is_frameable(req: &Request<'_>) -> bool {
+    req.route()
+        .and_then(|route| route.name.as_deref())
+        .is_some_and(|route_name| FRAMEABLE_ROUTES.contains(&route_name))
+}
+
+/// Get source of resources for base URL. Relative URLs point to site itself, which is always
+/// allowed, so source is returned only for absolute HTTP(S) URLs.
+fn get_base_url_source(base_url: &str) -> Option<&str> {
+    if base_url.starts_with("https://") || base_url.starts_with("http://") {
+        Some(base_url)
+    } else {
+        None
+    }
+}
+
+/// Replace value of directive in `Content-Security-Policy`, directive is added if it is missing.
+fn replace_csp_directive(policy: &str, name: &str, value: &str) -> String {
+    let mut directives: Vec<&str> = policy
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| {
+            !directive.is_empty() && directive.split_whitespace().next() != Some(name)
+        })
+        .collect();
+    let replaced_directive = format!("{} {}", name, value);
+    directives.push(&replaced_directive);
+    directives.join("; ")
+}
+
+/// Fairing which adds `Content-Security-Policy`, `X-Content-Type-Options`, `X-Frame-Options` and
+/// `Referrer-Policy` headers to every response. Routes from [`FRAMEABLE_ROUTES`] can be framed
+/// by any site.
+#[derive(Clone, Debug)]
+pub struct SecurityHeadersFairing {
+    config: SecurityHeadersConfig,
+    content_security_policy: String,
+}
+
+impl SecurityHeadersFairing {
+    /// Create fairing, resources from `base_urls` (asset and media base URLs) are allowed by
+    /// default policy.
+    pub fn new(config: SecurityHeadersConfig, base_urls: &[&str]) -> Self {
+        let content_security_policy = match &config.content_security_policy {
+            Some(content_security_policy) => content_security_policy.clone(),
+            None => {
+                let sources: String = base_urls
+                    .iter()
+                    .filter_map(|base_url| get_base_url_source(base_url))
+                    .map(|source| format!(" {}", source))
+                    .collect();
+                format!(
+                    "default-src 'self'{sources}; script-src 'self'{sources} 'nonce-{nonce}'; \
+                    object-src 'none'; base-uri 'self'; frame-ancestors {frame_ancestors}",
+                    sources = sources,
+                    nonce = CSP_NONCE_PLACEHOLDER,
+                    frame_ancestors = config.frame_options.frame_ancestors(),
+                )
+            }
+        };
+        Self {
+            config,
+            content_security_policy,
+        }
+    }
+}
+
+#[async_trait]
+impl Fairing for SecurityHeadersFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let content_security_policy = self
+            .content_security_policy
+            .replace(CSP_NONCE_PLACEHOLDER, &get_csp_nonce(req));
+
+        if is_frameable(req) {
+            res.set_header(Header::new(
+                "Content-Security-Policy",
+                replace_csp_directive(&content_security_policy, "frame-ancestors", "*"),
+            ));
+        } else {
+            res.set_header(Header::new(
+                "Content-Security-Policy",
+                content_security_policy,
+            ));
+            res.set_header(Header::new(
+                "X-Frame-Options",
+                self.config.frame_options.header_value(),
+            ));
+        }
+        res.set_header(Header::new("X-Content-Type-Options", "nosniff"));
+        res.set_header(Header::new(
+            "Referrer-Policy",
+            self.config.referrer_policy.clone(),
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use log::{info, warn};
+use rocket::{
+    async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    Build, Orbit, Rocket,
+};
+use sqlx::{Pool, Postgres};
+use tokio::time::{sleep, Instant};
+
+use crate::app::db::{try_set_upload_status, UploadStatus};
+
+/// Time to wait for in-flight chunk writes on shutdown.
+pub const UPLOAD_SHUTDOWN_WAIT: Duration = Duration::from_secs(5);
+
+const UPLOAD_SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Chunk uploads which are written by this process, used to stop accepting new chunks on shutdown.
+#[derive(Debug, Default)]
+pub struct UploadShutdownState {
+    is_shutting_down: AtomicBool,
+    writing_upload_ids: Mutex<HashSet<i64>>,
+}
+
+/// Upload chunk write in progress, it is finished when guard is dropped.
+pub struct UploadWriteGuard<'a> {
+    state: &'a UploadShutdownState,
+    id: i64,
+}
+
+impl Drop for UploadWriteGuard<'_> {
+    fn drop(&mut self) {
+        self.state
+            .writing_upload_ids
+            .lock()
+            .unwrap()
+            .remove(&self.id);
+    }
+}
+
+impl UploadShutdownState {
+    pub fn is_shutting_down(&self) -> bool {
+        self.is_shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Register chunk write, returns `None` if server is shutting down.
+    pub fn begin_write(&self, id: i64) -> Option<UploadWriteGuard<'_>> {
+        let mut writing_upload_ids = self.writing_upload_ids.lock().unwrap();
+        if self.is_shutting_down() {
+            return None;
+        }
+        writing_upload_ids.insert(id);
+        Some(UploadWriteGuard { state: self, id })
+    }
+
+    pub fn writing_upload_ids(&self) -> Vec<i64> {
+        self.writing_upload_ids
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+
+    /// Stop accepting new chunks, wait up to `wait` for in-flight writes and move uploads which
+    /// are still being written back to `Allocated` status, so that they can be resumed after
+    /// restart.
+    pub async fn shutdown(&self, wait: Duration, pool: &Pool<Postgres>) {
+        {
+            let _writing_upload_ids = self.writing_upload_ids.lock().unwrap();
+            self.is_shutting_down.store(true, Ordering::SeqCst);
+        }
+
+        let deadline = Instant::now() + wait;
+        while !self.writing_upload_ids.lock().unwrap().is_empty() && Instant::now() < deadline {
+            sleep(UPLOAD_SHUTDOWN_POLL_INTERVAL).await;
+        }
+
+        for id in self.writing_upload_ids() {
+            info!("Interrupting write of upload {}", id);
+            if let Err(err) = try_set_upload_status(id, UploadStatus::Allocated, pool).await {
+                warn!("Failed to reset status of upload {}: {:?}", id, err);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UploadShutdownFairing {}
+
+#[async_trait]
+impl Fairing for UploadShutdownFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Upload shutdown",
+            kind: Kind::Ignite | Kind::Shutdown,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket.manage(UploadShutdownState::default()))
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        if let (Some(state), Some(pool)) = (
+            rocket.state::<UploadShutdownState>(),
+            rocket.state::<Pool<Postgres>>(),
+        ) {
+            state.shutdown(UPLOAD_SHUTDOWN_WAIT, pool).await;
+        }
+    }
+}
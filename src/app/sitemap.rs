@@ -0,0 +1,201 @@
+use std::pin::Pin;
+
+use async_stream::stream;
+use log::warn;
+use rocket::{response::stream::TextStream, uri};
+use sqlx::{Pool, Postgres};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio_stream::Stream;
+
+use crate::{
+    app::db::{list_public_post_ids_and_dates, list_public_usernames},
+    utils::pagination::{Page, PageOverflowBehavior, PageParams},
+};
+
+/// Maximum number of URLs in one sitemap file, larger sitemaps are split into chunks listed in
+/// sitemap index.
+pub const SITEMAP_MAX_URL_COUNT: u64 = 50_000;
+
+/// Number of entries loaded from database at once while streaming sitemap.
+const SITEMAP_PAGE_SIZE: u64 = 1_000;
+
+const SITEMAP_CHUNK_PAGE_COUNT: u64 = SITEMAP_MAX_URL_COUNT / SITEMAP_PAGE_SIZE;
+
+const SITEMAP_XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const SITEMAP_NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+pub type SitemapStream = TextStream<Pin<Box<dyn Stream<Item = String> + Send>>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SitemapSection {
+    Users,
+    Posts,
+}
+
+impl SitemapSection {
+    /// Load page of sitemap entries as XML.
+    async fn get_entries(
+        self,
+        site_url: &str,
+        page_params: PageParams,
+        pool: &Pool<Postgres>,
+    ) -> Result<Page<String>, crate::error::Error> {
+        Ok(match self {
+            Self::Users => list_public_usernames(page_params, pool)
+                .await?
+                .map(|username| {
+                    format_sitemap_entry(
+                        "url",
+                        &format!(
+                            "{}{}",
+                            site_url,
+                            uri!(crate::app::views::user_detail_get(username.as_str()))
+                        ),
+                        None,
+                    )
+                }),
+            Self::Posts => list_public_post_ids_and_dates(page_params, pool)
+                .await?
+                .map(|post| {
+                    format_sitemap_entry(
+                        "url",
+                        &format!(
+                            "{}{}",
+                            site_url,
+                            uri!(crate::app::views::post_detail_get(post.id))
+                        ),
+                        Some(post.updated_at),
+                    )
+                }),
+        })
+    }
+
+    /// Get number of entries in sitemap section.
+    pub async fn get_item_count(self, pool: &Pool<Postgres>) -> Result<u64, crate::error::Error> {
+        let page_params = PageParams {
+            page_id: Some(0),
+            page_size: 1,
+            overflow_behavior: PageOverflowBehavior::Clamp,
+        };
+        Ok(match self {
+            Self::Users => {
+                list_public_usernames(page_params, pool)
+                    .await?
+                    .total_item_count
+            }
+            Self::Posts => {
+                list_public_post_ids_and_dates(page_params, pool)
+                    .await?
+                    .total_item_count
+            }
+        })
+    }
+}
+
+/// Get number of sitemap files needed for `item_count` URLs.
+pub fn get_sitemap_chunk_count(item_count: u64) -> u64 {
+    item_count.div_ceil(SITEMAP_MAX_URL_COUNT)
+}
+
+fn format_sitemap_entry(tag: &str, loc: &str, lastmod: Option<OffsetDateTime>) -> String {
+    let lastmod = lastmod
+        .and_then(|lastmod| lastmod.format(&Rfc3339).ok())
+        .map(|lastmod| format!("<lastmod>{}</lastmod>", lastmod))
+        .unwrap_or_default();
+    format!(
+        "<{tag}><loc>{}</loc>{}</{tag}>\n",
+        html_escape::encode_text(loc),
+        lastmod
+    )
+}
+
+/// Stream sitemap `urlset`. If `chunk` is given, only URLs of chunk with given ID in section are
+/// listed, otherwise all URLs are listed.
+///
+/// Entries are loaded from database page by page while response is sent. If loading fails, error
+/// is logged and response is truncated, because status is already sent.
+pub fn stream_sitemap_urlset(
+    pool: Pool<Postgres>,
+    site_url: String,
+    chunk: Option<(SitemapSection, u64)>,
+) -> SitemapStream {
+    let (sections, first_page_id, page_count) = match chunk {
+        Some((section, chunk_id)) => (
+            vec![section],
+            chunk_id * SITEMAP_CHUNK_PAGE_COUNT,
+            SITEMAP_CHUNK_PAGE_COUNT,
+        ),
+        None => (
+            vec![SitemapSection::Users, SitemapSection::Posts],
+            0,
+            u64::MAX,
+        ),
+    };
+
+    let urlset: Pin<Box<dyn Stream<Item = String> + Send>> = Box::pin(stream! {
+        yield format!("{}\n<urlset xmlns=\"{}\">\n", SITEMAP_XML_HEADER, SITEMAP_NAMESPACE);
+        // Pages are iterated without `iterate_pages`: future of async closure is not `Send`, and
+        // response stream should be `Send`.
+        for section in sections {
+            let last_page_id = first_page_id.saturating_add(page_count);
+            let mut page_id = first_page_id;
+            while page_id < last_page_id {
+                let page_params = PageParams {
+                    page_id: Some(page_id),
+                    page_size: SITEMAP_PAGE_SIZE,
+                    overflow_behavior: PageOverflowBehavior::Error,
+                };
+                match section.get_entries(&site_url, page_params, &pool).await {
+                    Ok(page) => {
+                        yield page.items.concat();
+                        if (page_id + 1) >= page.page_count {
+                            break;
+                        }
+                        page_id += 1;
+                    }
+                    Err(crate::error::Error::PageDoesNotExist) => break,
+                    Err(err) => {
+                        warn!("Failed to load sitemap {:?}: {:?}", section, err);
+                        return;
+                    }
+                }
+            }
+        }
+        yield "</urlset>\n".to_string();
+    });
+    TextStream::from(urlset)
+}
+
+/// Stream sitemap index listing chunks of each section.
+pub fn stream_sitemap_index(site_url: String, user_count: u64, post_count: u64) -> SitemapStream {
+    let index: Pin<Box<dyn Stream<Item = String> + Send>> = Box::pin(stream! {
+        yield format!(
+            "{}\n<sitemapindex xmlns=\"{}\">\n",
+            SITEMAP_XML_HEADER, SITEMAP_NAMESPACE
+        );
+        for chunk_id in 0..get_sitemap_chunk_count(user_count) {
+            yield format_sitemap_entry(
+                "sitemap",
+                &format!(
+                    "{}{}",
+                    site_url,
+                    uri!(crate::app::views::sitemap_users_get(chunk_id))
+                ),
+                None,
+            );
+        }
+        for chunk_id in 0..get_sitemap_chunk_count(post_count) {
+            yield format_sitemap_entry(
+                "sitemap",
+                &format!(
+                    "{}{}",
+                    site_url,
+                    uri!(crate::app::views::sitemap_posts_get(chunk_id))
+                ),
+                None,
+            );
+        }
+        yield "</sitemapindex>\n".to_string();
+    });
+    TextStream::from(index)
+}
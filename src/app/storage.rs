@@ -1,12 +1,29 @@
-use std::io::SeekFrom;
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, SeekFrom},
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
-use log::debug;
+use log::{debug, warn};
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
 use tokio::{
-    fs::{File, OpenOptions},
-    io::{copy, AsyncRead, AsyncSeekExt},
+    fs::{metadata, read_dir, remove_file, File, OpenOptions},
+    io::{copy, AsyncRead, AsyncReadExt, AsyncSeekExt},
 };
 
-use crate::{utils::try_remove_file, UploadStorage};
+use crate::{
+    app::{db::Upload, metrics::METRICS},
+    utils::{
+        signed_token::{SignedTokenKey, FILE_TOKEN_PARAMETER},
+        try_remove_file,
+    },
+    UploadStorage,
+};
 
 pub fn get_file_name(id: i64, extension: Option<&str>) -> String {
     match extension {
@@ -15,6 +32,15 @@ pub fn get_file_name(id: i64, extension: Option<&str>) -> String {
     }
 }
 
+/// Get upload ID from name of file in storage, see [`get_file_name`].
+pub fn parse_file_name(file_name: &str) -> Option<i64> {
+    let id = file_name.split('.').next()?;
+    if id.len() != 16 {
+        return None;
+    }
+    i64::from_str_radix(id, 16).ok()
+}
+
 pub fn get_file_url<'a, 'b: 'a>(
     id: i64,
     extension: Option<&'a str>,
@@ -31,6 +57,26 @@ pub fn get_file_url<'a, 'b: 'a>(
     }
 }
 
+/// Get URL of file with token which is checked by media route, token expires after `expiry`.
+pub fn get_signed_file_url(
+    id: i64,
+    extension: Option<&str>,
+    expiry: Duration,
+    storage: &UploadStorage,
+    key: &SignedTokenKey,
+) -> String {
+    let token = key.sign_file_name(
+        &get_file_name(id, extension),
+        OffsetDateTime::now_utc() + expiry,
+    );
+    format!(
+        "{}?{}={}",
+        get_file_url(id, extension, storage),
+        FILE_TOKEN_PARAMETER,
+        token
+    )
+}
+
 pub async fn allocate_private_file(
     id: i64,
     extension: Option<&str>,
@@ -52,13 +98,14 @@ pub async fn allocate_private_file(
     }
 }
 
+/// Write data to private file starting from `start_pos`, returns number of written bytes.
 pub async fn write_private_file<'r, 'a, R>(
     id: i64,
     extension: Option<&str>,
     data: &mut R,
     start_pos: u64,
     storage: &UploadStorage,
-) -> std::io::Result<()>
+) -> std::io::Result<u64>
 where
     R: AsyncRead,
     R: Unpin,
@@ -72,8 +119,37 @@ where
             let file_path = private_path.join(get_file_name(id, extension));
             let mut file = OpenOptions::new().write(true).open(file_path).await?;
             file.seek(SeekFrom::Start(start_pos)).await?;
-            copy(data, &mut file).await?;
-            Ok(())
+            let written_size = copy(data, &mut file).await?;
+            METRICS.upload_bytes_written.inc_by(written_size);
+            Ok(written_size)
+        }
+    }
+}
+
+/// Get hex-encoded SHA-256 hash of private file.
+pub async fn hash_private_file(
+    id: i64,
+    extension: Option<&str>,
+    storage: &UploadStorage,
+) -> std::io::Result<String> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path,
+            public_path: _,
+            base_url: _,
+        } => {
+            let file_path = private_path.join(get_file_name(id, extension));
+            let mut file = File::open(file_path).await?;
+            let mut hasher = Sha256::new();
+            let mut buffer = vec![0u8; 64 * 1024];
+            loop {
+                let read_size = file.read(&mut buffer).await?;
+                if read_size == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read_size]);
+            }
+            Ok(format!("{:x}", hasher.finalize()))
         }
     }
 }
@@ -97,6 +173,95 @@ pub async fn publish_file<'r, 'a>(
     }
 }
 
+/// Copy published file to given path outside of storage.
+pub async fn copy_public_file(
+    id: i64,
+    extension: Option<&str>,
+    destination: &Path,
+    storage: &UploadStorage,
+) -> std::io::Result<()> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path: _,
+            public_path,
+            base_url: _,
+        } => {
+            tokio::fs::copy(public_path.join(get_file_name(id, extension)), destination).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Open published file for reading.
+pub async fn open_public_file(
+    id: i64,
+    extension: Option<&str>,
+    storage: &UploadStorage,
+) -> std::io::Result<File> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path: _,
+            public_path,
+            base_url: _,
+        } => File::open(public_path.join(get_file_name(id, extension))).await,
+    }
+}
+
+/// Maximum number of bytes of text file shown in preview.
+pub const TEXT_PREVIEW_MAX_SIZE: u64 = 8 * 1024;
+
+/// Beginning of published text file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TextPreview {
+    pub content: String,
+    pub is_truncated: bool,
+}
+
+/// Read at most [`TEXT_PREVIEW_MAX_SIZE`] bytes of published file, invalid UTF-8 sequences are
+/// replaced.
+pub async fn read_text_preview(
+    id: i64,
+    extension: Option<&str>,
+    storage: &UploadStorage,
+) -> std::io::Result<TextPreview> {
+    let mut buffer = Vec::new();
+    open_public_file(id, extension, storage)
+        .await?
+        .take(TEXT_PREVIEW_MAX_SIZE + 1)
+        .read_to_end(&mut buffer)
+        .await?;
+
+    let is_truncated = buffer.len() as u64 > TEXT_PREVIEW_MAX_SIZE;
+    buffer.truncate(TEXT_PREVIEW_MAX_SIZE as usize);
+    let mut content = String::from_utf8_lossy(&buffer).into_owned();
+    if is_truncated {
+        // Last character may be cut in the middle.
+        content.truncate(content.trim_end_matches('\u{FFFD}').len());
+    }
+
+    Ok(TextPreview {
+        content,
+        is_truncated,
+    })
+}
+
+/// Read previews of text uploads, keyed by upload ID. Files which can not be read are skipped.
+pub async fn read_text_previews(
+    uploads: &[Upload],
+    storage: &UploadStorage,
+) -> HashMap<i64, TextPreview> {
+    let mut previews = HashMap::new();
+    for upload in uploads.iter().filter(|upload| upload.is_text()) {
+        match read_text_preview(upload.id, upload.extension.as_deref(), storage).await {
+            Ok(preview) => {
+                previews.insert(upload.id, preview);
+            }
+            Err(err) => warn!("Failed to read preview of upload {}: {:?}", upload.id, err),
+        }
+    }
+    previews
+}
+
 pub async fn unpublish_file<'r, 'a>(
     id: i64,
     extension: Option<&str>,
@@ -115,3 +280,102 @@ pub async fn unpublish_file<'r, 'a>(
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StorageDiskUsage {
+    pub private_size: u64,
+    pub public_size: u64,
+}
+
+async fn get_directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    let mut entries = read_dir(path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Get actual size of files in private and public storage directories.
+pub async fn get_storage_disk_usage(storage: &UploadStorage) -> std::io::Result<StorageDiskUsage> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path,
+            public_path,
+            base_url: _,
+        } => Ok(StorageDiskUsage {
+            private_size: get_directory_size(private_path).await?,
+            public_size: get_directory_size(public_path).await?,
+        }),
+    }
+}
+
+/// Get size of published file, or `None` if it does not exist.
+pub async fn get_public_file_size(
+    id: i64,
+    extension: Option<&str>,
+    storage: &UploadStorage,
+) -> std::io::Result<Option<u64>> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path: _,
+            public_path,
+            base_url: _,
+        } => match metadata(public_path.join(get_file_name(id, extension))).await {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+/// Check that storage directories are writable by creating and removing probe file.
+pub async fn probe_storage(storage: &UploadStorage) -> std::io::Result<()> {
+    match storage {
+        UploadStorage::FileSystem {
+            private_path,
+            public_path,
+            base_url: _,
+        } => {
+            let probe_file_name = format!(".probe-{:016x}", thread_rng().gen::<u64>());
+            for path in [private_path, public_path] {
+                let probe_file_path = path.join(&probe_file_name);
+                File::create(&probe_file_path).await?;
+                remove_file(probe_file_path).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Time to keep result of storage probe for readiness check.
+pub const STORAGE_PROBE_CACHE_TIME: Duration = Duration::from_secs(5);
+
+/// Last result of [`probe_storage`], so that frequent readiness checks do not touch disk.
+#[derive(Debug, Default)]
+pub struct StorageProbeCache {
+    last_probe: Mutex<Option<(Instant, bool)>>,
+}
+
+impl StorageProbeCache {
+    pub async fn is_writable(&self, storage: &UploadStorage) -> bool {
+        if let Some((probe_time, is_writable)) = *self.last_probe.lock().unwrap() {
+            if probe_time.elapsed() < STORAGE_PROBE_CACHE_TIME {
+                return is_writable;
+            }
+        }
+
+        let is_writable = match probe_storage(storage).await {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Storage probe failed: {}", err);
+                false
+            }
+        };
+        *self.last_probe.lock().unwrap() = Some((Instant::now(), is_writable));
+        is_writable
+    }
+}
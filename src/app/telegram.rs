@@ -0,0 +1,212 @@
+use std::sync::Mutex;
+
+use log::{info, warn};
+use rocket::{
+    async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    Build, Orbit, Rocket,
+};
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+use tokio::{sync::mpsc::UnboundedReceiver, time::sleep};
+
+use crate::{
+    app::{
+        db::{try_get_public_post, Post},
+        events::{subscribe_to_event_bus, PostEvent},
+    },
+    error, EmailConfig, TelegramConfig,
+};
+
+/// Maximum length of post description in Telegram messages, in characters.
+const TELEGRAM_DESCRIPTION_LENGTH: usize = 500;
+
+#[derive(Clone, Debug, Serialize)]
+struct SendMessageRequest<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+    parse_mode: &'static str,
+}
+
+/// Format message about post for Telegram `HTML` parse mode.
+pub fn format_telegram_message(post: &Post, site_url: &str) -> String {
+    let mut message = format!("<b>{}</b>\n\n", html_escape::encode_text(&post.title));
+
+    let description = post.description.trim();
+    if !description.is_empty() {
+        let description = match description.char_indices().nth(TELEGRAM_DESCRIPTION_LENGTH) {
+            Some((end_pos, _)) => format!("{}…", description[0..end_pos].trim_end()),
+            None => description.to_string(),
+        };
+        message.push_str(&html_escape::encode_text(&description));
+        message.push_str("\n\n");
+    }
+
+    message.push_str(&html_escape::encode_text(&format!(
+        "{}{}",
+        site_url.trim_end_matches('/'),
+        post.detail_url()
+    )));
+    message
+}
+
+pub struct TelegramNotifier {
+    config: TelegramConfig,
+    site_url: String,
+    client: reqwest::Client,
+}
+
+impl TelegramNotifier {
+    pub fn new(config: TelegramConfig, site_url: &str) -> Result<Self, error::Error> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(config.timeout)
+                .build()
+                .map_err(|err| error::Error::Telegram(err.to_string()))?,
+            config,
+            site_url: site_url.to_string(),
+        })
+    }
+
+    async fn send_message(&self, text: &str) -> Result<(), error::Error> {
+        // Request URL contains bot token, so it is not included in errors.
+        let response = self
+            .client
+            .post(format!(
+                "{}/bot{}/sendMessage",
+                self.config.api_url.trim_end_matches('/'),
+                self.config.bot_token
+            ))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&SendMessageRequest {
+                chat_id: &self.config.channel_id,
+                text,
+                parse_mode: "HTML",
+            })?)
+            .send()
+            .await
+            .map_err(|err| error::Error::Telegram(err.without_url().to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(error::Error::Telegram(format!(
+                "Bot API responded with status {}",
+                response.status()
+            )))
+        }
+    }
+
+    /// Announce post if event made it public and it is still visible to anonymous users. Failed
+    /// requests are retried up to `max_attempts` times. Returns `true` if message was sent.
+    pub async fn notify(
+        &self,
+        event: &PostEvent,
+        pool: &Pool<Postgres>,
+    ) -> Result<bool, error::Error> {
+        if !event.is_publication() {
+            return Ok(false);
+        }
+        let post = match try_get_public_post(event.post_id, pool).await? {
+            Some(post) => post,
+            None => return Ok(false),
+        };
+        let message = format_telegram_message(&post, &self.site_url);
+
+        let mut attempt_count = 0;
+        loop {
+            attempt_count += 1;
+            match self.send_message(&message).await {
+                Ok(()) => return Ok(true),
+                Err(err) if attempt_count < self.config.max_attempts => {
+                    warn!(
+                        "Failed to send post {} to Telegram (attempt {}): {:?}",
+                        post.id, attempt_count, err
+                    );
+                    sleep(self.config.retry_interval).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Fairing which subscribes to [`crate::app::events::EventBus`] and announces new public posts in
+/// Telegram channel. It does nothing if Telegram is not configured.
+pub struct TelegramFairing {
+    config: Option<TelegramConfig>,
+    receiver: Mutex<Option<UnboundedReceiver<PostEvent>>>,
+}
+
+impl TelegramFairing {
+    pub fn new(config: Option<TelegramConfig>) -> Self {
+        Self {
+            config,
+            receiver: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Fairing for TelegramFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Telegram",
+            kind: Kind::Ignite | Kind::Liftoff,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        if self.config.is_none() {
+            return Ok(rocket);
+        }
+        let (rocket, receiver) = subscribe_to_event_bus(rocket);
+        *self.receiver.lock().unwrap() = Some(receiver);
+        Ok(rocket)
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let config = match &self.config {
+            Some(config) => config.clone(),
+            None => return,
+        };
+        let (pool, email_config) = match (
+            rocket.state::<Pool<Postgres>>(),
+            rocket.state::<EmailConfig>(),
+        ) {
+            (Some(pool), Some(email_config)) => (pool.clone(), email_config),
+            _ => return,
+        };
+        let mut receiver = match self.receiver.lock().unwrap().take() {
+            Some(receiver) => receiver,
+            None => return,
+        };
+        let notifier = match TelegramNotifier::new(config, &email_config.site_url) {
+            Ok(notifier) => notifier,
+            Err(err) => {
+                warn!("Failed to start Telegram notifier: {:?}", err);
+                return;
+            }
+        };
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = receiver.recv() => match event {
+                        Some(event) => match notifier.notify(&event, &pool).await {
+                            Ok(true) => info!("Post {} is sent to Telegram", event.post_id),
+                            Ok(false) => {}
+                            Err(err) => warn!(
+                                "Failed to send post {} to Telegram: {:?}",
+                                event.post_id, err
+                            ),
+                        },
+                        None => break,
+                    },
+                    _ = &mut shutdown => break,
+                }
+            }
+        });
+    }
+}
@@ -1,164 +1,616 @@
-use archivanima_macros::TemplateWithQuery;
+use std::{collections::HashMap, time::Duration};
+
+use archivanima_macros::{TemplateWithFlash, TemplateWithQuery};
 use artushak_web_assets::asset_cache::AssetCacheManifest;
 use askama::Template;
-use rocket::uri;
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use rocket::{
+    async_trait,
+    outcome::try_outcome,
+    request::{self, FromRequest},
+    time::OffsetDateTime,
+    uri, Request, State,
+};
 
 use crate::{
-    app::db::{BanReason, Post, PostVisibility, User},
+    app::{
+        db::{
+            AdminDashboardStats, ApiToken, AuditAction, AuditLogEntry, BanAppeal, BanAppealStatus,
+            BanReason, BulkUserAction, ContentRating, FeedPost, Invite, Post, PostVisibility,
+            Session, StaticPage, Upload, UploadMediaKind, UploaderRequest, User, UserAdminDetails,
+            UserEmail, UserProfile, UserSort, UserStatus, UserStorageUsage, Webhook,
+            WebhookDelivery,
+        },
+        openapi::ApiOperation,
+        page_meta::PageMeta,
+        security_headers::get_csp_nonce,
+        storage::{get_file_url, get_signed_file_url, TextPreview},
+    },
     auth::Authentication,
+    error,
     utils::{
-        breadcrumbs::Breadcrumb, form_definition::FormDefinition, pagination::Page,
+        flash::FlashMessage,
+        form_definition::FormDefinition,
+        locale::Locale,
+        pagination::Page,
+        post_layout::PostListLayout,
+        signed_token::SignedTokenKey,
+        theme::Theme,
+        timezone::{get_utc_offset, timezone_offset_from_cookies},
         url_query::UrlQuery,
     },
-    UploadStorage,
+    FooterPage, RegistrationMode, UploadStorage,
 };
 
+/// Maximum length of post description in feeds, in characters.
+pub const FEED_SUMMARY_LENGTH: usize = 500;
+
+/// Check if URL from Markdown link is allowed: it should be relative or use `http`, `https` or
+/// `mailto` scheme.
+fn is_markdown_url_allowed(url: &str) -> bool {
+    match url.find([':', '/', '?', '#']) {
+        Some(pos) if url[pos..].starts_with(':') => matches!(
+            url[..pos].to_ascii_lowercase().as_str(),
+            "http" | "https" | "mailto"
+        ),
+        _ => true,
+    }
+}
+
+fn markdown_link_start(url: &str, title: &str) -> String {
+    let mut tag = format!(
+        "<a href=\"{}\" rel=\"nofollow noopener\"",
+        html_escape::encode_double_quoted_attribute(url)
+    );
+    if !title.is_empty() {
+        tag.push_str(&format!(
+            " title=\"{}\"",
+            html_escape::encode_double_quoted_attribute(title)
+        ));
+    }
+    tag.push('>');
+    tag
+}
+
+/// Render Markdown text (post description) to HTML. Raw HTML from source is escaped, links with
+/// disallowed URLs are replaced with their text, other links get `rel="nofollow noopener"` and
+/// images are rendered as links. Single line breaks are kept like in plain text descriptions.
+pub fn render_markdown(text: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    // Stack of link and image starts, `true` means that link was rendered and should be closed.
+    let mut link_stack = vec![];
+    let events = Parser::new_ext(text, options).filter_map(|event| match event {
+        Event::Html(html) => Some(Event::Text(html)),
+        Event::SoftBreak => Some(Event::HardBreak),
+        Event::Start(Tag::Link(_, url, title) | Tag::Image(_, url, title)) => {
+            let is_allowed = is_markdown_url_allowed(&url);
+            link_stack.push(is_allowed);
+            is_allowed.then(|| Event::Html(markdown_link_start(&url, &title).into()))
+        }
+        Event::End(Tag::Link(..) | Tag::Image(..)) => link_stack
+            .pop()
+            .unwrap_or(false)
+            .then(|| Event::Html("</a>".into())),
+        event => Some(event),
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Format date with `time` format description in timezone with given offset from UTC in minutes,
+/// see [`crate::utils::timezone`]. Absent date is formatted as empty string.
+pub fn format_datetime(
+    date: Option<&OffsetDateTime>,
+    format: &str,
+    timezone_offset: i32,
+) -> Result<String, time::Error> {
+    let date = match date {
+        Some(date) => date,
+        None => return Ok(String::new()),
+    };
+    let format_description = time::format_description::parse(format)?;
+    Ok(date
+        .to_offset(get_utc_offset(timezone_offset))
+        .format(&format_description)?)
+}
+
+/// Date which may be absent, so that `format_datetime` filter accepts both `OffsetDateTime` and
+/// `Option<OffsetDateTime>`.
+pub trait OptionalDateTime {
+    fn get_date_time(&self) -> Option<&OffsetDateTime>;
+}
+
+impl OptionalDateTime for OffsetDateTime {
+    fn get_date_time(&self) -> Option<&OffsetDateTime> {
+        Some(self)
+    }
+}
+
+impl OptionalDateTime for Option<OffsetDateTime> {
+    fn get_date_time(&self) -> Option<&OffsetDateTime> {
+        self.as_ref()
+    }
+}
+
+impl<T: OptionalDateTime> OptionalDateTime for &T {
+    fn get_date_time(&self) -> Option<&OffsetDateTime> {
+        (*self).get_date_time()
+    }
+}
+
 pub trait TemplateWithQuery {
     fn query(&self) -> Option<&str>;
 }
 
+/// Template which can show flash message in base template, derived for templates with
+/// `flash_message` field.
+pub trait TemplateWithFlash {
+    fn flash_message(&self) -> Option<&FlashMessage>;
+}
+
 #[derive(Clone, Debug)]
 pub struct AssetContext {
     pub asset_cache: AssetCacheManifest,
     pub base_url: String,
+    pub registration_mode: RegistrationMode,
+    pub footer_pages: Vec<FooterPage>,
 }
 
-#[derive(TemplateWithQuery, Template)]
-#[template(path = "index.html")]
-pub struct IndexTemplate<'a, 'b> {
+/// Data shown by base template on every page. Everything except page metadata is taken from
+/// request, so views get context as request guard and set metadata with [`PageContext::with_meta`].
+#[derive(Clone, Debug)]
+pub struct PageContext<'a> {
     pub user: Authentication,
     pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub locale: Locale,
+    pub theme: Theme,
+    /// Offset of user timezone from UTC in minutes, dates are shown in this timezone.
+    pub timezone_offset: i32,
+    /// Nonce of inline scripts, see [`crate::app::security_headers`].
+    pub csp_nonce: String,
+    pub meta: PageMeta,
+}
+
+impl<'a> PageContext<'a> {
+    pub fn with_meta(self, meta: impl Into<PageMeta>) -> Self {
+        Self {
+            meta: meta.into(),
+            ..self
+        }
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for PageContext<'r> {
+    type Error = error::Error;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = try_outcome!(req.guard::<Authentication>().await);
+        let asset_context: &State<AssetContext> = match req.guard().await {
+            request::Outcome::Success(asset_context) => asset_context,
+            request::Outcome::Error((status, ())) => {
+                return request::Outcome::Error((
+                    status,
+                    error::Error::Misc("asset context not found".to_string()),
+                ))
+            }
+            request::Outcome::Forward(status) => return request::Outcome::Forward(status),
+        };
+
+        request::Outcome::Success(Self {
+            user,
+            asset_context,
+            locale: Locale::resolve(req),
+            theme: Theme::from_cookies(req.cookies()),
+            timezone_offset: timezone_offset_from_cookies(req.cookies()).unwrap_or(0),
+            csp_nonce: get_csp_nonce(req),
+            meta: PageMeta::default(),
+        })
+    }
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "index.html")]
+pub struct IndexTemplate<'a, 'b> {
+    pub context: PageContext<'a>,
     pub pinned_posts: Vec<(i64, PostVisibility)>,
+    pub has_more_pinned_posts: bool,
     pub storage: &'b UploadStorage,
+    pub flash_message: Option<FlashMessage>,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "form.html")]
 pub struct FormTemplate<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
+    pub form: FormDefinition,
+}
+
+/// Page with confirmation message and form without fields, see `confirm` mode of
+/// `form_get_and_post!`.
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "confirm.html")]
+pub struct ConfirmTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub message: String,
+    pub form: FormDefinition,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "posts/ban.html")]
+pub struct PostBanFormTemplate<'a> {
+    pub context: PageContext<'a>,
     pub form: FormDefinition,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "registration-disabled.html")]
+pub struct RegistrationDisabledTemplate<'a> {
+    pub context: PageContext<'a>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "message.html")]
+pub struct MessageTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub message: String,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "error.html")]
+pub struct ErrorTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub status_code: u16,
+    pub message: String,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "users/detail.html")]
-pub struct UserDetailTemplate<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+pub struct UserDetailTemplate<'a, 'b> {
+    pub context: PageContext<'a>,
     pub item: User,
+    pub email: Option<UserEmail>,
+    pub profile: UserProfile,
+    pub storage: &'b UploadStorage,
+    pub storage_usage: Option<UserStorageUsage>,
+    pub default_storage_quota_bytes: u64,
+    pub invite_allowance: Option<i32>,
+    pub admin_details: Option<UserAdminDetails>,
+    pub ban_appeals: Vec<BanAppeal>,
+    pub flash_message: Option<FlashMessage>,
+    pub csrf_token: String,
+}
+
+#[derive(Template)]
+#[template(path = "feeds/atom.xml")]
+pub struct AtomFeedTemplate {
+    pub title: String,
+    pub site_url: String,
+    pub feed_url: String,
+    pub updated: OffsetDateTime,
+    pub items: Vec<FeedPost>,
+}
+
+#[derive(Template)]
+#[template(path = "feeds/rss.xml")]
+pub struct RssFeedTemplate {
+    pub title: String,
+    pub site_url: String,
+    pub feed_url: String,
+    pub updated: OffsetDateTime,
+    pub items: Vec<FeedPost>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "admin/dashboard.html")]
+pub struct AdminDashboardTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub stats: AdminDashboardStats,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "posts/ban-appeals.html")]
+pub struct BanAppealsListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub items: Vec<BanAppeal>,
+    pub flash_message: Option<FlashMessage>,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "users/avatar-edit.html")]
+pub struct AvatarEditTemplate<'a, 'b> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub avatar: Option<Upload>,
+    pub form: FormDefinition,
+    pub storage: &'b UploadStorage,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "users/verify-email.html")]
+pub struct EmailVerificationTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub verified_username: Option<String>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "users/list.html")]
 pub struct UsersListTemplate<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub page: Page<User>,
     pub page_base: UrlQuery,
+    pub sort: Option<String>,
+    pub status: Option<String>,
+    pub csrf_token: String,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "invites/list.html")]
+pub struct InvitesListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub page: Page<Invite>,
+    pub page_base: UrlQuery,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "audit-log.html")]
+pub struct AuditLogTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub page: Page<AuditLogEntry>,
+    pub page_base: UrlQuery,
+    pub actor: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "webhooks/list.html")]
+pub struct WebhooksListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub items: Vec<Webhook>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "webhooks/deliveries.html")]
+pub struct WebhookDeliveriesTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub webhook: Webhook,
+    pub page: Page<WebhookDelivery>,
+    pub page_base: UrlQuery,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "users/uploader-requests.html")]
+pub struct UploaderRequestsListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub items: Vec<UploaderRequest>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "users/sessions.html")]
+pub struct SessionsListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub items: Vec<Session>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "users/api-tokens.html")]
+pub struct ApiTokensListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub csrf_token: String,
+    pub items: Vec<ApiToken>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "api-docs.html")]
+pub struct ApiDocsTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub operations: Vec<ApiOperation>,
+    pub document: String,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "ban-reasons/list.html")]
 pub struct BanReasonListTemplate<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub items: Vec<BanReason>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "content-ratings/list.html")]
+pub struct ContentRatingListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub items: Vec<ContentRating>,
+    pub flash_message: Option<FlashMessage>,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "static-pages/detail.html")]
+pub struct StaticPageTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub item: StaticPage,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "static-pages/list.html")]
+pub struct StaticPageListTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub items: Vec<StaticPage>,
+    pub flash_message: Option<FlashMessage>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/list.html")]
 pub struct PostsListTemplate<'a, 'b> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
+    pub page: Page<(i64, PostVisibility)>,
+    pub storage: &'b UploadStorage,
+    pub page_base: UrlQuery,
+    pub layout: PostListLayout,
+    pub covers: HashMap<i64, Upload>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "posts/pinned.html")]
+pub struct PostsPinnedListTemplate<'a, 'b> {
+    pub context: PageContext<'a>,
     pub page: Page<(i64, PostVisibility)>,
     pub storage: &'b UploadStorage,
     pub page_base: UrlQuery,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/search.html")]
 pub struct PostsSearchTemplate<'a, 'b> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub query_string: Option<String>,
     pub page: Page<(i64, PostVisibility)>,
     pub storage: &'b UploadStorage,
     pub page_base: UrlQuery,
+    pub layout: PostListLayout,
+    pub covers: HashMap<i64, Upload>,
+}
+
+/// Post page without site layout, which can be embedded in other sites by `iframe`.
+#[derive(Template)]
+#[template(path = "posts/embed.html")]
+pub struct PostEmbedTemplate<'a, 'b> {
+    pub asset_context: &'a AssetContext,
+    pub locale: Locale,
+    pub theme: Theme,
+    pub item: Post,
+    pub storage: &'b UploadStorage,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/detail.html")]
 pub struct PostDetailTemplate<'a, 'b> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub item: Post,
     pub storage: &'b UploadStorage,
+    pub content_rating_form: Option<FormDefinition>,
+    pub text_previews: HashMap<i64, TextPreview>,
+    pub flash_message: Option<FlashMessage>,
+    /// Key and lifetime of signed file URLs, they are used only for age-restricted posts.
+    pub file_url_signing: Option<(SignedTokenKey, Duration)>,
 }
 
-#[derive(TemplateWithQuery, Template)]
+impl PostDetailTemplate<'_, '_> {
+    pub fn text_preview(&self, upload: &Upload) -> Option<&TextPreview> {
+        self.text_previews.get(&upload.id)
+    }
+
+    pub fn file_url(&self, upload: &Upload) -> String {
+        match &self.file_url_signing {
+            Some((signed_token_key, lifetime)) if self.item.min_age.is_some() => {
+                get_signed_file_url(
+                    upload.id,
+                    upload.extension.as_deref(),
+                    *lifetime,
+                    self.storage,
+                    signed_token_key,
+                )
+            }
+            _ => get_file_url(upload.id, upload.extension.as_deref(), self.storage),
+        }
+    }
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/detail-hidden.html")]
 pub struct PostDetailTemplateHidden<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
+    pub item_id: i64,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "posts/ban-by-hash.html")]
+pub struct UploadBanByHashTemplate<'a> {
+    pub context: PageContext<'a>,
+    pub form: FormDefinition,
+    pub post_ids: Vec<i64>,
+}
+
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
+#[template(path = "posts/detail-quarantined.html")]
+pub struct PostDetailTemplateQuarantined<'a> {
+    pub context: PageContext<'a>,
     pub item_id: i64,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/detail-age-restricted.html")]
 pub struct PostDetailTemplateAgeRestricted<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub item_id: i64,
     pub min_age: i32,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/detail-banned.html")]
 pub struct PostDetailTemplateBanned<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub item_id: i64,
     pub ban_reason: Option<BanReason>,
     pub ban_reason_text: Option<String>,
+    pub ban_expires_at: Option<OffsetDateTime>,
+    pub can_appeal_ban: bool,
+    pub last_ban_appeal: Option<BanAppeal>,
+    pub csrf_token: String,
+    pub flash_message: Option<FlashMessage>,
+}
+
+impl PostDetailTemplateBanned<'_> {
+    pub fn can_add_ban_appeal(&self) -> bool {
+        self.can_appeal_ban
+            && !self
+                .last_ban_appeal
+                .as_ref()
+                .is_some_and(|ban_appeal| ban_appeal.status == BanAppealStatus::Pending)
+    }
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/add.html")]
 pub struct PostAddTemplate<'a> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub csrf_token: String,
+    pub content_ratings: Vec<ContentRating>,
 }
 
-#[derive(TemplateWithQuery, Template)]
+#[derive(TemplateWithQuery, TemplateWithFlash, Template)]
 #[template(path = "posts/edit.html")]
 pub struct PostEditTemplate<'a, 'b> {
-    pub user: Authentication,
-    pub asset_context: &'a AssetContext,
-    pub breadcrumbs: Vec<Breadcrumb>,
+    pub context: PageContext<'a>,
     pub csrf_token: String,
     pub item: Post,
     pub storage: &'b UploadStorage,
+    pub content_ratings: Vec<ContentRating>,
 }
 
 mod filters {
     use std::fmt::Display;
 
-    use crate::utils::url_query::UrlQuery;
+    use html_escape::{encode_quoted_attribute, encode_text};
+    use rocket::time::OffsetDateTime;
+    use time::format_description::well_known::{Rfc2822, Rfc3339};
 
-    use super::AssetContext;
+    use crate::utils::{locale::Locale, post_layout::PostListLayout, url_query::UrlQuery};
+
+    use super::{AssetContext, OptionalDateTime};
 
     #[derive(Clone, Debug)]
     pub struct AssetNotFoundError {
@@ -240,6 +692,19 @@ mod filters {
             })??)
     }
 
+    pub fn render_markdown(input: &str) -> ::askama::Result<String> {
+        Ok(super::render_markdown(input))
+    }
+
+    pub fn format_datetime<T: OptionalDateTime>(
+        date: &T,
+        format: &str,
+        timezone_offset: &i32,
+    ) -> ::askama::Result<String> {
+        super::format_datetime(date.get_date_time(), format, *timezone_offset)
+            .map_err(|err| ::askama::Error::Custom(Box::new(err)))
+    }
+
     pub fn unwrap_or_string(
         string_option: &Option<String>,
         default_string: &str,
@@ -247,6 +712,47 @@ mod filters {
         Ok(string_option.clone().unwrap_or(default_string.to_string()))
     }
 
+    pub fn file_size(size: &i64, locale: &Locale) -> ::askama::Result<String> {
+        const UNITS: [&str; 4] = [
+            "unit.kibibyte",
+            "unit.mebibyte",
+            "unit.gibibyte",
+            "unit.tebibyte",
+        ];
+
+        if *size < 1024 {
+            return Ok(format!("{} {}", size, locale.translate("unit.byte")));
+        }
+        let mut value = *size as f64 / 1024.0;
+        let mut unit_index = 0;
+        while value >= 1024.0 && unit_index + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit_index += 1;
+        }
+        Ok(format!(
+            "{:.1} {}",
+            value,
+            locale.translate(UNITS[unit_index])
+        ))
+    }
+
+    pub fn truncate_chars(input: &str, max_length: usize) -> ::askama::Result<String> {
+        Ok(match input.char_indices().nth(max_length) {
+            Some((end_pos, _)) => format!("{}…", input[0..end_pos].trim_end()),
+            None => input.to_string(),
+        })
+    }
+
+    pub fn rfc3339(date: &OffsetDateTime) -> ::askama::Result<String> {
+        date.format(&Rfc3339)
+            .map_err(|err| ::askama::Error::Custom(Box::new(err)))
+    }
+
+    pub fn rfc2822(date: &OffsetDateTime) -> ::askama::Result<String> {
+        date.format(&Rfc2822)
+            .map_err(|err| ::askama::Error::Custom(Box::new(err)))
+    }
+
     pub fn strip_suffix(input: &str) -> ::askama::Result<String> {
         Ok(match input.rfind('.') {
             Some(suffix_start_pos) => input[0..suffix_start_pos].to_string(),
@@ -264,4 +770,50 @@ mod filters {
         url_copy.add("page_size".to_string(), page_size.to_string());
         ::askama::Result::Ok(url_copy.to_string())
     }
+
+    pub fn url_with_layout(url: &UrlQuery, layout: &PostListLayout) -> ::askama::Result<String> {
+        let mut url_copy = url.clone();
+        url_copy.add("layout".to_string(), layout.code().to_string());
+        ::askama::Result::Ok(url_copy.to_string())
+    }
+
+    pub fn page_jump_form(
+        url: &UrlQuery,
+        page_id: &u64,
+        page_count: &u64,
+        page_size: &u64,
+        locale: &Locale,
+    ) -> ::askama::Result<String> {
+        let mut result = "<form class=\"form-page-jump\" method=\"GET\">".to_string();
+        for (key, value) in url.iter() {
+            result += &("<input type=\"hidden\" name=\"".to_string()
+                + &encode_quoted_attribute(key)
+                + "\" value=\""
+                + &encode_quoted_attribute(value)
+                + "\" />");
+        }
+        result += &("<input type=\"hidden\" name=\"page_size\" value=\"".to_string()
+            + &page_size.to_string()
+            + "\" />");
+        result += &("<select name=\"page_id\" aria-label=\"".to_string()
+            + &encode_quoted_attribute(locale.translate("pagination.page_number"))
+            + "\">");
+        for option_page_id in 0..*page_count {
+            result += &("<option value=\"".to_string()
+                + &option_page_id.to_string()
+                + "\""
+                + if option_page_id == *page_id {
+                    " selected"
+                } else {
+                    ""
+                }
+                + ">"
+                + &(option_page_id + 1).to_string()
+                + "</option>");
+        }
+        result += &("</select> <button>".to_string()
+            + &encode_text(locale.translate("pagination.jump"))
+            + "</button></form>");
+        ::askama::Result::Ok(result)
+    }
 }
@@ -1,125 +1,214 @@
 use crate::{
     app::{
+        backup::{get_post_archive_size, stream_post_archive},
         db::{
-            change_user_password, list_ban_reasons, list_posts_with_pagination,
-            list_users_with_pagination, search_posts_with_pagination,
-            try_add_ban_reason_check_exists, try_add_invite_check_exists,
-            try_add_user_check_username_and_invite, try_ban_post_check_exists,
-            try_edit_ban_reason_check_exists, try_edit_user_check_exists, try_get_ban_reason,
-            try_get_post, try_get_user, try_get_user_full, try_remove_invite_check_exists,
-            try_unban_post_check_exists, BanReason, BanReasonIdSet, NewUser, PostVisibility, User,
-            UserStatus, UsernameAndInviteCheckError,
+            add_api_token, add_email_verification_token, add_failed_login_attempt,
+            add_failed_registration_attempt, add_password_reset_token, add_random_invites,
+            add_webhook, ban_all_posts_by_author, ban_posts_by_upload_hash, bulk_edit_users,
+            change_user_password, check_dummy_password, clear_login_attempts, delete_user_account,
+            get_admin_dashboard_stats, get_user_admin_details, get_user_theme,
+            get_user_timezone_offset, is_email_available, is_login_locked,
+            is_password_reset_token_valid, is_registration_locked, list_api_tokens_for_user,
+            list_audit_log_with_pagination, list_ban_appeals_by_author, list_ban_reasons,
+            list_content_ratings, list_invites_with_pagination, list_pending_ban_appeals,
+            list_pending_uploader_requests, list_pinned_posts_with_pagination,
+            list_post_cover_uploads, list_post_ids_by_upload_hash, list_posts_with_pagination,
+            list_public_posts_for_feed, list_sessions_for_user, list_static_pages,
+            list_users_with_pagination, list_webhook_deliveries_with_pagination, list_webhooks,
+            remove_unredeemed_invites_by_creator, remove_user_sessions,
+            search_posts_with_pagination, set_user_bio, set_user_birth_date, set_user_display_name,
+            set_user_invite_allowance, set_user_last_login, set_user_storage_quota, set_user_theme,
+            set_user_timezone_offset, try_add_ban_appeal_check_pending,
+            try_add_ban_reason_check_exists, try_add_content_rating_check_exists,
+            try_add_invite_check_exists, try_add_static_page_check_exists,
+            try_add_uploader_request_check_pending, try_add_user_check_username,
+            try_add_user_check_username_and_invite, try_add_user_invite_check_allowance,
+            try_ban_post_check_exists, try_change_user_birth_date_once,
+            try_decide_ban_appeal_check_pending, try_decide_uploader_request_check_pending,
+            try_edit_ban_reason_check_exists, try_edit_content_rating_check_exists,
+            try_edit_static_page_check_exists, try_edit_user_check_exists, try_get_ban_reason,
+            try_get_content_rating, try_get_invite, try_get_last_ban_appeal, try_get_post,
+            try_get_post_id_by_slug, try_get_public_post, try_get_static_page, try_get_user,
+            try_get_user_email, try_get_user_full, try_get_user_invite_allowance,
+            try_get_user_profile, try_get_user_storage_usage,
+            try_get_verified_email_for_password_reset, try_get_webhook,
+            try_remove_ban_reason_check_exists, try_remove_invite_check_exists,
+            try_remove_static_page_check_exists, try_remove_webhook_check_exists,
+            try_reset_password_with_token, try_revoke_api_token_check_owner,
+            try_revoke_session_check_owner, try_set_post_content_rating_check_exists,
+            try_set_post_quarantined_check_exists, try_set_user_email_check_unique,
+            try_toggle_webhook_check_exists, try_unban_post_check_exists, try_verify_email,
+            AccountDeletionMode, ApiTokenScope, AuditAction, BanAppealStatus, BanReason,
+            BanReasonIdSet, BanReasonRemoveResult, BulkUserAction, ContentRating, FeedPost,
+            NewUser, Post, PostVisibility, RatingIdSet, Upload, UploaderRequestStatus, User,
+            UserSort, UserStatus, UsernameAndInviteCheckError, WebhookEvent, DELETED_USERNAME,
         },
+        events::{EventBus, PostEvent},
+        metrics::{encode_metrics, MetricsAccess, METRICS},
+        openapi::{build_openapi_document, list_api_operations},
+        page_meta::{PageMeta, BREADCRUMB_POSTS, BREADCRUMB_ROOT, BREADCRUMB_USERS},
+        security_headers::get_csp_nonce,
+        sitemap::{
+            get_sitemap_chunk_count, stream_sitemap_index, stream_sitemap_urlset, SitemapSection,
+            SitemapStream, SITEMAP_MAX_URL_COUNT,
+        },
+        storage::read_text_previews,
         templates::{
-            AssetContext, BanReasonListTemplate, FormTemplate, IndexTemplate, PostAddTemplate,
-            PostDetailTemplate, PostDetailTemplateAgeRestricted, PostDetailTemplateBanned,
-            PostDetailTemplateHidden, PostEditTemplate, PostsListTemplate, PostsSearchTemplate,
-            UserDetailTemplate, UsersListTemplate,
+            AdminDashboardTemplate, ApiDocsTemplate, ApiTokensListTemplate, AssetContext,
+            AtomFeedTemplate, AuditLogTemplate, AvatarEditTemplate, BanAppealsListTemplate,
+            BanReasonListTemplate, ConfirmTemplate, ContentRatingListTemplate,
+            EmailVerificationTemplate, ErrorTemplate, FormTemplate, IndexTemplate,
+            InvitesListTemplate, MessageTemplate, PageContext, PostAddTemplate,
+            PostBanFormTemplate, PostDetailTemplate, PostDetailTemplateAgeRestricted,
+            PostDetailTemplateBanned, PostDetailTemplateHidden, PostDetailTemplateQuarantined,
+            PostEditTemplate, PostEmbedTemplate, PostsListTemplate, PostsPinnedListTemplate,
+            PostsSearchTemplate, RegistrationDisabledTemplate, RssFeedTemplate,
+            SessionsListTemplate, StaticPageListTemplate, StaticPageTemplate,
+            UploadBanByHashTemplate, UploaderRequestsListTemplate, UserDetailTemplate,
+            UsersListTemplate, WebhookDeliveriesTemplate, WebhooksListTemplate,
         },
     },
-    auth::{Admin, Authentication, Uploader, USERNAME_COOKIE_NAME},
+    auth::{
+        end_session, get_session_token, set_recent_auth, start_impersonation, start_session,
+        stop_impersonation, Admin, Authentication, RecentAuth, Uploader, UserAgent,
+    },
+    mail::{Mail, Mailer},
     utils::{
+        attachment::{CsvAttachment, ZipAttachment},
         breadcrumbs::Breadcrumb,
+        conditional::{make_etag, Conditional},
         csrf::CSRFProtectedForm,
-        csrf_lib::CsrfToken,
+        csrf_lib::{rotate_csrf_token, CsrfConfig, CsrfToken},
         date_to_offset_date_time,
-        form_definition::{FormDefinition, FormWithDefinition},
-        form_extra_validation::IdField,
-        pagination::PageParams,
+        feed::FeedResponse,
+        flash::{set_flash_message, FlashMessage},
+        form_definition::{FileUploadTarget, FormDefinition, FormWithDefinition},
+        form_extra_validation::{ExtraValidatedForm, IdField},
+        locale::{set_locale_cookie, Locale},
+        pagination::{Page, PageOverflowBehavior, PageParams},
+        post_layout::PostListLayout,
+        redirect::get_local_redirect_path,
+        secret::Secret,
+        signed_token::SignedTokenKey,
         template_with_status::{TemplateForbidden, TemplateUnavailableForLegal},
+        theme::{set_theme_cookie, Theme},
+        timezone::{
+            set_timezone_offset_cookie, timezone_offset_from_cookies, MAX_TIMEZONE_OFFSET,
+            MIN_TIMEZONE_OFFSET,
+        },
         url_query::UrlQuery,
     },
-    PaginationConfig, UploadConfig,
+    AuthConfig, EmailConfig, PaginationConfig, PasswordHashConfig, RegistrationMode, UploadConfig,
 };
 use archivanima_macros::{
     form_get_and_post, form_with_csrf, CheckCSRF, FormWithDefinition, RawForm,
 };
+use askama::Template;
 use lazy_static::lazy_static;
+use prometheus::Registry;
 use regex::Regex;
 use rocket::{
-    get,
-    http::{Cookie, CookieJar},
+    catch, get,
+    http::{ContentType, Cookie, CookieJar, Status},
     post,
-    response::Redirect,
-    time::Date,
-    uri, Either, FromForm, State,
+    response::{content::RawHtml, Redirect},
+    time::{Date, OffsetDateTime},
+    uri, Either, FromForm, Request, State,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
-use std::{borrow::Cow, collections::HashMap};
-use validator::{Validate, ValidationError, ValidationErrors};
+use std::{borrow::Cow, collections::HashMap, net::IpAddr, sync::Arc};
+use validator::{validate_email, Validate, ValidationError, ValidationErrors};
 
 use super::db::list_latest_pinned_posts;
 
 lazy_static! {
-    static ref BREADCRUMB_ROOT: Breadcrumb =
-        Breadcrumb::new_with_url("archivanima".to_string(), uri!(index_get()).to_string());
-    static ref BREADCRUMBS_INDEX: Vec<Breadcrumb> =
-        vec![Breadcrumb::new_without_url("archivanima".to_string())];
-    static ref BREADCRUMBS_REGISTRATION: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("регистрация".to_string()),
-    ];
-    static ref BREADCRUMBS_LOGIN: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("вход".to_string()),
-    ];
-    static ref BREADCRUMBS_LOGOUT: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("выход".to_string()),
-    ];
-    static ref BREADCRUMBS_CHANGE_PASSWORD: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("смена пароля".to_string()),
-    ];
-    static ref BREADCRUMB_USERS: Breadcrumb =
-        Breadcrumb::new_without_url("пользователи".to_string());
-    static ref BREADCRUMB_USERS_LIST: Vec<Breadcrumb> =
-        vec![BREADCRUMB_ROOT.clone(), BREADCRUMB_USERS.clone()];
-    static ref BREADCRUMBS_INVITE_ADD: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("инвайты".to_string()),
-        Breadcrumb::new_without_url("добавление".to_string()),
-    ];
-    static ref BREADCRUMBS_INVITE_REMOVE: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("инвайты".to_string()),
-        Breadcrumb::new_without_url("удаление".to_string()),
-    ];
-    static ref BREADCRUMBS_BAN_REASONS_LIST: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("причины бана".to_string())
-    ];
-    static ref BREADCRUMB_BAN_REASONS: Breadcrumb = Breadcrumb::new_with_url(
-        "причины бана".to_string(),
+    static ref PAGE_META_INDEX: PageMeta = PageMeta::index();
+    static ref PAGE_META_REGISTRATION: PageMeta = PageMeta::section("breadcrumb.registration");
+    static ref PAGE_META_LOGIN: PageMeta = PageMeta::section("breadcrumb.login");
+    static ref PAGE_META_LOGOUT: PageMeta = PageMeta::section("breadcrumb.logout");
+    static ref PAGE_META_CHANGE_PASSWORD: PageMeta =
+        PageMeta::section("breadcrumb.change_password");
+    static ref PAGE_META_CONFIRM_PASSWORD: PageMeta =
+        PageMeta::section("breadcrumb.confirm_password");
+    static ref PAGE_META_PROFILE: PageMeta = PageMeta::section("breadcrumb.profile");
+    static ref PAGE_META_DELETE_ACCOUNT: PageMeta = PageMeta::section("breadcrumb.delete_account");
+    static ref PAGE_META_AVATAR: PageMeta = PageMeta::section("breadcrumb.avatar");
+    static ref PAGE_META_VERIFY_EMAIL: PageMeta = PageMeta::section("breadcrumb.verify_email");
+    static ref PAGE_META_FORGOT_PASSWORD: PageMeta =
+        PageMeta::section("breadcrumb.forgot_password");
+    static ref PAGE_META_SESSIONS: PageMeta = PageMeta::section("breadcrumb.sessions");
+    static ref BREADCRUMB_API_TOKENS: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.api_tokens",
+        uri!(api_tokens_list_get()).to_string()
+    );
+    static ref PAGE_META_API_TOKENS: PageMeta = PageMeta::section("breadcrumb.api_tokens");
+    static ref PAGE_META_API_TOKEN_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_API_TOKENS, "breadcrumb.api_token_add");
+    static ref PAGE_META_API_DOCS: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_API_TOKENS, "breadcrumb.api_docs");
+    static ref PAGE_META_IMPERSONATION_STOP: PageMeta =
+        PageMeta::section("breadcrumb.impersonation_stop");
+    static ref PAGE_META_UPLOADER_REQUEST: PageMeta =
+        PageMeta::section("breadcrumb.uploader_request");
+    static ref PAGE_META_AUDIT_LOG: PageMeta = PageMeta::section("breadcrumb.audit_log");
+    static ref PAGE_META_ADMIN_DASHBOARD: PageMeta =
+        PageMeta::section("breadcrumb.admin_dashboard");
+    static ref PAGE_META_BAN_APPEALS_LIST: PageMeta = PageMeta::section("breadcrumb.ban_appeals");
+    static ref PAGE_META_UPLOADER_REQUESTS_LIST: PageMeta =
+        PageMeta::section("breadcrumb.uploader_requests");
+    static ref PAGE_META_WEBHOOKS_LIST: PageMeta = PageMeta::section("breadcrumb.webhooks");
+    static ref BREADCRUMB_WEBHOOKS: Breadcrumb =
+        Breadcrumb::message_with_url("breadcrumb.webhooks", uri!(webhooks_list_get()).to_string());
+    static ref PAGE_META_WEBHOOK_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_WEBHOOKS, "breadcrumb.add");
+    static ref PAGE_META_USERS_LIST: PageMeta = PageMeta::section("breadcrumb.users");
+    static ref PAGE_META_INVITES_LIST: PageMeta = PageMeta::section("breadcrumb.invites");
+    static ref BREADCRUMB_INVITES: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.invites",
+        uri!(invites_list_get(None as Option<u64>, None as Option<u64>)).to_string()
+    );
+    static ref PAGE_META_INVITE_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_INVITES, "breadcrumb.add");
+    static ref PAGE_META_INVITE_LINK: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_INVITES, "breadcrumb.invite_link");
+    static ref PAGE_META_INVITE_GENERATE: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_INVITES, "breadcrumb.invite_generate");
+    static ref PAGE_META_INVITE_REMOVE: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_INVITES, "breadcrumb.remove");
+    static ref PAGE_META_BAN_REASONS_LIST: PageMeta = PageMeta::section("breadcrumb.ban_reasons");
+    static ref BREADCRUMB_BAN_REASONS: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.ban_reasons",
         uri!(ban_reasons_list_get()).to_string()
     );
-    static ref BREADCRUMBS_BAN_REASON_ADD: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_BAN_REASONS.clone(),
-        Breadcrumb::new_without_url("добавление".to_string())
-    ];
+    static ref PAGE_META_BAN_REASON_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_BAN_REASONS, "breadcrumb.add");
     static ref BREADCRUMB_BAN_REASON_EDIT: Breadcrumb =
-        Breadcrumb::new_without_url("изменение".to_string());
-    static ref BREADCRUMB_POSTS: Breadcrumb = Breadcrumb::new_with_url(
-        "посты".to_string(),
-        uri!(posts_list_get(None as Option<u64>, None as Option<u64>)).to_string()
+        Breadcrumb::message_without_url("breadcrumb.edit");
+    static ref PAGE_META_CONTENT_RATINGS_LIST: PageMeta =
+        PageMeta::section("breadcrumb.content_ratings");
+    static ref BREADCRUMB_CONTENT_RATINGS: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.content_ratings",
+        uri!(content_ratings_list_get()).to_string()
     );
-    static ref BREADCRUMBS_POSTS_LIST: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        Breadcrumb::new_without_url("посты".to_string())
-    ];
-    static ref BREADCRUMBS_POST_ADD: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_POSTS.clone(),
-        Breadcrumb::new_without_url("добавление".to_string()),
-    ];
-    static ref BREADCRUMB_UPLOADS: Breadcrumb = Breadcrumb::new_without_url("файлы".to_string(),);
-    static ref BREADCRUMBS_UPLOAD_ADD: Vec<Breadcrumb> = vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_UPLOADS.clone(),
-        Breadcrumb::new_without_url("загрузка".to_string()),
-    ];
+    static ref PAGE_META_CONTENT_RATING_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_CONTENT_RATINGS, "breadcrumb.add");
+    static ref PAGE_META_STATIC_PAGES_LIST: PageMeta = PageMeta::section("breadcrumb.static_pages");
+    static ref BREADCRUMB_STATIC_PAGES: Breadcrumb = Breadcrumb::message_with_url(
+        "breadcrumb.static_pages",
+        uri!(static_pages_list_get()).to_string()
+    );
+    static ref PAGE_META_STATIC_PAGE_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_STATIC_PAGES, "breadcrumb.add");
+    static ref PAGE_META_POSTS_LIST: PageMeta = PageMeta::section("breadcrumb.posts");
+    static ref PAGE_META_POSTS_PINNED_LIST: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_POSTS, "breadcrumb.posts_pinned");
+    static ref PAGE_META_POST_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_POSTS, "breadcrumb.add");
+    static ref BREADCRUMB_UPLOADS: Breadcrumb =
+        Breadcrumb::message_without_url("breadcrumb.uploads");
+    static ref PAGE_META_UPLOAD_ADD: PageMeta =
+        PageMeta::subsection(&BREADCRUMB_UPLOADS, "breadcrumb.upload_add");
 }
 
 lazy_static! {
@@ -127,207 +216,606 @@ lazy_static! {
     static ref PASSWORD_CHARACTERS_REGEX: Regex = Regex::new(r"^[a-zA-Z0-9_\-/!\+=]+$").unwrap();
     static ref PASSWORD_LETTER_REGEX: Regex = Regex::new(r"[a-z]").unwrap();
     static ref PASSWORD_DIGIT_REGEX: Regex = Regex::new(r"[0-9]").unwrap();
+    static ref STATIC_PAGE_SLUG_CHARACTERS_REGEX: Regex = Regex::new(r"^[a-z0-9_\-]+$").unwrap();
 }
 
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "зарегистрироваться"]
+#[form_submit_name = "form.registration.submit"]
+#[validate(schema(
+    function = "validate_registration_passwords",
+    skip_on_field_errors = false
+))]
 pub struct RegistrationForm {
     #[validate(regex(
         path = "USERNAME_CHARACTERS_REGEX",
         code = "username_wrong_characters",
-        message = "имя пользователя может содержать только латинские буквы, цифры и нижние подчёркивания (_)"
+        message = "validation.username_wrong_characters"
     ))]
     #[validate(length(
         min = 2,
         code = "username_too_short",
-        message = "имя пользователя должно быть не короче 2 символов"
+        message = "validation.username_too_short"
     ))]
     #[validate(length(
         max = 64,
         code = "username_too_long",
-        message = "имя пользователя должно быть не длиннее 64 символов"
+        message = "validation.username_too_long"
     ))]
-    #[form_field_verbose_name = "имя пользователя"]
+    #[form_field_required]
+    #[form_field_min = 2]
+    #[form_field_max = 64]
+    #[form_field_verbose_name = "form.registration.username"]
+    #[form_field_help = "form.registration.username_help"]
+    #[form_field_group = "form.registration.group_credentials"]
     username: String,
 
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "инвайт-код"]
-    invite_code: String,
+    #[form_field_verbose_name = "form.registration.invite_code"]
+    #[form_field_help = "form.registration.invite_code_help"]
+    #[form_field_group = "form.registration.group_credentials"]
+    invite_code: Option<Secret<String>>,
+
+    #[form_field_type = "Hidden"]
+    #[form_field_verbose_name = "form.registration.invite_token"]
+    #[form_field_group = "form.registration.group_credentials"]
+    invite_token: Option<String>,
 
     #[validate(length(
         min = 8,
         code = "password_too_short",
-        message = "пароль должен быть не короче 8 символов"
+        message = "validation.password_too_short"
     ))]
     #[validate(regex(
         path = "PASSWORD_CHARACTERS_REGEX",
         code = "password_wrong_characters",
-        message = "пароль может содержать лишь латинские буквы, цифры, и символы _, -, /, +, = и !"
+        message = "validation.password_wrong_characters"
     ))]
     #[validate(regex(
         path = "PASSWORD_LETTER_REGEX",
         code = "password_missing_letter",
-        message = "пароль должен содержать по меньшей мере одну латинскую букву"
+        message = "validation.password_missing_letter"
     ))]
     #[validate(regex(
         path = "PASSWORD_DIGIT_REGEX",
         code = "password_missing_digit",
-        message = "пароль должен содержать по меньшей мере одну цифру"
+        message = "validation.password_missing_digit"
     ))]
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "пароль"]
-    password: String,
+    #[form_field_required]
+    #[form_field_min = 8]
+    #[form_field_verbose_name = "form.registration.password"]
+    #[form_field_help = "form.registration.password_help"]
+    #[form_field_group = "form.registration.group_credentials"]
+    password: Secret<String>,
 
-    #[validate(must_match(other = "password", message = "Passwords must match"))]
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "продублировать пароль"]
-    password2: String,
+    #[form_field_required]
+    #[form_field_verbose_name = "form.registration.password2"]
+    #[form_field_group = "form.registration.group_credentials"]
+    password2: Secret<String>,
 
+    #[validate(custom = "validate_birth_date")]
     #[form_field_type = "Date"]
-    #[form_field_optional]
-    #[form_field_verbose_name = "дата рождения"]
+    #[form_field_verbose_name = "form.registration.birth_date"]
+    #[form_field_group = "form.registration.group_personal"]
     birth_date: Option<Date>,
+
+    #[validate(custom = "validate_optional_email")]
+    #[form_field_type = "EMail"]
+    #[form_field_verbose_name = "form.registration.email"]
+    #[form_field_placeholder = "form.registration.email_placeholder"]
+    #[form_field_group = "form.registration.group_personal"]
+    email: Option<String>,
+}
+
+/// Passwords are compared for whole form, so mismatch is shown above fields.
+fn validate_registration_passwords(form: &RegistrationForm) -> Result<(), ValidationError> {
+    if form.password == form.password2 {
+        Ok(())
+    } else {
+        Err(ValidationError {
+            code: Cow::from("passwords_must_match"),
+            message: Some(Cow::from("validation.passwords_must_match")),
+            params: HashMap::new(),
+        })
+    }
 }
 
 impl RegistrationForm {
-    fn new(csrf_token: &str) -> Self {
+    fn new(csrf_token: &str, invite_code: Option<&str>, invite_token: Option<&str>) -> Self {
         Self {
             username: "".to_string(),
-            invite_code: "".to_string(),
-            password: "".to_string(),
-            password2: "".to_string(),
+            invite_code: invite_code.map(|invite_code| Secret::new(invite_code.to_string())),
+            invite_token: invite_token.map(str::to_string),
+            password: Secret::default(),
+            password2: Secret::default(),
             csrf_token: csrf_token.to_string(),
             birth_date: None,
+            email: None,
         }
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
             username: self.username.clone(),
-            invite_code: "".to_string(),
-            password: "".to_string(),
-            password2: "".to_string(),
+            invite_code: None,
+            invite_token: self.invite_token.clone(),
+            password: Secret::default(),
+            password2: Secret::default(),
             csrf_token: self.csrf_token.clone(),
             birth_date: self.birth_date,
+            email: self.email.clone(),
+        }
+    }
+
+    fn get_invite_token(&self) -> Option<&str> {
+        self.invite_token
+            .as_deref()
+            .filter(|invite_token| !invite_token.is_empty())
+    }
+
+    /// Invite fields are omitted in open registration mode, invite code field is also omitted
+    /// when registering by signed invite link.
+    fn get_definition_for_mode(
+        &self,
+        errors: ValidationErrors,
+        registration_mode: RegistrationMode,
+    ) -> FormDefinition {
+        let mut definition = self.get_definition(errors);
+        if registration_mode == RegistrationMode::Open {
+            definition
+                .retain_fields(|field| field.name != "invite_code" && field.name != "invite_token");
+        } else if self.get_invite_token().is_some() {
+            definition.retain_fields(|field| field.name != "invite_code");
         }
+        definition
     }
 }
 
-#[get("/auth/register")]
-pub fn registration_get(
-    user: Authentication,
-    csrf_token: CsrfToken,
-    asset_context: &State<AssetContext>,
-) -> Either<FormTemplate, Redirect> {
-    if !user.is_anonymous() {
-        Either::Right(Redirect::to(uri!(index_get()))) // TODO
+fn validate_optional_email(email: &str) -> Result<(), ValidationError> {
+    if email.is_empty() || validate_email(email) {
+        Ok(())
     } else {
-        Either::Left(FormTemplate {
-            user,
-            form: RegistrationForm::new(&csrf_token.authenticity_token())
-                .get_definition(ValidationErrors::new()),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_REGISTRATION.clone(),
+        Err(ValidationError {
+            code: Cow::from("email_invalid"),
+            message: Some(Cow::from("validation.email_invalid")),
+            params: HashMap::new(),
+        })
+    }
+}
+
+fn get_optional_email(email: &Option<String>) -> Option<&str> {
+    email.as_deref().filter(|email| !email.is_empty())
+}
+
+fn validate_birth_date(birth_date: &Date) -> Result<(), ValidationError> {
+    let today = OffsetDateTime::now_utc().date();
+    if *birth_date > today {
+        return Err(ValidationError {
+            code: Cow::from("birth_date_in_future"),
+            message: Some(Cow::from("validation.birth_date_in_future")),
+            params: HashMap::new(),
+        });
+    }
+
+    let mut age = today.year() - birth_date.year();
+    if (today.month(), today.day()) < (birth_date.month(), birth_date.day()) {
+        age -= 1;
+    }
+    if age > 120 {
+        return Err(ValidationError {
+            code: Cow::from("birth_date_too_old"),
+            message: Some(Cow::from("validation.birth_date_too_old")),
+            params: HashMap::new(),
+        });
+    }
+
+    Ok(())
+}
+
+fn get_birth_date_already_changed_errors() -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        "birth_date",
+        ValidationError {
+            code: Cow::from("birth_date_already_changed"),
+            message: Some(Cow::from("validation.birth_date_already_changed")),
+            params: HashMap::new(),
+        },
+    );
+    errors
+}
+
+fn get_optional_display_name(display_name: &Option<String>) -> Option<&str> {
+    display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|display_name| !display_name.is_empty())
+}
+
+fn get_email_already_in_use_errors() -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        "email",
+        ValidationError {
+            code: Cow::from("email_already_in_use"),
+            message: Some(Cow::from("validation.email_already_in_use")),
+            params: HashMap::new(),
+        },
+    );
+    errors
+}
+
+async fn send_email_verification(
+    username: &str,
+    email: &str,
+    email_config: &EmailConfig,
+    mailer: &dyn Mailer,
+    locale: Locale,
+    pool: &Pool<Postgres>,
+) -> Result<(), crate::error::Error> {
+    let token = add_email_verification_token(username, email, pool).await?;
+
+    let result = mailer
+        .send(Mail {
+            to: email.to_string(),
+            subject: locale
+                .translate("mail.email_verification.subject")
+                .to_string(),
+            body: locale.translate_with_args(
+                "mail.email_verification.body",
+                &[
+                    ("username", username),
+                    (
+                        "link",
+                        &format!("{}{}", email_config.site_url, uri!(verify_email_get(token))),
+                    ),
+                ],
+            ),
         })
+        .await;
+    if let Err(err) = result {
+        log::error!("Failed to send verification mail to {}: {:?}", email, err);
+    }
+
+    Ok(())
+}
+
+#[get("/auth/register?<invite>&<token>")]
+pub fn registration_get<'a>(
+    invite: Option<&str>,
+    token: Option<&str>,
+    csrf_token: CsrfToken,
+    context: PageContext<'a>,
+    registration_mode: &State<RegistrationMode>,
+    cookies: &CookieJar<'_>,
+) -> Either<Either<FormTemplate<'a>, TemplateForbidden<RegistrationDisabledTemplate<'a>>>, Redirect>
+{
+    if !context.user.is_anonymous() {
+        set_flash_message(cookies, FlashMessage::info("flash.already_logged_in"));
+        Either::Right(Redirect::to(uri!(index_get())))
+    } else if !registration_mode.is_enabled() {
+        Either::Left(Either::Right(TemplateForbidden {
+            template: RegistrationDisabledTemplate {
+                context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+            },
+        }))
+    } else {
+        Either::Left(Either::Left(FormTemplate {
+            form: RegistrationForm::new(&csrf_token.authenticity_token(), invite, token)
+                .get_definition_for_mode(ValidationErrors::new(), **registration_mode),
+            context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+        }))
     }
 }
 
+/// Errors of failed login. Unless [`AuthConfig::show_login_error_details`] is set, same error is
+/// shown for missing user and for wrong password.
+fn get_login_failed_errors(is_user_missing: bool, auth_config: &AuthConfig) -> ValidationErrors {
+    let (field, code) = match (auth_config.show_login_error_details, is_user_missing) {
+        (false, _) => ("password", "credentials_invalid"),
+        (true, true) => ("username", "username_not_found"),
+        (true, false) => ("password", "password_invalid"),
+    };
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        field,
+        ValidationError {
+            code: Cow::from(code),
+            message: Some(Cow::from(format!("validation.{}", code))),
+            params: HashMap::new(),
+        },
+    );
+    errors
+}
+
+fn get_too_many_registration_attempts_errors(field: &'static str) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        field,
+        ValidationError {
+            code: Cow::from("too_many_attempts"),
+            message: Some(Cow::from("validation.too_many_registration_attempts")),
+            params: HashMap::new(),
+        },
+    );
+    errors
+}
+
 #[post("/auth/register", data = "<form>")]
-pub async fn registration_post<'a, 'b, 'c>(
+#[allow(clippy::too_many_arguments)]
+pub async fn registration_post<'a, 'b, 'c, 'd>(
     cookies: &'a CookieJar<'_>,
     form: CSRFProtectedForm<RegistrationForm>,
     pool: &'b State<Pool<Postgres>>,
-    user: Authentication,
-    asset_context: &'c State<AssetContext>,
-) -> Result<Either<Redirect, FormTemplate<'c>>, crate::error::Error> {
-    if !user.is_anonymous() {
-        return Ok(Either::Left(Redirect::to(uri!(index_get())))); // TODO
+    user_agent: UserAgent,
+    client_ip: Option<IpAddr>,
+    context: PageContext<'c>,
+    auth_config: &'d State<AuthConfig>,
+    password_hash_config: &'d State<PasswordHashConfig>,
+    email_config: &'d State<EmailConfig>,
+    mailer: &'d State<Arc<dyn Mailer>>,
+    registration_mode: &'d State<RegistrationMode>,
+    signed_token_key: &'d State<SignedTokenKey>,
+) -> Result<
+    Either<Redirect, Either<FormTemplate<'c>, TemplateForbidden<RegistrationDisabledTemplate<'c>>>>,
+    crate::error::Error,
+> {
+    if !context.user.is_anonymous() {
+        set_flash_message(cookies, FlashMessage::info("flash.already_logged_in"));
+        return Ok(Either::Left(Redirect::to(uri!(index_get()))));
     }
 
-    match form.validate() {
-        Ok(()) => {
-            let new_user = NewUser {
-                username: &form.username,
-                password: &form.password,
-                is_active: true,
-                is_admin: false,
-                is_uploader: false,
-                birth_date: form.birth_date.map(date_to_offset_date_time),
-            };
+    let registration_mode = **registration_mode;
+    if !registration_mode.is_enabled() {
+        return Ok(Either::Right(Either::Right(TemplateForbidden {
+            template: RegistrationDisabledTemplate {
+                context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+            },
+        })));
+    }
 
-            match try_add_user_check_username_and_invite(new_user, &form.invite_code, pool).await? {
-                Ok(()) => {
-                    cookies
-                        .add_private(Cookie::build((USERNAME_COOKIE_NAME, form.username.clone())));
+    if let Err(errors) = form.validate() {
+        return Ok(Either::Right(Either::Left(FormTemplate {
+            form: form
+                .clear_sensitive()
+                .get_definition_for_mode(errors, registration_mode),
+            context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+        })));
+    }
 
-                    Ok(Either::Left(Redirect::to(uri!(index_get())))) // TODO
-                }
-                Err(UsernameAndInviteCheckError::UserAlreadyExists) => {
-                    let mut errors = ValidationErrors::new();
-                    errors.add(
-                        "username",
-                        ValidationError {
-                            code: Cow::from("username_already_in_use"),
-                            message: Some(Cow::from("имя пользователя уже занято")),
-                            params: HashMap::new(),
-                        },
-                    );
-                    Ok(Either::Right(FormTemplate {
-                        user,
-                        form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: BREADCRUMBS_REGISTRATION.clone(),
-                    }))
-                }
-                Err(UsernameAndInviteCheckError::InvalidInviteCode) => {
-                    let mut errors = ValidationErrors::new();
-                    errors.add(
-                        "invite_code",
-                        ValidationError {
-                            code: Cow::from("invite_code_invalid"),
-                            message: Some(Cow::from("инвайт-код недействителен")),
-                            params: HashMap::new(),
-                        },
-                    );
-                    Ok(Either::Right(FormTemplate {
-                        user,
-                        form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: BREADCRUMBS_REGISTRATION.clone(),
-                    }))
+    let new_user = NewUser {
+        username: &form.username,
+        password: &form.password,
+        is_active: true,
+        is_admin: false,
+        is_uploader: false,
+        birth_date: form.birth_date.map(date_to_offset_date_time),
+        email: get_optional_email(&form.email),
+    };
+
+    let ip_address = client_ip.map(|client_ip| client_ip.to_string());
+
+    // Failed attempts with taken username are limited too, so that usernames can not be checked
+    // quickly.
+    let result = if registration_mode == RegistrationMode::Open {
+        if is_registration_locked(
+            None,
+            ip_address.as_deref(),
+            auth_config.max_registration_attempts,
+            auth_config.registration_attempt_window,
+            pool,
+        )
+        .await?
+        {
+            return Ok(Either::Right(Either::Left(FormTemplate {
+                form: form.clear_sensitive().get_definition_for_mode(
+                    get_too_many_registration_attempts_errors("username"),
+                    registration_mode,
+                ),
+                context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+            })));
+        }
+
+        let is_email_taken = match new_user.email {
+            Some(email) => !is_email_available(email, pool).await?,
+            None => false,
+        };
+        let result = if is_email_taken {
+            Err(UsernameAndInviteCheckError::EmailAlreadyExists)
+        } else {
+            try_add_user_check_username(new_user, password_hash_config, pool)
+                .await?
+                .ok_or(UsernameAndInviteCheckError::UserAlreadyExists)
+        };
+        if let Err(UsernameAndInviteCheckError::UserAlreadyExists) = result {
+            add_failed_registration_attempt(
+                None,
+                ip_address.as_deref(),
+                auth_config.registration_attempt_window,
+                pool,
+            )
+            .await?;
+        }
+        result
+    } else {
+        let invite_code = match form.get_invite_token() {
+            Some(invite_token) => {
+                match signed_token_key.verify_invite_code(invite_token, OffsetDateTime::now_utc()) {
+                    Ok(invite_code) => invite_code,
+                    Err(_) => {
+                        let mut form_real = form.clear_sensitive();
+                        form_real.invite_token = None;
+                        let mut errors = ValidationErrors::new();
+                        errors.add(
+                            "invite_code",
+                            ValidationError {
+                                code: Cow::from("invite_token_invalid"),
+                                message: Some(Cow::from("validation.invite_token_invalid")),
+                                params: HashMap::new(),
+                            },
+                        );
+                        return Ok(Either::Right(Either::Left(FormTemplate {
+                            form: form_real.get_definition_for_mode(errors, registration_mode),
+                            context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+                        })));
+                    }
                 }
             }
+            None => form
+                .invite_code
+                .clone()
+                .map(Secret::into_inner)
+                .unwrap_or_default(),
+        };
+        let invite_code = invite_code.as_str();
+
+        if is_registration_locked(
+            Some(invite_code),
+            ip_address.as_deref(),
+            auth_config.max_registration_attempts,
+            auth_config.registration_attempt_window,
+            pool,
+        )
+        .await?
+        {
+            return Ok(Either::Right(Either::Left(FormTemplate {
+                form: form.clear_sensitive().get_definition_for_mode(
+                    get_too_many_registration_attempts_errors("invite_code"),
+                    registration_mode,
+                ),
+                context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+            })));
+        }
+
+        let result = try_add_user_check_username_and_invite(
+            new_user,
+            invite_code,
+            password_hash_config,
+            pool,
+        )
+        .await?;
+        // Only invalid invite codes are counted against the code, taken username is counted
+        // against IP address only.
+        let failed_invite_code = match result {
+            Err(
+                UsernameAndInviteCheckError::InvalidInviteCode
+                | UsernameAndInviteCheckError::ExpiredInviteCode,
+            ) => Some(Some(invite_code)),
+            Err(UsernameAndInviteCheckError::UserAlreadyExists) => Some(None),
+            _ => None,
+        };
+        if let Some(failed_invite_code) = failed_invite_code {
+            add_failed_registration_attempt(
+                failed_invite_code,
+                ip_address.as_deref(),
+                auth_config.registration_attempt_window,
+                pool,
+            )
+            .await?;
+        }
+        result
+    };
+
+    let errors = match result {
+        Ok(()) => {
+            if let Some(email) = get_optional_email(&form.email) {
+                send_email_verification(
+                    &form.username,
+                    email,
+                    email_config,
+                    mailer.as_ref(),
+                    context.locale,
+                    pool,
+                )
+                .await?;
+            }
+
+            start_session(
+                cookies,
+                &form.username,
+                &user_agent,
+                false,
+                auth_config,
+                pool,
+            )
+            .await?;
+
+            set_flash_message(cookies, FlashMessage::success("flash.registration_done"));
+            return Ok(Either::Left(Redirect::to(uri!(index_get()))));
         }
-        Err(errors) => Ok(Either::Right(FormTemplate {
-            user,
-            form: form.clear_sensitive().get_definition(errors),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_REGISTRATION.clone(),
-        })),
-    }
+        Err(UsernameAndInviteCheckError::UserAlreadyExists) => {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "username",
+                ValidationError {
+                    code: Cow::from("username_already_in_use"),
+                    message: Some(Cow::from("validation.username_already_in_use")),
+                    params: HashMap::new(),
+                },
+            );
+            errors
+        }
+        Err(UsernameAndInviteCheckError::EmailAlreadyExists) => get_email_already_in_use_errors(),
+        Err(UsernameAndInviteCheckError::ExpiredInviteCode) => {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "invite_code",
+                ValidationError {
+                    code: Cow::from("invite_code_expired"),
+                    message: Some(Cow::from("validation.invite_code_expired")),
+                    params: HashMap::new(),
+                },
+            );
+            errors
+        }
+        Err(UsernameAndInviteCheckError::InvalidInviteCode) => {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "invite_code",
+                ValidationError {
+                    code: Cow::from("invite_code_invalid"),
+                    message: Some(Cow::from("validation.invite_code_invalid")),
+                    params: HashMap::new(),
+                },
+            );
+            errors
+        }
+    };
+
+    Ok(Either::Right(Either::Left(FormTemplate {
+        form: form
+            .clear_sensitive()
+            .get_definition_for_mode(errors, registration_mode),
+        context: context.with_meta(PAGE_META_REGISTRATION.clone()),
+    })))
 }
 
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "войти"]
+#[form_submit_name = "form.login.submit"]
 pub struct LoginForm {
-    #[form_field_verbose_name = "имя пользователя"]
+    #[form_field_verbose_name = "form.login.username"]
     username: String,
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "пароль"]
-    password: String,
+    #[form_field_verbose_name = "form.login.password"]
+    password: Secret<String>,
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.login.remember_me"]
+    remember_me: bool,
 }
 
 impl LoginForm {
     fn new(csrf_token: &str) -> Self {
         Self {
             username: "".to_string(),
-            password: "".to_string(),
+            password: Secret::default(),
+            remember_me: false,
             csrf_token: csrf_token.to_string(),
         }
     }
@@ -335,102 +823,180 @@ impl LoginForm {
     fn clear_sensitive(&self) -> Self {
         Self {
             username: self.username.clone(),
-            password: "".to_string(),
+            password: Secret::default(),
+            remember_me: self.remember_me,
             csrf_token: self.csrf_token.clone(),
         }
     }
 }
 
 #[get("/auth/login")]
-pub fn login_get(
-    user: Authentication,
+pub fn login_get<'a>(
     csrf_token: CsrfToken,
-    asset_context: &State<AssetContext>,
-) -> Either<FormTemplate, Redirect> {
-    if !user.is_anonymous() {
-        Either::Right(Redirect::to(uri!(index_get()))) // TODO
+    context: PageContext<'a>,
+    cookies: &CookieJar<'_>,
+) -> Either<FormTemplate<'a>, Redirect> {
+    if !context.user.is_anonymous() {
+        set_flash_message(cookies, FlashMessage::info("flash.already_logged_in"));
+        Either::Right(Redirect::to(uri!(index_get())))
     } else {
         Either::Left(FormTemplate {
-            user,
             form: LoginForm::new(&csrf_token.authenticity_token())
                 .get_definition(ValidationErrors::new()),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+            context: context.with_meta(PAGE_META_LOGIN.clone()),
         })
     }
 }
 
-#[post("/auth/login", data = "<form>")]
-pub async fn login_post<'a, 'b, 'c>(
+#[post("/auth/login?<next>", data = "<form>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn login_post<'a, 'b, 'c, 'd>(
     cookies: &'a CookieJar<'_>,
     form: CSRFProtectedForm<LoginForm>,
+    next: Option<&str>,
     pool: &'b State<Pool<Postgres>>,
-    user: Authentication,
-    asset_context: &'c State<AssetContext>,
+    user_agent: UserAgent,
+    client_ip: Option<IpAddr>,
+    context: PageContext<'c>,
+    auth_config: &'d State<AuthConfig>,
+    password_hash_config: &'d State<PasswordHashConfig>,
+    csrf_config: &'d State<CsrfConfig>,
 ) -> Result<Either<Redirect, FormTemplate<'c>>, crate::error::Error> {
-    if user.is_authenticated() {
-        return Ok(Either::Left(Redirect::to(uri!(index_get())))); // TODO
+    if context.user.is_authenticated() {
+        set_flash_message(cookies, FlashMessage::info("flash.already_logged_in"));
+        return Ok(Either::Left(Redirect::to(uri!(index_get()))));
     }
 
     match form.validate() {
         Ok(()) => {
-            match try_get_user_full(&form.username, pool).await? {
+            let ip_address = client_ip.map(|client_ip| client_ip.to_string());
+
+            if is_login_locked(
+                &form.username,
+                ip_address.as_deref(),
+                auth_config.max_login_attempts,
+                auth_config.login_attempt_window,
+                auth_config.login_lockout_duration,
+                pool,
+            )
+            .await?
+            {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "username",
+                    ValidationError {
+                        code: Cow::from("too_many_attempts"),
+                        message: Some(Cow::from("validation.too_many_login_attempts")),
+                        params: HashMap::new(),
+                    },
+                );
+                return Ok(Either::Right(FormTemplate {
+                    form: form.clear_sensitive().get_definition(errors),
+                    context: context.with_meta(PAGE_META_LOGIN.clone()),
+                }));
+            }
+
+            match try_get_user_full(&form.username, pool)
+                .await?
+                .filter(|user_real| user_real.username != DELETED_USERNAME)
+            {
                 Some(user_real) => {
                     let verification_result = user_real.check_password(&form.password)?;
                     if verification_result {
-                        cookies
-                            .add_private(Cookie::build((USERNAME_COOKIE_NAME, user_real.username)));
-                        Ok(Either::Left(Redirect::to(uri!(index_get()))))
-                        // TODO
+                        clear_login_attempts(&user_real.username, ip_address.as_deref(), pool)
+                            .await?;
+                        set_user_last_login(&user_real.username, pool).await?;
+                        if user_real.needs_rehash(password_hash_config)? {
+                            change_user_password(
+                                &user_real.username,
+                                &form.password,
+                                user_real.must_change_password,
+                                password_hash_config,
+                                pool,
+                            )
+                            .await?;
+                        }
+                        start_session(
+                            cookies,
+                            &user_real.username,
+                            &user_agent,
+                            form.remember_me,
+                            auth_config,
+                            pool,
+                        )
+                        .await?;
+                        set_recent_auth(cookies, &user_real.username, auth_config);
+                        rotate_csrf_token(cookies, csrf_config);
+                        if let Some(theme) = get_user_theme(&user_real.username, pool).await? {
+                            set_theme_cookie(cookies, theme);
+                        }
+                        if let Some(timezone_offset) =
+                            get_user_timezone_offset(&user_real.username, pool).await?
+                        {
+                            set_timezone_offset_cookie(cookies, Some(timezone_offset));
+                        }
+                        set_flash_message(cookies, FlashMessage::success("flash.login_done"));
+                        match next.and_then(get_local_redirect_path) {
+                            Some(next) => Ok(Either::Left(Redirect::to(next.to_string()))),
+                            None => Ok(Either::Left(Redirect::to(uri!(index_get())))),
+                        }
                     } else {
-                        let mut errors = ValidationErrors::new();
-                        errors.add(
-                            "password",
-                            ValidationError {
-                                code: Cow::from("password_invalid"),
-                                message: Some(Cow::from("неверный пароль")),
-                                params: HashMap::new(),
-                            },
-                        );
+                        add_failed_login_attempt(
+                            &form.username,
+                            ip_address.as_deref(),
+                            auth_config.login_attempt_window,
+                            pool,
+                        )
+                        .await?;
+                        let errors = get_login_failed_errors(false, auth_config);
                         Ok(Either::Right(FormTemplate {
-                            user,
                             form: form.clear_sensitive().get_definition(errors),
-                            asset_context,
-                            breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+                            context: context.with_meta(PAGE_META_LOGIN.clone()),
                         }))
                     }
                 }
                 None => {
-                    let mut errors = ValidationErrors::new();
-                    errors.add(
-                        "username",
-                        ValidationError {
-                            code: Cow::from("username_not_found"),
-                            message: Some(Cow::from("неверное имя пользователя")),
-                            params: HashMap::new(),
-                        },
-                    );
+                    check_dummy_password(&form.password, password_hash_config)?;
+                    add_failed_login_attempt(
+                        &form.username,
+                        ip_address.as_deref(),
+                        auth_config.login_attempt_window,
+                        pool,
+                    )
+                    .await?;
+                    let errors = get_login_failed_errors(true, auth_config);
                     Ok(Either::Right(FormTemplate {
-                        user,
                         form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+                        context: context.with_meta(PAGE_META_LOGIN.clone()),
                     }))
                 }
             }
         }
         Err(errors) => Ok(Either::Right(FormTemplate {
-            user,
             form: form.clear_sensitive().get_definition(errors),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+            context: context.with_meta(PAGE_META_LOGIN.clone()),
         })),
     }
 }
 
+/// Form without fields for `confirm` mode of `form_get_and_post!`: action is done after user
+/// submits it with valid CSRF token.
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.confirm.submit"]
+pub struct ConfirmForm {}
+
+impl ConfirmForm {
+    pub fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+        }
+    }
+}
+
 #[form_with_csrf]
 #[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
-#[form_submit_name = "выйти"]
+#[form_submit_name = "form.logout.submit"]
 pub struct LogoutForm {}
 
 impl LogoutForm {
@@ -442,20 +1008,19 @@ impl LogoutForm {
 }
 
 #[get("/auth/logout")]
-pub fn logout_get(
-    user: Authentication,
+pub fn logout_get<'a>(
     csrf_token: CsrfToken,
-    asset_context: &State<AssetContext>,
-) -> Either<FormTemplate, Redirect> {
-    if user.is_anonymous() {
-        Either::Right(Redirect::to(uri!(index_get()))) // TODO
+    context: PageContext<'a>,
+    cookies: &CookieJar<'_>,
+) -> Either<FormTemplate<'a>, Redirect> {
+    if context.user.is_anonymous() {
+        set_flash_message(cookies, FlashMessage::info("flash.already_logged_out"));
+        Either::Right(Redirect::to(uri!(index_get())))
     } else {
         Either::Left(FormTemplate {
-            user,
             form: LogoutForm::new(&csrf_token.authenticity_token())
                 .get_definition(ValidationErrors::new()),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_LOGOUT.clone(),
+            context: context.with_meta(PAGE_META_LOGOUT.clone()),
         })
     }
 }
@@ -465,64 +1030,71 @@ pub async fn logout_post(
     cookies: &CookieJar<'_>,
     _form: CSRFProtectedForm<LogoutForm>,
     user: Authentication,
-) -> Redirect {
+    pool: &State<Pool<Postgres>>,
+    auth_config: &State<AuthConfig>,
+) -> Result<Redirect, crate::error::Error> {
     if !user.is_anonymous() {
-        cookies.remove_private(Cookie::build(USERNAME_COOKIE_NAME));
+        end_session(cookies, auth_config, pool).await?;
     }
 
-    cookies.remove_private(Cookie::build(crate::utils::csrf::COOKIE_NAME)); // TODO
+    cookies.remove_private(
+        auth_config
+            .cookie_security
+            .apply(Cookie::build(crate::utils::csrf::COOKIE_NAME)),
+    );
 
-    Redirect::to(uri!(index_get())) // TODO
+    set_flash_message(cookies, FlashMessage::success("flash.logout_done"));
+    Ok(Redirect::to(uri!(index_get())))
 }
 
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "сменить"]
+#[form_submit_name = "form.change_password.submit"]
 pub struct ChangePasswordForm {
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "старый пароль"]
-    old_password: String,
+    #[form_field_verbose_name = "form.change_password.old_password"]
+    old_password: Secret<String>,
 
     #[validate(length(
         min = 8,
         code = "password_too_short",
-        message = "пароль должен быть не короче 8 символов"
+        message = "validation.password_too_short"
     ))]
     #[validate(regex(
         path = "PASSWORD_CHARACTERS_REGEX",
         code = "password_wrong_characters",
-        message = "пароль может содержать лишь латинские буквы, цифры, и символы _, -, /, +, = и !"
+        message = "validation.password_wrong_characters"
     ))]
     #[validate(regex(
         path = "PASSWORD_LETTER_REGEX",
         code = "password_missing_letter",
-        message = "пароль должен содержать по меньшей мере одну латинскую букву"
+        message = "validation.password_missing_letter"
     ))]
     #[validate(regex(
         path = "PASSWORD_DIGIT_REGEX",
         code = "password_missing_digit",
-        message = "пароль должен содержать по меньшей мере одну цифру"
+        message = "validation.password_missing_digit"
     ))]
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "новый пароль"]
-    new_password: String,
+    #[form_field_verbose_name = "form.change_password.new_password"]
+    new_password: Secret<String>,
 }
 
 impl ChangePasswordForm {
     fn new(csrf_token: &str) -> Self {
         Self {
-            old_password: "".to_string(),
-            new_password: "".to_string(),
+            old_password: Secret::default(),
+            new_password: Secret::default(),
             csrf_token: csrf_token.to_string(),
         }
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
-            old_password: "".to_string(),
-            new_password: "".to_string(),
+            old_password: Secret::default(),
+            new_password: Secret::default(),
             csrf_token: self.csrf_token.clone(),
         }
     }
@@ -530,350 +1102,487 @@ impl ChangePasswordForm {
 
 #[get("/auth/change-password")]
 pub fn change_password_get(
-    user: User,
+    _user: User,
+    _recent_auth: RecentAuth,
     csrf_token: CsrfToken,
-    asset_context: &State<AssetContext>,
+    context: PageContext<'_>,
 ) -> FormTemplate {
     FormTemplate {
-        user: Authentication::Authenticated(user),
         form: ChangePasswordForm::new(&csrf_token.authenticity_token())
             .get_definition(ValidationErrors::new()),
-        asset_context,
-        breadcrumbs: BREADCRUMBS_CHANGE_PASSWORD.clone(),
+        context: context.with_meta(PAGE_META_CHANGE_PASSWORD.clone()),
     }
 }
 
 #[post("/auth/change-password", data = "<form>")]
-pub async fn change_password_post<'a, 'b, 'c>(
+#[allow(clippy::too_many_arguments)]
+pub async fn change_password_post<'a, 'b, 'c, 'd>(
+    cookies: &'a CookieJar<'_>,
     form: CSRFProtectedForm<ChangePasswordForm>,
     pool: &'b State<Pool<Postgres>>,
     user: User,
-    asset_context: &'c State<AssetContext>,
+    _recent_auth: RecentAuth,
+    context: PageContext<'c>,
+    auth_config: &'d State<AuthConfig>,
+    password_hash_config: &'d State<PasswordHashConfig>,
 ) -> Result<Either<Redirect, FormTemplate<'c>>, crate::error::Error> {
     match form.validate() {
         Ok(()) => {
             let user_full = try_get_user_full(&user.username, pool).await?.unwrap();
             let verification_result = user_full.check_password(&form.old_password)?;
             if verification_result {
-                change_user_password(&user.username, &form.new_password, pool).await?;
+                change_user_password(
+                    &user.username,
+                    &form.new_password,
+                    false,
+                    password_hash_config,
+                    pool,
+                )
+                .await?;
+                remove_user_sessions(
+                    &user.username,
+                    get_session_token(cookies, auth_config).as_deref(),
+                    pool,
+                )
+                .await?;
+                set_flash_message(cookies, FlashMessage::success("flash.password_changed"));
                 Ok(Either::Left(Redirect::to(uri!(index_get()))))
-                // TODO
             } else {
                 let mut errors = ValidationErrors::new();
                 errors.add(
                     "old_password",
                     ValidationError {
                         code: Cow::from("old_password_invalid"),
-                        message: Some(Cow::from("неверный старый пароль")),
+                        message: Some(Cow::from("validation.old_password_invalid")),
                         params: HashMap::new(),
                     },
                 );
                 Ok(Either::Right(FormTemplate {
-                    user: Authentication::Authenticated(user),
                     form: form.clear_sensitive().get_definition(errors),
-                    asset_context,
-                    breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+                    context: context.with_meta(PAGE_META_LOGIN.clone()),
                 }))
             }
         }
         Err(errors) => Ok(Either::Right(FormTemplate {
-            user: Authentication::Authenticated(user),
             form: form.clear_sensitive().get_definition(errors),
-            asset_context,
-            breadcrumbs: BREADCRUMBS_LOGIN.clone(),
+            context: context.with_meta(PAGE_META_LOGIN.clone()),
         })),
     }
 }
 
-#[get("/user/by-username/<username>")]
-pub async fn user_detail_get<'a, 'b, 'c>(
-    user: Authentication,
-    pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
-    username: &'c str,
-) -> Result<UserDetailTemplate<'b>, crate::error::Error> {
-    let item = match &user {
-        Authentication::Authenticated(user_real) if user_real.username == username => {
-            user_real.clone()
-        }
-        _ => try_get_user(username, pool)
-            .await?
-            .ok_or(crate::error::Error::DoesNotExist)?,
-    };
-
-    Ok(UserDetailTemplate {
-        user,
-        asset_context,
-        breadcrumbs: vec![
-            BREADCRUMB_ROOT.clone(),
-            BREADCRUMB_USERS.clone(),
-            Breadcrumb::new_without_url(item.username.clone()),
-        ],
-        item,
-    })
-}
-
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "сохранить"]
-pub struct UserEditForm {
-    #[form_field_type = "Radio"]
-    #[form_field_verbose_name = "статус"]
-    status: UserStatus,
+#[form_submit_name = "form.confirm_password.submit"]
+pub struct ConfirmPasswordForm {
+    #[form_field_type = "Password"]
+    #[form_field_required]
+    #[form_field_verbose_name = "form.confirm_password.password"]
+    #[form_field_help = "form.confirm_password.password_help"]
+    password: Secret<String>,
 }
 
-impl UserEditForm {
-    async fn load(
-        username: &str,
-        csrf_token: &str,
-        pool: &State<Pool<Postgres>>,
-    ) -> Result<Self, crate::error::Error> {
-        match try_get_user(username, pool).await? {
-            Some(user) => Ok(Self {
-                status: user.into(),
-                csrf_token: csrf_token.to_string(),
-            }),
-            None => Err(crate::error::Error::DoesNotExist),
+impl ConfirmPasswordForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            password: Secret::default(),
+            csrf_token: csrf_token.to_string(),
         }
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
+            password: Secret::default(),
             csrf_token: self.csrf_token.clone(),
-            status: self.status,
         }
     }
 
+    /// Password is checked like on login, with the same limit of failed attempts.
     async fn process(
         &self,
-        username: &str,
+        cookies: &CookieJar<'_>,
+        auth_config: &State<AuthConfig>,
+        user: &Authentication,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_edit_user_check_exists(username, self.status, pool).await? {
-            Some(()) => Ok(Either::Left(Redirect::to(uri!(user_detail_get(username))))),
-            None => Err(crate::error::Error::DoesNotExist),
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let mut errors = ValidationErrors::new();
+
+        if is_login_locked(
+            &username,
+            None,
+            auth_config.max_login_attempts,
+            auth_config.login_attempt_window,
+            auth_config.login_lockout_duration,
+            pool,
+        )
+        .await?
+        {
+            errors.add(
+                "password",
+                ValidationError {
+                    code: Cow::from("too_many_attempts"),
+                    message: Some(Cow::from("validation.too_many_login_attempts")),
+                    params: HashMap::new(),
+                },
+            );
+            return Ok(Either::Right(errors));
+        }
+
+        let user_full = try_get_user_full(&username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        if user_full.check_password(&self.password)? {
+            clear_login_attempts(&username, None, pool).await?;
+            set_recent_auth(cookies, &username, auth_config);
+            Ok(Either::Left(Redirect::to(uri!(index_get()))))
+        } else {
+            add_failed_login_attempt(&username, None, auth_config.login_attempt_window, pool)
+                .await?;
+            errors.add(
+                "password",
+                ValidationError {
+                    code: Cow::from("password_invalid"),
+                    message: Some(Cow::from("validation.password_invalid")),
+                    params: HashMap::new(),
+                },
+            );
+            Ok(Either::Right(errors))
         }
     }
 }
 
 form_get_and_post!(
-    edit,
+    simple,
     FormTemplate,
-    UserEditForm,
-    user_edit,
-    "/users/by-username/<username>/edit",
-    vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_USERS.clone(),
-        Breadcrumb::new_with_url(
-            username.to_string(),
-            uri!(user_detail_get(username)).to_string()
-        ),
-        Breadcrumb::new_without_url("управление".to_string())
-    ],
-    (Admin),
-    (username: &str),
-    false
+    ConfirmPasswordForm,
+    confirm_password,
+    "/auth/confirm-password",
+    PAGE_META_CONFIRM_PASSWORD.clone(),
+    (User),
+    (cookies: &CookieJar<'_>, auth_config: &State<AuthConfig>),
+    true,
+    redirect(uri!(index_get()))
 );
 
-#[get("/users?<page_id>&<page_size>")]
-pub async fn users_list_get<'a, 'b, 'c>(
-    user: Authentication,
-    pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
-    pagination_config: &'c State<PaginationConfig>,
-    page_id: Option<u64>,
-    page_size: Option<u64>,
-    _admin: Admin,
-) -> Result<UsersListTemplate<'b>, crate::error::Error> {
-    let page_params = PageParams {
-        page_id,
-        page_size: page_size.unwrap_or(pagination_config.default_page_size),
-    };
-    page_params.check(pagination_config)?;
-
-    let page = list_users_with_pagination(pool, page_params).await?;
-
-    Ok(UsersListTemplate {
-        user,
-        asset_context,
-        breadcrumbs: BREADCRUMB_USERS_LIST.clone(),
-        page,
-        page_base: UrlQuery::new(),
-    })
-}
-
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "добавить"]
-pub struct InviteAddForm {
-    #[validate(regex(
-        path = "USERNAME_CHARACTERS_REGEX",
-        code = "username_wrong_characters",
-        message = "инвайт-код может содержать только латинские буквы, цифры и нижние подчёркивания (_)"
-    ))]
-    #[validate(length(
-        min = 1,
-        code = "username_too_short",
-        message = "инвайт-код должен быть не короче 1 символов"
-    ))]
+#[form_submit_name = "form.profile_edit.submit"]
+pub struct ProfileEditForm {
     #[validate(length(
         max = 64,
-        code = "username_too_long",
-        message = "инвайт-код должен быть не длиннее 64 символов"
+        code = "display_name_too_long",
+        message = "validation.display_name_too_long"
     ))]
-    #[form_field_type = "Password"]
-    #[form_field_verbose_name = "инвайт-код"]
-    invite_code: String,
+    #[form_field_type = "Text"]
+    #[form_field_max = 64]
+    #[form_field_verbose_name = "form.profile_edit.display_name"]
+    display_name: Option<String>,
+
+    #[validate(custom = "validate_birth_date")]
+    #[form_field_type = "Date"]
+    #[form_field_verbose_name = "form.profile_edit.birth_date"]
+    birth_date: Option<Date>,
+
+    #[validate(length(max = 2000, code = "bio_too_long", message = "validation.bio_too_long"))]
+    #[form_field_type = "TextArea"]
+    #[form_field_max = 2000]
+    #[form_field_verbose_name = "form.profile_edit.bio"]
+    bio: String,
+
+    #[validate(custom = "validate_optional_email")]
+    #[form_field_type = "EMail"]
+    #[form_field_verbose_name = "form.profile_edit.email"]
+    email: Option<String>,
+
+    #[form_field_type = "Radio"]
+    #[form_field_verbose_name = "form.profile_edit.theme"]
+    theme: Theme,
+
+    #[validate(range(
+        min = -720.0,
+        max = 840.0,
+        message = "validation.timezone_offset_out_of_range"
+    ))]
+    #[form_field_type = "Number"]
+    #[form_field_min = "-720"]
+    #[form_field_max = 840]
+    #[form_field_verbose_name = "form.profile_edit.timezone_offset"]
+    timezone_offset: Option<f64>,
 }
 
-impl InviteAddForm {
-    fn new(csrf_token: &str) -> Self {
-        Self {
+impl ProfileEditForm {
+    async fn load(
+        _email_config: &State<EmailConfig>,
+        _mailer: &State<Arc<dyn Mailer>>,
+        cookies: &CookieJar<'_>,
+        _locale: Locale,
+        user: &Authentication,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let user_email = try_get_user_email(&username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        let user_profile = try_get_user_profile(&username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        Ok(Self {
+            display_name: user.map(|user| user.display_name.clone()).flatten(),
+            birth_date: user.birth_date().map(|birth_date| birth_date.date()),
+            bio: user_profile.bio,
+            email: user_email.email,
+            theme: get_user_theme(&username, pool)
+                .await?
+                .unwrap_or_else(|| Theme::from_cookies(cookies)),
+            timezone_offset: get_user_timezone_offset(&username, pool)
+                .await?
+                .or_else(|| timezone_offset_from_cookies(cookies))
+                .map(f64::from),
             csrf_token: csrf_token.to_string(),
-            invite_code: "".to_string(),
-        }
+        })
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
-            invite_code: "".to_string(),
+            display_name: self.display_name.clone(),
+            birth_date: self.birth_date,
+            bio: self.bio.clone(),
+            email: self.email.clone(),
+            theme: self.theme,
+            timezone_offset: self.timezone_offset,
             csrf_token: self.csrf_token.clone(),
         }
     }
 
     async fn process(
         &self,
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        cookies: &CookieJar<'_>,
+        locale: Locale,
+        user: &Authentication,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_add_invite_check_exists(&self.invite_code, pool).await? {
-            Some(()) => Ok(Either::Left(Redirect::to(uri!(index_get())))),
-            None => {
-                let mut errors = ValidationErrors::new();
-                errors.add(
-                    "invite_code",
-                    ValidationError {
-                        code: Cow::from("invite_already_exists"),
-                        message: Some(Cow::from("инвайт-код уже существует")),
-                        params: HashMap::new(),
-                    },
-                );
-                Ok(Either::Right(errors))
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+
+        if user.birth_date().map(|birth_date| birth_date.date()) != self.birth_date
+            && try_change_user_birth_date_once(
+                &username,
+                self.birth_date.map(date_to_offset_date_time),
+                pool,
+            )
+            .await?
+            .is_none()
+        {
+            return Ok(Either::Right(get_birth_date_already_changed_errors()));
+        }
+
+        let email = get_optional_email(&self.email);
+        let old_email = try_get_user_email(&username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+
+        match try_set_user_email_check_unique(&username, email, pool).await? {
+            Some(()) => {
+                set_user_display_name(
+                    &username,
+                    get_optional_display_name(&self.display_name),
+                    pool,
+                )
+                .await?;
+                set_user_bio(&username, &self.bio, pool).await?;
+                set_user_theme(&username, self.theme, pool).await?;
+                set_theme_cookie(cookies, self.theme);
+                let timezone_offset = self.timezone_offset.map(|offset| {
+                    (offset.round() as i32).clamp(MIN_TIMEZONE_OFFSET, MAX_TIMEZONE_OFFSET)
+                });
+                set_user_timezone_offset(&username, timezone_offset, pool).await?;
+                set_timezone_offset_cookie(cookies, timezone_offset);
+
+                if let Some(email) = email {
+                    let is_already_verified = old_email.is_verified()
+                        && old_email
+                            .email
+                            .is_some_and(|old_email| old_email.eq_ignore_ascii_case(email));
+                    if !is_already_verified {
+                        send_email_verification(
+                            &username,
+                            email,
+                            email_config,
+                            mailer.as_ref(),
+                            locale,
+                            pool,
+                        )
+                        .await?;
+                    }
+                }
+
+                Ok(Either::Left(Redirect::to(uri!(user_detail_get(username)))))
             }
+            None => Ok(Either::Right(get_email_already_in_use_errors())),
         }
     }
 }
 
 form_get_and_post!(
-    simple,
+    edit,
     FormTemplate,
-    InviteAddForm,
-    invite_add,
-    "/invites/add",
-    BREADCRUMBS_INVITE_ADD.clone(),
-    (Admin),
-    (),
-    false
+    ProfileEditForm,
+    profile_edit,
+    "/users/me/edit",
+    PAGE_META_PROFILE.clone(),
+    (User),
+    (
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        cookies: &CookieJar<'_>,
+        locale: Locale
+    ),
+    true,
+    "flash.profile_saved"
 );
 
+/// Form with avatar file input, file is sent by TypeScript uploader.
+#[derive(Clone, Debug, FormWithDefinition)]
+pub struct AvatarUploadForm {
+    #[form_field_type = "File"]
+    #[form_field_required]
+    #[form_field_accept = ".png,.jpg,.jpeg,.gif,.webp"]
+    #[form_field_verbose_name = "form.avatar_upload.file"]
+    #[form_field_help = "form.avatar_upload.file_help"]
+    file: FileUploadTarget,
+}
+
+#[get("/users/me/avatar")]
+pub async fn avatar_edit_get<'a, 'b>(
+    user: User,
+    csrf_token: CsrfToken,
+    context: PageContext<'a>,
+    pool: &State<Pool<Postgres>>,
+    upload_config: &'b State<UploadConfig>,
+) -> Result<AvatarEditTemplate<'a, 'b>, crate::error::Error> {
+    let profile = try_get_user_profile(&user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let csrf_token = csrf_token.authenticity_token();
+    let form = AvatarUploadForm {
+        file: FileUploadTarget {
+            post_id: None,
+            csrf_token: csrf_token.clone(),
+            max_size: upload_config.max_avatar_size,
+        },
+    }
+    .get_definition(ValidationErrors::new());
+
+    Ok(AvatarEditTemplate {
+        context: context.with_meta(PAGE_META_AVATAR.clone()),
+        csrf_token,
+        avatar: profile.avatar,
+        form,
+        storage: &upload_config.storage,
+    })
+}
+
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "удалить"]
-pub struct InviteRemoveForm {
+#[form_submit_name = "form.account_delete.submit"]
+pub struct AccountDeleteForm {
+    #[form_field_type = "Radio"]
+    #[form_field_verbose_name = "form.account_delete.mode"]
+    mode: AccountDeletionMode,
     #[form_field_type = "Password"]
-    #[form_field_verbose_name = "инвайт-код"]
-    invite_code: String,
+    #[form_field_verbose_name = "form.account_delete.password"]
+    password: Secret<String>,
 }
 
-impl InviteRemoveForm {
-    fn new(csrf_token: &str) -> Self {
-        Self {
+impl AccountDeleteForm {
+    async fn load(
+        _upload_config: &State<UploadConfig>,
+        _user: &Authentication,
+        csrf_token: &str,
+        _pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        Ok(Self {
+            mode: AccountDeletionMode::Anonymize,
+            password: Secret::default(),
             csrf_token: csrf_token.to_string(),
-            invite_code: "".to_string(),
-        }
+        })
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
-            invite_code: "".to_string(),
+            mode: self.mode,
+            password: Secret::default(),
             csrf_token: self.csrf_token.clone(),
         }
     }
 
     async fn process(
         &self,
+        upload_config: &State<UploadConfig>,
+        user: &Authentication,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_remove_invite_check_exists(&self.invite_code, pool).await? {
-            Some(()) => Ok(Either::Left(Redirect::to(uri!(index_get())))),
-            None => {
-                let mut errors = ValidationErrors::new();
-                errors.add(
-                    "invite_code",
-                    ValidationError {
-                        code: Cow::from("invite_does_not_exist"),
-                        message: Some(Cow::from("инвайт-код не существует")),
-                        params: HashMap::new(),
-                    },
-                );
-                Ok(Either::Right(errors))
-            }
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let user_full = try_get_user_full(&username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+
+        if !user_full.check_password(&self.password)? {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "password",
+                ValidationError {
+                    code: Cow::from("password_invalid"),
+                    message: Some(Cow::from("validation.password_invalid")),
+                    params: HashMap::new(),
+                },
+            );
+            return Ok(Either::Right(errors));
         }
+
+        delete_user_account(&username, self.mode, pool, &upload_config.storage)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+
+        Ok(Either::Left(Redirect::to(uri!(index_get()))))
     }
 }
 
 form_get_and_post!(
-    simple,
+    edit,
     FormTemplate,
-    InviteRemoveForm,
-    invite_remove,
-    "/invites/remove",
-    BREADCRUMBS_INVITE_REMOVE.to_vec(),
-    (Admin),
-    (),
-    false
+    AccountDeleteForm,
+    account_delete,
+    "/auth/delete-account",
+    PAGE_META_DELETE_ACCOUNT.clone(),
+    (User, RecentAuth),
+    (upload_config: &State<UploadConfig>),
+    true,
+    "flash.account_deleted"
 );
 
-#[get("/")]
-pub async fn index_get<'a, 'b, 'c, 'd>(
-    user: Authentication,
-    pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
-    pagination_config: &'c State<PaginationConfig>,
-    upload_config: &'d State<UploadConfig>,
-) -> Result<IndexTemplate<'b, 'd>, crate::error::Error> {
-    let pinned_posts = list_latest_pinned_posts(pool, pagination_config.default_page_size, &user)
-        .await?
-        .into_iter()
-        .map(|post| (post.id, post.check_visible(&user)))
-        .collect();
-    Ok(IndexTemplate {
-        user,
-        asset_context,
-        breadcrumbs: BREADCRUMBS_INDEX.clone(),
-        pinned_posts,
-        storage: &upload_config.storage,
-    })
-}
+#[get("/auth/verify-email/<token>")]
+pub async fn verify_email_get<'a>(
+    token: &str,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    email_config: &State<EmailConfig>,
+) -> Result<EmailVerificationTemplate<'a>, crate::error::Error> {
+    let verified_username =
+        try_verify_email(token, email_config.verification_token_lifetime, pool).await?;
 
-#[get("/ban-reasons")]
-pub async fn ban_reasons_list_get<'a, 'b>(
-    user: Authentication,
-    pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
-    _admin: Admin,
-) -> Result<BanReasonListTemplate<'b>, crate::error::Error> {
-    Ok(BanReasonListTemplate {
-        user,
-        asset_context,
-        breadcrumbs: BREADCRUMBS_BAN_REASONS_LIST.clone(),
-        items: list_ban_reasons(pool).await?,
+    Ok(EmailVerificationTemplate {
+        context: context.with_meta(PAGE_META_VERIFY_EMAIL.clone()),
+        verified_username,
     })
 }
 
@@ -881,136 +1590,1922 @@ pub async fn ban_reasons_list_get<'a, 'b>(
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "добавить"]
-pub struct BanReasonAddForm {
-    #[validate(regex(
-        path = "USERNAME_CHARACTERS_REGEX",
-        code = "id_wrong_characters",
-        message = "ID может содержать только латинские буквы, цифры и нижние подчёркивания (_)"
-    ))]
-    #[validate(length(
-        max = 64,
-        code = "id_too_long",
-        message = "ID должно быть не длиннее 64 символов"
-    ))]
-    #[validate(length(
-        min = 1,
-        code = "id_too_short",
-        message = "ID должно быть не короче 1 символов"
-    ))]
-    #[form_field_verbose_name = "ID"]
-    id: String,
-
-    #[form_field_verbose_name = "описание"]
-    description: String,
+#[form_submit_name = "form.forgot_password.submit"]
+pub struct ForgotPasswordForm {
+    #[form_field_verbose_name = "form.forgot_password.username_or_email"]
+    username_or_email: String,
 }
 
-impl BanReasonAddForm {
+impl ForgotPasswordForm {
     fn new(csrf_token: &str) -> Self {
         Self {
+            username_or_email: "".to_string(),
             csrf_token: csrf_token.to_string(),
-            id: "".to_string(),
-            description: "".to_string(),
         }
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
+            username_or_email: self.username_or_email.clone(),
             csrf_token: self.csrf_token.clone(),
-            id: self.id.clone(),
-            description: self.description.clone(),
         }
     }
 
     async fn process(
         &self,
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        locale: Locale,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_add_ban_reason_check_exists(
-            BanReason {
-                id: self.id.clone(),
-                description: Some(self.description.clone()),
-            },
-            pool,
-        )
-        .await?
+        if let Some((username, email)) =
+            try_get_verified_email_for_password_reset(self.username_or_email.trim(), pool).await?
         {
-            Some(()) => Ok(Either::Left(Redirect::to(uri!(ban_reasons_list_get())))),
-            None => {
-                let mut errors = ValidationErrors::new();
-                errors.add(
-                    "id",
-                    ValidationError {
-                        code: Cow::from("id_already_exists"),
-                        message: Some(Cow::from("причина бана с таким ID уже существует")),
-                        params: HashMap::new(),
-                    },
-                );
-                Ok(Either::Right(errors))
+            let token = add_password_reset_token(&username, pool).await?;
+
+            let result = mailer
+                .send(Mail {
+                    to: email.clone(),
+                    subject: locale.translate("mail.password_reset.subject").to_string(),
+                    body: format!(
+                        "{}\n\n{}",
+                        locale.translate_with_args(
+                            "mail.password_reset.body",
+                            &[
+                                ("username", &username),
+                                (
+                                    "link",
+                                    &format!(
+                                        "{}{}",
+                                        email_config.site_url,
+                                        uri!(reset_password_get(token))
+                                    ),
+                                ),
+                            ],
+                        ),
+                        locale.translate("mail.password_reset.not_requested")
+                    ),
+                })
+                .await;
+            if let Err(err) = result {
+                log::error!("Failed to send password reset mail to {}: {:?}", email, err);
             }
         }
+
+        Ok(Either::Left(Redirect::to(uri!(forgot_password_sent_get()))))
     }
 }
 
 form_get_and_post!(
     simple,
     FormTemplate,
-    BanReasonAddForm,
-    ban_reason_add,
-    "/ban-reasons/add",
-    BREADCRUMBS_BAN_REASON_ADD.to_vec(),
-    (Admin),
+    ForgotPasswordForm,
+    forgot_password,
+    "/auth/forgot-password",
+    PAGE_META_FORGOT_PASSWORD.clone(),
     (),
+    (
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        locale: Locale
+    ),
     false
 );
 
+#[get("/auth/forgot-password/sent")]
+pub fn forgot_password_sent_get(context: PageContext<'_>) -> MessageTemplate {
+    let message = context
+        .locale
+        .translate("message.password_reset_sent")
+        .to_string();
+    MessageTemplate {
+        context: context.with_meta(PAGE_META_FORGOT_PASSWORD.clone()),
+        message,
+    }
+}
+
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "сохранить"]
-pub struct BanReasonEditForm {
-    #[form_field_verbose_name = "описание"]
-    description: String,
+#[form_submit_name = "form.reset_password.submit"]
+pub struct ResetPasswordForm {
+    #[validate(length(
+        min = 8,
+        code = "password_too_short",
+        message = "validation.password_too_short"
+    ))]
+    #[validate(regex(
+        path = "PASSWORD_CHARACTERS_REGEX",
+        code = "password_wrong_characters",
+        message = "validation.password_wrong_characters"
+    ))]
+    #[validate(regex(
+        path = "PASSWORD_LETTER_REGEX",
+        code = "password_missing_letter",
+        message = "validation.password_missing_letter"
+    ))]
+    #[validate(regex(
+        path = "PASSWORD_DIGIT_REGEX",
+        code = "password_missing_digit",
+        message = "validation.password_missing_digit"
+    ))]
+    #[form_field_type = "Password"]
+    #[form_field_verbose_name = "form.reset_password.new_password"]
+    new_password: Secret<String>,
+
+    #[validate(must_match(other = "new_password", message = "validation.passwords_must_match"))]
+    #[form_field_type = "Password"]
+    #[form_field_verbose_name = "form.reset_password.new_password2"]
+    new_password2: Secret<String>,
 }
 
-impl BanReasonEditForm {
+impl ResetPasswordForm {
     async fn load(
-        id: &str,
+        token: &str,
+        _password_hash_config: &State<PasswordHashConfig>,
+        email_config: &State<EmailConfig>,
         csrf_token: &str,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Self, crate::error::Error> {
-        match try_get_ban_reason(id, pool).await? {
-            Some(ban_reason) => Ok(Self {
+        if is_password_reset_token_valid(token, email_config.password_reset_token_lifetime, pool)
+            .await?
+        {
+            Ok(Self {
+                new_password: Secret::default(),
+                new_password2: Secret::default(),
                 csrf_token: csrf_token.to_string(),
-                description: ban_reason.description.unwrap_or("".to_string()),
-            }),
-            None => Err(crate::error::Error::DoesNotExist),
+            })
+        } else {
+            Err(crate::error::Error::DoesNotExist)
         }
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
+            new_password: Secret::default(),
+            new_password2: Secret::default(),
             csrf_token: self.csrf_token.clone(),
-            description: self.description.clone(),
         }
     }
 
     async fn process(
         &self,
-        id: &str,
+        token: &str,
+        password_hash_config: &State<PasswordHashConfig>,
+        email_config: &State<EmailConfig>,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_edit_ban_reason_check_exists(
-            BanReason {
-                id: id.to_string(),
-                description: Some(self.description.clone()),
-            },
+        match try_reset_password_with_token(
+            token,
+            &self.new_password,
+            email_config.password_reset_token_lifetime,
+            password_hash_config,
             pool,
         )
         .await?
         {
-            Some(()) => Ok(Either::Left(Redirect::to(uri!(ban_reasons_list_get())))),
+            Some(_) => Ok(Either::Left(Redirect::to(uri!(login_get())))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    ResetPasswordForm,
+    reset_password,
+    "/auth/reset-password/<token>",
+    PAGE_META_FORGOT_PASSWORD.clone(),
+    (),
+    (
+        token: &str,
+        password_hash_config: &State<PasswordHashConfig>,
+        email_config: &State<EmailConfig>
+    ),
+    false
+);
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.session_revoke.submit"]
+pub struct SessionRevokeForm {}
+
+#[get("/auth/sessions")]
+pub async fn sessions_list_get<'a, 'b, 'c>(
+    cookies: &'a CookieJar<'_>,
+    user: User,
+    csrf_token: CsrfToken,
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    auth_config: &'c State<AuthConfig>,
+    flash_message: Option<FlashMessage>,
+) -> Result<SessionsListTemplate<'b>, crate::error::Error> {
+    let items = list_sessions_for_user(
+        &user.username,
+        get_session_token(cookies, auth_config).as_deref(),
+        pool,
+    )
+    .await?;
+
+    Ok(SessionsListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_SESSIONS.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        items,
+    })
+}
+
+#[post("/auth/sessions/by-id/<id>/revoke", data = "<_form>")]
+pub async fn session_revoke_post(
+    id: i64,
+    cookies: &CookieJar<'_>,
+    _form: CSRFProtectedForm<SessionRevokeForm>,
+    user: User,
+    pool: &State<Pool<Postgres>>,
+    auth_config: &State<AuthConfig>,
+) -> Result<Redirect, crate::error::Error> {
+    let is_current = list_sessions_for_user(
+        &user.username,
+        get_session_token(cookies, auth_config).as_deref(),
+        pool,
+    )
+    .await?
+    .iter()
+    .any(|session| session.id == id && session.is_current);
+
+    try_revoke_session_check_owner(id, &user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    set_flash_message(cookies, FlashMessage::success("flash.session_revoked"));
+
+    if is_current {
+        end_session(cookies, auth_config, pool).await?;
+        cookies.remove_private(
+            auth_config
+                .cookie_security
+                .apply(Cookie::build(crate::utils::csrf::COOKIE_NAME)),
+        );
+
+        Ok(Redirect::to(uri!(index_get())))
+    } else {
+        Ok(Redirect::to(uri!(sessions_list_get())))
+    }
+}
+
+#[post("/auth/sessions/revoke-other", data = "<_form>")]
+pub async fn sessions_revoke_other_post(
+    cookies: &CookieJar<'_>,
+    _form: CSRFProtectedForm<SessionRevokeForm>,
+    user: User,
+    pool: &State<Pool<Postgres>>,
+    auth_config: &State<AuthConfig>,
+) -> Result<Redirect, crate::error::Error> {
+    remove_user_sessions(
+        &user.username,
+        get_session_token(cookies, auth_config).as_deref(),
+        pool,
+    )
+    .await?;
+
+    set_flash_message(
+        cookies,
+        FlashMessage::success("flash.other_sessions_revoked"),
+    );
+    Ok(Redirect::to(uri!(sessions_list_get())))
+}
+
+#[get("/auth/tokens")]
+pub async fn api_tokens_list_get<'a, 'b>(
+    user: User,
+    _recent_auth: RecentAuth,
+    csrf_token: CsrfToken,
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    flash_message: Option<FlashMessage>,
+) -> Result<ApiTokensListTemplate<'b>, crate::error::Error> {
+    let items = list_api_tokens_for_user(&user.username, pool).await?;
+
+    Ok(ApiTokensListTemplate {
+        context: context.with_meta(PAGE_META_API_TOKENS.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        items,
+        flash_message,
+    })
+}
+
+#[get("/api-docs")]
+pub async fn api_docs_get<'a>(
+    context: PageContext<'a>,
+) -> Result<ApiDocsTemplate<'a>, crate::error::Error> {
+    let operations = list_api_operations();
+    let document = serde_json::to_string_pretty(&build_openapi_document(&operations))?;
+
+    Ok(ApiDocsTemplate {
+        context: context.with_meta(PAGE_META_API_DOCS.clone()),
+        operations,
+        document,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.api_token_add.submit"]
+pub struct ApiTokenAddForm {
+    #[validate(length(
+        min = 1,
+        code = "label_too_short",
+        message = "validation.label_too_short"
+    ))]
+    #[validate(length(
+        max = 128,
+        code = "label_too_long",
+        message = "validation.label_too_long"
+    ))]
+    #[form_field_verbose_name = "form.api_token_add.label"]
+    label: String,
+
+    #[form_field_type = "Radio"]
+    #[form_field_verbose_name = "form.api_token_add.scope"]
+    scope: ApiTokenScope,
+}
+
+impl ApiTokenAddForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            label: "".to_string(),
+            scope: ApiTokenScope::Read,
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            label: self.label.clone(),
+            scope: self.scope,
+        }
+    }
+
+    async fn process(
+        &self,
+        cookies: &CookieJar<'_>,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let token = add_api_token(&username, &self.label, self.scope, pool).await?;
+        set_flash_message(
+            cookies,
+            FlashMessage::info("flash.api_token_created").with_arg("token", token),
+        );
+        Ok(Either::Left(Redirect::to(uri!(api_tokens_list_get()))))
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    ApiTokenAddForm,
+    api_token_add,
+    "/auth/tokens/add",
+    PAGE_META_API_TOKEN_ADD.clone(),
+    (User, RecentAuth),
+    (cookies: &CookieJar<'_>),
+    true
+);
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.api_token_revoke.submit"]
+pub struct ApiTokenRevokeForm {}
+
+#[post("/auth/tokens/by-id/<id>/revoke", data = "<_form>")]
+pub async fn api_token_revoke_post(
+    id: i64,
+    _form: CSRFProtectedForm<ApiTokenRevokeForm>,
+    user: User,
+    _recent_auth: RecentAuth,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_revoke_api_token_check_owner(id, &user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(cookies, FlashMessage::success("flash.api_token_revoked"));
+    Ok(Redirect::to(uri!(api_tokens_list_get())))
+}
+
+#[get("/user/by-username/<username>")]
+pub async fn user_detail_get<'a, 'b, 'c, 'd>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    username: &'c str,
+    upload_config: &'d State<UploadConfig>,
+    csrf_token: CsrfToken,
+    flash_message: Option<FlashMessage>,
+) -> Result<UserDetailTemplate<'b, 'd>, crate::error::Error> {
+    let item = match &context.user {
+        Authentication::Authenticated(user_real) | Authentication::Impersonated(user_real, _)
+            if user_real.username == username =>
+        {
+            user_real.clone()
+        }
+        _ => try_get_user(username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?,
+    };
+
+    let email = if context.user.is_admin() || context.user.username().as_deref() == Some(username) {
+        try_get_user_email(username, pool).await?
+    } else {
+        None
+    };
+
+    let profile = try_get_user_profile(username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let storage_usage =
+        if context.user.is_admin() || context.user.username().as_deref() == Some(username) {
+            try_get_user_storage_usage(username, pool).await?
+        } else {
+            None
+        };
+
+    let invite_allowance =
+        if context.user.is_admin() || context.user.username().as_deref() == Some(username) {
+            try_get_user_invite_allowance(username, pool).await?
+        } else {
+            None
+        };
+
+    let admin_details = if context.user.is_admin() {
+        get_user_admin_details(username, pool).await?
+    } else {
+        None
+    };
+
+    let ban_appeals = if context.user.username().as_deref() == Some(username) {
+        list_ban_appeals_by_author(username, pool).await?
+    } else {
+        vec![]
+    };
+
+    Ok(UserDetailTemplate {
+        email,
+        profile,
+        storage_usage,
+        invite_allowance,
+        admin_details,
+        ban_appeals,
+        flash_message,
+        csrf_token: csrf_token.authenticity_token(),
+        default_storage_quota_bytes: upload_config.default_storage_quota_bytes,
+        storage: &upload_config.storage,
+        context: context.with_meta(PageMeta::for_user(&item.username)),
+        item,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.user_edit.submit"]
+pub struct UserEditForm {
+    #[validate(length(
+        max = 64,
+        code = "display_name_too_long",
+        message = "validation.display_name_too_long"
+    ))]
+    #[form_field_type = "Text"]
+    #[form_field_max = 64]
+    #[form_field_verbose_name = "form.user_edit.display_name"]
+    display_name: Option<String>,
+    #[validate(custom = "validate_birth_date")]
+    #[form_field_type = "Date"]
+    #[form_field_verbose_name = "form.user_edit.birth_date"]
+    birth_date: Option<Date>,
+    #[form_field_type = "Select"]
+    #[form_field_verbose_name = "form.user_edit.status"]
+    status: UserStatus,
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.user_edit.revoke_sessions"]
+    revoke_sessions: bool,
+    #[form_field_type = "Password"]
+    #[form_field_verbose_name = "form.user_edit.new_password"]
+    new_password: Option<Secret<String>>,
+    #[form_field_type = "Number"]
+    #[form_field_verbose_name = "form.user_edit.storage_quota_mib"]
+    #[validate(range(min = 0.0, message = "validation.storage_quota_negative"))]
+    storage_quota_mib: Option<f64>,
+    #[form_field_type = "Number"]
+    #[form_field_min = 0]
+    #[form_field_max = 1000]
+    #[form_field_verbose_name = "form.user_edit.invite_allowance"]
+    #[validate(range(
+        min = 0.0,
+        max = 1000.0,
+        message = "validation.invite_allowance_out_of_range"
+    ))]
+    invite_allowance: Option<f64>,
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.user_edit.revoke_invites"]
+    revoke_invites: bool,
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.user_edit.ban_posts"]
+    ban_posts: bool,
+    #[validate(length(
+        max = 1024,
+        code = "ban_reason_text_too_long",
+        message = "validation.ban_reason_text_too_long"
+    ))]
+    #[form_field_type = "Text"]
+    #[form_field_max = 1024]
+    #[form_field_verbose_name = "form.user_edit.ban_posts_reason_text"]
+    ban_posts_reason_text: Option<String>,
+}
+
+const BYTES_IN_MIB: f64 = 1024.0 * 1024.0;
+
+impl UserEditForm {
+    async fn load(
+        username: &str,
+        _password_hash_config: &State<PasswordHashConfig>,
+        _cookies: &CookieJar<'_>,
+        _user: &Authentication,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        let storage_usage = try_get_user_storage_usage(username, pool).await?;
+        let invite_allowance = try_get_user_invite_allowance(username, pool).await?;
+        match (
+            try_get_user(username, pool).await?,
+            storage_usage,
+            invite_allowance,
+        ) {
+            (Some(user), Some(storage_usage), Some(invite_allowance)) => Ok(Self {
+                display_name: user.display_name.clone(),
+                birth_date: user.birth_date.map(|birth_date| birth_date.date()),
+                status: user.into(),
+                revoke_sessions: false,
+                new_password: None,
+                storage_quota_mib: storage_usage
+                    .storage_quota_bytes
+                    .map(|storage_quota_bytes| storage_quota_bytes as f64 / BYTES_IN_MIB),
+                invite_allowance: Some(invite_allowance as f64),
+                revoke_invites: false,
+                ban_posts: false,
+                ban_posts_reason_text: None,
+                csrf_token: csrf_token.to_string(),
+            }),
+            _ => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            display_name: self.display_name.clone(),
+            birth_date: self.birth_date,
+            status: self.status,
+            revoke_sessions: self.revoke_sessions,
+            new_password: None,
+            storage_quota_mib: self.storage_quota_mib,
+            invite_allowance: self.invite_allowance,
+            revoke_invites: self.revoke_invites,
+            ban_posts: self.ban_posts,
+            ban_posts_reason_text: self.ban_posts_reason_text.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        username: &str,
+        password_hash_config: &State<PasswordHashConfig>,
+        cookies: &CookieJar<'_>,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let new_password = self
+            .new_password
+            .as_ref()
+            .map(|new_password| new_password.expose().as_str())
+            .filter(|new_password| !new_password.is_empty());
+        if new_password.is_some_and(|new_password| new_password.len() < 8) {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "new_password",
+                ValidationError {
+                    code: Cow::from("password_too_short"),
+                    message: Some(Cow::from("validation.password_too_short")),
+                    params: HashMap::new(),
+                },
+            );
+            return Ok(Either::Right(errors));
+        }
+        match try_edit_user_check_exists(username, self.status, &actor_username, pool).await? {
+            Some(()) => {
+                if let Some(new_password) = new_password {
+                    change_user_password(username, new_password, true, password_hash_config, pool)
+                        .await?;
+                }
+                if self.revoke_sessions || new_password.is_some() {
+                    remove_user_sessions(username, None, pool).await?;
+                }
+                set_user_display_name(
+                    username,
+                    get_optional_display_name(&self.display_name),
+                    pool,
+                )
+                .await?;
+                set_user_birth_date(
+                    username,
+                    self.birth_date.map(date_to_offset_date_time),
+                    pool,
+                )
+                .await?;
+                set_user_storage_quota(
+                    username,
+                    self.storage_quota_mib
+                        .map(|storage_quota_mib| (storage_quota_mib * BYTES_IN_MIB) as i64),
+                    pool,
+                )
+                .await?;
+                set_user_invite_allowance(
+                    username,
+                    self.invite_allowance
+                        .map_or(0, |invite_allowance| invite_allowance as i32),
+                    pool,
+                )
+                .await?;
+                if self.status == UserStatus::Banned && self.revoke_invites {
+                    remove_unredeemed_invites_by_creator(username, &actor_username, pool).await?;
+                }
+                if self.status == UserStatus::Banned && self.ban_posts {
+                    let banned_post_count = ban_all_posts_by_author(
+                        username,
+                        None,
+                        self.ban_posts_reason_text
+                            .clone()
+                            .filter(|ban_reason_text| !ban_reason_text.is_empty()),
+                        &actor_username,
+                        pool,
+                    )
+                    .await?;
+                    set_flash_message(
+                        cookies,
+                        FlashMessage::success("flash.user_posts_banned")
+                            .with_arg("count", banned_post_count),
+                    );
+                }
+                Ok(Either::Left(Redirect::to(uri!(user_detail_get(username)))))
+            }
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    UserEditForm,
+    user_edit,
+    "/users/by-username/<username>/edit",
+    PageMeta::for_user_action(username, "breadcrumb.user_edit"),
+    (Admin),
+    (
+        username: &str,
+        password_hash_config: &State<PasswordHashConfig>,
+        cookies: &CookieJar<'_>
+    ),
+    true
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.user_delete.submit"]
+pub struct UserDeleteForm {
+    #[form_field_type = "Radio"]
+    #[form_field_verbose_name = "form.user_delete.mode"]
+    mode: AccountDeletionMode,
+}
+
+impl UserDeleteForm {
+    async fn load(
+        username: &str,
+        _upload_config: &State<UploadConfig>,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_user(username, pool).await? {
+            Some(user) if user.username != DELETED_USERNAME => Ok(Self {
+                mode: AccountDeletionMode::Anonymize,
+                csrf_token: csrf_token.to_string(),
+            }),
+            _ => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            mode: self.mode,
+            csrf_token: self.csrf_token.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        username: &str,
+        upload_config: &State<UploadConfig>,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match delete_user_account(username, self.mode, pool, &upload_config.storage).await? {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(users_list_get(
+                None as Option<u64>,
+                None as Option<u64>,
+                None as Option<UserSort>,
+                None as Option<UserStatus>
+            ))))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    UserDeleteForm,
+    user_delete,
+    "/users/by-username/<username>/delete",
+    PageMeta::for_user_action(username, "breadcrumb.remove"),
+    (Admin),
+    (username: &str, upload_config: &State<UploadConfig>),
+    false,
+    "flash.user_deleted"
+);
+
+#[derive(Clone, Debug, Deserialize, Serialize, FromForm, CheckCSRF)]
+pub struct UserInviteAddForm {
+    csrf_token: String,
+}
+
+#[post("/users/me/invites/add", data = "<_form>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn user_invite_add_post<'a>(
+    _form: CSRFProtectedForm<UserInviteAddForm>,
+    current_user: User,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    auth_config: &State<AuthConfig>,
+    email_config: &State<EmailConfig>,
+    signed_token_key: &State<SignedTokenKey>,
+) -> Result<MessageTemplate<'a>, crate::error::Error> {
+    let message = match try_add_user_invite_check_allowance(&current_user.username, pool).await? {
+        Some(invite_code) => context.locale.translate_with_args(
+            "message.user_invite_added",
+            &[
+                ("invite_code", &invite_code),
+                (
+                    "link",
+                    &get_invite_link(
+                        &invite_code,
+                        None,
+                        auth_config,
+                        email_config,
+                        signed_token_key,
+                    ),
+                ),
+            ],
+        ),
+        None => context
+            .locale
+            .translate("message.no_invites_available")
+            .to_string(),
+    };
+
+    Ok(MessageTemplate {
+        context: context.with_meta(PageMeta::for_user_action(
+            &current_user.username,
+            "breadcrumb.user_invite_add",
+        )),
+        message,
+    })
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, FromForm, CheckCSRF)]
+pub struct UserBulkForm {
+    csrf_token: String,
+    usernames: Vec<String>,
+    action: BulkUserAction,
+}
+
+#[post("/users/bulk", data = "<form>")]
+pub async fn users_bulk_post<'a>(
+    form: CSRFProtectedForm<UserBulkForm>,
+    admin_user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+) -> Result<MessageTemplate<'a>, crate::error::Error> {
+    let changed_count =
+        bulk_edit_users(&form.usernames, form.action, &admin_user.username, pool).await?;
+
+    let message = context.locale.translate_with_args(
+        "message.users_bulk_edited",
+        &[("count", &changed_count.to_string())],
+    );
+    Ok(MessageTemplate {
+        context: context.with_meta(PageMeta::subsection(
+            &BREADCRUMB_USERS,
+            "breadcrumb.users_bulk",
+        )),
+        message,
+    })
+}
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.user_impersonate.submit"]
+pub struct UserImpersonateForm {}
+
+impl UserImpersonateForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+        }
+    }
+}
+
+async fn get_impersonation_target(
+    username: &str,
+    pool: &Pool<Postgres>,
+) -> Result<User, crate::error::Error> {
+    let target = try_get_user(username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    if target.is_admin || !target.is_active {
+        return Err(crate::error::Error::AccessDenied);
+    }
+    Ok(target)
+}
+
+#[get("/users/by-username/<username>/impersonate")]
+pub async fn user_impersonate_get<'a, 'b>(
+    username: &'a str,
+    _admin: Admin,
+    csrf_token: CsrfToken,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'b>,
+) -> Result<FormTemplate<'b>, crate::error::Error> {
+    let target = get_impersonation_target(username, pool).await?;
+
+    Ok(FormTemplate {
+        form: UserImpersonateForm::new(&csrf_token.authenticity_token())
+            .get_definition(ValidationErrors::new()),
+        context: context.with_meta(PageMeta::for_user_action(
+            &target.username,
+            "breadcrumb.impersonate",
+        )),
+    })
+}
+
+#[post("/users/by-username/<username>/impersonate", data = "<_form>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn user_impersonate_post(
+    username: &str,
+    cookies: &CookieJar<'_>,
+    _form: CSRFProtectedForm<UserImpersonateForm>,
+    user: User,
+    _admin: Admin,
+    user_agent: UserAgent,
+    pool: &State<Pool<Postgres>>,
+    auth_config: &State<AuthConfig>,
+    csrf_config: &State<CsrfConfig>,
+) -> Result<Redirect, crate::error::Error> {
+    let target = get_impersonation_target(username, pool).await?;
+
+    start_impersonation(
+        cookies,
+        &user.username,
+        &target.username,
+        &user_agent,
+        auth_config,
+        pool,
+    )
+    .await?;
+    rotate_csrf_token(cookies, csrf_config);
+
+    Ok(Redirect::to(uri!(index_get())))
+}
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.impersonation_stop.submit"]
+pub struct ImpersonationStopForm {}
+
+impl ImpersonationStopForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+        }
+    }
+}
+
+#[get("/auth/stop-impersonating")]
+pub fn impersonation_stop_get(
+    csrf_token: CsrfToken,
+    context: PageContext<'_>,
+) -> Either<FormTemplate, Redirect> {
+    if context.user.impersonator_username().is_none() {
+        Either::Right(Redirect::to(uri!(index_get())))
+    } else {
+        Either::Left(FormTemplate {
+            form: ImpersonationStopForm::new(&csrf_token.authenticity_token())
+                .get_definition(ValidationErrors::new()),
+            context: context.with_meta(PAGE_META_IMPERSONATION_STOP.clone()),
+        })
+    }
+}
+
+#[post("/auth/stop-impersonating", data = "<_form>")]
+pub async fn impersonation_stop_post(
+    cookies: &CookieJar<'_>,
+    _form: CSRFProtectedForm<ImpersonationStopForm>,
+    user: Authentication,
+    user_agent: UserAgent,
+    pool: &State<Pool<Postgres>>,
+    auth_config: &State<AuthConfig>,
+    csrf_config: &State<CsrfConfig>,
+) -> Result<Redirect, crate::error::Error> {
+    let target_username = user.username();
+
+    stop_impersonation(
+        cookies,
+        target_username.as_deref(),
+        &user_agent,
+        auth_config,
+        pool,
+    )
+    .await?;
+    rotate_csrf_token(cookies, csrf_config);
+
+    match target_username {
+        Some(target_username) => Ok(Redirect::to(uri!(user_detail_get(target_username)))),
+        None => Ok(Redirect::to(uri!(index_get()))),
+    }
+}
+
+#[get("/users?<page_id>&<page_size>&<sort>&<status>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn users_list_get<'a, 'b, 'c>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
+    sort: Option<UserSort>,
+    status: Option<UserStatus>,
+    csrf_token: CsrfToken,
+    _admin: Admin,
+    flash_message: Option<FlashMessage>,
+) -> Result<Either<UsersListTemplate<'b>, Redirect>, crate::error::Error> {
+    let page_params = PageParams {
+        page_id,
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
+    };
+    page_params.check(pagination_config)?;
+
+    let page = list_users_with_pagination(
+        pool,
+        page_params,
+        sort.unwrap_or(UserSort::Username),
+        status,
+    )
+    .await?;
+
+    if page_id.is_some_and(|page_id| page_id != page.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(users_list_get(
+            Some(page.page_id),
+            Some(page.page_size),
+            sort,
+            status
+        )))));
+    }
+
+    let mut page_base = UrlQuery::new();
+    if let Some(sort) = sort {
+        page_base.add("sort".to_string(), sort.get_option());
+    }
+    if let Some(status) = status {
+        page_base.add("status".to_string(), status.get_option());
+    }
+
+    Ok(Either::Left(UsersListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_USERS_LIST.clone()),
+        page,
+        page_base,
+        sort: sort.map(UserSort::get_option),
+        status: status.map(UserStatus::get_option),
+        csrf_token: csrf_token.authenticity_token(),
+    }))
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.uploader_request.submit"]
+pub struct UploaderRequestForm {
+    #[validate(length(
+        min = 1,
+        code = "motivation_too_short",
+        message = "validation.motivation_too_short"
+    ))]
+    #[validate(length(
+        max = 2000,
+        code = "motivation_too_long",
+        message = "validation.motivation_too_long"
+    ))]
+    #[form_field_type = "TextArea"]
+    #[form_field_verbose_name = "form.uploader_request.motivation"]
+    motivation: String,
+}
+
+impl UploaderRequestForm {
+    async fn load(
+        user: &Authentication,
+        csrf_token: &str,
+        _pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        if user.is_uploader() {
+            return Err(crate::error::Error::AccessDenied);
+        }
+        Ok(Self {
+            motivation: "".to_string(),
+            csrf_token: csrf_token.to_string(),
+        })
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            motivation: self.motivation.clone(),
+            csrf_token: self.csrf_token.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        if user.is_uploader() {
+            return Err(crate::error::Error::AccessDenied);
+        }
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_add_uploader_request_check_pending(&username, &self.motivation, pool).await? {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(user_detail_get(username))))),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "motivation",
+                    ValidationError {
+                        code: Cow::from("uploader_request_already_pending"),
+                        message: Some(Cow::from("validation.uploader_request_already_pending")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    UploaderRequestForm,
+    uploader_request,
+    "/users/me/request-uploader",
+    PAGE_META_UPLOADER_REQUEST.clone(),
+    (User),
+    (),
+    true,
+    "flash.uploader_request_sent"
+);
+
+#[get("/admin/audit-log?<page_id>&<page_size>&<actor>&<action>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn audit_log_get<'a, 'b, 'c>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
+    actor: Option<&str>,
+    action: Option<AuditAction>,
+    _admin: Admin,
+) -> Result<Either<AuditLogTemplate<'b>, Redirect>, crate::error::Error> {
+    let actor = actor.filter(|actor| !actor.is_empty());
+    let page_params = PageParams {
+        page_id,
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
+    };
+    page_params.check(pagination_config)?;
+
+    let page = list_audit_log_with_pagination(pool, page_params, actor, action).await?;
+
+    if page_id.is_some_and(|page_id| page_id != page.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(audit_log_get(
+            Some(page.page_id),
+            Some(page.page_size),
+            actor,
+            action
+        )))));
+    }
+
+    let mut page_base = UrlQuery::new();
+    if let Some(actor) = actor {
+        page_base.add("actor".to_string(), actor.to_string());
+    }
+    if let Some(action) = action {
+        page_base.add("action".to_string(), action.get_option());
+    }
+
+    Ok(Either::Left(AuditLogTemplate {
+        context: context.with_meta(PAGE_META_AUDIT_LOG.clone()),
+        page,
+        page_base,
+        actor: actor.map(str::to_string),
+        action: action.map(AuditAction::get_option),
+    }))
+}
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.uploader_request_decide.submit"]
+pub struct UploaderRequestDecideForm {}
+
+#[get("/admin/uploader-requests")]
+pub async fn uploader_requests_list_get<'a>(
+    _admin: Admin,
+    csrf_token: CsrfToken,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    flash_message: Option<FlashMessage>,
+) -> Result<UploaderRequestsListTemplate<'a>, crate::error::Error> {
+    Ok(UploaderRequestsListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_UPLOADER_REQUESTS_LIST.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        items: list_pending_uploader_requests(pool).await?,
+    })
+}
+
+#[post("/admin/uploader-requests/by-id/<id>/approve", data = "<_form>")]
+pub async fn uploader_request_approve_post(
+    id: i64,
+    _form: CSRFProtectedForm<UploaderRequestDecideForm>,
+    user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    let username = try_decide_uploader_request_check_pending(
+        id,
+        UploaderRequestStatus::Approved,
+        &user.username,
+        pool,
+    )
+    .await?
+    .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let target = try_get_user(&username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    if UserStatus::from(target) == UserStatus::User {
+        try_edit_user_check_exists(&username, UserStatus::Uploader, &user.username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+    }
+
+    set_flash_message(
+        cookies,
+        FlashMessage::success("flash.uploader_request_approved"),
+    );
+    Ok(Redirect::to(uri!(uploader_requests_list_get())))
+}
+
+#[post("/admin/uploader-requests/by-id/<id>/reject", data = "<_form>")]
+pub async fn uploader_request_reject_post(
+    id: i64,
+    _form: CSRFProtectedForm<UploaderRequestDecideForm>,
+    user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_decide_uploader_request_check_pending(
+        id,
+        UploaderRequestStatus::Rejected,
+        &user.username,
+        pool,
+    )
+    .await?
+    .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(
+        cookies,
+        FlashMessage::success("flash.uploader_request_rejected"),
+    );
+    Ok(Redirect::to(uri!(uploader_requests_list_get())))
+}
+
+#[get("/invites?<page_id>&<page_size>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn invites_list_get<'a, 'b, 'c>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
+    csrf_token: CsrfToken,
+    _admin: Admin,
+    flash_message: Option<FlashMessage>,
+) -> Result<Either<InvitesListTemplate<'b>, Redirect>, crate::error::Error> {
+    let page_params = PageParams {
+        page_id,
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
+    };
+    page_params.check(pagination_config)?;
+
+    let page = list_invites_with_pagination(pool, page_params).await?;
+
+    if page_id.is_some_and(|page_id| page_id != page.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(invites_list_get(
+            Some(page.page_id),
+            Some(page.page_size)
+        )))));
+    }
+
+    Ok(Either::Left(InvitesListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_INVITES_LIST.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        page,
+        page_base: UrlQuery::new(),
+    }))
+}
+
+fn get_invite_link(
+    invite_code: &str,
+    invite_expires_at: Option<OffsetDateTime>,
+    auth_config: &AuthConfig,
+    email_config: &EmailConfig,
+    signed_token_key: &SignedTokenKey,
+) -> String {
+    let link_expires_at = OffsetDateTime::now_utc() + auth_config.invite_link_lifetime;
+    let expires_at = match invite_expires_at {
+        Some(invite_expires_at) if invite_expires_at < link_expires_at => invite_expires_at,
+        _ => link_expires_at,
+    };
+    let token = signed_token_key.sign_invite_code(invite_code, expires_at);
+    format!(
+        "{}{}",
+        email_config.site_url,
+        uri!(registration_get(None as Option<&str>, Some(token)))
+    )
+}
+
+#[get("/invites/by-code/<invite_code>/link")]
+#[allow(clippy::too_many_arguments)]
+pub async fn invite_link_get<'a>(
+    invite_code: &str,
+    current_user: User,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    auth_config: &State<AuthConfig>,
+    email_config: &State<EmailConfig>,
+    signed_token_key: &State<SignedTokenKey>,
+) -> Result<MessageTemplate<'a>, crate::error::Error> {
+    let invite = try_get_invite(invite_code, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    if !current_user.is_admin && invite.created_by.as_ref() != Some(&current_user.username) {
+        return Err(crate::error::Error::AccessDenied);
+    }
+
+    let message = context.locale.translate_with_args(
+        "message.invite_link",
+        &[(
+            "link",
+            &get_invite_link(
+                &invite.invite_code,
+                invite.expires_at,
+                auth_config,
+                email_config,
+                signed_token_key,
+            ),
+        )],
+    );
+    Ok(MessageTemplate {
+        context: context.with_meta(PAGE_META_INVITE_LINK.clone()),
+        message,
+    })
+}
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.invite_revoke.submit"]
+pub struct InviteRevokeForm {}
+
+#[post("/invites/by-code/<invite_code>/revoke", data = "<_form>")]
+pub async fn invite_revoke_post(
+    invite_code: &str,
+    _form: CSRFProtectedForm<InviteRevokeForm>,
+    user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_remove_invite_check_exists(invite_code, &user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(cookies, FlashMessage::success("flash.invite_revoked"));
+    Ok(Redirect::to(uri!(invites_list_get(
+        None as Option<u64>,
+        None as Option<u64>
+    ))))
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.invite_add.submit"]
+pub struct InviteAddForm {
+    #[validate(regex(
+        path = "USERNAME_CHARACTERS_REGEX",
+        code = "username_wrong_characters",
+        message = "validation.invite_code_wrong_characters"
+    ))]
+    #[validate(length(
+        min = 1,
+        code = "username_too_short",
+        message = "validation.invite_code_too_short"
+    ))]
+    #[validate(length(
+        max = 64,
+        code = "username_too_long",
+        message = "validation.invite_code_too_long"
+    ))]
+    #[form_field_type = "Password"]
+    #[form_field_verbose_name = "form.invite_add.invite_code"]
+    #[form_field_help = "form.invite_add.invite_code_help"]
+    invite_code: Secret<String>,
+
+    #[form_field_type = "Date"]
+    #[form_field_verbose_name = "form.invite_add.expires_on"]
+    expires_on: Option<Date>,
+
+    #[validate(range(
+        min = 1.0,
+        max = 1000000.0,
+        message = "validation.max_uses_out_of_range"
+    ))]
+    #[form_field_type = "Number"]
+    #[form_field_verbose_name = "form.invite_add.max_uses"]
+    #[form_field_placeholder = "form.invite_add.max_uses_placeholder"]
+    max_uses: Option<f64>,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.invite_add.grants_uploader"]
+    grants_uploader: bool,
+
+    #[validate(custom = "validate_optional_email")]
+    #[form_field_type = "EMail"]
+    #[form_field_verbose_name = "form.invite_add.email"]
+    #[form_field_help = "form.invite_add.email_help"]
+    email: Option<String>,
+}
+
+impl InviteAddForm {
+    async fn load(
+        _email_config: &State<EmailConfig>,
+        _mailer: &State<Arc<dyn Mailer>>,
+        _locale: Locale,
+        _user: &Authentication,
+        csrf_token: &str,
+        _pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        Ok(Self {
+            csrf_token: csrf_token.to_string(),
+            invite_code: Secret::default(),
+            expires_on: None,
+            max_uses: None,
+            grants_uploader: false,
+            email: None,
+        })
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            invite_code: Secret::default(),
+            expires_on: self.expires_on,
+            max_uses: self.max_uses,
+            grants_uploader: self.grants_uploader,
+            email: self.email.clone(),
+            csrf_token: self.csrf_token.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        locale: Locale,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let expires_at = self
+            .expires_on
+            .and_then(|expires_on| expires_on.next_day())
+            .map(date_to_offset_date_time);
+        match try_add_invite_check_exists(
+            &self.invite_code,
+            Some(&actor_username),
+            expires_at,
+            self.max_uses.map_or(1, |max_uses| max_uses as i32),
+            self.grants_uploader,
+            pool,
+        )
+        .await?
+        {
+            Some(()) => {
+                if let Some(email) = get_optional_email(&self.email) {
+                    let result = mailer
+                        .send(Mail {
+                            to: email.to_string(),
+                            subject: locale.translate("mail.invite.subject").to_string(),
+                            body: locale.translate_with_args(
+                                "mail.invite.body",
+                                &[(
+                                    "link",
+                                    &format!(
+                                        "{}{}",
+                                        email_config.site_url,
+                                        uri!(registration_get(
+                                            Some(self.invite_code.expose()),
+                                            None as Option<&str>
+                                        ))
+                                    ),
+                                )],
+                            ),
+                        })
+                        .await;
+                    if let Err(err) = result {
+                        log::error!("Failed to send invite mail to {}: {:?}", email, err);
+                        try_remove_invite_check_exists(&self.invite_code, &actor_username, pool)
+                            .await?;
+                        let mut errors = ValidationErrors::new();
+                        errors.add(
+                            "email",
+                            ValidationError {
+                                code: Cow::from("invite_mail_failed"),
+                                message: Some(Cow::from("validation.invite_mail_failed")),
+                                params: HashMap::new(),
+                            },
+                        );
+                        return Ok(Either::Right(errors));
+                    }
+                }
+                Ok(Either::Left(Redirect::to(uri!(invites_list_get(
+                    None as Option<u64>,
+                    None as Option<u64>
+                )))))
+            }
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "invite_code",
+                    ValidationError {
+                        code: Cow::from("invite_already_exists"),
+                        message: Some(Cow::from("validation.invite_already_exists")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    InviteAddForm,
+    invite_add,
+    "/invites/add",
+    PAGE_META_INVITE_ADD.clone(),
+    (Admin),
+    (
+        email_config: &State<EmailConfig>,
+        mailer: &State<Arc<dyn Mailer>>,
+        locale: Locale
+    ),
+    true,
+    "flash.invite_added",
+    redirect(uri!(invite_add_get()))
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.invite_generate.submit"]
+pub struct InviteGenerateForm {
+    #[validate(range(
+        min = 1.0,
+        max = 1000.0,
+        message = "validation.invite_count_out_of_range"
+    ))]
+    #[form_field_type = "Number"]
+    #[form_field_verbose_name = "form.invite_generate.count"]
+    count: f64,
+
+    #[form_field_type = "Date"]
+    #[form_field_verbose_name = "form.invite_generate.expires_on"]
+    expires_on: Option<Date>,
+
+    #[validate(range(
+        min = 1.0,
+        max = 1000000.0,
+        message = "validation.max_uses_out_of_range"
+    ))]
+    #[form_field_type = "Number"]
+    #[form_field_verbose_name = "form.invite_generate.max_uses"]
+    max_uses: Option<f64>,
+}
+
+#[get("/invites/generate")]
+pub async fn invite_generate_get<'a>(
+    csrf_token: CsrfToken,
+    context: PageContext<'a>,
+    _admin: Admin,
+) -> FormTemplate<'a> {
+    FormTemplate {
+        form: InviteGenerateForm {
+            csrf_token: csrf_token.authenticity_token(),
+            count: 10.0,
+            expires_on: None,
+            max_uses: None,
+        }
+        .get_definition(ValidationErrors::new()),
+        context: context.with_meta(PAGE_META_INVITE_GENERATE.clone()),
+    }
+}
+
+#[post("/invites/generate", data = "<form>")]
+pub async fn invite_generate_post<'a, 'b>(
+    pool: &'a State<Pool<Postgres>>,
+    form: CSRFProtectedForm<InviteGenerateForm>,
+    context: PageContext<'b>,
+    _admin: Admin,
+) -> Result<Either<CsvAttachment, FormTemplate<'b>>, crate::error::Error> {
+    if let Err(errors) = form.validate() {
+        return Ok(Either::Right(FormTemplate {
+            form: form.get_definition(errors),
+            context: context.with_meta(PAGE_META_INVITE_GENERATE.clone()),
+        }));
+    }
+
+    let expires_at = form
+        .expires_on
+        .and_then(|expires_on| expires_on.next_day())
+        .map(date_to_offset_date_time);
+    let max_uses = form.max_uses.map_or(1, |max_uses| max_uses as i32);
+    let invite_codes = add_random_invites(
+        form.count as u32,
+        context.user.username().as_deref(),
+        expires_at,
+        max_uses,
+        pool,
+    )
+    .await?;
+
+    let expires_on = form
+        .expires_on
+        .map(|expires_on| expires_on.to_string())
+        .unwrap_or_default();
+    let mut body = "invite_code,expires_on,max_uses\n".to_string();
+    for invite_code in invite_codes {
+        body.push_str(&format!("{},{},{}\n", invite_code, expires_on, max_uses));
+    }
+
+    Ok(Either::Left(CsvAttachment {
+        filename: "invites.csv".to_string(),
+        body,
+    }))
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.invite_remove.submit"]
+pub struct InviteRemoveForm {
+    #[form_field_type = "Password"]
+    #[form_field_verbose_name = "form.invite_remove.invite_code"]
+    invite_code: Secret<String>,
+}
+
+impl InviteRemoveForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            invite_code: Secret::default(),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            invite_code: Secret::default(),
+            csrf_token: self.csrf_token.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_remove_invite_check_exists(&self.invite_code, &actor_username, pool).await? {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(invites_list_get(
+                None as Option<u64>,
+                None as Option<u64>
+            ))))),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "invite_code",
+                    ValidationError {
+                        code: Cow::from("invite_does_not_exist"),
+                        message: Some(Cow::from("validation.invite_does_not_exist")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    InviteRemoveForm,
+    invite_remove,
+    "/invites/remove",
+    PAGE_META_INVITE_REMOVE.clone(),
+    (Admin),
+    (),
+    true,
+    "flash.invite_removed"
+);
+
+#[get("/")]
+pub async fn index_get<'a, 'b, 'c, 'd>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    upload_config: &'d State<UploadConfig>,
+    flash_message: Option<FlashMessage>,
+) -> Result<IndexTemplate<'b, 'd>, crate::error::Error> {
+    let (pinned_posts_raw, pinned_post_count) =
+        list_latest_pinned_posts(pool, pagination_config.default_page_size, &context.user).await?;
+    let pinned_posts: Vec<_> = pinned_posts_raw
+        .into_iter()
+        .map(|post| (post.id, post.check_visible(&context.user)))
+        .collect();
+    let has_more_pinned_posts = pinned_post_count > pinned_posts.len() as u64;
+    Ok(IndexTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_INDEX.clone()),
+        pinned_posts,
+        has_more_pinned_posts,
+        storage: &upload_config.storage,
+    })
+}
+
+/// Switch interface language, choice is stored in cookie.
+#[get("/locale/<locale>")]
+pub fn locale_set_get(locale: Locale, cookies: &CookieJar<'_>) -> Redirect {
+    set_locale_cookie(cookies, locale);
+    Redirect::to(uri!(index_get()))
+}
+
+/// Switch color theme, choice is stored in cookie and, if user is logged in, in user row.
+#[get("/theme/<theme>")]
+pub async fn theme_set_get(
+    theme: Theme,
+    user: Authentication,
+    cookies: &CookieJar<'_>,
+    pool: &State<Pool<Postgres>>,
+) -> Result<Redirect, crate::error::Error> {
+    if let Some(username) = user.username() {
+        set_user_theme(&username, theme, pool).await?;
+    }
+    set_theme_cookie(cookies, theme);
+    Ok(Redirect::to(uri!(index_get())))
+}
+
+#[get("/ban-reasons")]
+pub async fn ban_reasons_list_get<'a, 'b>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    _admin: Admin,
+    flash_message: Option<FlashMessage>,
+) -> Result<BanReasonListTemplate<'b>, crate::error::Error> {
+    Ok(BanReasonListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_BAN_REASONS_LIST.clone()),
+        items: list_ban_reasons(pool).await?,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.ban_reason_add.submit"]
+pub struct BanReasonAddForm {
+    #[validate(regex(
+        path = "USERNAME_CHARACTERS_REGEX",
+        code = "id_wrong_characters",
+        message = "validation.id_wrong_characters"
+    ))]
+    #[validate(length(max = 64, code = "id_too_long", message = "validation.id_too_long"))]
+    #[validate(length(min = 1, code = "id_too_short", message = "validation.id_too_short"))]
+    #[form_field_verbose_name = "form.ban_reason_add.id"]
+    id: String,
+
+    #[form_field_verbose_name = "form.ban_reason_add.description"]
+    description: String,
+
+    #[validate(length(
+        max = 500,
+        code = "default_text_too_long",
+        message = "validation.default_text_too_long"
+    ))]
+    #[form_field_verbose_name = "form.ban_reason_add.default_text"]
+    default_text: String,
+}
+
+impl BanReasonAddForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            id: "".to_string(),
+            description: "".to_string(),
+            default_text: "".to_string(),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            id: self.id.clone(),
+            description: self.description.clone(),
+            default_text: self.default_text.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_add_ban_reason_check_exists(
+            BanReason {
+                id: self.id.clone(),
+                description: Some(self.description.clone()),
+                default_text: if self.default_text.is_empty() {
+                    None
+                } else {
+                    Some(self.default_text.clone())
+                },
+            },
+            pool,
+        )
+        .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(ban_reasons_list_get())))),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "id",
+                    ValidationError {
+                        code: Cow::from("id_already_exists"),
+                        message: Some(Cow::from("validation.ban_reason_id_already_exists")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    BanReasonAddForm,
+    ban_reason_add,
+    "/ban-reasons/add",
+    PAGE_META_BAN_REASON_ADD.clone(),
+    (Admin),
+    (),
+    false,
+    "flash.ban_reason_added",
+    redirect(uri!(ban_reason_add_get()))
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.ban_reason_edit.submit"]
+pub struct BanReasonEditForm {
+    #[form_field_verbose_name = "form.ban_reason_edit.description"]
+    description: String,
+
+    #[validate(length(
+        max = 500,
+        code = "default_text_too_long",
+        message = "validation.default_text_too_long"
+    ))]
+    #[form_field_verbose_name = "form.ban_reason_edit.default_text"]
+    default_text: String,
+}
+
+impl BanReasonEditForm {
+    async fn load(
+        id: &str,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_ban_reason(id, pool).await? {
+            Some(ban_reason) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+                description: ban_reason.description.unwrap_or("".to_string()),
+                default_text: ban_reason.default_text.unwrap_or("".to_string()),
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            description: self.description.clone(),
+            default_text: self.default_text.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        id: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_edit_ban_reason_check_exists(
+            BanReason {
+                id: id.to_string(),
+                description: Some(self.description.clone()),
+                default_text: if self.default_text.is_empty() {
+                    None
+                } else {
+                    Some(self.default_text.clone())
+                },
+            },
+            pool,
+        )
+        .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(ban_reasons_list_get())))),
             None => Err(crate::error::Error::DoesNotExist),
         }
     }
@@ -1022,143 +3517,1119 @@ form_get_and_post!(
     BanReasonEditForm,
     ban_reason_edit,
     "/ban-reasons/by-id/<id>/edit",
-    vec![
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_BAN_REASONS.clone(),
+        Breadcrumb::message_with_args_without_url("breadcrumb.edit_with_id", &[("id", id)])
+    ]),
+    (Admin),
+    (id: &str),
+    false,
+    "flash.ban_reason_saved"
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.ban_reason_remove.submit"]
+pub struct BanReasonRemoveForm {
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.ban_reason_remove.detach_posts"]
+    detach_posts: bool,
+}
+
+impl BanReasonRemoveForm {
+    async fn load(
+        id: &str,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_ban_reason(id, pool).await? {
+            Some(_) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+                detach_posts: false,
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            detach_posts: self.detach_posts,
+        }
+    }
+
+    async fn process(
+        &self,
+        id: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_remove_ban_reason_check_exists(id, self.detach_posts, pool).await? {
+            Some(BanReasonRemoveResult::Removed) => {
+                Ok(Either::Left(Redirect::to(uri!(ban_reasons_list_get()))))
+            }
+            Some(BanReasonRemoveResult::ReferencedByPosts(post_count)) => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "detach_posts",
+                    ValidationError {
+                        code: Cow::from("ban_reason_in_use"),
+                        message: Some(Cow::from("validation.ban_reason_in_use")),
+                        params: HashMap::from([(
+                            Cow::from("count"),
+                            serde_json::Value::from(post_count),
+                        )]),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    BanReasonRemoveForm,
+    ban_reason_remove,
+    "/ban-reasons/by-id/<id>/remove",
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_BAN_REASONS.clone(),
+        Breadcrumb::message_with_args_without_url("breadcrumb.remove_with_id", &[("id", id)])
+    ]),
+    (Admin),
+    (id: &str),
+    false,
+    "flash.ban_reason_removed"
+);
+
+#[get("/content-ratings")]
+pub async fn content_ratings_list_get<'a, 'b>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    _admin: Admin,
+    flash_message: Option<FlashMessage>,
+) -> Result<ContentRatingListTemplate<'b>, crate::error::Error> {
+    Ok(ContentRatingListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_CONTENT_RATINGS_LIST.clone()),
+        items: list_content_ratings(pool).await?,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.content_rating_add.submit"]
+pub struct ContentRatingAddForm {
+    #[validate(regex(
+        path = "USERNAME_CHARACTERS_REGEX",
+        code = "id_wrong_characters",
+        message = "validation.id_wrong_characters"
+    ))]
+    #[validate(length(max = 64, code = "id_too_long", message = "validation.id_too_long"))]
+    #[validate(length(min = 1, code = "id_too_short", message = "validation.id_too_short"))]
+    #[form_field_verbose_name = "form.content_rating_add.id"]
+    id: String,
+
+    #[validate(length(
+        min = 1,
+        code = "label_too_short",
+        message = "validation.label_too_short"
+    ))]
+    #[validate(length(
+        max = 100,
+        code = "label_too_long",
+        message = "validation.content_rating_label_too_long"
+    ))]
+    #[form_field_verbose_name = "form.content_rating_add.label"]
+    label: String,
+
+    #[validate(range(min = 0.0, max = 21.0, message = "validation.min_age_out_of_range"))]
+    #[form_field_type = "Number"]
+    #[form_field_required]
+    #[form_field_min = 0]
+    #[form_field_max = 21]
+    #[form_field_verbose_name = "form.content_rating_add.min_age"]
+    min_age: f64,
+
+    #[form_field_verbose_name = "form.content_rating_add.description"]
+    description: String,
+}
+
+impl ContentRatingAddForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            id: "".to_string(),
+            label: "".to_string(),
+            min_age: 0.0,
+            description: "".to_string(),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            id: self.id.clone(),
+            label: self.label.clone(),
+            min_age: self.min_age,
+            description: self.description.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_add_content_rating_check_exists(
+            ContentRating {
+                id: self.id.clone(),
+                label: self.label.clone(),
+                min_age: self.min_age as i32,
+                description: if self.description.is_empty() {
+                    None
+                } else {
+                    Some(self.description.clone())
+                },
+            },
+            pool,
+        )
+        .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(content_ratings_list_get())))),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "id",
+                    ValidationError {
+                        code: Cow::from("id_already_exists"),
+                        message: Some(Cow::from("validation.content_rating_id_already_exists")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    ContentRatingAddForm,
+    content_rating_add,
+    "/content-ratings/add",
+    PAGE_META_CONTENT_RATING_ADD.clone(),
+    (Admin),
+    (),
+    false,
+    "flash.content_rating_added"
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.content_rating_edit.submit"]
+pub struct ContentRatingEditForm {
+    #[validate(length(
+        min = 1,
+        code = "label_too_short",
+        message = "validation.label_too_short"
+    ))]
+    #[validate(length(
+        max = 100,
+        code = "label_too_long",
+        message = "validation.content_rating_label_too_long"
+    ))]
+    #[form_field_verbose_name = "form.content_rating_edit.label"]
+    label: String,
+
+    #[validate(range(min = 0.0, max = 21.0, message = "validation.min_age_out_of_range"))]
+    #[form_field_type = "Number"]
+    #[form_field_required]
+    #[form_field_min = 0]
+    #[form_field_max = 21]
+    #[form_field_verbose_name = "form.content_rating_edit.min_age"]
+    min_age: f64,
+
+    #[form_field_verbose_name = "form.content_rating_edit.description"]
+    description: String,
+}
+
+impl ContentRatingEditForm {
+    async fn load(
+        id: &str,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_content_rating(id, pool).await? {
+            Some(content_rating) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+                label: content_rating.label,
+                min_age: content_rating.min_age as f64,
+                description: content_rating.description.unwrap_or("".to_string()),
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            label: self.label.clone(),
+            min_age: self.min_age,
+            description: self.description.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        id: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_edit_content_rating_check_exists(
+            ContentRating {
+                id: id.to_string(),
+                label: self.label.clone(),
+                min_age: self.min_age as i32,
+                description: if self.description.is_empty() {
+                    None
+                } else {
+                    Some(self.description.clone())
+                },
+            },
+            pool,
+        )
+        .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(content_ratings_list_get())))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    ContentRatingEditForm,
+    content_rating_edit,
+    "/content-ratings/by-id/<id>/edit",
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_CONTENT_RATINGS.clone(),
+        Breadcrumb::message_with_args_without_url("breadcrumb.edit_with_id", &[("id", id)])
+    ]),
+    (Admin),
+    (id: &str),
+    false,
+    "flash.content_rating_saved"
+);
+
+#[get("/pages/<slug>")]
+pub async fn static_page_get<'a, 'b>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    slug: &str,
+) -> Result<StaticPageTemplate<'b>, crate::error::Error> {
+    let item = try_get_static_page(slug, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    Ok(StaticPageTemplate {
+        context: context.with_meta(PageMeta::new(vec![
+            BREADCRUMB_ROOT.clone(),
+            Breadcrumb::new_without_url(item.title.clone()),
+        ])),
+        item,
+    })
+}
+
+#[get("/admin/pages")]
+pub async fn static_pages_list_get<'a, 'b>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    _admin: Admin,
+    flash_message: Option<FlashMessage>,
+) -> Result<StaticPageListTemplate<'b>, crate::error::Error> {
+    Ok(StaticPageListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_STATIC_PAGES_LIST.clone()),
+        items: list_static_pages(pool).await?,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.static_page_add.submit"]
+pub struct StaticPageAddForm {
+    #[validate(regex(
+        path = "STATIC_PAGE_SLUG_CHARACTERS_REGEX",
+        code = "slug_wrong_characters",
+        message = "validation.slug_wrong_characters"
+    ))]
+    #[validate(length(max = 64, code = "slug_too_long", message = "validation.slug_too_long"))]
+    #[validate(length(
+        min = 1,
+        code = "slug_too_short",
+        message = "validation.slug_too_short"
+    ))]
+    #[form_field_verbose_name = "form.static_page_add.slug"]
+    slug: String,
+
+    #[validate(length(
+        min = 1,
+        code = "label_too_short",
+        message = "validation.label_too_short"
+    ))]
+    #[validate(length(
+        max = 128,
+        code = "label_too_long",
+        message = "validation.label_too_long"
+    ))]
+    #[form_field_verbose_name = "form.static_page_add.title"]
+    title: String,
+
+    #[validate(length(
+        max = 100000,
+        code = "static_page_body_too_long",
+        message = "validation.static_page_body_too_long"
+    ))]
+    #[form_field_type = "TextArea"]
+    #[form_field_verbose_name = "form.static_page_add.body"]
+    body: String,
+}
+
+impl StaticPageAddForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            slug: "".to_string(),
+            title: "".to_string(),
+            body: "".to_string(),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            slug: self.slug.clone(),
+            title: self.title.clone(),
+            body: self.body.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_add_static_page_check_exists(&self.slug, &self.title, &self.body, &username, pool)
+            .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(static_page_get(
+                &self.slug
+            ))))),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "slug",
+                    ValidationError {
+                        code: Cow::from("slug_already_exists"),
+                        message: Some(Cow::from("validation.static_page_slug_already_exists")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
+        }
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    StaticPageAddForm,
+    static_page_add,
+    "/admin/pages/add",
+    PAGE_META_STATIC_PAGE_ADD.clone(),
+    (Admin),
+    (),
+    true,
+    "flash.static_page_added"
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.static_page_edit.submit"]
+pub struct StaticPageEditForm {
+    #[validate(length(
+        min = 1,
+        code = "label_too_short",
+        message = "validation.label_too_short"
+    ))]
+    #[validate(length(
+        max = 128,
+        code = "label_too_long",
+        message = "validation.label_too_long"
+    ))]
+    #[form_field_verbose_name = "form.static_page_edit.title"]
+    title: String,
+
+    #[validate(length(
+        max = 100000,
+        code = "static_page_body_too_long",
+        message = "validation.static_page_body_too_long"
+    ))]
+    #[form_field_type = "TextArea"]
+    #[form_field_verbose_name = "form.static_page_edit.body"]
+    body: String,
+}
+
+impl StaticPageEditForm {
+    async fn load(
+        slug: &str,
+        _user: &Authentication,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_static_page(slug, pool).await? {
+            Some(static_page) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+                title: static_page.title,
+                body: static_page.body,
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            title: self.title.clone(),
+            body: self.body.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        slug: &str,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_edit_static_page_check_exists(slug, &self.title, &self.body, &username, pool)
+            .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(static_page_get(slug))))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    StaticPageEditForm,
+    static_page_edit,
+    "/admin/pages/by-slug/<slug>/edit",
+    PageMeta::new(vec![
         BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_BAN_REASONS.clone(),
-        Breadcrumb::new_without_url(format!("изменение ({})", id))
-    ],
+        BREADCRUMB_STATIC_PAGES.clone(),
+        Breadcrumb::new_with_url(slug.to_string(), uri!(static_page_get(slug)).to_string()),
+        Breadcrumb::message_without_url("breadcrumb.edit")
+    ]),
     (Admin),
-    (id: &str),
-    false
+    (slug: &str),
+    true,
+    "flash.static_page_saved"
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.static_page_remove.submit"]
+pub struct StaticPageRemoveForm {}
+
+impl StaticPageRemoveForm {
+    async fn load(
+        slug: &str,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_static_page(slug, pool).await? {
+            Some(_) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+        }
+    }
+
+    async fn process(
+        &self,
+        slug: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        match try_remove_static_page_check_exists(slug, pool).await? {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(static_pages_list_get())))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    StaticPageRemoveForm,
+    static_page_remove,
+    "/admin/pages/by-slug/<slug>/remove",
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_STATIC_PAGES.clone(),
+        Breadcrumb::new_with_url(slug.to_string(), uri!(static_page_get(slug)).to_string()),
+        Breadcrumb::message_without_url("breadcrumb.remove")
+    ]),
+    (Admin),
+    (slug: &str),
+    false,
+    "flash.static_page_removed"
 );
 
-#[get("/posts?<page_id>&<page_size>")]
+/// Get first image of each visible post in page, they are shown in grid layout only.
+async fn get_post_list_covers(
+    page: &Page<(i64, PostVisibility)>,
+    layout: PostListLayout,
+    pool: &Pool<Postgres>,
+) -> Result<HashMap<i64, Upload>, crate::error::Error> {
+    if layout != PostListLayout::Grid {
+        return Ok(HashMap::new());
+    }
+    let post_ids: Vec<i64> = page
+        .items
+        .iter()
+        .filter(|(_, visibility)| matches!(visibility, PostVisibility::Visible(_)))
+        .map(|(post_id, _)| *post_id)
+        .collect();
+    list_post_cover_uploads(&post_ids, pool).await
+}
+
+#[get("/posts?<page_id>&<page_size>&<layout>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn posts_list_get<'a, 'b, 'c>(
-    user: Authentication,
     pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
+    context: PageContext<'b>,
     pagination_config: &'c State<PaginationConfig>,
     page_id: Option<u64>,
     page_size: Option<u64>,
+    layout: Option<PostListLayout>,
+    cookies: &CookieJar<'_>,
     upload_config: &'c State<UploadConfig>,
-) -> Result<PostsListTemplate<'b, 'c>, crate::error::Error> {
+) -> Result<Either<PostsListTemplate<'b, 'c>, Redirect>, crate::error::Error> {
+    let layout = PostListLayout::resolve(layout, cookies);
     let page_params = PageParams {
         page_id,
         page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
     };
     page_params.check(pagination_config)?;
 
-    let page_raw = list_posts_with_pagination(pool, page_params, &user).await?;
-    let page = page_raw.map(|post| (post.id, post.clone().check_visible(&user)));
+    let page_raw = list_posts_with_pagination(pool, page_params, &context.user).await?;
 
-    Ok(PostsListTemplate {
-        user,
-        asset_context,
-        breadcrumbs: BREADCRUMBS_POSTS_LIST.clone(),
+    if page_id.is_some_and(|page_id| page_id != page_raw.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(posts_list_get(
+            Some(page_raw.page_id),
+            Some(page_raw.page_size),
+            None as Option<PostListLayout>
+        )))));
+    }
+
+    let page = page_raw.map(|post| (post.id, post.clone().check_visible(&context.user)));
+    let covers = get_post_list_covers(&page, layout, pool).await?;
+
+    Ok(Either::Left(PostsListTemplate {
+        context: context.with_meta(PAGE_META_POSTS_LIST.clone()),
         page,
         storage: &upload_config.storage,
         page_base: UrlQuery::new(),
-    })
+        layout,
+        covers,
+    }))
 }
 
-#[get("/posts/by-id/<id>")]
-pub async fn post_detail_get<'a, 'b, 'c>(
-    user: Authentication,
+#[get("/posts/pinned?<page_id>&<page_size>")]
+pub async fn posts_pinned_list_get<'a, 'b, 'c>(
     pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
-    id: i64,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
     upload_config: &'c State<UploadConfig>,
-) -> Result<
+) -> Result<Either<PostsPinnedListTemplate<'b, 'c>, Redirect>, crate::error::Error> {
+    let page_params = PageParams {
+        page_id: Some(page_id.unwrap_or(0)),
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
+    };
+    page_params.check(pagination_config)?;
+
+    let page_raw = list_pinned_posts_with_pagination(pool, page_params, &context.user).await?;
+
+    if page_id.is_some_and(|page_id| page_id != page_raw.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(posts_pinned_list_get(
+            Some(page_raw.page_id),
+            Some(page_raw.page_size)
+        )))));
+    }
+
+    let page = page_raw.map(|post| (post.id, post.clone().check_visible(&context.user)));
+
+    Ok(Either::Left(PostsPinnedListTemplate {
+        context: context.with_meta(PAGE_META_POSTS_PINNED_LIST.clone()),
+        page,
+        storage: &upload_config.storage,
+        page_base: UrlQuery::new(),
+    }))
+}
+
+/// Post detail page depends on post, its published uploads and user who sees it. Post visibility
+/// changes also update `updated_at`, so that responses other than `200 OK` can not match ETag.
+fn get_post_detail_etag(
+    post: &Post,
+    user: &Authentication,
+    csrf_token: &CsrfToken,
+    flash_message: Option<&FlashMessage>,
+) -> Result<String, crate::error::Error> {
+    make_etag(&(
+        (post.id, post.updated_at, &post.author_display_name),
+        post.uploads
+            .iter()
+            .map(|upload| upload.id)
+            .collect::<Vec<_>>(),
+        (
+            user.username(),
+            user.map(|user| user.display_name.clone()),
+            user.is_authenticated(),
+            user.is_admin(),
+            user.is_uploader(),
+            user.impersonator_username(),
+            user.birth_date(),
+        ),
+        &csrf_token.value,
+        flash_message.map(|flash_message| {
+            (
+                flash_message.level.as_str(),
+                &flash_message.message,
+                &flash_message.args,
+            )
+        }),
+    ))
+}
+
+type PostDetailResponse<'b, 'c> = Conditional<
     Either<
         PostDetailTemplate<'b, 'c>,
         Either<
             Either<
-                TemplateForbidden<PostDetailTemplateHidden<'b>>,
-                TemplateForbidden<PostDetailTemplateAgeRestricted<'b>>,
+                Either<
+                    TemplateForbidden<PostDetailTemplateHidden<'b>>,
+                    TemplateForbidden<PostDetailTemplateAgeRestricted<'b>>,
+                >,
+                TemplateForbidden<PostDetailTemplateQuarantined<'b>>,
             >,
             TemplateUnavailableForLegal<PostDetailTemplateBanned<'b>>,
         >,
     >,
-    crate::error::Error,
-> {
-    let post = try_get_post(id, pool, &user)
+>;
+
+#[get("/posts/by-id/<id>")]
+pub async fn post_detail_get<'a, 'b, 'c>(
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    id: i64,
+    upload_config: &'c State<UploadConfig>,
+    signed_token_key: &State<SignedTokenKey>,
+    csrf_token: CsrfToken,
+    flash_message: Option<FlashMessage>,
+) -> Result<PostDetailResponse<'b, 'c>, crate::error::Error> {
+    render_post_detail(
+        context,
+        pool,
+        id,
+        upload_config,
+        signed_token_key,
+        csrf_token,
+        flash_message,
+    )
+    .await
+}
+
+/// Short permanent link to post, it is redirected to canonical post URL.
+#[get("/p/<id>")]
+pub async fn post_short_link_get(
+    id: i64,
+    user: Authentication,
+    pool: &State<Pool<Postgres>>,
+) -> Result<Redirect, crate::error::Error> {
+    get_canonical_post_redirect(id, &user, pool).await
+}
+
+/// Show post by its slug. Old slugs (assigned before title was edited) are redirected to canonical
+/// post URL.
+#[get("/posts/<slug>")]
+pub async fn post_by_slug_get<'a, 'b, 'c>(
+    slug: &str,
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    upload_config: &'c State<UploadConfig>,
+    signed_token_key: &State<SignedTokenKey>,
+    csrf_token: CsrfToken,
+    flash_message: Option<FlashMessage>,
+) -> Result<Either<PostDetailResponse<'b, 'c>, Redirect>, crate::error::Error> {
+    let (id, current_slug) = try_get_post_id_by_slug(slug, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    if current_slug.as_deref() == Some(slug) {
+        Ok(Either::Left(
+            render_post_detail(
+                context,
+                pool,
+                id,
+                upload_config,
+                signed_token_key,
+                csrf_token,
+                flash_message,
+            )
+            .await?,
+        ))
+    } else {
+        Ok(Either::Right(
+            get_canonical_post_redirect(id, &context.user, pool).await?,
+        ))
+    }
+}
+
+/// Redirect to canonical URL of post. Slug is made from title, so it is only used if post is
+/// visible to user.
+async fn get_canonical_post_redirect(
+    id: i64,
+    user: &Authentication,
+    pool: &Pool<Postgres>,
+) -> Result<Redirect, crate::error::Error> {
+    let post = try_get_post(id, pool, user)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    let url = match post.check_visible(user) {
+        PostVisibility::Visible(post) => post.detail_url().to_string(),
+        _ => uri!(post_detail_get(id)).to_string(),
+    };
+    Ok(Redirect::moved(url))
+}
+
+async fn render_post_detail<'b, 'c>(
+    context: PageContext<'b>,
+    pool: &Pool<Postgres>,
+    id: i64,
+    upload_config: &'c UploadConfig,
+    signed_token_key: &SignedTokenKey,
+    csrf_token: CsrfToken,
+    flash_message: Option<FlashMessage>,
+) -> Result<PostDetailResponse<'b, 'c>, crate::error::Error> {
+    let post = try_get_post(id, pool, &context.user)
         .await?
         .ok_or(crate::error::Error::DoesNotExist)?;
     let post_id = post.id;
+    let can_appeal_ban = post.can_appeal_ban(&context.user);
+    let content_rating_form = if post.can_set_content_rating(&context.user) {
+        Some(
+            PostContentRatingForm::new(
+                post.content_rating_id.clone(),
+                &RatingIdSet::new(&list_content_ratings(pool).await?),
+                &csrf_token.authenticity_token(),
+            )
+            .get_definition(ValidationErrors::new()),
+        )
+    } else {
+        None
+    };
+
+    let etag = get_post_detail_etag(&post, &context.user, &csrf_token, flash_message.as_ref())?;
+    // Signed file URLs expire, so page with them must not be reused from cache.
+    let has_signed_file_urls =
+        upload_config.signed_url_lifetime.is_some() && post.min_age.is_some();
 
-    match post.check_visible(&user) {
+    let response: Result<_, crate::error::Error> = match post.check_visible(&context.user) {
         PostVisibility::Visible(post) => Ok(Either::Left(PostDetailTemplate {
-            user,
-            asset_context,
-            breadcrumbs: vec![
-                BREADCRUMB_ROOT.clone(),
-                BREADCRUMB_POSTS.clone(),
-                Breadcrumb::new_without_url(format!("#{}: {}", post.id, &post.title)),
-            ],
+            context: context.with_meta(PageMeta::for_post(&post)),
+            text_previews: read_text_previews(&post.uploads, &upload_config.storage).await,
             item: post,
             storage: &upload_config.storage,
+            content_rating_form,
+            flash_message,
+            file_url_signing: upload_config
+                .signed_url_lifetime
+                .map(|lifetime| (signed_token_key.clone(), lifetime)),
         })),
-        PostVisibility::Hidden => Ok(Either::Right(Either::Left(Either::Left(
+        PostVisibility::Hidden => Ok(Either::Right(Either::Left(Either::Left(Either::Left(
             TemplateForbidden {
                 template: PostDetailTemplateHidden {
-                    user,
-                    asset_context,
-                    breadcrumbs: vec![
-                        BREADCRUMB_ROOT.clone(),
-                        BREADCRUMB_POSTS.clone(),
-                        Breadcrumb::new_without_url(format!("#{}", post_id)),
-                    ],
+                    context: context.with_meta(PageMeta::for_post_id(post_id)),
                     item_id: post_id,
                 },
             },
-        )))),
-        PostVisibility::AgeRestricted(min_age) => Ok(Either::Right(Either::Left(Either::Right(
-            TemplateForbidden {
+        ))))),
+        PostVisibility::AgeRestricted(min_age) => Ok(Either::Right(Either::Left(Either::Left(
+            Either::Right(TemplateForbidden {
                 template: PostDetailTemplateAgeRestricted {
-                    user,
-                    asset_context,
-                    breadcrumbs: vec![
-                        BREADCRUMB_ROOT.clone(),
-                        BREADCRUMB_POSTS.clone(),
-                        Breadcrumb::new_without_url(format!("#{}", post_id)),
-                    ],
+                    context: context.with_meta(PageMeta::for_post_id(post_id)),
                     item_id: post_id,
                     min_age,
                 },
+            }),
+        )))),
+        PostVisibility::Quarantined => Ok(Either::Right(Either::Left(Either::Right(
+            TemplateForbidden {
+                template: PostDetailTemplateQuarantined {
+                    context: context.with_meta(PageMeta::for_post_id(post_id)),
+                    item_id: post_id,
+                },
             },
         )))),
-        PostVisibility::Banned(ban_reason, ban_reason_text) => {
+        PostVisibility::Banned(ban_reason, ban_reason_text, ban_expires_at) => {
+            let last_ban_appeal = if can_appeal_ban {
+                try_get_last_ban_appeal(post_id, pool).await?
+            } else {
+                None
+            };
             Ok(Either::Right(Either::Right(TemplateUnavailableForLegal {
                 template: PostDetailTemplateBanned {
-                    user,
-                    asset_context,
-                    breadcrumbs: vec![
-                        BREADCRUMB_ROOT.clone(),
-                        BREADCRUMB_POSTS.clone(),
-                        Breadcrumb::new_without_url(format!("#{}", post_id)),
-                    ],
+                    context: context.with_meta(PageMeta::for_post_id(post_id)),
                     item_id: post_id,
                     ban_reason,
                     ban_reason_text,
+                    ban_expires_at,
+                    can_appeal_ban,
+                    last_ban_appeal,
+                    csrf_token: csrf_token.authenticity_token(),
+                    flash_message,
                 },
             })))
         }
+    };
+
+    if has_signed_file_urls {
+        Ok(Conditional::new(response?))
+    } else {
+        Ok(Conditional::new(response?).with_etag(etag))
+    }
+}
+
+#[get("/posts/by-id/<id>/download.zip")]
+pub async fn post_download_zip_get(
+    user: Authentication,
+    pool: &State<Pool<Postgres>>,
+    upload_config: &State<UploadConfig>,
+    id: i64,
+) -> Result<ZipAttachment, crate::error::Error> {
+    let post = match try_get_post(id, pool, &user)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?
+        .check_visible(&user)
+    {
+        PostVisibility::Visible(post) => post,
+        _ => return Err(crate::error::Error::AccessDenied),
+    };
+
+    if get_post_archive_size(&post) > upload_config.max_post_archive_size {
+        return Err(crate::error::Error::ArchiveTooLarge);
+    }
+
+    Ok(ZipAttachment {
+        filename: format!("post-{}.zip", id),
+        reader: stream_post_archive(post, upload_config.storage.clone()),
+    })
+}
+
+#[get("/posts/by-id/<id>/embed")]
+pub async fn post_embed_get<'a, 'b>(
+    pool: &State<Pool<Postgres>>,
+    asset_context: &'a State<AssetContext>,
+    locale: Locale,
+    theme: Theme,
+    upload_config: &'b State<UploadConfig>,
+    id: i64,
+) -> Result<PostEmbedTemplate<'a, 'b>, crate::error::Error> {
+    let item = try_get_public_post(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    Ok(PostEmbedTemplate {
+        asset_context,
+        locale,
+        theme,
+        item,
+        storage: &upload_config.storage,
+    })
+}
+
+/// Number of latest posts listed in feeds.
+const FEED_POST_COUNT: i64 = 50;
+
+const FEED_TITLE: &str = "archivanima";
+
+fn get_feed_updated(items: &[FeedPost]) -> OffsetDateTime {
+    items
+        .iter()
+        .map(|item| item.updated_at)
+        .max()
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
+#[get("/feed.atom")]
+pub async fn feed_atom_get(
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+) -> Result<Conditional<FeedResponse<AtomFeedTemplate>>, crate::error::Error> {
+    let items = list_public_posts_for_feed(FEED_POST_COUNT, pool).await?;
+    let etag = make_etag(&items)?;
+    let site_url = email_config.site_url.trim_end_matches('/').to_string();
+
+    Ok(Conditional::new(FeedResponse {
+        template: AtomFeedTemplate {
+            title: FEED_TITLE.to_string(),
+            feed_url: format!("{}{}", site_url, uri!(feed_atom_get())),
+            site_url,
+            updated: get_feed_updated(&items),
+            items,
+        },
+        content_type: ContentType::new("application", "atom+xml"),
+    })
+    .with_etag(etag))
+}
+
+#[get("/feed.rss")]
+pub async fn feed_rss_get(
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+) -> Result<Conditional<FeedResponse<RssFeedTemplate>>, crate::error::Error> {
+    let items = list_public_posts_for_feed(FEED_POST_COUNT, pool).await?;
+    let etag = make_etag(&items)?;
+    let site_url = email_config.site_url.trim_end_matches('/').to_string();
+
+    Ok(Conditional::new(FeedResponse {
+        template: RssFeedTemplate {
+            title: FEED_TITLE.to_string(),
+            feed_url: format!("{}{}", site_url, uri!(feed_rss_get())),
+            site_url,
+            updated: get_feed_updated(&items),
+            items,
+        },
+        content_type: ContentType::new("application", "rss+xml"),
+    })
+    .with_etag(etag))
+}
+
+#[get("/sitemap.xml")]
+pub async fn sitemap_get(
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+) -> Result<(ContentType, SitemapStream), crate::error::Error> {
+    let site_url = email_config.site_url.trim_end_matches('/').to_string();
+    let user_count = SitemapSection::Users.get_item_count(pool).await?;
+    let post_count = SitemapSection::Posts.get_item_count(pool).await?;
+
+    if user_count + post_count <= SITEMAP_MAX_URL_COUNT {
+        Ok((
+            ContentType::XML,
+            stream_sitemap_urlset(pool.inner().clone(), site_url, None),
+        ))
+    } else {
+        Ok((
+            ContentType::XML,
+            stream_sitemap_index(site_url, user_count, post_count),
+        ))
+    }
+}
+
+async fn get_sitemap_chunk(
+    section: SitemapSection,
+    chunk_id: u64,
+    pool: &Pool<Postgres>,
+    email_config: &EmailConfig,
+) -> Result<(ContentType, SitemapStream), crate::error::Error> {
+    let item_count = section.get_item_count(pool).await?;
+    if chunk_id >= get_sitemap_chunk_count(item_count) {
+        return Err(crate::error::Error::DoesNotExist);
     }
+
+    Ok((
+        ContentType::XML,
+        stream_sitemap_urlset(
+            pool.clone(),
+            email_config.site_url.trim_end_matches('/').to_string(),
+            Some((section, chunk_id)),
+        ),
+    ))
+}
+
+#[get("/sitemaps/users/<chunk_id>")]
+pub async fn sitemap_users_get(
+    chunk_id: u64,
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+) -> Result<(ContentType, SitemapStream), crate::error::Error> {
+    get_sitemap_chunk(SitemapSection::Users, chunk_id, pool, email_config).await
+}
+
+#[get("/sitemaps/posts/<chunk_id>")]
+pub async fn sitemap_posts_get(
+    chunk_id: u64,
+    pool: &State<Pool<Postgres>>,
+    email_config: &State<EmailConfig>,
+) -> Result<(ContentType, SitemapStream), crate::error::Error> {
+    get_sitemap_chunk(SitemapSection::Posts, chunk_id, pool, email_config).await
+}
+
+#[get("/metrics")]
+pub async fn metrics_get(
+    _access: MetricsAccess,
+    pool: &State<Pool<Postgres>>,
+    registry: &State<Registry>,
+) -> Result<(ContentType, String), crate::error::Error> {
+    METRICS.update_pool_metrics(pool);
+    Ok((ContentType::Plain, encode_metrics(registry)?))
 }
 
 #[get("/posts/add")]
-pub fn post_add_get(
-    user: User,
+pub async fn post_add_get<'a>(
+    _user: User,
     csrf_token: CsrfToken,
-    asset_context: &State<AssetContext>,
+    context: PageContext<'a>,
+    pool: &State<Pool<Postgres>>,
     _uploader: Uploader,
-) -> PostAddTemplate {
-    PostAddTemplate {
-        user: Authentication::Authenticated(user),
-        asset_context,
-        breadcrumbs: BREADCRUMBS_POST_ADD.clone(),
+) -> Result<PostAddTemplate<'a>, crate::error::Error> {
+    Ok(PostAddTemplate {
+        context: context.with_meta(PAGE_META_POST_ADD.clone()),
         csrf_token: csrf_token.authenticity_token(),
-    }
+        content_ratings: list_content_ratings(pool).await?,
+    })
 }
 
 #[get("/posts/by-id/<id>/edit")]
@@ -1166,14 +4637,13 @@ pub fn post_add_get(
 pub async fn post_edit_get<'a, 'b, 'c>(
     id: i64,
     user: User,
-    authentication: Authentication,
     csrf_token: CsrfToken,
-    asset_context: &'a State<AssetContext>,
+    context: PageContext<'a>,
     pool: &'b State<Pool<Postgres>>,
     _uploader: Uploader,
     upload_config: &'c State<UploadConfig>,
 ) -> Result<PostEditTemplate<'a, 'c>, crate::error::Error> {
-    let post = try_get_post(id, pool, &authentication)
+    let post = try_get_post(id, pool, &context.user)
         .await?
         .ok_or(crate::error::Error::DoesNotExist)?;
 
@@ -1182,63 +4652,444 @@ pub async fn post_edit_get<'a, 'b, 'c>(
     }
 
     Ok(PostEditTemplate {
-        user: Authentication::Authenticated(user),
-        asset_context,
-        breadcrumbs: vec![
-            BREADCRUMB_ROOT.clone(),
-            BREADCRUMB_POSTS.clone(),
-            Breadcrumb::new_with_url(format!("#{}", id), uri!(post_detail_get(id)).to_string()),
-            Breadcrumb::new_without_url("изменение".to_string()),
-        ],
+        context: context.with_meta(PageMeta::for_post_action(id, "breadcrumb.edit")),
         csrf_token: csrf_token.authenticity_token(),
         item: post,
         storage: &upload_config.storage,
+        content_ratings: list_content_ratings(pool).await?,
     })
 }
 
 #[form_with_csrf]
 #[derive(RawForm, Clone, Debug, Validate, FormWithDefinition)]
-#[form_submit_name = "забанить"]
+#[form_submit_name = "form.post_ban.submit"]
 pub struct PostBanForm {
     #[extra_validated(crate::app::db::BanReasonIdSet)]
     #[form_field_type = "RadioId"]
-    #[form_field_verbose_name = "причина бана"]
+    #[form_field_verbose_name = "form.post_ban.ban_reason_id"]
     ban_reason_id: IdField,
 
     #[validate(length(
         max = 500,
         code = "ban_reason_text_too_long",
-        message = "описание должно быть не длиннее 500 символов"
+        message = "validation.ban_description_too_long"
     ))]
-    #[form_field_verbose_name = "описание причины бана"]
+    #[form_field_verbose_name = "form.post_ban.ban_reason_text"]
     ban_reason_text: String,
+
+    #[form_field_type = "Date"]
+    #[form_field_verbose_name = "form.post_ban.ban_expires_on"]
+    ban_expires_on: Option<Date>,
 }
 
 impl PostBanForm {
     async fn load(
         id: i64,
         ban_reason_id_set: BanReasonIdSet,
+        _event_bus: &State<EventBus>,
+        user: &Authentication,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_post(id, pool, user).await? {
+            Some(post) => match post.ban {
+                Some((ban_reason, ban_reason_text, ban_expires_at)) => Ok(Self {
+                    csrf_token: csrf_token.to_string(),
+                    ban_reason_text: ban_reason_text
+                        .or_else(|| {
+                            ban_reason
+                                .as_ref()
+                                .and_then(|ban_reason| ban_reason.default_text.clone())
+                        })
+                        .unwrap_or("".to_string()),
+                    ban_reason_id: IdField::load(
+                        ban_reason.map(|ban_reason| ban_reason.id),
+                        &ban_reason_id_set,
+                    )
+                    .0,
+                    ban_expires_on: ban_expires_at
+                        .and_then(|ban_expires_at| ban_expires_at.date().previous_day()),
+                }),
+                None => Ok(Self {
+                    csrf_token: csrf_token.to_string(),
+                    ban_reason_id: IdField::load(None, &ban_reason_id_set).0,
+                    ban_reason_text: "".to_string(),
+                    ban_expires_on: None,
+                }),
+            },
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            ban_reason_id: self.ban_reason_id.clone(),
+            ban_reason_text: self.ban_reason_text.clone(),
+            ban_expires_on: self.ban_expires_on,
+        }
+    }
+
+    async fn process(
+        &self,
+        id: i64,
+        _ban_reason_id_set: BanReasonIdSet,
+        event_bus: &State<EventBus>,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let ban_expires_at = self
+            .ban_expires_on
+            .and_then(|ban_expires_on| ban_expires_on.next_day())
+            .map(date_to_offset_date_time);
+        if ban_expires_at.is_some_and(|ban_expires_at| ban_expires_at <= OffsetDateTime::now_utc())
+        {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "ban_expires_on",
+                ValidationError {
+                    code: Cow::from("ban_expires_on_in_past"),
+                    message: Some(Cow::from("validation.ban_expires_on_in_past")),
+                    params: HashMap::new(),
+                },
+            );
+            return Ok(Either::Right(errors));
+        }
+        match try_ban_post_check_exists(
+            id,
+            self.ban_reason_id.value.clone(),
+            if self.ban_reason_text.is_empty() {
+                None
+            } else {
+                Some(self.ban_reason_text.clone())
+            },
+            ban_expires_at,
+            &actor_username,
+            pool,
+        )
+        .await?
+        {
+            Some(()) => {
+                event_bus.emit(PostEvent::new(WebhookEvent::PostBanned, id, None));
+                Ok(Either::Left(Redirect::to(uri!(post_detail_get(id)))))
+            }
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit_extra,
+    PostBanFormTemplate,
+    PostBanForm,
+    post_ban,
+    "/posts/by-id/<id>/ban",
+    PageMeta::for_post_action(id, "breadcrumb.post_ban"),
+    (Admin),
+    (id: i64, ban_reason_id_set: BanReasonIdSet, event_bus: &State<EventBus>),
+    true,
+    "flash.post_banned"
+);
+
+#[form_with_csrf]
+#[derive(RawForm, Clone, Debug, Validate, FormWithDefinition)]
+#[form_submit_name = "form.upload_ban_by_hash.submit"]
+pub struct UploadBanByHashForm {
+    #[extra_validated(crate::app::db::BanReasonIdSet)]
+    #[form_field_type = "RadioId"]
+    #[form_field_verbose_name = "form.upload_ban_by_hash.ban_reason_id"]
+    ban_reason_id: IdField,
+
+    #[validate(length(
+        max = 500,
+        code = "ban_reason_text_too_long",
+        message = "validation.ban_description_too_long"
+    ))]
+    #[form_field_verbose_name = "form.upload_ban_by_hash.ban_reason_text"]
+    ban_reason_text: String,
+}
+
+impl UploadBanByHashForm {
+    fn new(ban_reason_id_set: &BanReasonIdSet, csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            ban_reason_id: IdField::load(None, ban_reason_id_set).0,
+            ban_reason_text: "".to_string(),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            ban_reason_id: self.ban_reason_id.clone(),
+            ban_reason_text: self.ban_reason_text.clone(),
+        }
+    }
+}
+
+fn get_upload_ban_by_hash_page_meta(id: i64) -> PageMeta {
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_POSTS.clone(),
+        Breadcrumb::message_with_args_without_url(
+            "breadcrumb.upload_ban_by_hash",
+            &[("id", &id.to_string())],
+        ),
+    ])
+}
+
+#[get("/uploads/by-id/<id>/ban-by-hash")]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_ban_by_hash_get<'a>(
+    id: i64,
+    _admin: Admin,
+    ban_reason_id_set: BanReasonIdSet,
+    csrf_token: CsrfToken,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+) -> Result<UploadBanByHashTemplate<'a>, crate::error::Error> {
+    let post_ids = list_post_ids_by_upload_hash(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    Ok(UploadBanByHashTemplate {
+        context: context.with_meta(get_upload_ban_by_hash_page_meta(id)),
+        form: UploadBanByHashForm::new(&ban_reason_id_set, &csrf_token.authenticity_token())
+            .get_definition(ValidationErrors::new()),
+        post_ids,
+    })
+}
+
+#[post("/uploads/by-id/<id>/ban-by-hash", data = "<form>")]
+pub async fn upload_ban_by_hash_post<'a>(
+    id: i64,
+    _admin: Admin,
+    form: ExtraValidatedForm<UploadBanByHashForm>,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    event_bus: &State<EventBus>,
+) -> Result<Either<MessageTemplate<'a>, UploadBanByHashTemplate<'a>>, crate::error::Error> {
+    let ExtraValidatedForm(form, mut errors) = form;
+    if let Err(new_errors) = form.validate() {
+        new_errors
+            .field_errors()
+            .iter()
+            .for_each(|(key, value_errors)| {
+                value_errors
+                    .iter()
+                    .for_each(|err| errors.add(key, err.clone()))
+            });
+    }
+
+    if !errors.is_empty() {
+        let post_ids = list_post_ids_by_upload_hash(id, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        return Ok(Either::Right(UploadBanByHashTemplate {
+            context: context.with_meta(get_upload_ban_by_hash_page_meta(id)),
+            form: form.clear_sensitive().get_definition(errors),
+            post_ids,
+        }));
+    }
+
+    let actor_username = context
+        .user
+        .username()
+        .ok_or(crate::error::Error::AccessDenied)?;
+    let post_ids = ban_posts_by_upload_hash(
+        id,
+        form.ban_reason_id.value.clone(),
+        if form.ban_reason_text.is_empty() {
+            None
+        } else {
+            Some(form.ban_reason_text.clone())
+        },
+        &actor_username,
+        pool,
+    )
+    .await?
+    .ok_or(crate::error::Error::DoesNotExist)?;
+    for post_id in post_ids.iter() {
+        event_bus.emit(PostEvent::new(WebhookEvent::PostBanned, *post_id, None));
+    }
+
+    let message = if post_ids.is_empty() {
+        context
+            .locale
+            .translate("message.no_matching_posts")
+            .to_string()
+    } else {
+        context.locale.translate_with_args(
+            "message.posts_banned",
+            &[(
+                "post_ids",
+                &post_ids
+                    .iter()
+                    .map(|post_id| format!("#{}", post_id))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )],
+        )
+    };
+    Ok(Either::Left(MessageTemplate {
+        context: context.with_meta(get_upload_ban_by_hash_page_meta(id)),
+        message,
+    }))
+}
+
+pub struct PostUnbanAction;
+
+impl PostUnbanAction {
+    async fn check(
+        id: i64,
+        _event_bus: &State<EventBus>,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<(), crate::error::Error> {
+        try_get_post(id, pool, user)
+            .await?
+            .map(|_| ())
+            .ok_or(crate::error::Error::DoesNotExist)
+    }
+
+    async fn process(
+        id: i64,
+        event_bus: &State<EventBus>,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_unban_post_check_exists(id, &actor_username, pool).await? {
+            Some(()) => {
+                event_bus.emit(PostEvent::new(WebhookEvent::PostUnbanned, id, None));
+                Ok(Either::Left(Redirect::to(uri!(post_detail_get(id)))))
+            }
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    confirm(
+        context
+            .locale
+            .translate_with_args("message.post_unban_confirm", &[("id", &id.to_string())])
+    ),
+    ConfirmTemplate,
+    PostUnbanAction,
+    post_unban,
+    "/posts/by-id/<id>/unban",
+    PageMeta::for_post_action(id, "breadcrumb.post_unban"),
+    (Admin),
+    (id: i64, event_bus: &State<EventBus>),
+    true,
+    "flash.post_unbanned"
+);
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.post_quarantine.submit"]
+pub struct PostQuarantineForm {
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.post_quarantine.is_quarantined"]
+    is_quarantined: bool,
+}
+
+impl PostQuarantineForm {
+    async fn load(
+        id: i64,
+        user: &Authentication,
+        csrf_token: &str,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Self, crate::error::Error> {
+        match try_get_post(id, pool, user).await? {
+            Some(post) => Ok(Self {
+                csrf_token: csrf_token.to_string(),
+                is_quarantined: post.is_quarantined,
+            }),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            is_quarantined: self.is_quarantined,
+        }
+    }
+
+    async fn process(
+        &self,
+        id: i64,
+        user: &Authentication,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        match try_set_post_quarantined_check_exists(id, self.is_quarantined, &actor_username, pool)
+            .await?
+        {
+            Some(()) => Ok(Either::Left(Redirect::to(uri!(post_detail_get(id))))),
+            None => Err(crate::error::Error::DoesNotExist),
+        }
+    }
+}
+
+form_get_and_post!(
+    edit,
+    FormTemplate,
+    PostQuarantineForm,
+    post_quarantine,
+    "/posts/by-id/<id>/quarantine",
+    PageMeta::for_post_action(id, "breadcrumb.post_quarantine"),
+    (Admin),
+    (id: i64),
+    true,
+    "flash.post_quarantine_changed"
+);
+
+#[form_with_csrf]
+#[derive(RawForm, Clone, Debug, Validate, FormWithDefinition)]
+#[form_submit_name = "form.post_content_rating.submit"]
+pub struct PostContentRatingForm {
+    #[extra_validated(crate::app::db::RatingIdSet)]
+    #[form_field_type = "RadioId"]
+    #[form_field_verbose_name = "form.post_content_rating.content_rating_id"]
+    content_rating_id: IdField,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.post_content_rating.reset_content_rating"]
+    reset_content_rating: bool,
+}
+
+impl PostContentRatingForm {
+    fn new(
+        content_rating_id: Option<String>,
+        content_rating_id_set: &RatingIdSet,
+        csrf_token: &str,
+    ) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            content_rating_id: IdField::load(content_rating_id, content_rating_id_set).0,
+            reset_content_rating: false,
+        }
+    }
+
+    async fn load(
+        id: i64,
+        content_rating_id_set: RatingIdSet,
         user: &Authentication,
         csrf_token: &str,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Self, crate::error::Error> {
         match try_get_post(id, pool, user).await? {
-            Some(post) => match post.ban {
-                Some((ban_reason, ban_reason_text)) => Ok(Self {
-                    csrf_token: csrf_token.to_string(),
-                    ban_reason_id: IdField::load(
-                        ban_reason.map(|ban_reason| ban_reason.id),
-                        &ban_reason_id_set,
-                    )
-                    .0,
-                    ban_reason_text: ban_reason_text.unwrap_or("".to_string()),
-                }),
-                None => Ok(Self {
-                    csrf_token: csrf_token.to_string(),
-                    ban_reason_id: IdField::load(None, &ban_reason_id_set).0,
-                    ban_reason_text: "".to_string(),
-                }),
-            },
+            Some(post) => Ok(Self::new(
+                post.content_rating_id,
+                &content_rating_id_set,
+                csrf_token,
+            )),
             None => Err(crate::error::Error::DoesNotExist),
         }
     }
@@ -1246,29 +5097,26 @@ impl PostBanForm {
     fn clear_sensitive(&self) -> Self {
         Self {
             csrf_token: self.csrf_token.clone(),
-            ban_reason_id: self.ban_reason_id.clone(),
-            ban_reason_text: self.ban_reason_text.clone(),
+            content_rating_id: self.content_rating_id.clone(),
+            reset_content_rating: self.reset_content_rating,
         }
     }
 
     async fn process(
         &self,
         id: i64,
-        _ban_reason_id_set: BanReasonIdSet,
-        _user: &Authentication,
+        _content_rating_id_set: RatingIdSet,
+        user: &Authentication,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_ban_post_check_exists(
-            id,
-            self.ban_reason_id.value.clone(),
-            if self.ban_reason_text.is_empty() {
-                None
-            } else {
-                Some(self.ban_reason_text.clone())
-            },
-            pool,
-        )
-        .await?
+        let actor_username = user.username().ok_or(crate::error::Error::AccessDenied)?;
+        let content_rating_id = if self.reset_content_rating {
+            None
+        } else {
+            self.content_rating_id.value.as_deref()
+        };
+        match try_set_post_content_rating_check_exists(id, content_rating_id, &actor_username, pool)
+            .await?
         {
             Some(()) => Ok(Either::Left(Redirect::to(uri!(post_detail_get(id))))),
             None => Err(crate::error::Error::DoesNotExist),
@@ -1279,48 +5127,59 @@ impl PostBanForm {
 form_get_and_post!(
     edit_extra,
     FormTemplate,
-    PostBanForm,
-    post_ban,
-    "/posts/by-id/<id>/ban",
-    vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_POSTS.clone(),
-        Breadcrumb::new_with_url(
-            format!("#{}", id),
-            uri!(post_detail_get(id)).to_string()
-        ),
-        Breadcrumb::new_without_url("бан".to_string())
-    ],
+    PostContentRatingForm,
+    post_content_rating,
+    "/posts/by-id/<id>/content-rating",
+    PageMeta::for_post_action(id, "breadcrumb.post_content_rating"),
     (Admin),
-    (id: i64, ban_reason_id_set: BanReasonIdSet),
-    true
+    (id: i64, content_rating_id_set: RatingIdSet),
+    true,
+    "flash.post_content_rating_saved"
 );
 
 #[form_with_csrf]
 #[derive(
     Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
 )]
-#[form_submit_name = "разбанить"]
-pub struct PostUnbanForm {}
+#[form_submit_name = "form.post_ban_appeal.submit"]
+pub struct PostBanAppealForm {
+    #[validate(length(
+        min = 1,
+        code = "message_too_short",
+        message = "validation.message_too_short"
+    ))]
+    #[validate(length(
+        max = 2000,
+        code = "message_too_long",
+        message = "validation.message_too_long"
+    ))]
+    #[form_field_type = "TextArea"]
+    #[form_field_verbose_name = "form.post_ban_appeal.message"]
+    message: String,
+}
 
-impl PostUnbanForm {
+impl PostBanAppealForm {
     async fn load(
         id: i64,
         user: &Authentication,
         csrf_token: &str,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Self, crate::error::Error> {
-        if try_get_post(id, pool, user).await?.is_some() {
-            Ok(Self {
-                csrf_token: csrf_token.to_string(),
-            })
-        } else {
-            Err(crate::error::Error::DoesNotExist)
+        let post = try_get_post(id, pool, user)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        if !post.can_appeal_ban(user) {
+            return Err(crate::error::Error::AccessDenied);
         }
+        Ok(Self {
+            message: "".to_string(),
+            csrf_token: csrf_token.to_string(),
+        })
     }
 
     fn clear_sensitive(&self) -> Self {
         Self {
+            message: self.message.clone(),
             csrf_token: self.csrf_token.clone(),
         }
     }
@@ -1328,12 +5187,31 @@ impl PostUnbanForm {
     async fn process(
         &self,
         id: i64,
-        _user: &Authentication,
+        user: &Authentication,
         pool: &State<Pool<Postgres>>,
     ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
-        match try_unban_post_check_exists(id, pool).await? {
+        let post = try_get_post(id, pool, user)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+        if !post.can_appeal_ban(user) {
+            return Err(crate::error::Error::AccessDenied);
+        }
+        match try_add_ban_appeal_check_pending(id, &post.author_username, &self.message, pool)
+            .await?
+        {
             Some(()) => Ok(Either::Left(Redirect::to(uri!(post_detail_get(id))))),
-            None => Err(crate::error::Error::DoesNotExist),
+            None => {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "message",
+                    ValidationError {
+                        code: Cow::from("ban_appeal_already_pending"),
+                        message: Some(Cow::from("validation.ban_appeal_already_pending")),
+                        params: HashMap::new(),
+                    },
+                );
+                Ok(Either::Right(errors))
+            }
         }
     }
 }
@@ -1341,43 +5219,365 @@ impl PostUnbanForm {
 form_get_and_post!(
     edit,
     FormTemplate,
-    PostUnbanForm,
-    post_unban,
-    "/posts/by-id/<id>/unban",
-    vec![
-        BREADCRUMB_ROOT.clone(),
-        BREADCRUMB_POSTS.clone(),
-        Breadcrumb::new_with_url(
-            format!("#{}", id),
-            uri!(post_detail_get(id)).to_string()
-        ),
-        Breadcrumb::new_without_url("разбан".to_string())
-    ],
-    (Admin),
+    PostBanAppealForm,
+    post_ban_appeal,
+    "/posts/by-id/<id>/appeal",
+    PageMeta::for_post_action(id, "breadcrumb.ban_appeal"),
+    (User),
     (id: i64),
-    true
+    true,
+    "flash.ban_appeal_sent"
+);
+
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.ban_appeal_decide.submit"]
+pub struct BanAppealDecideForm {}
+
+#[get("/admin")]
+pub async fn admin_dashboard_get<'a>(
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+) -> Result<AdminDashboardTemplate<'a>, crate::error::Error> {
+    Ok(AdminDashboardTemplate {
+        context: context.with_meta(PAGE_META_ADMIN_DASHBOARD.clone()),
+        stats: get_admin_dashboard_stats(pool).await?,
+    })
+}
+
+#[get("/admin/appeals")]
+pub async fn ban_appeals_list_get<'a>(
+    _admin: Admin,
+    csrf_token: CsrfToken,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    flash_message: Option<FlashMessage>,
+) -> Result<BanAppealsListTemplate<'a>, crate::error::Error> {
+    Ok(BanAppealsListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_BAN_APPEALS_LIST.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        items: list_pending_ban_appeals(pool).await?,
+    })
+}
+
+#[post("/admin/appeals/by-id/<id>/approve", data = "<_form>")]
+pub async fn ban_appeal_approve_post(
+    id: i64,
+    _form: CSRFProtectedForm<BanAppealDecideForm>,
+    user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    event_bus: &State<EventBus>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    let post_id =
+        try_decide_ban_appeal_check_pending(id, BanAppealStatus::Approved, &user.username, pool)
+            .await?
+            .ok_or(crate::error::Error::DoesNotExist)?;
+
+    try_unban_post_check_exists(post_id, &user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+    event_bus.emit(PostEvent::new(WebhookEvent::PostUnbanned, post_id, None));
+
+    set_flash_message(cookies, FlashMessage::success("flash.ban_appeal_approved"));
+    Ok(Redirect::to(uri!(ban_appeals_list_get())))
+}
+
+#[post("/admin/appeals/by-id/<id>/reject", data = "<_form>")]
+pub async fn ban_appeal_reject_post(
+    id: i64,
+    _form: CSRFProtectedForm<BanAppealDecideForm>,
+    user: User,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_decide_ban_appeal_check_pending(id, BanAppealStatus::Rejected, &user.username, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(cookies, FlashMessage::success("flash.ban_appeal_rejected"));
+    Ok(Redirect::to(uri!(ban_appeals_list_get())))
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), ValidationError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(ValidationError {
+            code: Cow::from("url_invalid"),
+            message: Some(Cow::from("validation.url_invalid")),
+            params: HashMap::new(),
+        })
+    }
+}
+
+#[get("/admin/webhooks")]
+pub async fn webhooks_list_get<'a>(
+    _admin: Admin,
+    csrf_token: CsrfToken,
+    pool: &State<Pool<Postgres>>,
+    context: PageContext<'a>,
+    flash_message: Option<FlashMessage>,
+) -> Result<WebhooksListTemplate<'a>, crate::error::Error> {
+    Ok(WebhooksListTemplate {
+        flash_message,
+        context: context.with_meta(PAGE_META_WEBHOOKS_LIST.clone()),
+        csrf_token: csrf_token.authenticity_token(),
+        items: list_webhooks(pool).await?,
+    })
+}
+
+#[form_with_csrf]
+#[derive(
+    Clone, Debug, Validate, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF,
+)]
+#[form_submit_name = "form.webhook_add.submit"]
+pub struct WebhookAddForm {
+    #[validate(custom = "validate_webhook_url")]
+    #[validate(length(max = 2048, code = "url_too_long", message = "validation.url_too_long"))]
+    #[form_field_type = "Url"]
+    #[form_field_verbose_name = "form.webhook_add.url"]
+    url: String,
+
+    #[validate(length(
+        min = 1,
+        code = "secret_is_blank",
+        message = "validation.secret_is_blank"
+    ))]
+    #[validate(length(
+        max = 256,
+        code = "secret_too_long",
+        message = "validation.secret_too_long"
+    ))]
+    #[form_field_verbose_name = "form.webhook_add.secret"]
+    secret: String,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.webhook_add.post_created"]
+    post_created: bool,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.webhook_add.post_published"]
+    post_published: bool,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.webhook_add.post_banned"]
+    post_banned: bool,
+
+    #[form_field_type = "Checkbox"]
+    #[form_field_verbose_name = "form.webhook_add.post_unbanned"]
+    post_unbanned: bool,
+}
+
+impl WebhookAddForm {
+    fn new(csrf_token: &str) -> Self {
+        Self {
+            csrf_token: csrf_token.to_string(),
+            url: "".to_string(),
+            secret: "".to_string(),
+            post_created: true,
+            post_published: true,
+            post_banned: true,
+            post_unbanned: true,
+        }
+    }
+
+    fn clear_sensitive(&self) -> Self {
+        Self {
+            csrf_token: self.csrf_token.clone(),
+            url: self.url.clone(),
+            secret: "".to_string(),
+            post_created: self.post_created,
+            post_published: self.post_published,
+            post_banned: self.post_banned,
+            post_unbanned: self.post_unbanned,
+        }
+    }
+
+    fn get_events(&self) -> Vec<WebhookEvent> {
+        [
+            (WebhookEvent::PostCreated, self.post_created),
+            (WebhookEvent::PostPublished, self.post_published),
+            (WebhookEvent::PostBanned, self.post_banned),
+            (WebhookEvent::PostUnbanned, self.post_unbanned),
+        ]
+        .into_iter()
+        .filter_map(|(event, is_selected)| if is_selected { Some(event) } else { None })
+        .collect()
+    }
+
+    async fn process(
+        &self,
+        pool: &State<Pool<Postgres>>,
+    ) -> Result<Either<Redirect, ValidationErrors>, crate::error::Error> {
+        let events = self.get_events();
+        if events.is_empty() {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "post_created",
+                ValidationError {
+                    code: Cow::from("events_empty"),
+                    message: Some(Cow::from("validation.events_empty")),
+                    params: HashMap::new(),
+                },
+            );
+            return Ok(Either::Right(errors));
+        }
+
+        add_webhook(
+            &self.url,
+            &self.secret,
+            WebhookEvent::get_mask(&events),
+            pool,
+        )
+        .await?;
+
+        Ok(Either::Left(Redirect::to(uri!(webhooks_list_get()))))
+    }
+}
+
+form_get_and_post!(
+    simple,
+    FormTemplate,
+    WebhookAddForm,
+    webhook_add,
+    "/admin/webhooks/add",
+    PAGE_META_WEBHOOK_ADD.clone(),
+    (Admin),
+    (),
+    false,
+    "flash.webhook_added"
 );
 
-#[get("/posts/search?<query>&<page_id>&<page_size>")]
+#[form_with_csrf]
+#[derive(Clone, Debug, FormWithDefinition, Deserialize, Serialize, FromForm, CheckCSRF)]
+#[form_submit_name = "form.webhook_action.submit"]
+pub struct WebhookActionForm {}
+
+#[post("/admin/webhooks/by-id/<id>/toggle", data = "<_form>")]
+pub async fn webhook_toggle_post(
+    id: i64,
+    _form: CSRFProtectedForm<WebhookActionForm>,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_toggle_webhook_check_exists(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(cookies, FlashMessage::success("flash.webhook_toggled"));
+    Ok(Redirect::to(uri!(webhooks_list_get())))
+}
+
+#[post("/admin/webhooks/by-id/<id>/remove", data = "<_form>")]
+pub async fn webhook_remove_post(
+    id: i64,
+    _form: CSRFProtectedForm<WebhookActionForm>,
+    _admin: Admin,
+    pool: &State<Pool<Postgres>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, crate::error::Error> {
+    try_remove_webhook_check_exists(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    set_flash_message(cookies, FlashMessage::success("flash.webhook_removed"));
+    Ok(Redirect::to(uri!(webhooks_list_get())))
+}
+
+fn get_webhook_deliveries_page_meta(id: i64) -> PageMeta {
+    PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_WEBHOOKS.clone(),
+        Breadcrumb::message_with_args_without_url(
+            "breadcrumb.webhook_deliveries",
+            &[("id", &id.to_string())],
+        ),
+    ])
+}
+
+#[get("/admin/webhooks/by-id/<id>/deliveries?<page_id>&<page_size>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn webhook_deliveries_get<'a, 'b, 'c>(
+    id: i64,
+    pool: &'a State<Pool<Postgres>>,
+    context: PageContext<'b>,
+    pagination_config: &'c State<PaginationConfig>,
+    page_id: Option<u64>,
+    page_size: Option<u64>,
+    _admin: Admin,
+) -> Result<Either<WebhookDeliveriesTemplate<'b>, Redirect>, crate::error::Error> {
+    let webhook = try_get_webhook(id, pool)
+        .await?
+        .ok_or(crate::error::Error::DoesNotExist)?;
+
+    let page_params = PageParams {
+        page_id,
+        page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
+    };
+    page_params.check(pagination_config)?;
+
+    let page = list_webhook_deliveries_with_pagination(id, page_params, pool).await?;
+
+    if page_id.is_some_and(|page_id| page_id != page.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(webhook_deliveries_get(
+            id,
+            Some(page.page_id),
+            Some(page.page_size)
+        )))));
+    }
+
+    Ok(Either::Left(WebhookDeliveriesTemplate {
+        context: context.with_meta(get_webhook_deliveries_page_meta(id)),
+        webhook,
+        page,
+        page_base: UrlQuery::new(),
+    }))
+}
+
+#[get("/posts/search?<query>&<page_id>&<page_size>&<layout>")]
 #[allow(clippy::too_many_arguments)]
 pub async fn posts_search_get<'a, 'b, 'c>(
-    user: Authentication,
     pool: &'a State<Pool<Postgres>>,
-    asset_context: &'b State<AssetContext>,
+    context: PageContext<'b>,
     pagination_config: &'c State<PaginationConfig>,
     query: Option<String>,
     page_id: Option<u64>,
     page_size: Option<u64>,
+    layout: Option<PostListLayout>,
+    cookies: &CookieJar<'_>,
     upload_config: &'c State<UploadConfig>,
-) -> Result<PostsSearchTemplate<'b, 'c>, crate::error::Error> {
+) -> Result<Either<PostsSearchTemplate<'b, 'c>, Redirect>, crate::error::Error> {
+    let layout = PostListLayout::resolve(layout, cookies);
     let page_params = PageParams {
         page_id,
         page_size: page_size.unwrap_or(pagination_config.default_page_size),
+        overflow_behavior: PageOverflowBehavior::Clamp,
     };
     page_params.check(pagination_config)?;
 
-    let page_raw = search_posts_with_pagination(pool, query.as_deref(), page_params, &user).await?;
-    let page = page_raw.map(|post| (post.id, post.clone().check_visible(&user)));
+    let search_timer = METRICS.search_duration.start_timer();
+    let page_raw =
+        search_posts_with_pagination(pool, query.as_deref(), page_params, &context.user).await?;
+    search_timer.observe_duration();
+
+    if page_id.is_some_and(|page_id| page_id != page_raw.page_id) {
+        return Ok(Either::Right(Redirect::to(uri!(posts_search_get(
+            query,
+            Some(page_raw.page_id),
+            Some(page_raw.page_size),
+            None as Option<PostListLayout>
+        )))));
+    }
+
+    let page = page_raw.map(|post| (post.id, post.clone().check_visible(&context.user)));
+    let covers = get_post_list_covers(&page, layout, pool).await?;
 
     let query_string = query.clone().unwrap_or_default();
 
@@ -1385,21 +5585,115 @@ pub async fn posts_search_get<'a, 'b, 'c>(
         .into_iter()
         .collect();
 
-    Ok(PostsSearchTemplate {
-        user,
-        asset_context,
-        breadcrumbs: vec![
-            BREADCRUMB_ROOT.clone(),
-            BREADCRUMB_POSTS.clone(),
-            Breadcrumb::new_without_url(if query_string.is_empty() {
-                "поиск".to_string()
-            } else {
-                format!("поиск: {}", query_string)
-            }),
-        ],
+    let page_meta = PageMeta::new(vec![
+        BREADCRUMB_ROOT.clone(),
+        BREADCRUMB_POSTS.clone(),
+        if query_string.is_empty() {
+            Breadcrumb::message_without_url("breadcrumb.search")
+        } else {
+            Breadcrumb::new_without_url(format!(
+                "{}: {}",
+                context.locale.translate("breadcrumb.search"),
+                query_string
+            ))
+        },
+    ]);
+
+    Ok(Either::Left(PostsSearchTemplate {
+        context: context.with_meta(page_meta),
         page,
         storage: &upload_config.storage,
         query_string: query,
         page_base,
-    })
+        layout,
+        covers,
+    }))
+}
+
+fn get_error_title_and_message(status: Status) -> (&'static str, &'static str) {
+    match status.code {
+        403 => ("error.forbidden.title", "error.forbidden.message"),
+        404 => ("error.not_found.title", "error.not_found.message"),
+        422 => (
+            "error.unprocessable_entity.title",
+            "error.unprocessable_entity.message",
+        ),
+        451 => (
+            "error.unavailable_for_legal_reasons.title",
+            "error.unavailable_for_legal_reasons.message",
+        ),
+        _ => ("error.internal.title", "error.internal.message"),
+    }
+}
+
+/// Render HTML error page, page context is taken from request because catchers can not use request
+/// guards as arguments. Authentication errors are ignored here, so that error page is shown to
+/// anonymous user instead.
+pub async fn render_error_page(
+    status: Status,
+    request: &Request<'_>,
+) -> Result<RawHtml<String>, Status> {
+    let asset_context = request
+        .guard::<&State<AssetContext>>()
+        .await
+        .succeeded()
+        .ok_or(Status::InternalServerError)?;
+    let user = request
+        .guard::<Authentication>()
+        .await
+        .succeeded()
+        .unwrap_or(Authentication::Anonymous);
+
+    let (title, message) = get_error_title_and_message(status);
+    let locale = Locale::resolve(request);
+    ErrorTemplate {
+        context: PageContext {
+            user,
+            asset_context,
+            locale,
+            theme: Theme::from_cookies(request.cookies()),
+            timezone_offset: timezone_offset_from_cookies(request.cookies()).unwrap_or(0),
+            csp_nonce: get_csp_nonce(request),
+            meta: PageMeta::new(vec![
+                BREADCRUMB_ROOT.clone(),
+                Breadcrumb::message_without_url(title),
+            ]),
+        },
+        status_code: status.code,
+        message: locale.translate(message).to_string(),
+    }
+    .render()
+    .map(RawHtml)
+    .map_err(|_| Status::InternalServerError)
+}
+
+#[catch(403)]
+pub async fn forbidden_catcher(request: &Request<'_>) -> Result<RawHtml<String>, Status> {
+    render_error_page(Status::Forbidden, request).await
+}
+
+#[catch(404)]
+pub async fn not_found_catcher(request: &Request<'_>) -> Result<RawHtml<String>, Status> {
+    render_error_page(Status::NotFound, request).await
+}
+
+#[catch(422)]
+pub async fn unprocessable_entity_catcher(
+    request: &Request<'_>,
+) -> Result<RawHtml<String>, Status> {
+    render_error_page(Status::UnprocessableEntity, request).await
+}
+
+#[catch(451)]
+pub async fn unavailable_for_legal_reasons_catcher(
+    request: &Request<'_>,
+) -> Result<RawHtml<String>, Status> {
+    render_error_page(Status::UnavailableForLegalReasons, request).await
+}
+
+#[catch(500)]
+pub async fn internal_server_error_catcher(
+    request: &Request<'_>,
+) -> Result<RawHtml<String>, Status> {
+    render_error_page(Status::InternalServerError, request).await
 }
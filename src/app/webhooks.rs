@@ -0,0 +1,269 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use rocket::{
+    async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    time::OffsetDateTime,
+    Build, Orbit, Rocket,
+};
+use sha2::Sha256;
+use sqlx::{Pool, Postgres};
+use tokio::{sync::mpsc::UnboundedReceiver, time::sleep};
+
+use crate::{
+    app::{
+        db::{
+            add_webhook_deliveries, list_due_webhook_deliveries, set_webhook_delivery_result,
+            WebhookDeliveryStatus, WebhookEvent,
+        },
+        events::{subscribe_to_event_bus, PostEvent},
+    },
+    error, WebhookConfig,
+};
+
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Archivanima-Event";
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Archivanima-Signature";
+
+/// Interval of checking for deliveries which should be retried.
+const WEBHOOK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const WEBHOOK_DELIVERY_BATCH_SIZE: i64 = 100;
+
+/// HMAC-SHA256 of payload with webhook secret, sent in [`WEBHOOK_SIGNATURE_HEADER`], so that
+/// receiver can check that request was sent by this server.
+pub fn sign_webhook_payload(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+/// Delay before next attempt after `attempt_count` failed ones, it is doubled after each attempt.
+pub fn get_webhook_retry_delay(config: &WebhookConfig, attempt_count: u32) -> Duration {
+    let multiplier = 2u32.saturating_pow(attempt_count.saturating_sub(1));
+    config
+        .retry_base_interval
+        .saturating_mul(multiplier)
+        .min(config.max_retry_interval)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait WebhookClient: Send + Sync {
+    /// Send request, returns response status.
+    async fn send(&self, request: WebhookRequest) -> Result<u16, error::Error>;
+}
+
+pub struct HttpWebhookClient {
+    client: reqwest::Client,
+}
+
+impl HttpWebhookClient {
+    pub fn new(timeout: Duration) -> Result<Self, error::Error> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+        })
+    }
+}
+
+#[async_trait]
+impl WebhookClient for HttpWebhookClient {
+    async fn send(&self, request: WebhookRequest) -> Result<u16, error::Error> {
+        let mut request_builder = self
+            .client
+            .post(&request.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        for (name, value) in request.headers {
+            request_builder = request_builder.header(name, value);
+        }
+        let response = request_builder.body(request.body).send().await?;
+
+        Ok(response.status().as_u16())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LogWebhookClient {
+    sent_requests: Mutex<Vec<WebhookRequest>>,
+}
+
+impl LogWebhookClient {
+    #[cfg(test)]
+    pub fn sent_requests(&self) -> Vec<WebhookRequest> {
+        self.sent_requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl WebhookClient for LogWebhookClient {
+    async fn send(&self, request: WebhookRequest) -> Result<u16, error::Error> {
+        info!(
+            "Sending webhook to {} with headers {:?}:\n{}",
+            request.url, request.headers, request.body
+        );
+        self.sent_requests.lock().unwrap().push(request);
+        Ok(200)
+    }
+}
+
+/// Queue deliveries of event to subscribed webhooks. Creation of hidden post is not announced,
+/// webhooks receive [`WebhookEvent::PostPublished`] when it is published.
+pub async fn enqueue_webhook_deliveries(
+    event: &PostEvent,
+    pool: &Pool<Postgres>,
+) -> Result<u64, error::Error> {
+    if event.event == WebhookEvent::PostCreated && !event.is_publication() {
+        return Ok(0);
+    }
+    add_webhook_deliveries(event.event, &serde_json::to_string(event)?, pool).await
+}
+
+/// Attempt deliveries which are due at `now`, failed ones are retried with exponential backoff
+/// until `max_attempts` is reached. Returns number of attempted deliveries.
+pub async fn deliver_due_webhooks(
+    client: &dyn WebhookClient,
+    config: &WebhookConfig,
+    now: OffsetDateTime,
+    pool: &Pool<Postgres>,
+) -> Result<u64, error::Error> {
+    let tasks = list_due_webhook_deliveries(now, WEBHOOK_DELIVERY_BATCH_SIZE, pool).await?;
+    let task_count = tasks.len() as u64;
+
+    for task in tasks {
+        let result = client
+            .send(WebhookRequest {
+                headers: vec![
+                    (WEBHOOK_EVENT_HEADER, task.event.get_option()),
+                    (
+                        WEBHOOK_SIGNATURE_HEADER,
+                        sign_webhook_payload(&task.secret, &task.payload),
+                    ),
+                ],
+                url: task.url,
+                body: task.payload,
+            })
+            .await;
+
+        let (response_status, error) = match result {
+            Ok(response_status) if (200..300).contains(&response_status) => {
+                set_webhook_delivery_result(
+                    task.id,
+                    WebhookDeliveryStatus::Succeeded,
+                    Some(response_status),
+                    None,
+                    now,
+                    pool,
+                )
+                .await?;
+                continue;
+            }
+            Ok(response_status) => (Some(response_status), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        let attempt_count = task.attempt_count as u32 + 1;
+        let status = if attempt_count >= config.max_attempts {
+            WebhookDeliveryStatus::Failed
+        } else {
+            WebhookDeliveryStatus::Pending
+        };
+        set_webhook_delivery_result(
+            task.id,
+            status,
+            response_status,
+            error.as_deref(),
+            now + get_webhook_retry_delay(config, attempt_count),
+            pool,
+        )
+        .await?;
+    }
+
+    Ok(task_count)
+}
+
+/// Fairing which subscribes to [`crate::app::events::EventBus`] and delivers its events to
+/// webhooks in background.
+pub struct WebhookFairing {
+    config: WebhookConfig,
+    client: Arc<dyn WebhookClient>,
+    receiver: Mutex<Option<UnboundedReceiver<PostEvent>>>,
+}
+
+impl WebhookFairing {
+    pub fn new(config: WebhookConfig, client: Arc<dyn WebhookClient>) -> Self {
+        Self {
+            config,
+            client,
+            receiver: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Fairing for WebhookFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Webhooks",
+            kind: Kind::Ignite | Kind::Liftoff,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let (rocket, receiver) = subscribe_to_event_bus(rocket);
+        *self.receiver.lock().unwrap() = Some(receiver);
+        Ok(rocket)
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let pool = match rocket.state::<Pool<Postgres>>() {
+            Some(pool) => pool.clone(),
+            None => return,
+        };
+        let mut receiver = match self.receiver.lock().unwrap().take() {
+            Some(receiver) => receiver,
+            None => return,
+        };
+        let config = self.config.clone();
+        let client = self.client.clone();
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = receiver.recv() => match event {
+                        Some(event) => {
+                            if let Err(err) = enqueue_webhook_deliveries(&event, &pool).await {
+                                warn!("Failed to queue webhooks for {:?}: {:?}", event, err);
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = sleep(WEBHOOK_POLL_INTERVAL) => {}
+                    _ = &mut shutdown => break,
+                }
+                if let Err(err) =
+                    deliver_due_webhooks(&*client, &config, OffsetDateTime::now_utc(), &pool).await
+                {
+                    warn!("Failed to deliver webhooks: {:?}", err);
+                }
+            }
+        });
+    }
+}
@@ -1,33 +1,70 @@
+use std::io::Cursor;
+
 use rocket::{
     async_trait,
-    http::Status,
+    fairing::{Fairing, Info, Kind},
+    http::{Cookie, CookieJar, Method, Status},
     request::{self, FromRequest, Outcome},
-    Request, State,
+    uri, Request, Response, State,
 };
 use sqlx::{Pool, Postgres};
 use time::OffsetDateTime;
 
 use crate::{
-    app::db::{try_get_user, User},
-    error,
+    app::db::{
+        add_impersonation_log_entry, add_session, remove_session, try_get_user,
+        try_get_user_by_api_token, try_get_user_by_session, ApiTokenScope, ImpersonationAction,
+        User, DELETED_USERNAME,
+    },
+    error, AuthConfig,
 };
 
-pub const USERNAME_COOKIE_NAME: &str = "username";
+const PASSWORD_CHANGE_ALLOWED_ROUTES: [&str; 6] = [
+    "change_password_get",
+    "change_password_post",
+    "confirm_password_get",
+    "confirm_password_post",
+    "logout_get",
+    "logout_post",
+];
+
+const API_TOKEN_AUTHORIZATION_PREFIX: &str = "Bearer ";
+
+/// Scope of API token which was used to authenticate request, `None` for cookie sessions.
+#[derive(Clone, Copy, Debug, Default)]
+struct RequestApiTokenScope(Option<ApiTokenScope>);
 
 #[derive(Clone, Debug)]
 pub enum Authentication {
     Authenticated(User),
+    Impersonated(User, String),
     Banned(User),
     Anonymous,
 }
 
 impl Authentication {
+    /// Administrator authentication for CLI commands, which sees all posts, including quarantined
+    /// ones.
+    pub fn system() -> Self {
+        Authentication::Authenticated(User {
+            username: DELETED_USERNAME.to_string(),
+            is_active: true,
+            is_admin: true,
+            is_uploader: false,
+            birth_date: None,
+            must_change_password: false,
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            last_login_at: None,
+            display_name: None,
+        })
+    }
+
     pub fn is_anonymous(&self) -> bool {
         matches!(self, Self::Anonymous)
     }
 
     pub fn is_authenticated(&self) -> bool {
-        matches!(self, Self::Authenticated(_))
+        matches!(self, Self::Authenticated(_) | Self::Impersonated(_, _))
     }
 
     pub fn map<T, F>(&self, f: F) -> Option<T>
@@ -36,6 +73,7 @@ impl Authentication {
     {
         match self {
             Authentication::Authenticated(user) => Some(f(user)),
+            Authentication::Impersonated(user, _) => Some(f(user)),
             Authentication::Banned(user) => Some(f(user)),
             Authentication::Anonymous => None,
         }
@@ -56,6 +94,13 @@ impl Authentication {
     pub fn birth_date(&self) -> Option<OffsetDateTime> {
         self.map(|user| user.birth_date).flatten()
     }
+
+    pub fn impersonator_username(&self) -> Option<&str> {
+        match self {
+            Authentication::Impersonated(_, impersonator_username) => Some(impersonator_username),
+            _ => None,
+        }
+    }
 }
 
 #[async_trait]
@@ -65,17 +110,81 @@ impl<'r> FromRequest<'r> for Authentication {
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
         let result: &request::Outcome<Self, Self::Error> = req
             .local_cache_async(async {
-                let cookies = req.cookies();
-                match cookies.get_private("username") {
-                    Some(cookie) => {
+                let auth_config_result: Outcome<&State<AuthConfig>, ()> = req.guard().await;
+                let auth_config = match auth_config_result {
+                    Outcome::Success(auth_config) => auth_config,
+                    Outcome::Error((status, ())) => {
+                        return Outcome::Error((
+                            status,
+                            error::Error::Misc("auth config not found".to_string()),
+                        ))
+                    }
+                    Outcome::Forward(status) => return Outcome::Forward(status),
+                };
+                if let Some(token) = get_bearer_token(req) {
+                    let pool_state_result: Outcome<&State<Pool<Postgres>>, ()> = req.guard().await;
+                    return match pool_state_result {
+                        Outcome::Success(pool_state) => {
+                            authenticate_with_api_token(req, token, pool_state).await
+                        }
+                        Outcome::Error((status, ())) => {
+                            Outcome::Error((status, error::Error::PoolNotFound))
+                        }
+                        Outcome::Forward(status) => Outcome::Forward(status),
+                    };
+                }
+                let impersonator_username = get_impersonator_username(req.cookies(), auth_config);
+                match get_session_token(req.cookies(), auth_config) {
+                    Some(token) => {
                         let pool_state_result: Outcome<&State<Pool<Postgres>>, ()> =
                             req.guard().await;
                         match pool_state_result {
                             Outcome::Success(pool_state) => {
-                                match try_get_user(cookie.value(), pool_state).await {
-                                    Ok(Some(user)) if user.is_active => request::Outcome::Success(
-                                        Authentication::Authenticated(user),
-                                    ),
+                                match try_get_user_by_session(&token, pool_state).await {
+                                    Ok(Some(user)) if user.username == DELETED_USERNAME => {
+                                        request::Outcome::Success(Authentication::Anonymous)
+                                    }
+                                    Ok(Some(user))
+                                        if user.is_active
+                                            && user.must_change_password
+                                            && impersonator_username.is_none()
+                                            && !is_password_change_allowed(req) =>
+                                    {
+                                        request::Outcome::Error((
+                                            Status::Forbidden,
+                                            error::Error::PasswordChangeRequired,
+                                        ))
+                                    }
+                                    Ok(Some(user)) if user.is_active => match impersonator_username
+                                    {
+                                        Some(impersonator_username) => {
+                                            match try_get_user(&impersonator_username, pool_state)
+                                                .await
+                                            {
+                                                Ok(Some(impersonator))
+                                                    if impersonator.is_active
+                                                        && impersonator.is_admin =>
+                                                {
+                                                    request::Outcome::Success(
+                                                        Authentication::Impersonated(
+                                                            user,
+                                                            impersonator.username,
+                                                        ),
+                                                    )
+                                                }
+                                                Ok(_) => request::Outcome::Success(
+                                                    Authentication::Anonymous,
+                                                ),
+                                                Err(err) => request::Outcome::Error((
+                                                    Status::InternalServerError,
+                                                    err,
+                                                )),
+                                            }
+                                        }
+                                        None => request::Outcome::Success(
+                                            Authentication::Authenticated(user),
+                                        ),
+                                    },
                                     Ok(Some(user_banned)) => request::Outcome::Success(
                                         Authentication::Banned(user_banned),
                                     ),
@@ -100,6 +209,345 @@ impl<'r> FromRequest<'r> for Authentication {
     }
 }
 
+fn get_bearer_token<'a>(req: &'a Request<'_>) -> Option<&'a str> {
+    req.headers()
+        .get_one("Authorization")
+        .and_then(|value| value.strip_prefix(API_TOKEN_AUTHORIZATION_PREFIX))
+}
+
+/// Authenticate request with `Authorization: Bearer` API token instead of session cookie. Tokens
+/// with read scope can only be used for `GET` and `HEAD` requests.
+async fn authenticate_with_api_token(
+    req: &Request<'_>,
+    token: &str,
+    pool: &Pool<Postgres>,
+) -> request::Outcome<Authentication, error::Error> {
+    match try_get_user_by_api_token(token, pool).await {
+        Ok(Some((user, _))) if user.username == DELETED_USERNAME => {
+            request::Outcome::Error((Status::Unauthorized, error::Error::AccessDenied))
+        }
+        Ok(Some((user, scope))) => {
+            req.local_cache(|| RequestApiTokenScope(Some(scope)));
+            if !scope.allows_write() && !matches!(req.method(), Method::Get | Method::Head) {
+                request::Outcome::Error((Status::Forbidden, error::Error::AccessDenied))
+            } else if !user.is_active {
+                request::Outcome::Success(Authentication::Banned(user))
+            } else if user.must_change_password && !is_password_change_allowed(req) {
+                request::Outcome::Error((Status::Forbidden, error::Error::PasswordChangeRequired))
+            } else {
+                request::Outcome::Success(Authentication::Authenticated(user))
+            }
+        }
+        Ok(None) => request::Outcome::Error((Status::Unauthorized, error::Error::AccessDenied)),
+        Err(err) => request::Outcome::Error((Status::InternalServerError, err)),
+    }
+}
+
+/// Scope of API token used to authenticate request, `None` if request is not authenticated by
+/// token.
+pub async fn get_api_token_scope(req: &Request<'_>) -> Option<ApiTokenScope> {
+    let _: request::Outcome<Authentication, error::Error> = req.guard().await;
+    req.local_cache(RequestApiTokenScope::default).0
+}
+
+fn is_password_change_allowed(req: &Request<'_>) -> bool {
+    req.route()
+        .and_then(|route| route.name.as_deref())
+        .is_some_and(|route_name| PASSWORD_CHANGE_ALLOWED_ROUTES.contains(&route_name))
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PasswordChangeFairing {}
+
+#[async_trait]
+impl Fairing for PasswordChangeFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Password change",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let authentication: &request::Outcome<Authentication, error::Error> =
+            req.local_cache(|| Outcome::Success(Authentication::Anonymous));
+        if matches!(
+            authentication,
+            Outcome::Error((_, error::Error::PasswordChangeRequired))
+        ) && !req.uri().path().as_str().starts_with("/api/")
+        {
+            res.set_status(Status::SeeOther);
+            res.remove_header("Content-Type");
+            res.set_raw_header(
+                "Location",
+                uri!(crate::app::views::change_password_get).to_string(),
+            );
+            res.set_sized_body(0, Cursor::new(""));
+        }
+    }
+}
+
+pub fn get_session_token(cookies: &CookieJar<'_>, auth_config: &AuthConfig) -> Option<String> {
+    cookies
+        .get_private(
+            &auth_config
+                .cookie_security
+                .cookie_name(&auth_config.session_cookie_name),
+        )
+        .map(|cookie| cookie.value().to_string())
+}
+
+pub async fn start_session(
+    cookies: &CookieJar<'_>,
+    username: &str,
+    user_agent: &UserAgent,
+    remember_me: bool,
+    auth_config: &AuthConfig,
+    pool: &Pool<Postgres>,
+) -> Result<(), error::Error> {
+    let lifetime = if remember_me {
+        auth_config.long_session_lifetime
+    } else {
+        auth_config.short_session_lifetime
+    };
+    let token = add_session(username, user_agent.0.as_deref(), lifetime, pool).await?;
+    cookies.add_private(
+        auth_config.cookie_security.apply(
+            Cookie::build((auth_config.session_cookie_name.clone(), token))
+                .expires(OffsetDateTime::now_utc() + lifetime),
+        ),
+    );
+    Ok(())
+}
+
+pub async fn end_session(
+    cookies: &CookieJar<'_>,
+    auth_config: &AuthConfig,
+    pool: &Pool<Postgres>,
+) -> Result<(), error::Error> {
+    if let Some(token) = get_session_token(cookies, auth_config) {
+        remove_session(&token, pool).await?;
+    }
+    cookies.remove_private(
+        auth_config
+            .cookie_security
+            .apply(Cookie::build(auth_config.session_cookie_name.clone())),
+    );
+    cookies.remove_private(
+        auth_config
+            .cookie_security
+            .apply(Cookie::build(auth_config.recent_auth_cookie_name.clone())),
+    );
+    Ok(())
+}
+
+/// Remember that user has just entered password, see [`RecentAuth`].
+pub fn set_recent_auth(cookies: &CookieJar<'_>, username: &str, auth_config: &AuthConfig) {
+    let now = OffsetDateTime::now_utc();
+    cookies.add_private(
+        auth_config.cookie_security.apply(
+            Cookie::build((
+                auth_config.recent_auth_cookie_name.clone(),
+                format!("{}:{}", now.unix_timestamp(), username),
+            ))
+            .expires(now + auth_config.recent_auth_lifetime),
+        ),
+    );
+}
+
+/// Get time when user last entered password, cookie of other user is ignored.
+pub fn get_recent_auth_time(
+    cookies: &CookieJar<'_>,
+    username: &str,
+    auth_config: &AuthConfig,
+) -> Option<OffsetDateTime> {
+    let cookie = cookies.get_private(
+        &auth_config
+            .cookie_security
+            .cookie_name(&auth_config.recent_auth_cookie_name),
+    )?;
+    let (timestamp, cookie_username) = cookie.value().split_once(':')?;
+    if cookie_username != username {
+        return None;
+    }
+    OffsetDateTime::from_unix_timestamp(timestamp.parse().ok()?).ok()
+}
+
+pub fn get_impersonator_username(
+    cookies: &CookieJar<'_>,
+    auth_config: &AuthConfig,
+) -> Option<String> {
+    cookies
+        .get_private(
+            &auth_config
+                .cookie_security
+                .cookie_name(&auth_config.impersonation_cookie_name),
+        )
+        .map(|cookie| cookie.value().to_string())
+}
+
+pub async fn start_impersonation(
+    cookies: &CookieJar<'_>,
+    admin_username: &str,
+    target_username: &str,
+    user_agent: &UserAgent,
+    auth_config: &AuthConfig,
+    pool: &Pool<Postgres>,
+) -> Result<(), error::Error> {
+    end_session(cookies, auth_config, pool).await?;
+    start_session(
+        cookies,
+        target_username,
+        user_agent,
+        false,
+        auth_config,
+        pool,
+    )
+    .await?;
+    cookies.add_private(
+        auth_config.cookie_security.apply(
+            Cookie::build((
+                auth_config.impersonation_cookie_name.clone(),
+                admin_username.to_string(),
+            ))
+            .expires(OffsetDateTime::now_utc() + auth_config.short_session_lifetime),
+        ),
+    );
+    add_impersonation_log_entry(
+        admin_username,
+        Some(target_username),
+        ImpersonationAction::Start,
+        pool,
+    )
+    .await
+}
+
+pub async fn stop_impersonation(
+    cookies: &CookieJar<'_>,
+    target_username: Option<&str>,
+    user_agent: &UserAgent,
+    auth_config: &AuthConfig,
+    pool: &Pool<Postgres>,
+) -> Result<(), error::Error> {
+    let admin_username =
+        get_impersonator_username(cookies, auth_config).ok_or(error::Error::AccessDenied)?;
+
+    end_session(cookies, auth_config, pool).await?;
+    cookies.remove_private(
+        auth_config
+            .cookie_security
+            .apply(Cookie::build(auth_config.impersonation_cookie_name.clone())),
+    );
+    add_impersonation_log_entry(
+        &admin_username,
+        target_username,
+        ImpersonationAction::Stop,
+        pool,
+    )
+    .await?;
+
+    match try_get_user(&admin_username, pool).await? {
+        Some(admin) if admin.is_active && admin.is_admin => {
+            start_session(
+                cookies,
+                &admin_username,
+                user_agent,
+                false,
+                auth_config,
+                pool,
+            )
+            .await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Mark of request which failed [`RecentAuth`] check.
+#[derive(Clone, Copy, Debug, Default)]
+struct RecentAuthFailed(bool);
+
+/// Guard of sensitive actions (like password change or account deletion): user must have entered
+/// password within `recent_auth_lifetime`, at login or on password confirmation page. Requests
+/// without recent authentication are redirected to that page by [`RecentAuthFairing`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecentAuth {}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for RecentAuth {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let user = match req.guard::<User>().await {
+            Outcome::Success(user) => user,
+            Outcome::Error((status, _)) => return Outcome::Error((status, ())),
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+        let auth_config = match req.rocket().state::<AuthConfig>() {
+            Some(auth_config) => auth_config,
+            None => return Outcome::Error((Status::InternalServerError, ())),
+        };
+
+        let is_recent = get_recent_auth_time(req.cookies(), &user.username, auth_config)
+            .is_some_and(|auth_time| {
+                OffsetDateTime::now_utc() < auth_time + auth_config.recent_auth_lifetime
+            });
+        if is_recent {
+            Outcome::Success(Self {})
+        } else {
+            req.local_cache(|| RecentAuthFailed(true));
+            Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
+
+/// Fairing which redirects requests that failed [`RecentAuth`] check to password confirmation
+/// page, user is returned to page from `GET` request after confirmation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecentAuthFairing {}
+
+#[async_trait]
+impl Fairing for RecentAuthFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Recent authentication",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if !req.local_cache(RecentAuthFailed::default).0 {
+            return;
+        }
+        let next = if req.method() == Method::Get {
+            Some(req.uri().to_string())
+        } else {
+            None
+        };
+        res.set_status(Status::SeeOther);
+        res.remove_header("Content-Type");
+        // Form is posted to URL of page, so `next` is kept for POST route.
+        res.set_raw_header(
+            "Location",
+            uri!(crate::app::views::confirm_password_post(next.as_deref())).to_string(),
+        );
+        res.set_sized_body(0, Cursor::new(""));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserAgent(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for UserAgent {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(Self(
+            req.headers().get_one("User-Agent").map(str::to_string),
+        ))
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Admin {}
 
@@ -135,9 +583,9 @@ impl<'r> FromRequest<'r> for Uploader {
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
         req.local_cache_async(async {
             match req.guard().await {
-                Outcome::Success(Authentication::Authenticated(user)) if user.is_uploader() => {
-                    Outcome::Success(Self {})
-                }
+                Outcome::Success(
+                    Authentication::Authenticated(user) | Authentication::Impersonated(user, _),
+                ) if user.is_uploader() => Outcome::Success(Self {}),
                 Outcome::Success(_) => {
                     Outcome::Error((Status::Forbidden, error::Error::AccessDenied))
                 }
@@ -157,7 +605,9 @@ impl<'r> FromRequest<'r> for User {
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
         req.local_cache_async(async {
             match req.guard().await {
-                Outcome::Success(Authentication::Authenticated(user)) => Outcome::Success(user),
+                Outcome::Success(
+                    Authentication::Authenticated(user) | Authentication::Impersonated(user, _),
+                ) => Outcome::Success(user),
                 Outcome::Success(_) => {
                     Outcome::Error((Status::Forbidden, error::Error::AccessDenied))
                 }
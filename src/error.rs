@@ -3,11 +3,20 @@ use std::fmt::Display;
 use argon2::password_hash;
 use askama_rocket::Responder;
 use log::debug;
-use rocket::{http::Status, response, Request};
+use rocket::{
+    catch,
+    http::Status,
+    request,
+    response::{self, status::Custom},
+    serde::json::Json,
+    Request, Response,
+};
 use serde::Serialize;
 use sqlx::error::BoxDynError;
 use validator::ValidationErrors;
 
+use crate::{auth::Authentication, utils::locale::Locale};
+
 #[derive(Clone, Debug)]
 pub enum Error {
     Misc(String),
@@ -16,6 +25,7 @@ pub enum Error {
     PoolNotFound,
     Rocket(String),
     AccessDenied,
+    PasswordChangeRequired,
     DoesNotExist,
     InvalidPagination,
     PageDoesNotExist,
@@ -23,9 +33,26 @@ pub enum Error {
     ValidationErrors(validator::ValidationErrors),
     InvalidUploadState,
     InvalidContentRange,
+    Mail(String),
+    ShuttingDown,
+    /// Rate limit is exceeded, request can be retried after given number of seconds.
+    RateLimited(u64),
+    /// Idempotency key was used for different request or for request still in progress.
+    IdempotencyKeyConflict,
+    Webhook(String),
+    Telegram(String),
+    /// Total size of files in archive exceeds limit.
+    ArchiveTooLarge,
+    /// Upload chunk is larger than maximum file size.
+    UploadChunkTooLarge,
+    /// Size of upload chunk data is different from its content range.
+    UploadChunkSizeMismatch,
     Unknown,
 }
 
+/// Value of `Retry-After` header for requests rejected during shutdown.
+const SHUTDOWN_RETRY_AFTER_SECONDS: u64 = 30;
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -38,6 +65,7 @@ impl Display for Error {
                 Error::PoolNotFound => "DB error",
                 Error::Rocket(_) => "Rocket error",
                 Error::AccessDenied => "Access denied",
+                Error::PasswordChangeRequired => "Password change required",
                 Error::DoesNotExist => "Object does not exist",
                 Error::InvalidPagination => "Invalid pagination param",
                 Error::PageDoesNotExist => "Page does not exist",
@@ -45,6 +73,15 @@ impl Display for Error {
                 Error::ValidationErrors(_) => "Validation errors",
                 Error::InvalidUploadState => "Invalid upload state",
                 Error::InvalidContentRange => "Invalid content range",
+                Error::Mail(_) => "Mail error",
+                Error::ShuttingDown => "Server is shutting down",
+                Error::RateLimited(_) => "Too many requests",
+                Error::IdempotencyKeyConflict => "Idempotency key conflict",
+                Error::Webhook(_) => "Webhook error",
+                Error::Telegram(_) => "Telegram error",
+                Error::ArchiveTooLarge => "Archive is too large",
+                Error::UploadChunkTooLarge => "Upload chunk is too large",
+                Error::UploadChunkSizeMismatch => "Upload chunk size does not match content range",
                 Error::Unknown => "Unknown error",
             }
         )
@@ -64,6 +101,7 @@ impl std::error::Error for Error {
             Error::PoolNotFound => "DB error",
             Error::Rocket(_) => "Rocket error",
             Error::AccessDenied => "Access denied",
+            Error::PasswordChangeRequired => "Password change required",
             Error::DoesNotExist => "Object does not exist",
             Error::InvalidPagination => "Invalid pagination param",
             Error::PageDoesNotExist => "Page does not exist",
@@ -71,6 +109,15 @@ impl std::error::Error for Error {
             Error::ValidationErrors(_) => "Validation errors",
             Error::InvalidUploadState => "Invalid upload state",
             Error::InvalidContentRange => "Invalid content range",
+            Error::Mail(_) => "Mail error",
+            Error::ShuttingDown => "Server is shutting down",
+            Error::RateLimited(_) => "Too many requests",
+            Error::IdempotencyKeyConflict => "Idempotency key conflict",
+            Error::Webhook(_) => "Webhook error",
+            Error::Telegram(_) => "Telegram error",
+            Error::ArchiveTooLarge => "Archive is too large",
+            Error::UploadChunkTooLarge => "Upload chunk is too large",
+            Error::UploadChunkSizeMismatch => "Upload chunk size does not match content range",
             Error::Unknown => "Unknown error",
         }
     }
@@ -106,6 +153,54 @@ impl From<ValidationErrors> for Error {
     }
 }
 
+impl From<lettre::error::Error> for Error {
+    fn from(value: lettre::error::Error) -> Self {
+        Self::Mail(value.to_string())
+    }
+}
+
+impl From<lettre::address::AddressError> for Error {
+    fn from(value: lettre::address::AddressError) -> Self {
+        Self::Mail(value.to_string())
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for Error {
+    fn from(value: lettre::transport::smtp::Error) -> Self {
+        Self::Mail(value.to_string())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Self::Webhook(value.to_string())
+    }
+}
+
+impl From<async_zip::error::ZipError> for Error {
+    fn from(value: async_zip::error::ZipError) -> Self {
+        Self::IO(value.to_string())
+    }
+}
+
+impl From<prometheus::Error> for Error {
+    fn from(value: prometheus::Error) -> Self {
+        Self::Misc(value.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Misc(value.to_string())
+    }
+}
+
+impl From<time::error::Format> for Error {
+    fn from(value: time::error::Format) -> Self {
+        Self::Misc(value.to_string())
+    }
+}
+
 impl From<BoxDynError> for Error {
     fn from(value: BoxDynError) -> Self {
         Self::Misc(value.to_string())
@@ -120,6 +215,7 @@ pub enum ErrorResponse {
     PoolNotFound,
     Rocket,
     AccessDenied,
+    PasswordChangeRequired,
     DoesNotExist,
     InvalidPagination,
     PageDoesNotExist,
@@ -127,6 +223,15 @@ pub enum ErrorResponse {
     ValidationErrors(validator::ValidationErrors),
     InvalidUploadState,
     InvalidContentRange,
+    Mail,
+    ShuttingDown,
+    RateLimited,
+    IdempotencyKeyConflict,
+    Webhook,
+    Telegram,
+    ArchiveTooLarge,
+    UploadChunkTooLarge,
+    UploadChunkSizeMismatch,
     Unknown,
 }
 
@@ -139,6 +244,7 @@ impl From<Error> for ErrorResponse {
             Error::PoolNotFound => Self::PoolNotFound,
             Error::Rocket(_) => Self::Rocket,
             Error::AccessDenied => Self::AccessDenied,
+            Error::PasswordChangeRequired => Self::PasswordChangeRequired,
             Error::DoesNotExist => Self::DoesNotExist,
             Error::InvalidPagination => Self::InvalidPagination,
             Error::PageDoesNotExist => Self::PageDoesNotExist,
@@ -146,13 +252,30 @@ impl From<Error> for ErrorResponse {
             Error::ValidationErrors(err) => Self::ValidationErrors(err),
             Error::InvalidUploadState => Self::InvalidUploadState,
             Error::InvalidContentRange => Self::InvalidContentRange,
+            Error::Mail(_) => Self::Mail,
+            Error::ShuttingDown => Self::ShuttingDown,
+            Error::RateLimited(_) => Self::RateLimited,
+            Error::IdempotencyKeyConflict => Self::IdempotencyKeyConflict,
+            Error::Webhook(_) => Self::Webhook,
+            Error::Telegram(_) => Self::Telegram,
+            Error::ArchiveTooLarge => Self::ArchiveTooLarge,
+            Error::UploadChunkTooLarge => Self::UploadChunkTooLarge,
+            Error::UploadChunkSizeMismatch => Self::UploadChunkSizeMismatch,
             Error::Unknown => Self::Unknown,
         }
     }
 }
 
+/// API requests get JSON error body instead of status-only response.
+fn is_api_request(request: &Request<'_>) -> bool {
+    request.uri().path().starts_with("/api/")
+        || request
+            .accept()
+            .is_some_and(|accept| accept.preferred().is_json())
+}
+
 impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
-    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'o> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
         debug!("Processing error {:?}", &self);
         let status_code = match self {
             Error::Misc(_) => Status::InternalServerError,
@@ -161,6 +284,7 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
             Error::PoolNotFound => Status::InternalServerError,
             Error::Rocket(_) => Status::InternalServerError,
             Error::AccessDenied => Status::Forbidden,
+            Error::PasswordChangeRequired => Status::Forbidden,
             Error::DoesNotExist => Status::NotFound,
             Error::InvalidPagination => Status::UnprocessableEntity,
             Error::PageDoesNotExist => Status::NotFound,
@@ -168,11 +292,68 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
             Error::ValidationErrors(_) => Status::UnprocessableEntity,
             Error::InvalidUploadState => Status::Conflict,
             Error::InvalidContentRange => Status::BadRequest,
+            Error::Mail(_) => Status::InternalServerError,
+            Error::ShuttingDown => Status::ServiceUnavailable,
+            Error::RateLimited(_) => Status::TooManyRequests,
+            Error::IdempotencyKeyConflict => Status::Conflict,
+            Error::Webhook(_) => Status::InternalServerError,
+            Error::Telegram(_) => Status::InternalServerError,
+            Error::ArchiveTooLarge => Status::PayloadTooLarge,
+            Error::UploadChunkTooLarge => Status::PayloadTooLarge,
+            Error::UploadChunkSizeMismatch => Status::BadRequest,
             Error::Unknown => Status::InternalServerError,
         };
         if status_code.code % 100 == 5 {
             log::error!("Internal server error: {:?}", &self);
         }
-        Err(status_code)
+
+        let retry_after = match self {
+            Error::ShuttingDown => Some(SHUTDOWN_RETRY_AFTER_SECONDS.to_string()),
+            Error::RateLimited(retry_after) => Some(retry_after.to_string()),
+            _ => None,
+        };
+        if is_api_request(request) {
+            let error_response = match ErrorResponse::from(self) {
+                ErrorResponse::ValidationErrors(mut errors) => {
+                    Locale::resolve(request).translate_validation_errors(&mut errors);
+                    ErrorResponse::ValidationErrors(errors)
+                }
+                error_response => error_response,
+            };
+            let mut response = Custom(status_code, Json(error_response)).respond_to(request)?;
+            if let Some(retry_after) = retry_after {
+                response.set_raw_header("Retry-After", retry_after);
+            }
+            Ok(response)
+        } else if let Some(retry_after) = retry_after {
+            Response::build()
+                .status(status_code)
+                .raw_header("Retry-After", retry_after)
+                .ok()
+        } else {
+            Err(status_code)
+        }
     }
 }
+
+/// Catcher for API routes, so that request guard failures also get JSON error body.
+#[catch(default)]
+pub fn api_default_catcher(status: Status, request: &Request<'_>) -> Custom<Json<ErrorResponse>> {
+    let authentication: &request::Outcome<Authentication, Error> =
+        request.local_cache(|| request::Outcome::Success(Authentication::Anonymous));
+    let error_response = match status.code {
+        403 if matches!(
+            authentication,
+            request::Outcome::Error((_, Error::PasswordChangeRequired))
+        ) =>
+        {
+            ErrorResponse::PasswordChangeRequired
+        }
+        401 | 403 => ErrorResponse::AccessDenied,
+        404 => ErrorResponse::DoesNotExist,
+        429 => ErrorResponse::RateLimited,
+        _ if status.class().is_server_error() => ErrorResponse::Unknown,
+        _ => ErrorResponse::Misc,
+    };
+    Custom(status, Json(error_response))
+}
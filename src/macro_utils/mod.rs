@@ -1,3 +1,5 @@
+use syn::{GenericArgument, PathArguments, Type};
+
 pub fn first_letter_to_uppercase(s: &str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -5,3 +7,77 @@ pub fn first_letter_to_uppercase(s: &str) -> String {
         Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
     }
 }
+
+/// Get `T` from `Vec<T>` type, returns `None` for other types.
+pub fn get_vec_item_type(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(type_path) => type_path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Vec" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+            GenericArgument::Type(item_type) => Some(item_type),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check if type is `Option<T>`.
+pub fn is_option_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| {
+                segment.ident == "Option"
+                    && matches!(segment.arguments, PathArguments::AngleBracketed(_))
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Check if type is `Secret<T>` or `Option<Secret<T>>`.
+pub fn is_secret_type(ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(type_path) => match type_path.path.segments.last() {
+            Some(segment) => segment,
+            None => return false,
+        },
+        _ => return false,
+    };
+    if segment.ident == "Secret" {
+        return true;
+    }
+    if segment.ident != "Option" {
+        return false;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(item_type)) => is_secret_type(item_type),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Convert `CamelCase` identifier to `kebab-case`.
+pub fn camel_case_to_kebab_case(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        if c.is_uppercase() {
+            if !result.is_empty() {
+                result.push('-');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
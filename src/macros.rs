@@ -14,7 +14,6 @@ use syn::{
     parse::{Parse, Parser},
     parse_macro_input,
     punctuated::Punctuated,
-    spanned::Spanned,
     token::{Comma, Paren},
     Attribute,
     Data::Struct,
@@ -22,7 +21,10 @@ use syn::{
     Type,
 };
 
-use macro_utils::first_letter_to_uppercase;
+use macro_utils::{
+    camel_case_to_kebab_case, first_letter_to_uppercase, get_vec_item_type, is_option_type,
+    is_secret_type,
+};
 
 fn parse_form_field_verbose_name_attribute(attribute: &Attribute) -> Option<String> {
     if attribute.path().is_ident("form_field_verbose_name") {
@@ -37,7 +39,7 @@ fn parse_form_field_verbose_name_attribute(attribute: &Attribute) -> Option<Stri
                 panic!("Attribute form_field_verbose_name requires string literal");
             }
         } else {
-            panic!("Attribute form_field_verbose_name should be in form of #[form_field_verbose_name = \"NAME\"]");
+            panic!("Attribute form_field_verbose_name should be in form of #[form_field_verbose_name = \"KEY\"]");
         }
     }
 
@@ -49,6 +51,9 @@ enum FieldType {
     Checkbox,
     Radio,
     RadioId,
+    Select,
+    SelectId,
+    MultiSelect,
     Date,
     Number,
     EMail,
@@ -58,14 +63,19 @@ enum FieldType {
     Password,
     Url,
     Hidden,
+    File,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 enum FieldProcessType {
     Regular,
     WrapSome,
+    RegularSecret,
+    WrapSomeSecret,
     ValueList,
     ValueListLoaded,
+    MultiValueList,
+    FileUpload,
 }
 
 impl FieldType {
@@ -74,6 +84,9 @@ impl FieldType {
             "Checkbox" => Some(FieldType::Checkbox),
             "Radio" => Some(FieldType::Radio),
             "RadioId" => Some(FieldType::RadioId),
+            "Select" => Some(FieldType::Select),
+            "SelectId" => Some(FieldType::SelectId),
+            "MultiSelect" => Some(FieldType::MultiSelect),
             "Date" => Some(FieldType::Date),
             "Number" => Some(FieldType::Number),
             "EMail" => Some(FieldType::EMail),
@@ -83,14 +96,19 @@ impl FieldType {
             "Password" => Some(FieldType::Password),
             "URL" => Some(FieldType::Url),
             "Hidden" => Some(FieldType::Hidden),
+            "File" => Some(FieldType::File),
             _ => None,
         }
     }
 
-    fn get_process_type(&self, is_optional: bool) -> FieldProcessType {
+    fn get_process_type(&self, is_optional: bool, is_secret: bool) -> FieldProcessType {
         match self {
-            FieldType::Radio => FieldProcessType::ValueList,
-            FieldType::RadioId => FieldProcessType::ValueListLoaded,
+            _ if is_secret && is_optional => FieldProcessType::RegularSecret,
+            _ if is_secret => FieldProcessType::WrapSomeSecret,
+            FieldType::Radio | FieldType::Select => FieldProcessType::ValueList,
+            FieldType::RadioId | FieldType::SelectId => FieldProcessType::ValueListLoaded,
+            FieldType::MultiSelect => FieldProcessType::MultiValueList,
+            FieldType::File => FieldProcessType::FileUpload,
             FieldType::Checkbox => FieldProcessType::Regular,
             _ if is_optional => FieldProcessType::Regular,
             _ => FieldProcessType::WrapSome,
@@ -102,6 +120,9 @@ impl FieldType {
             FieldType::Checkbox => "Checkbox",
             FieldType::Radio => "Radio",
             FieldType::RadioId => "Radio",
+            FieldType::Select => "Select",
+            FieldType::SelectId => "Select",
+            FieldType::MultiSelect => "MultiSelect",
             FieldType::Date => "Date",
             FieldType::Number => "Number",
             FieldType::EMail => "EMail",
@@ -111,6 +132,7 @@ impl FieldType {
             FieldType::Password => "Password",
             FieldType::Url => "URL",
             FieldType::Hidden => "Hidden",
+            FieldType::File => "File",
         }
     }
 }
@@ -124,6 +146,9 @@ impl Display for FieldType {
                 FieldType::Checkbox => "Checkbox",
                 FieldType::Radio => "Radio",
                 FieldType::RadioId => "RadioId",
+                FieldType::Select => "Select",
+                FieldType::SelectId => "SelectId",
+                FieldType::MultiSelect => "MultiSelect",
                 FieldType::Date => "Date",
                 FieldType::Number => "Number",
                 FieldType::EMail => "EMail",
@@ -133,6 +158,7 @@ impl Display for FieldType {
                 FieldType::Password => "Password",
                 FieldType::Url => "URL",
                 FieldType::Hidden => "Hidden",
+                FieldType::File => "File",
             }
         )
     }
@@ -157,9 +183,21 @@ fn parse_form_field_type_attribute(attribute: &Attribute) -> Option<FieldType> {
     None
 }
 
-fn parse_form_field_optiional_attribute(attribute: &Attribute) -> Option<()> {
+fn parse_form_field_required_attribute(attribute: &Attribute) -> Option<()> {
+    if let Meta::Path(path) = &attribute.meta {
+        if path.is_ident("form_field_required") {
+            Some(())
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+fn parse_form_field_multiple_attribute(attribute: &Attribute) -> Option<()> {
     if let Meta::Path(path) = &attribute.meta {
-        if path.is_ident("form_field_optional") {
+        if path.is_ident("form_field_multiple") {
             Some(())
         } else {
             None
@@ -169,13 +207,159 @@ fn parse_form_field_optiional_attribute(attribute: &Attribute) -> Option<()> {
     }
 }
 
+/// Parse number attribute value. Negative numbers are given as string literals (like `"-720"`),
+/// because attribute values can not be unary expressions.
+fn parse_number_literal(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            attrs: _,
+            lit: Lit::Int(value),
+        }) => value.base10_parse().ok(),
+        Expr::Lit(ExprLit {
+            attrs: _,
+            lit: Lit::Float(value),
+        }) => value.base10_parse().ok(),
+        Expr::Lit(ExprLit {
+            attrs: _,
+            lit: Lit::Str(value),
+        }) => value.value().parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_form_field_number_attribute(attribute: &Attribute, name: &str) -> Option<f64> {
+    if attribute.path().is_ident(name) {
+        if let Meta::NameValue(name_value) = &attribute.meta {
+            return Some(
+                parse_number_literal(&name_value.value)
+                    .unwrap_or_else(|| panic!("Attribute {} requires number literal", name)),
+            );
+        } else {
+            panic!(
+                "Attribute {} should be in form of #[{} = NUMBER]",
+                name, name
+            );
+        }
+    }
+    None
+}
+
+fn parse_form_field_string_attribute(attribute: &Attribute, name: &str) -> Option<String> {
+    if attribute.path().is_ident(name) {
+        if let Meta::NameValue(name_value) = &attribute.meta {
+            if let Expr::Lit(ExprLit {
+                attrs: _,
+                lit: Lit::Str(value),
+            }) = &name_value.value
+            {
+                return Some(value.value());
+            } else {
+                panic!("Attribute {} requires string literal", name);
+            }
+        } else {
+            panic!(
+                "Attribute {} should be in form of #[{} = \"TEXT\"]",
+                name, name
+            );
+        }
+    }
+    None
+}
+
+fn quote_optional_string(value: &Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value.to_string()) },
+        None => quote! { None },
+    }
+}
+
+fn quote_optional_number(value: Option<f64>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) if value < 0.0 => {
+            let literal = Literal::f64_unsuffixed(-value);
+            quote! { Some(-#literal) }
+        }
+        Some(value) => {
+            let literal = Literal::f64_unsuffixed(value);
+            quote! { Some(#literal) }
+        }
+        None => quote! { None },
+    }
+}
+
+/// Constraints of form field set by `form_field_required`, `form_field_min`, `form_field_max`,
+/// `form_field_step` and `form_field_pattern` attributes.
+#[derive(Clone, Debug, Default)]
+struct FieldConstraintAttributes {
+    is_required: bool,
+    min: Option<f64>,
+    max: Option<f64>,
+    step: Option<f64>,
+    pattern: Option<String>,
+}
+
+impl FieldConstraintAttributes {
+    /// Set constraint from attribute, other attributes are ignored.
+    fn parse_attribute(&mut self, attribute: &Attribute) {
+        if parse_form_field_required_attribute(attribute).is_some() {
+            self.is_required = true;
+        } else if let Some(min) = parse_form_field_number_attribute(attribute, "form_field_min") {
+            self.min = Some(min);
+        } else if let Some(max) = parse_form_field_number_attribute(attribute, "form_field_max") {
+            self.max = Some(max);
+        } else if let Some(step) = parse_form_field_number_attribute(attribute, "form_field_step") {
+            self.step = Some(step);
+        } else if let Some(pattern) =
+            parse_form_field_string_attribute(attribute, "form_field_pattern")
+        {
+            self.pattern = Some(pattern);
+        }
+    }
+
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        let is_required = self.is_required;
+        let min = quote_optional_number(self.min);
+        let max = quote_optional_number(self.max);
+        let step = quote_optional_number(self.step);
+        let pattern = quote_optional_string(&self.pattern);
+        quote! {
+            crate::utils::form_definition::FieldConstraints {
+                is_required: #is_required,
+                min: #min,
+                max: #max,
+                step: #step,
+                pattern: #pattern,
+            }
+        }
+    }
+}
+
+/// Derive form definition. Values of `form_submit_name` and `form_field_verbose_name` attributes
+/// are message keys, they are translated when form is rendered (text which is not a key is shown
+/// as is). Constraints from `form_field_required`, `form_field_min`, `form_field_max`,
+/// `form_field_step` and `form_field_pattern` attributes are rendered as HTML attributes, they do
+/// not replace validation. Values of `form_field_help` and `form_field_placeholder` attributes are
+/// message keys too. Field of `File` type should be `FileUploadTarget`, its input is set up with
+/// `form_field_accept` and `form_field_multiple` attributes. Fields with the same
+/// `form_field_group` message key are rendered together in fieldset. Fields of `Option<T>` type
+/// are optional, `None` is rendered as input without value.
+/// Input ids are prefixed with form struct name in kebab case.
 #[proc_macro_derive(
     FormWithDefinition,
     attributes(
         form_submit_name,
         form_field_verbose_name,
         form_field_type,
-        form_field_optional
+        form_field_required,
+        form_field_min,
+        form_field_max,
+        form_field_step,
+        form_field_pattern,
+        form_field_help,
+        form_field_placeholder,
+        form_field_accept,
+        form_field_multiple,
+        form_field_group
     )
 )]
 pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
@@ -199,7 +383,7 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         panic!("Attribute form_submit_name requires string literal");
                     }
                 } else {
-                    panic!("Attribute form_submit_name should be in form of #[form_submit_name = \"NAME\"]");
+                    panic!("Attribute form_submit_name should be in form of #[form_submit_name = \"KEY\"]");
                 }
             }
         }
@@ -219,7 +403,17 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         first_letter_to_uppercase(&str::replace(&name_string, "_", " "));
                     let field_type_raw = field.ty;
                     let mut field_type = FieldType::Text;
-                    let mut is_optional = false;
+                    // Optional fields are not wrapped in `Some`, their `None` is rendered as
+                    // empty input without value.
+                    let is_optional = is_option_type(&field_type_raw);
+                    // Secret fields are rendered with their value, it is exposed only here.
+                    let is_secret = is_secret_type(&field_type_raw);
+                    let mut constraints = FieldConstraintAttributes::default();
+                    let mut help = None;
+                    let mut placeholder = None;
+                    let mut accept = None;
+                    let mut is_multiple = false;
+                    let mut group = None;
                     for attribute in field.attrs {
                         if let Some(verbose_name_real) =
                             parse_form_field_verbose_name_attribute(&attribute)
@@ -229,13 +423,31 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                             parse_form_field_type_attribute(&attribute)
                         {
                             field_type = field_type_real;
-                        } else if parse_form_field_optiional_attribute(&attribute).is_some() {
-                            is_optional = true;
+                        } else if let Some(help_real) =
+                            parse_form_field_string_attribute(&attribute, "form_field_help")
+                        {
+                            help = Some(help_real);
+                        } else if let Some(placeholder_real) =
+                            parse_form_field_string_attribute(&attribute, "form_field_placeholder")
+                        {
+                            placeholder = Some(placeholder_real);
+                        } else if let Some(accept_real) =
+                            parse_form_field_string_attribute(&attribute, "form_field_accept")
+                        {
+                            accept = Some(accept_real);
+                        } else if parse_form_field_multiple_attribute(&attribute).is_some() {
+                            is_multiple = true;
+                        } else if let Some(group_real) =
+                            parse_form_field_string_attribute(&attribute, "form_field_group")
+                        {
+                            group = Some(group_real);
+                        } else {
+                            constraints.parse_attribute(&attribute);
                         }
                     }
 
                     field_args.push((
-                        field_type.get_process_type(is_optional),
+                        field_type.get_process_type(is_optional, is_secret),
                         ident,
                         LitStr::new(&verbose_name_string, Span::call_site()),
                         LitStr::new(&name_string, Span::call_site()),
@@ -244,6 +456,12 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                             Span::call_site(),
                         ),
                         field_type_raw,
+                        constraints.to_tokens(),
+                        quote_optional_string(&help),
+                        quote_optional_string(&placeholder),
+                        quote_optional_string(&accept),
+                        is_multiple,
+                        group,
                     ));
                 }
             }
@@ -252,7 +470,8 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
         let field_expressions: Vec<proc_macro2::TokenStream> = field_args.iter()
             .map(|(
                 process_type,
-                field_ident, verbose_name_literal, name_literal, field_type_ident, field_type_raw
+                field_ident, verbose_name_literal, name_literal, field_type_ident, field_type_raw,
+                constraints, help, placeholder, accept, is_multiple, _group
             )|
             match process_type {
                 FieldProcessType::WrapSome => quote! {
@@ -261,6 +480,9 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         verbose_name: #verbose_name_literal.to_string(),
                         field_type: crate::utils::form_definition::FieldData::#field_type_ident(Some(self.#field_ident.clone())),
                         errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
                     }
                 },
                 FieldProcessType::Regular => quote! {
@@ -269,6 +491,31 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         verbose_name: #verbose_name_literal.to_string(),
                         field_type: crate::utils::form_definition::FieldData::#field_type_ident(self.#field_ident.clone()),
                         errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
+                    }
+                },
+                FieldProcessType::WrapSomeSecret => quote! {
+                    crate::utils::form_definition::FieldDefinition {
+                        name: #name_literal.to_string(),
+                        verbose_name: #verbose_name_literal.to_string(),
+                        field_type: crate::utils::form_definition::FieldData::#field_type_ident(Some(self.#field_ident.expose().clone())),
+                        errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
+                    }
+                },
+                FieldProcessType::RegularSecret => quote! {
+                    crate::utils::form_definition::FieldDefinition {
+                        name: #name_literal.to_string(),
+                        verbose_name: #verbose_name_literal.to_string(),
+                        field_type: crate::utils::form_definition::FieldData::#field_type_ident(self.#field_ident.as_ref().map(|value| value.expose().clone())),
+                        errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
                     }
                 },
                 FieldProcessType::ValueList => quote! {
@@ -277,6 +524,9 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         verbose_name: #verbose_name_literal.to_string(),
                         field_type: crate::utils::form_definition::FieldData::#field_type_ident(#field_type_raw::get_options(), Some(self.#field_ident.clone().get_option())),
                         errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
                     }
                 },
                 FieldProcessType::ValueListLoaded => quote! {
@@ -285,19 +535,87 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
                         verbose_name: #verbose_name_literal.to_string(),
                         field_type: crate::utils::form_definition::FieldData::#field_type_ident(self.#field_ident.values.clone(), self.#field_ident.value.clone()),
                         errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
+                    }
+                },
+                FieldProcessType::MultiValueList => {
+                    let item_type = get_vec_item_type(field_type_raw)
+                        .expect("MultiSelect field should have Vec type");
+                    quote! {
+                        crate::utils::form_definition::FieldDefinition {
+                            name: #name_literal.to_string(),
+                            verbose_name: #verbose_name_literal.to_string(),
+                            field_type: crate::utils::form_definition::FieldData::#field_type_ident(#item_type::get_options(), self.#field_ident.iter().cloned().map(#item_type::get_option).collect()),
+                            errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                            constraints: #constraints,
+                            help: #help,
+                            placeholder: #placeholder,
+                        }
                     }
                 }
+                FieldProcessType::FileUpload => quote! {
+                    crate::utils::form_definition::FieldDefinition {
+                        name: #name_literal.to_string(),
+                        verbose_name: #verbose_name_literal.to_string(),
+                        field_type: crate::utils::form_definition::FieldData::#field_type_ident {
+                            accept: #accept,
+                            multiple: #is_multiple,
+                            target: self.#field_ident.clone(),
+                        },
+                        errors: (*field_errors.get(#name_literal).unwrap_or(&&vec![])).clone(),
+                        constraints: #constraints,
+                        help: #help,
+                        placeholder: #placeholder,
+                    }
+                },
             })
             .collect();
 
+        // Fields are split into ungrouped ones and groups, groups are ordered by first field.
+        let mut ungrouped_field_expressions = Vec::new();
+        let mut groups: Vec<(String, Vec<proc_macro2::TokenStream>)> = Vec::new();
+        for (field_arg, field_expression) in field_args.iter().zip(field_expressions) {
+            match &field_arg.11 {
+                None => ungrouped_field_expressions.push(field_expression),
+                Some(group) => match groups.iter_mut().find(|(name, _)| name == group) {
+                    Some((_, group_field_expressions)) => {
+                        group_field_expressions.push(field_expression)
+                    }
+                    None => groups.push((group.clone(), vec![field_expression])),
+                },
+            }
+        }
+        let group_names: Vec<LitStr> = groups
+            .iter()
+            .map(|(name, _)| LitStr::new(name, Span::call_site()))
+            .collect();
+        let group_field_expressions: Vec<Vec<proc_macro2::TokenStream>> = groups
+            .into_iter()
+            .map(|(_, expressions)| expressions)
+            .collect();
+        // Input ids are prefixed with form name, so that several forms can be on one page.
+        let id_prefix = LitStr::new(&camel_case_to_kebab_case(&name.to_string()), name.span());
+        let definition_expression = quote! {
+            let field_errors = errors.field_errors();
+            let fields = vec![#(#ungrouped_field_expressions),*];
+            let groups: Vec<(String, Vec<crate::utils::form_definition::FieldDefinition>)> = vec![
+                #((#group_names.to_string(), vec![#(#group_field_expressions),*])),*
+            ];
+            let form_errors = crate::utils::form_definition::get_form_errors(
+                &field_errors,
+                fields.iter().chain(groups.iter().flat_map(|(_, fields)| fields.iter())),
+            );
+        };
+
         let result;
         if let Some(submit_name_real) = submit_name {
             result = quote! {
                 impl #generics crate::utils::form_definition::FormWithDefinition for #name #generics {
                     fn get_definition(&self, errors: validator::ValidationErrors) -> crate::utils::form_definition::FormDefinition {
-                        let field_errors = errors.field_errors();
-                        let fields = vec![#(#field_expressions),*];
-                        return FormDefinition { fields, submit_name: Some(#submit_name_real.to_string()) };
+                        #definition_expression
+                        return FormDefinition { fields, groups, form_errors, submit_name: Some(#submit_name_real.to_string()), id_prefix: Some(#id_prefix.to_string()) };
                     }
                 }
             };
@@ -305,9 +623,8 @@ pub fn derive_form_with_definition(form: TokenStream) -> TokenStream {
             result = quote! {
                 impl #generics crate::utils::form_definition::FormWithDefinition for #name #generics {
                     fn get_definition(&self, errors: validator::ValidationErrors) -> crate::utils::form_definition::FormDefinition {
-                        let field_errors = errors.field_errors();
-                        let fields = vec![#(#field_expressions),*];
-                        return FormDefinition { fields, submit_name: None };
+                        #definition_expression
+                        return FormDefinition { fields, groups, form_errors, submit_name: None, id_prefix: Some(#id_prefix.to_string()) };
                     }
                 }
             };
@@ -342,163 +659,250 @@ pub fn form_with_csrf(_args: TokenStream, form: TokenStream) -> TokenStream {
     }
 }
 
-fn parse_extra_validated_attribute(attribute: &Attribute) -> Option<ExprPath> {
-    if attribute.path().is_ident("extra_validated") {
-        if let Meta::List(list) = &attribute.meta {
-            Some(
-                syn::parse2(list.tokens.clone())
-                    .expect("Attribute extra_validated requires type path arg"),
-            )
+/// Arguments of `extra_validated` attribute: type of ID set request guard and optional name of its
+/// method which gets ID set for this field from raw form.
+struct ExtraValidatedArgs {
+    id_set_type: ExprPath,
+    loader: Option<Ident>,
+}
+
+impl Parse for ExtraValidatedArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let id_set_type = input.parse()?;
+        let loader = if input.is_empty() {
+            None
         } else {
-            panic!("Attribute extra_validated should be in form of #[extra_validated(\"TYPE\")");
+            let _comma: Comma = input.parse()?;
+            let name: Ident = input.parse()?;
+            if name != "loader" {
+                return Err(syn::Error::new(name.span(), "expected `loader = METHOD`"));
+            }
+            let _eq: Token![=] = input.parse()?;
+            Some(input.parse()?)
+        };
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens in extra_validated attribute"));
         }
+        Ok(ExtraValidatedArgs {
+            id_set_type,
+            loader,
+        })
+    }
+}
+
+fn parse_extra_validated_attribute(
+    attribute: &Attribute,
+) -> Option<syn::Result<ExtraValidatedArgs>> {
+    if attribute.path().is_ident("extra_validated") {
+        Some(match &attribute.meta {
+            Meta::List(list) => syn::parse2(list.tokens.clone()),
+            _ => Err(syn::Error::new_spanned(
+                attribute,
+                "attribute extra_validated should be in form of #[extra_validated(TYPE)] or #[extra_validated(TYPE, loader = METHOD)]",
+            )),
+        })
     } else {
         None
     }
 }
 
+fn is_id_field_type(field_type: &Type) -> bool {
+    match field_type {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "IdField")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Derive raw form struct and request data guard for form with fields loaded from ID sets. Field
+/// with `#[extra_validated(TYPE)]` attribute should have `IdField` type, its value is checked
+/// against ID set of `TYPE`, which is loaded as request guard. With `#[extra_validated(TYPE,
+/// loader = METHOD)]` ID set is got by calling `METHOD` on guard with raw form as argument, so
+/// options of field can depend on other fields. Fields can use the same or different ID set
+/// types, each type is loaded once.
 #[proc_macro_derive(RawForm, attributes(extra_validated))]
 #[allow(clippy::redundant_clone)]
 pub fn generate_raw_form(form: TokenStream) -> TokenStream {
     let input = parse_macro_input!(form as DeriveInput);
+    match generate_raw_form_impl(input) {
+        Ok(result) => result.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
 
+fn generate_raw_form_impl(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = input.ident.clone();
     let name_raw = Ident::new(&(input.ident.to_string() + "Raw"), input.ident.span());
     let vis = input.vis.clone();
 
-    match &input.data {
-        syn::Data::Struct(struct_data) => {
-            if let syn::Fields::Named(fields) = &struct_data.fields {
-                let mut extra_validated_field_idents = Vec::new();
-                let mut regular_field_idents = Vec::new();
-                let mut raw_fields = Vec::new();
-                let mut field_converters = Vec::new();
-                let mut id_set_types = Vec::new();
-                let mut id_set_args = Vec::new();
-
-                for field in fields.named.iter() {
-                    let field_copy = field.clone();
-                    let vis = field_copy.vis;
-                    let ident = field_copy.ident.unwrap();
-                    let extra_validated_attrs: Vec<ExprPath> = field
-                        .attrs
-                        .iter()
-                        .filter_map(parse_extra_validated_attribute)
-                        .collect();
-                    if extra_validated_attrs.len() > 1 {
-                        panic!("Field can not have more than one extra_validated attribute");
-                    }
-                    let extra_validated_attr = extra_validated_attrs.first().cloned();
-
-                    match extra_validated_attr {
-                        Some(id_set_type) => {
-                            extra_validated_field_idents.push(ident.clone());
-                            raw_fields.push(quote! {#vis #ident: Option<String>});
-
-                            let id_set_index = id_set_types
-                                .iter()
-                                .position(|id_set_at| id_set_at == &id_set_type)
-                                .unwrap_or_else(|| {
-                                    let index = id_set_types.len();
-                                    id_set_types.push(id_set_type.clone());
-                                    id_set_args.push(Ident::new(
-                                        &format!("id_set_arg_{}", index),
-                                        id_set_type.span(),
-                                    ));
-                                    index
-                                });
-                            let id_set_arg = Ident::new(
-                                &format!("id_set_arg_{}", id_set_index),
-                                id_set_type.span(),
-                            );
-                            let field_name = LitStr::new(&ident.to_string(), ident.span());
-
-                            field_converters.push(quote! {
-                                let (#ident, has_err) = crate::utils::form_extra_validation::IdField::load(raw_form.#ident, #id_set_arg);
-                                if has_err {
-                                    errors.add(
-                                        #field_name,
-                                        ValidationError {
-                                            code: Cow::from("invalid_id"),
-                                            message: Some(Cow::from("некорректное значение")),
-                                            params: HashMap::new(),
-                                        },
-                                    )
-                                }
-                            });
-                        }
-                        None => {
-                            regular_field_idents.push(ident.clone());
-                            let mut field_copy = field.clone();
-                            field_copy.attrs = vec![];
-                            raw_fields.push(quote! {#vis #field_copy});
-                            field_converters.push(quote! {
-                                let #ident = raw_form.#ident;
-                            });
-                        }
-                    }
+    let fields = match &input.data {
+        syn::Data::Struct(struct_data) => match &struct_data.fields {
+            syn::Fields::Named(fields) => fields,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "RawForm can be derived for structs with named fields only",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "RawForm can be derived for structs only",
+            ))
+        }
+    };
+
+    let mut extra_validated_field_idents = Vec::new();
+    let mut regular_field_idents = Vec::new();
+    let mut raw_fields = Vec::new();
+    let mut extra_validated_field_converters = Vec::new();
+    let mut regular_field_converters = Vec::new();
+    let mut id_set_types = Vec::new();
+    let mut id_set_args = Vec::new();
+
+    for field in fields.named.iter() {
+        let field_vis = &field.vis;
+        let ident = field.ident.clone().unwrap();
+        let mut extra_validated_attrs = Vec::new();
+        for attribute in field.attrs.iter() {
+            if let Some(args) = parse_extra_validated_attribute(attribute) {
+                if !extra_validated_attrs.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        attribute,
+                        "field can not have more than one extra_validated attribute",
+                    ));
                 }
+                extra_validated_attrs.push(args?);
+            }
+        }
 
-                quote!(
-                    #[derive(Clone, Debug, rocket::FromForm, CheckCSRF)]
-                    #vis struct #name_raw {
-                        #(#raw_fields),*
+        match extra_validated_attrs.pop() {
+            Some(ExtraValidatedArgs {
+                id_set_type,
+                loader,
+            }) => {
+                if !is_id_field_type(&field.ty) {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "field with extra_validated attribute should have IdField type",
+                    ));
+                }
+                extra_validated_field_idents.push(ident.clone());
+                raw_fields.push(quote! {#field_vis #ident: Option<String>});
+
+                // Types are compared by tokens, so that the same type gets one guard argument.
+                let id_set_type_string = quote!(#id_set_type).to_string();
+                let id_set_index = id_set_types
+                    .iter()
+                    .position(|id_set_type_other: &ExprPath| {
+                        quote!(#id_set_type_other).to_string() == id_set_type_string
+                    })
+                    .unwrap_or_else(|| {
+                        let index = id_set_types.len();
+                        id_set_types.push(id_set_type.clone());
+                        id_set_args.push(Ident::new(
+                            &format!("id_set_arg_{}", index),
+                            Span::call_site(),
+                        ));
+                        index
+                    });
+                let id_set_arg = &id_set_args[id_set_index];
+                let id_set_expr = match loader {
+                    Some(loader) => quote!(&#id_set_arg.#loader(&raw_form)),
+                    None => quote!(#id_set_arg),
+                };
+                let field_name = LitStr::new(&ident.to_string(), ident.span());
+
+                extra_validated_field_converters.push(quote! {
+                    let (#ident, has_err) = crate::utils::form_extra_validation::IdField::load(
+                        raw_form.#ident.clone(),
+                        #id_set_expr,
+                    );
+                    if has_err {
+                        errors.add(
+                            #field_name,
+                            validator::ValidationError {
+                                code: std::borrow::Cow::from("invalid_id"),
+                                message: Some(std::borrow::Cow::from("validation.invalid_id")),
+                                params: std::collections::HashMap::new(),
+                            },
+                        )
                     }
+                });
+            }
+            None => {
+                regular_field_idents.push(ident.clone());
+                let mut field_copy = field.clone();
+                field_copy.attrs = vec![];
+                raw_fields.push(quote! {#field_copy});
+                regular_field_converters.push(quote! {
+                    let #ident = raw_form.#ident;
+                });
+            }
+        }
+    }
 
-                    impl #name {
-                        fn try_load(
-                            raw_form: #name_raw, #(#id_set_args: &#id_set_types),*
-                        ) -> crate::utils::form_extra_validation::ExtraValidatedForm<Self> {
-                            let mut errors = validator::ValidationErrors::new();
-                            #(#field_converters)*
-                            crate::utils::form_extra_validation::ExtraValidatedForm(
-                                Self {
-                                    #(#extra_validated_field_idents,)*
-                                    #(#regular_field_idents,)*
-                                },
-                                errors
-                            )
-                        }
-                    }
+    Ok(quote!(
+        #[derive(Clone, Debug, rocket::FromForm, CheckCSRF)]
+        #vis struct #name_raw {
+            #(#raw_fields),*
+        }
 
-                    #[rocket::async_trait]
-                    impl<'r> rocket::data::FromData<'r> for crate::utils::form_extra_validation::ExtraValidatedForm<#name> {
-                        type Error = Option<rocket::form::Errors<'r>>;
-
-                        async fn from_data(
-                            req: &'r rocket::request::Request<'_>, data: rocket::data::Data<'r>
-                        ) -> rocket::data::Outcome<'r, Self> {
-                            #(
-                                let #id_set_args: #id_set_types = match req.guard().await {
-                                    rocket::request::Outcome::Success(id_set) => id_set,
-                                    rocket::request::Outcome::Error((status, _)) => {return rocket::data::Outcome::Error((status, None));},
-                                    rocket::request::Outcome::Forward(status) => {return rocket::data::Outcome::Forward((data, status));},
-                                };
-                            )*
-
-                            let raw_form_result: rocket::data::Outcome<
-                                'r, crate::utils::csrf::CSRFProtectedForm<#name_raw>
-                            > =
-                                rocket::data::FromData::from_data(req, data).await;
-
-                            match raw_form_result {
-                                rocket::data::Outcome::Success(raw_form) => {
-                                    rocket::data::Outcome::Success(#name::try_load((*raw_form).clone(), #(&#id_set_args),*))
-                                },
-                                rocket::data::Outcome::Error((status, err)) => {
-                                    rocket::data::Outcome::Error((status, Some(err)))
-                                }
-                                rocket::data::Outcome::Forward((data, status)) => rocket::data::Outcome::Forward((data, status)),
-                            }
-                        }
+        impl #name {
+            fn try_load(
+                raw_form: #name_raw, #(#id_set_args: &#id_set_types),*
+            ) -> crate::utils::form_extra_validation::ExtraValidatedForm<Self> {
+                let mut errors = validator::ValidationErrors::new();
+                // ID fields are loaded first, because loaders can borrow whole raw form.
+                #(#extra_validated_field_converters)*
+                #(#regular_field_converters)*
+                crate::utils::form_extra_validation::ExtraValidatedForm(
+                    Self {
+                        #(#extra_validated_field_idents,)*
+                        #(#regular_field_idents,)*
+                    },
+                    errors
+                )
+            }
+        }
+
+        #[rocket::async_trait]
+        impl<'r> rocket::data::FromData<'r> for crate::utils::form_extra_validation::ExtraValidatedForm<#name> {
+            type Error = Option<rocket::form::Errors<'r>>;
+
+            async fn from_data(
+                req: &'r rocket::request::Request<'_>, data: rocket::data::Data<'r>
+            ) -> rocket::data::Outcome<'r, Self> {
+                #(
+                    let #id_set_args: #id_set_types = match req.guard().await {
+                        rocket::request::Outcome::Success(id_set) => id_set,
+                        rocket::request::Outcome::Error((status, _)) => {return rocket::data::Outcome::Error((status, None));},
+                        rocket::request::Outcome::Forward(status) => {return rocket::data::Outcome::Forward((data, status));},
+                    };
+                )*
+
+                let raw_form_result: rocket::data::Outcome<
+                    'r, crate::utils::csrf::CSRFProtectedForm<#name_raw>
+                > =
+                    rocket::data::FromData::from_data(req, data).await;
+
+                match raw_form_result {
+                    rocket::data::Outcome::Success(raw_form) => {
+                        rocket::data::Outcome::Success(#name::try_load((*raw_form).clone(), #(&#id_set_args),*))
+                    },
+                    rocket::data::Outcome::Error((status, err)) => {
+                        rocket::data::Outcome::Error((status, Some(err)))
                     }
-                ).into()
-            } else {
-                panic!("`generate_raw_form` has to be used with named item structs ")
+                    rocket::data::Outcome::Forward((data, status)) => rocket::data::Outcome::Forward((data, status)),
+                }
             }
         }
-        _ => panic!("`generate_raw_form` has to be used with structs "),
-    }
+    ))
 }
 
 #[proc_macro_derive(CheckCSRF)]
@@ -524,6 +928,8 @@ pub fn derive_check_csrf(form: TokenStream) -> TokenStream {
 
 struct FormMethodInput {
     mode: Ident,
+    /// Message expression of `confirm` mode, given in parentheses after mode name.
+    confirm_message: Option<Expr>,
     _comma0: Comma,
     template_name: Type,
     _comma1: Comma,
@@ -533,7 +939,7 @@ struct FormMethodInput {
     _comma3: Comma,
     url: Literal,
     _comma4: Comma,
-    breadcrumbs: Expr,
+    page_meta: Expr,
     _comma5: Comma,
     _paren1: Paren,
     guard_types: Punctuated<Type, Comma>,
@@ -542,11 +948,22 @@ struct FormMethodInput {
     extra_args: Punctuated<FnArg, Comma>,
     _comma7: Comma,
     pass_authentication: LitBool,
+    success_message: Option<LitStr>,
+    /// Redirect target expression, given as `redirect(...)`, it replaces redirect returned by
+    /// `process`.
+    success_redirect: Option<Expr>,
 }
 
 impl Parse for FormMethodInput {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mode = input.parse()?;
+        let confirm_message = if input.peek(Paren) {
+            let content0;
+            parenthesized!(content0 in input);
+            Some(content0.parse()?)
+        } else {
+            None
+        };
 
         let _comma0 = input.parse()?;
         let template_name = input.parse()?;
@@ -561,7 +978,7 @@ impl Parse for FormMethodInput {
         let url = input.parse()?;
 
         let _comma4 = input.parse()?;
-        let breadcrumbs = input.parse()?;
+        let page_meta = input.parse()?;
 
         let _comma5 = input.parse()?;
         let content1;
@@ -576,8 +993,26 @@ impl Parse for FormMethodInput {
         let _comma7 = input.parse()?;
         let pass_authentication = input.parse()?;
 
+        let mut success_message = None;
+        let mut success_redirect = None;
+        while !input.is_empty() {
+            let _comma: Comma = input.parse()?;
+            if input.peek(LitStr) {
+                success_message = Some(input.parse()?);
+            } else {
+                let name: Ident = input.parse()?;
+                if name != "redirect" {
+                    return Err(syn::Error::new(name.span(), "expected `redirect(...)`"));
+                }
+                let content;
+                parenthesized!(content in input);
+                success_redirect = Some(content.parse()?);
+            }
+        }
+
         Ok(FormMethodInput {
             mode,
+            confirm_message,
             _comma0,
             template_name,
             _comma1,
@@ -587,7 +1022,7 @@ impl Parse for FormMethodInput {
             _comma3,
             url,
             _comma4,
-            breadcrumbs,
+            page_meta,
             _comma5,
             _paren1,
             guard_types,
@@ -596,6 +1031,8 @@ impl Parse for FormMethodInput {
             extra_args,
             _comma7,
             pass_authentication,
+            success_message,
+            success_redirect,
         })
     }
 }
@@ -605,6 +1042,7 @@ enum Mode {
     Simple,
     Edit,
     EditExtra,
+    Confirm,
 }
 
 impl TryFrom<Ident> for Mode {
@@ -615,6 +1053,7 @@ impl TryFrom<Ident> for Mode {
             "simple" => Ok(Self::Simple),
             "edit" => Ok(Self::Edit),
             "edit_extra" => Ok(Self::EditExtra),
+            "confirm" => Ok(Self::Confirm),
             _ => Err(()),
         }
     }
@@ -625,6 +1064,12 @@ pub fn form_get_and_post(args: TokenStream) -> TokenStream {
     let args_input = parse_macro_input!(args as FormMethodInput);
 
     let mode: Mode = args_input.mode.try_into().unwrap();
+    let confirm_message = match (&mode, args_input.confirm_message) {
+        (Mode::Confirm, Some(confirm_message)) => Some(confirm_message),
+        (Mode::Confirm, None) => panic!("form_get_and_post! confirm mode requires message"),
+        (_, Some(_)) => panic!("form_get_and_post! accepts message in confirm mode only"),
+        (_, None) => None,
+    };
     let url = args_input.url;
     let function_name_get = Ident::new(
         &(args_input.function_name.to_string() + "_get"),
@@ -636,7 +1081,7 @@ pub fn form_get_and_post(args: TokenStream) -> TokenStream {
     );
     let template_type_name = args_input.template_name;
     let form_type_name = args_input.form_type;
-    let breadcrumbs = args_input.breadcrumbs;
+    let page_meta = args_input.page_meta;
     let guard_types: Vec<Type> = args_input.guard_types.iter().cloned().collect();
     let guard_args: Vec<Ident> = (0..guard_types.len())
         .map(|i| Ident::new(&format!("_guard_{}", i), Span::call_site()))
@@ -653,10 +1098,57 @@ pub fn form_get_and_post(args: TokenStream) -> TokenStream {
         })
         .collect();
     let pass_authentication = args_input.pass_authentication.value();
+    // Optional flash message shown after successful form processing.
+    let flash_arg = args_input
+        .success_message
+        .as_ref()
+        .map(|_| quote!(flash_cookies: &rocket::http::CookieJar<'_>,));
+    let set_flash_expr = args_input.success_message.map(|success_message| {
+        quote!(
+            crate::utils::flash::set_flash_message(
+                flash_cookies,
+                crate::utils::flash::FlashMessage::success(#success_message),
+            );
+        )
+    });
+
+    // With redirect target set, `next` query parameter can override it with local path.
+    let post_url = if args_input.success_redirect.is_some() {
+        let url_value = syn::parse_str::<LitStr>(&url.to_string())
+            .expect("form_get_and_post! URL should be string literal")
+            .value();
+        let separator = if url_value.contains('?') { "&" } else { "?" };
+        Literal::string(&format!("{}{}<next>", url_value, separator))
+    } else {
+        url.clone()
+    };
+    let next_arg = args_input
+        .success_redirect
+        .as_ref()
+        .map(|_| quote!(next: Option<&str>,));
+    let success_arm = match args_input.success_redirect {
+        Some(success_redirect) => quote!(
+            Either::Left(_) => {
+                #set_flash_expr
+                Ok(Either::Left(
+                    match next.and_then(crate::utils::redirect::get_local_redirect_path) {
+                        Some(next) => rocket::response::Redirect::to(next.to_string()),
+                        None => rocket::response::Redirect::to(#success_redirect),
+                    },
+                ))
+            }
+        ),
+        None => quote!(
+            Either::Left(redirect) => {
+                #set_flash_expr
+                Ok(Either::Left(redirect))
+            }
+        ),
+    };
 
     let load_expr = if pass_authentication {
         quote!(
-            #form_type_name::load(#(#extra_arg_names,)* &user, &csrf_token.authenticity_token(), pool)
+            #form_type_name::load(#(#extra_arg_names,)* &context.user, &csrf_token.authenticity_token(), pool)
         )
     } else {
         quote!(
@@ -665,139 +1157,147 @@ pub fn form_get_and_post(args: TokenStream) -> TokenStream {
     };
     let process_expr = if pass_authentication {
         quote!(
-            form.process(#(#extra_arg_names,)* &user, pool)
+            form.process(#(#extra_arg_names,)* &context.user, pool)
         )
     } else {
         quote!(
             form.process(#(#extra_arg_names,)* pool)
         )
     };
+    // In confirm mode form type has no data, its associated functions check that action is
+    // possible and perform it.
+    let (check_expr, action_expr) = if pass_authentication {
+        (
+            quote!(#form_type_name::check(#(#extra_arg_names,)* &context.user, pool)),
+            quote!(#form_type_name::process(#(#extra_arg_names,)* &context.user, pool)),
+        )
+    } else {
+        (
+            quote!(#form_type_name::check(#(#extra_arg_names,)* pool)),
+            quote!(#form_type_name::process(#(#extra_arg_names,)* pool)),
+        )
+    };
 
     let result = match mode {
         Mode::Simple => quote!(
+            // Extra arguments are used by POST handler, GET handler takes them too, so that both
+            // routes are guarded in the same way.
             #[get(#url)]
+            #[allow(clippy::too_many_arguments)]
+            #[allow(unused_variables)]
             pub fn #function_name_get<'a>(
-                user: Authentication,
                 csrf_token: CsrfToken,
-                asset_context: &'a rocket::State<AssetContext>,
+                context: PageContext<'a>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
             ) -> #template_type_name<'a> {
                 #template_type_name {
                     form: #form_type_name::new(&csrf_token.authenticity_token())
                         .get_definition(ValidationErrors::new()),
-                    user: user.into(),
-                    asset_context,
-                    breadcrumbs: #breadcrumbs,
+                    context: context.with_meta(#page_meta),
                 }
             }
 
-            #[post(#url, data = "<form>")]
+            #[post(#post_url, data = "<form>")]
+            #[allow(clippy::too_many_arguments)]
             pub async fn #function_name_post<'a, 'b>(
-                user: Authentication,
                 pool: &'a rocket::State<Pool<Postgres>>,
                 form: CSRFProtectedForm<#form_type_name>,
-                asset_context: &'b rocket::State<AssetContext>,
+                context: PageContext<'b>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
+                #flash_arg
+                #next_arg
             ) -> Result<rocket::Either<rocket::response::Redirect, #template_type_name<'b>>, crate::error::Error> {
                 match form.validate() {
                     Ok(()) => {
                         match #process_expr.await? {
-                            Either::Left(redirect) => Ok(Either::Left(redirect)),
+                            #success_arm
                             Either::Right(errors) => Ok(Either::Right(#template_type_name {
-                                user: user.into(),
                                 form: form.clear_sensitive().get_definition(errors),
-                                asset_context,
-                                breadcrumbs: #breadcrumbs,
+                                context: context.with_meta(#page_meta),
                             })),
                         }
                     }
                     Err(errors) => Ok(rocket::Either::Right(#template_type_name {
-                        user: user.into(),
                         form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: #breadcrumbs,
+                        context: context.with_meta(#page_meta),
                     })),
                 }
             }
         ),
         Mode::Edit => quote!(
             #[get(#url)]
+            #[allow(clippy::too_many_arguments)]
             pub async fn #function_name_get<'a, 'b>(
-                user: Authentication,
                 pool: &'a rocket::State<Pool<Postgres>>,
                 csrf_token: CsrfToken,
-                asset_context: &'b rocket::State<AssetContext>,
+                context: PageContext<'b>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
             ) -> Result<#template_type_name<'b>, crate::error::Error>{
                 Ok(#template_type_name {
                     form: #load_expr.await?
                         .get_definition(ValidationErrors::new()),
-                    user: user.into(),
-                    asset_context,
-                    breadcrumbs: #breadcrumbs,
+                    context: context.with_meta(#page_meta),
                 })
             }
 
-            #[post(#url, data = "<form>")]
+            #[post(#post_url, data = "<form>")]
+            #[allow(clippy::too_many_arguments)]
             pub async fn #function_name_post<'a, 'b>(
-                user: Authentication,
                 pool: &'a rocket::State<Pool<Postgres>>,
                 form: CSRFProtectedForm<#form_type_name>,
-                asset_context: &'b rocket::State<AssetContext>,
+                context: PageContext<'b>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
+                #flash_arg
+                #next_arg
             ) -> Result<rocket::Either<rocket::response::Redirect, #template_type_name<'b>>, crate::error::Error> {
                 match form.validate() {
                     Ok(()) => {
                         match #process_expr.await? {
-                            Either::Left(redirect) => Ok(Either::Left(redirect)),
+                            #success_arm
                             Either::Right(errors) => Ok(Either::Right(#template_type_name {
-                                user: user.into(),
                                 form: form.clear_sensitive().get_definition(errors),
-                                asset_context,
-                                breadcrumbs: #breadcrumbs,
+                                context: context.with_meta(#page_meta),
                             })),
                         }
                     }
                     Err(errors) => Ok(Either::Right(#template_type_name {
-                        user: user.into(),
                         form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: #breadcrumbs,
+                        context: context.with_meta(#page_meta),
                     })),
                 }
             }
         ),
         Mode::EditExtra => quote!(
             #[get(#url)]
+            #[allow(clippy::too_many_arguments)]
             pub async fn #function_name_get<'a, 'b>(
-                user: Authentication,
                 pool: &'a rocket::State<Pool<Postgres>>,
                 csrf_token: CsrfToken,
-                asset_context: &'b rocket::State<AssetContext>,
+                context: PageContext<'b>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
             ) -> Result<#template_type_name<'b>, crate::error::Error>{
                 Ok(#template_type_name {
                     form: #load_expr.await?
                         .get_definition(ValidationErrors::new()),
-                    user: user.into(),
-                    asset_context,
-                    breadcrumbs: #breadcrumbs,
+                    context: context.with_meta(#page_meta),
                 })
             }
 
-            #[post(#url, data = "<form>")]
+            #[post(#post_url, data = "<form>")]
+            #[allow(clippy::too_many_arguments)]
             pub async fn #function_name_post<'a, 'b>(
-                user: Authentication,
                 pool: &'a rocket::State<Pool<Postgres>>,
                 form: crate::utils::form_extra_validation::ExtraValidatedForm<#form_type_name>,
-                asset_context: &'b rocket::State<AssetContext>,
+                context: PageContext<'b>,
                 #(#guard_args: #guard_types,)*
                 #(#extra_args,)*
+                #flash_arg
+                #next_arg
             ) -> Result<rocket::Either<rocket::response::Redirect, #template_type_name<'b>>, crate::error::Error> {
                 let crate::utils::form_extra_validation::ExtraValidatedForm(form, mut errors) = form;
                 match form.validate() {
@@ -810,24 +1310,60 @@ pub fn form_get_and_post(args: TokenStream) -> TokenStream {
                 }
                 if errors.is_empty() {
                     match #process_expr.await? {
-                        Either::Left(redirect) => Ok(Either::Left(redirect)),
+                        #success_arm
                         Either::Right(errors) => Ok(Either::Right(#template_type_name {
-                            user: user.into(),
                             form: form.clear_sensitive().get_definition(errors),
-                            asset_context,
-                            breadcrumbs: #breadcrumbs,
+                            context: context.with_meta(#page_meta),
                         })),
                     }
                 } else {
                     Ok(Either::Right(#template_type_name {
-                        user: user.into(),
                         form: form.clear_sensitive().get_definition(errors),
-                        asset_context,
-                        breadcrumbs: #breadcrumbs,
+                        context: context.with_meta(#page_meta),
                     }))
                 }
             }
         ),
+        Mode::Confirm => quote!(
+            #[get(#url)]
+            #[allow(clippy::too_many_arguments)]
+            pub async fn #function_name_get<'a, 'b>(
+                pool: &'a rocket::State<Pool<Postgres>>,
+                csrf_token: CsrfToken,
+                context: PageContext<'b>,
+                #(#guard_args: #guard_types,)*
+                #(#extra_args,)*
+            ) -> Result<#template_type_name<'b>, crate::error::Error>{
+                #check_expr.await?;
+                Ok(#template_type_name {
+                    message: (#confirm_message).into(),
+                    form: crate::app::views::ConfirmForm::new(&csrf_token.authenticity_token())
+                        .get_definition(ValidationErrors::new()),
+                    context: context.with_meta(#page_meta),
+                })
+            }
+
+            #[post(#post_url, data = "<form>")]
+            #[allow(clippy::too_many_arguments)]
+            pub async fn #function_name_post<'a, 'b>(
+                pool: &'a rocket::State<Pool<Postgres>>,
+                form: CSRFProtectedForm<crate::app::views::ConfirmForm>,
+                context: PageContext<'b>,
+                #(#guard_args: #guard_types,)*
+                #(#extra_args,)*
+                #flash_arg
+                #next_arg
+            ) -> Result<rocket::Either<rocket::response::Redirect, #template_type_name<'b>>, crate::error::Error> {
+                match #action_expr.await? {
+                    #success_arm
+                    Either::Right(errors) => Ok(Either::Right(#template_type_name {
+                        message: (#confirm_message).into(),
+                        form: form.get_definition(errors),
+                        context: context.with_meta(#page_meta),
+                    })),
+                }
+            }
+        ),
     };
 
     result.into()
@@ -874,3 +1410,45 @@ pub fn derive_template_query(form: TokenStream) -> TokenStream {
         panic!("TemplateWithQuery can be derived for structs only");
     }
 }
+
+#[proc_macro_derive(TemplateWithFlash)]
+pub fn derive_template_flash(form: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(form as DeriveInput);
+    let name = input.ident;
+    let generics = input.generics;
+
+    if let Struct(struct_data) = input.data {
+        match struct_data.fields {
+            Fields::Unit => {
+                panic!("TemplateWithFlash can not be derived for unit structs");
+            }
+            Fields::Unnamed(_) => {
+                panic!("TemplateWithFlash can not be derived for tuple structs");
+            }
+            Fields::Named(named_fields) => {
+                if named_fields.named.iter().any(|field| {
+                    field.ident.as_ref().map(|ident| ident.to_string())
+                        == Some("flash_message".to_string())
+                }) {
+                    quote! {
+                        impl #generics crate::app::templates::TemplateWithFlash for #name #generics {
+                            fn flash_message<'template_with_flash_a>(&'template_with_flash_a self) -> Option<&'template_with_flash_a crate::utils::flash::FlashMessage> {
+                                self.flash_message.as_ref()
+                            }
+                        }
+                    }.into()
+                } else {
+                    quote! {
+                        impl #generics crate::app::templates::TemplateWithFlash for #name #generics {
+                            fn flash_message<'template_with_flash_a>(&'template_with_flash_a self) -> Option<&'template_with_flash_a crate::utils::flash::FlashMessage> {
+                                None
+                            }
+                        }
+                    }.into()
+                }
+            }
+        }
+    } else {
+        panic!("TemplateWithFlash can be derived for structs only");
+    }
+}
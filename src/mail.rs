@@ -0,0 +1,87 @@
+use std::sync::Mutex;
+
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use log::info;
+use rocket::async_trait;
+
+use crate::error;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), error::Error>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        from: &str,
+    ) -> Result<Self, error::Error> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+            .port(port)
+            .credentials(Credentials::new(username.to_string(), password.to_string()))
+            .build();
+
+        Ok(Self {
+            transport,
+            from: from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), error::Error> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(mail.to.parse()?)
+            .subject(mail.subject)
+            .body(mail.body)?;
+
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LogMailer {
+    sent_mails: Mutex<Vec<Mail>>,
+}
+
+impl LogMailer {
+    #[cfg(test)]
+    pub fn sent_mails(&self) -> Vec<Mail> {
+        self.sent_mails.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<(), error::Error> {
+        info!(
+            "Sending mail to {} with subject {:?}:\n{}",
+            mail.to, mail.subject, mail.body
+        );
+        self.sent_mails.lock().unwrap().push(mail);
+
+        Ok(())
+    }
+}
@@ -5,11 +5,15 @@
 
 use std::{
     collections::HashMap,
+    fmt::Display,
+    net::IpAddr,
     path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 
 use app::db::set_uploads_hidden;
+use argon2::{password_hash, Algorithm, Argon2, Params, Version};
 use artushak_web_assets::{
     asset_config::AssetConfig,
     asset_filter::{AssetFilter, AssetFilterRegistry},
@@ -20,28 +24,66 @@ use asset_filters::{
     run_executable::AssetFilterRunExecutable, tsc::AssetFilterTsc, AssetFilterCustomError,
 };
 use clap::{Parser, Subcommand};
+use ipnet::IpNet;
 use log::info;
-use rocket::{fs::FileServer, routes, Build, Rocket};
+use mail::{LogMailer, Mailer, SmtpMailer};
+use rand::{thread_rng, Rng};
+use rocket::{
+    catchers,
+    figment::Profile,
+    fs::FileServer,
+    http::{Cookie, SameSite},
+    routes,
+    shield::{Frame, Shield},
+    Build, Rocket, Shutdown,
+};
 use rpassword::prompt_password;
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, PgPool};
-use tokio::runtime::Runtime;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use subtle::ConstantTimeEq;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::{runtime::Runtime, time::sleep};
 use tokio_stream::StreamExt;
-use utils::csrf_lib;
+use utils::{csrf_lib, secret::Secret, signed_token::SignedTokenKey};
 
 use crate::{
     app::{
-        db::{list_old_in_progress_uploads_and_set_hiding, try_add_user_check_username, NewUser},
-        storage::unpublish_file,
+        db::{
+            add_cleanup_run, add_random_invites, change_user_password, clear_expired_post_bans,
+            generate_random_password, get_storage_stats,
+            list_old_in_progress_uploads_and_set_hiding, list_published_uploads_with_pagination,
+            list_users_with_pagination, remove_expired_idempotency_keys, remove_expired_sessions,
+            remove_user_sessions, set_user_must_change_password, try_add_user_check_username,
+            try_ban_post_check_exists, try_get_ban_reason, try_get_post, try_get_user,
+            try_set_upload_status, try_set_upload_status_check_exists, try_unban_post_check_exists,
+            NewUser, Post, StorageStats, UploadStatus, User, UserSort, UserStatus,
+            DELETED_USERNAME,
+        },
+        idempotency::IDEMPOTENCY_KEY_LIFETIME,
+        metrics::METRICS,
+        storage::{
+            get_public_file_size, get_storage_disk_usage, unpublish_file, StorageDiskUsage,
+            StorageProbeCache,
+        },
         templates::AssetContext,
+        webhooks::{HttpWebhookClient, LogWebhookClient, WebhookClient},
+    },
+    auth::Authentication,
+    utils::{
+        file_server::ConditionalFileServer,
+        page_stream::iterate_pages,
+        pagination::{PageOverflowBehavior, PageParams},
     },
-    utils::page_stream::iterate_pages,
 };
 
 mod app;
 mod asset_filters;
 mod auth;
 mod error;
+mod mail;
 #[cfg(test)]
 mod test;
 mod utils;
@@ -56,6 +98,7 @@ struct CLIOptions {
 enum CLISubcommand {
     Run,
     Pack,
+    CheckConfig,
     AddUser {
         #[arg(long)]
         username: String,
@@ -64,10 +107,84 @@ enum CLISubcommand {
         #[arg(long)]
         is_admin: bool,
     },
+    ListUsers {
+        #[arg(long)]
+        filter: Option<String>,
+        #[arg(long, value_parser = parse_user_status)]
+        status: Option<UserStatus>,
+        #[arg(long)]
+        json: bool,
+    },
+    SetPassword {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        random: bool,
+    },
     CleanupStorage {
         #[arg(long)]
         page_size: u64,
     },
+    Stats {
+        #[arg(long)]
+        json: bool,
+    },
+    BanPost {
+        #[arg(long)]
+        id: i64,
+        #[arg(long)]
+        reason_id: Option<String>,
+        #[arg(long)]
+        text: Option<String>,
+        #[arg(long)]
+        hide_files: bool,
+    },
+    UnbanPost {
+        #[arg(long)]
+        id: i64,
+    },
+    ExportPosts {
+        #[arg(long)]
+        output: PathBuf,
+    },
+    ImportPosts {
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        author: String,
+    },
+    GenerateInvites {
+        #[arg(long)]
+        count: u32,
+        #[arg(long, value_parser = parse_duration_with_unit)]
+        expires_in: Option<Duration>,
+        #[arg(long, default_value_t = 1)]
+        max_uses: i32,
+    },
+}
+
+/// Parse duration like `7d`, `12h`, `30m` or `45s`.
+fn parse_duration_with_unit(value: &str) -> Result<Duration, String> {
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 'd')) => (&value[..index], 24 * 60 * 60),
+        Some((index, 'h')) => (&value[..index], 60 * 60),
+        Some((index, 'm')) => (&value[..index], 60),
+        Some((index, 's')) => (&value[..index], 1),
+        _ => return Err("duration must end with d, h, m or s".to_string()),
+    };
+    let number: u64 = number.parse().map_err(|err| format!("{}", err))?;
+    Ok(Duration::from_secs(number * multiplier))
+}
+
+/// Parse user status like `banned`, `user`, `uploader` or `admin`.
+fn parse_user_status(value: &str) -> Result<UserStatus, String> {
+    match value {
+        "banned" => Ok(UserStatus::Banned),
+        "user" => Ok(UserStatus::User),
+        "uploader" => Ok(UserStatus::Uploader),
+        "admin" => Ok(UserStatus::Admin),
+        _ => Err("status must be banned, user, uploader or admin".to_string()),
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -77,10 +194,267 @@ pub struct Config {
     asset_base_url: String,
     asset_config: AssetConfig,
     serve_assets: bool,
-    db_url: String,
+    db_url: Secret<String>,
     max_db_connections: u32,
     pagination_config: PaginationConfig,
     upload_config: UploadConfig,
+    auth_config: AuthConfig,
+    password_hash_config: PasswordHashConfig,
+    email_config: EmailConfig,
+    #[serde(default)]
+    registration_mode: RegistrationMode,
+    #[serde(default)]
+    rate_limit_config: RateLimitConfig,
+    #[serde(default)]
+    webhook_config: WebhookConfig,
+    /// Announce new public posts in Telegram channel, if set.
+    #[serde(default)]
+    telegram_config: Option<TelegramConfig>,
+    #[serde(default)]
+    metrics_config: MetricsConfig,
+    #[serde(default)]
+    security_headers_config: SecurityHeadersConfig,
+    /// Static pages linked in footer of every page.
+    #[serde(default)]
+    footer_pages: Vec<FooterPage>,
+}
+
+/// Link to static page in footer, page itself is edited by admins.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FooterPage {
+    pub slug: String,
+    pub title: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigProblemSeverity {
+    Warning,
+    /// Server must not be launched with this problem.
+    Fatal,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigProblem {
+    pub severity: ConfigProblemSeverity,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn fatal(message: String) -> Self {
+        Self {
+            severity: ConfigProblemSeverity::Fatal,
+            message,
+        }
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.severity == ConfigProblemSeverity::Fatal
+    }
+
+    pub fn log(&self) {
+        match self.severity {
+            ConfigProblemSeverity::Warning => log::warn!("Config problem: {}", self.message),
+            ConfigProblemSeverity::Fatal => log::error!("Config problem: {}", self.message),
+        }
+    }
+}
+
+impl Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            ConfigProblemSeverity::Warning => write!(f, "warning: {}", self.message),
+            ConfigProblemSeverity::Fatal => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+fn check_writable_directory(name: &str, path: &Path, problems: &mut Vec<ConfigProblem>) {
+    if !path.is_dir() {
+        problems.push(ConfigProblem::fatal(format!(
+            "{} {} is not a directory",
+            name,
+            path.display()
+        )));
+        return;
+    }
+
+    let probe_file_path = path.join(".config-check-probe");
+    if let Err(err) =
+        std::fs::File::create(&probe_file_path).and_then(|_| std::fs::remove_file(&probe_file_path))
+    {
+        problems.push(ConfigProblem::fatal(format!(
+            "{} {} is not writable: {}",
+            name,
+            path.display(),
+            err
+        )));
+    }
+}
+
+fn check_base_url(
+    name: &str,
+    base_url: &str,
+    severity: ConfigProblemSeverity,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    if !base_url.starts_with('/') || !base_url.ends_with('/') {
+        problems.push(ConfigProblem {
+            severity,
+            message: format!("{} {} must start and end with /", name, base_url),
+        });
+    }
+}
+
+impl Config {
+    /// Check config for problems which would otherwise only surface on first request.
+    pub fn validate(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+
+        if let Err(err) = self.db_url.parse::<PgConnectOptions>() {
+            problems.push(ConfigProblem::fatal(format!("db_url is invalid: {}", err)));
+        }
+        if self.max_db_connections == 0 {
+            problems.push(ConfigProblem::fatal(
+                "max_db_connections must be positive".to_string(),
+            ));
+        }
+
+        if self.pagination_config.default_page_size == 0 {
+            problems.push(ConfigProblem::fatal(
+                "default_page_size must be positive".to_string(),
+            ));
+        }
+        if self.pagination_config.max_page_size < self.pagination_config.default_page_size {
+            problems.push(ConfigProblem::fatal(format!(
+                "max_page_size {} is less than default_page_size {}",
+                self.pagination_config.max_page_size, self.pagination_config.default_page_size
+            )));
+        }
+        if self.upload_config.max_post_batch_size == 0 {
+            problems.push(ConfigProblem::fatal(
+                "max_post_batch_size must be positive".to_string(),
+            ));
+        }
+        for (group_name, bucket_config) in [
+            ("uploads", self.rate_limit_config.uploads),
+            ("posts", self.rate_limit_config.posts),
+            ("search", self.rate_limit_config.search),
+        ] {
+            if let Some(bucket_config) = bucket_config {
+                if bucket_config.capacity == 0 || bucket_config.refill_interval.is_zero() {
+                    problems.push(ConfigProblem::fatal(format!(
+                        "rate limit for {} must have positive capacity and refill_interval",
+                        group_name
+                    )));
+                }
+            }
+        }
+
+        if self.webhook_config.max_attempts == 0 || self.webhook_config.timeout.is_zero() {
+            problems.push(ConfigProblem::fatal(
+                "webhook max_attempts and timeout must be positive".to_string(),
+            ));
+        }
+        if let Some(telegram_config) = &self.telegram_config {
+            if telegram_config.bot_token.is_empty() || telegram_config.channel_id.is_empty() {
+                problems.push(ConfigProblem::fatal(
+                    "telegram bot_token and channel_id must not be empty".to_string(),
+                ));
+            }
+            if telegram_config.max_attempts == 0 || telegram_config.timeout.is_zero() {
+                problems.push(ConfigProblem::fatal(
+                    "telegram max_attempts and timeout must be positive".to_string(),
+                ));
+            }
+        }
+
+        let cookie_security = &self.auth_config.cookie_security;
+        if cookie_security.secure == Some(false) {
+            if cookie_security.same_site == CookieSameSite::None {
+                problems.push(ConfigProblem::fatal(
+                    "cookie same_site none requires secure cookies".to_string(),
+                ));
+            }
+            if ["__Host-", "__Secure-"]
+                .iter()
+                .any(|prefix| cookie_security.name_prefix.starts_with(prefix))
+            {
+                problems.push(ConfigProblem::fatal(format!(
+                    "cookie name_prefix {} requires secure cookies",
+                    cookie_security.name_prefix
+                )));
+            }
+        }
+
+        if self
+            .metrics_config
+            .bearer_token
+            .as_ref()
+            .is_some_and(|bearer_token| bearer_token.is_empty())
+        {
+            problems.push(ConfigProblem::fatal(
+                "metrics bearer_token must not be empty".to_string(),
+            ));
+        }
+
+        // Base URLs are only mounted when assets are served by application itself, otherwise
+        // they may be absolute.
+        let base_url_severity = if self.serve_assets {
+            ConfigProblemSeverity::Fatal
+        } else {
+            ConfigProblemSeverity::Warning
+        };
+        check_base_url(
+            "asset_base_url",
+            &self.asset_base_url,
+            base_url_severity,
+            &mut problems,
+        );
+        match &self.upload_config.storage {
+            UploadStorage::FileSystem {
+                private_path,
+                public_path,
+                base_url,
+            } => {
+                check_writable_directory("private_path", private_path, &mut problems);
+                check_writable_directory("public_path", public_path, &mut problems);
+                check_base_url("base_url", base_url, base_url_severity, &mut problems);
+            }
+        }
+
+        // Missing manifest is loaded as empty one, so its existence is checked separately.
+        if !self.asset_cache_manifest_path.is_file() {
+            problems.push(ConfigProblem::fatal(format!(
+                "asset cache manifest {} does not exist (run pack first)",
+                self.asset_cache_manifest_path.display()
+            )));
+        } else if let Err(err) =
+            load_cache_manifest::<AssetFilterCustomError>(&self.asset_cache_manifest_path)
+        {
+            problems.push(ConfigProblem::fatal(format!(
+                "asset cache manifest {} can not be loaded (run pack first): {:?}",
+                self.asset_cache_manifest_path.display(),
+                err
+            )));
+        }
+
+        problems
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationMode {
+    #[default]
+    InviteOnly,
+    Open,
+    Closed,
+}
+
+impl RegistrationMode {
+    pub fn is_enabled(&self) -> bool {
+        *self != RegistrationMode::Closed
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -89,11 +463,400 @@ pub struct PaginationConfig {
     pub default_page_size: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuthConfig {
+    pub session_cookie_name: String,
+    pub impersonation_cookie_name: String,
+    pub short_session_lifetime: Duration,
+    pub long_session_lifetime: Duration,
+    pub max_login_attempts: u32,
+    pub login_attempt_window: Duration,
+    pub login_lockout_duration: Duration,
+    pub max_registration_attempts: u32,
+    pub registration_attempt_window: Duration,
+    pub invite_link_lifetime: Duration,
+    /// Attributes of session, impersonation and CSRF cookies.
+    #[serde(default)]
+    pub cookie_security: CookieSecurity,
+    /// Show different login errors for missing user and wrong password. It is disabled by
+    /// default, because it shows which usernames exist.
+    #[serde(default)]
+    pub show_login_error_details: bool,
+    /// Accept CSRF tokens made by previous bcrypt-based scheme. Enable it only while CSRF cookies
+    /// issued before HMAC tokens are not expired.
+    #[serde(default)]
+    pub accept_legacy_csrf_tokens: bool,
+    /// Name of cookie with time when user last entered password.
+    #[serde(default = "default_recent_auth_cookie_name")]
+    pub recent_auth_cookie_name: String,
+    /// Password change, API token management and account deletion require password to be entered
+    /// within this time.
+    #[serde(default = "default_recent_auth_lifetime")]
+    pub recent_auth_lifetime: Duration,
+}
+
+fn default_recent_auth_cookie_name() -> String {
+    "recent_auth".to_string()
+}
+
+fn default_recent_auth_lifetime() -> Duration {
+    Duration::from_secs(10 * 60)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieSameSite {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl From<CookieSameSite> for SameSite {
+    fn from(same_site: CookieSameSite) -> Self {
+        match same_site {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        }
+    }
+}
+
+/// Attributes of cookies which identify user. `secure` is enabled by default except in debug
+/// profile, see [`CookieSecurity::for_profile`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CookieSecurity {
+    #[serde(default)]
+    pub secure: Option<bool>,
+    #[serde(default)]
+    pub same_site: CookieSameSite,
+    /// Prefix of cookie names, like `__Host-` (it requires `secure`).
+    #[serde(default)]
+    pub name_prefix: String,
+}
+
+impl CookieSecurity {
+    /// Set `secure` if it is not configured: cookies are not secure only in debug profile, where
+    /// server is usually accessed over plain HTTP.
+    pub fn for_profile(mut self, profile: &Profile) -> Self {
+        if self.secure.is_none() {
+            self.secure = Some(*profile != rocket::Config::DEBUG_PROFILE);
+        }
+        self
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.secure.unwrap_or(true)
+    }
+
+    pub fn cookie_name(&self, name: &str) -> String {
+        self.name_prefix.clone() + name
+    }
+
+    /// Add name prefix and security attributes to cookie. Path is always `/`, as required for
+    /// `__Host-` prefix.
+    pub fn apply(&self, cookie: impl Into<Cookie<'static>>) -> Cookie<'static> {
+        let mut cookie = cookie.into();
+        cookie.set_name(self.cookie_name(cookie.name()));
+        cookie.set_path("/");
+        cookie.set_secure(self.is_secure());
+        cookie.set_same_site(SameSite::from(self.same_site));
+        cookie.set_http_only(true);
+        cookie
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasswordHashConfig {
+    pub memory_cost: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl PasswordHashConfig {
+    pub fn get_hasher(&self) -> Result<Argon2<'static>, error::Error> {
+        let params = Params::new(self.memory_cost, self.iterations, self.parallelism, None)
+            .map_err(password_hash::Error::from)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            memory_cost: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub site_url: String,
+    pub verification_token_lifetime: Duration,
+    pub password_reset_token_lifetime: Duration,
+    pub mailer: MailerConfig,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MailerConfig {
+    Smtp {
+        host: String,
+        port: u16,
+        username: String,
+        password: String,
+        from: String,
+    },
+    Log,
+}
+
+impl MailerConfig {
+    pub fn get_mailer(&self) -> Result<Arc<dyn Mailer>, error::Error> {
+        match self {
+            MailerConfig::Smtp {
+                host,
+                port,
+                username,
+                password,
+                from,
+            } => Ok(Arc::new(SmtpMailer::new(
+                host, *port, username, password, from,
+            )?)),
+            MailerConfig::Log => Ok(Arc::new(LogMailer::default())),
+        }
+    }
+}
+
+/// Token bucket parameters: up to `capacity` requests in burst, one request is restored every
+/// `refill_interval`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitBucketConfig {
+    pub capacity: u32,
+    pub refill_interval: Duration,
+}
+
+/// Rate limits for route groups, group is not limited if its bucket is not set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Use last address from `X-Forwarded-For` header, which is appended by proxy, as client IP
+    /// address.
+    #[serde(default)]
+    pub trust_proxy: bool,
+    #[serde(default)]
+    pub uploads: Option<RateLimitBucketConfig>,
+    #[serde(default)]
+    pub posts: Option<RateLimitBucketConfig>,
+    #[serde(default)]
+    pub search: Option<RateLimitBucketConfig>,
+}
+
+/// Webhook delivery parameters, failed delivery is retried after `retry_base_interval`, and
+/// interval is doubled after each attempt up to `max_retry_interval`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub timeout: Duration,
+    pub max_attempts: u32,
+    pub retry_base_interval: Duration,
+    pub max_retry_interval: Duration,
+    /// Log webhook requests instead of sending them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_attempts: 8,
+            retry_base_interval: Duration::from_secs(30),
+            max_retry_interval: Duration::from_secs(6 * 60 * 60),
+            dry_run: false,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn get_client(&self) -> Result<Arc<dyn WebhookClient>, error::Error> {
+        if self.dry_run {
+            Ok(Arc::new(LogWebhookClient::default()))
+        } else {
+            Ok(Arc::new(HttpWebhookClient::new(self.timeout)?))
+        }
+    }
+}
+
+/// Telegram bot which posts messages to channel, it should be administrator of channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    /// Channel username (like `@channel`) or numeric ID.
+    pub channel_id: String,
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+    #[serde(default = "default_telegram_timeout")]
+    pub timeout: Duration,
+    /// Number of attempts to send message, failed attempts are retried after `retry_interval`.
+    #[serde(default = "default_telegram_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_telegram_retry_interval")]
+    pub retry_interval: Duration,
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+fn default_telegram_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_telegram_max_attempts() -> u32 {
+    3
+}
+
+fn default_telegram_retry_interval() -> Duration {
+    Duration::from_secs(30)
+}
+
+/// Access to metrics endpoint, it is allowed for clients from `allowed_networks` and for ones
+/// sending `bearer_token` in `Authorization` header.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    #[serde(default = "default_metrics_allowed_networks")]
+    pub allowed_networks: Vec<IpNet>,
+    #[serde(default)]
+    pub bearer_token: Option<String>,
+    /// Use last address from `X-Forwarded-For` header, which is appended by proxy, as client IP
+    /// address. If it is not set, proxied requests are allowed only with bearer token.
+    #[serde(default)]
+    pub trust_proxy: bool,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_networks: default_metrics_allowed_networks(),
+            bearer_token: None,
+            trust_proxy: false,
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Check access of client, `ip` is `None` if client address is unknown (request came through
+    /// untrusted proxy), then only bearer token is accepted.
+    pub fn is_allowed(&self, ip: Option<IpAddr>, bearer_token: Option<&str>) -> bool {
+        let is_token_valid = match (&self.bearer_token, bearer_token) {
+            (Some(expected_token), Some(bearer_token)) => expected_token
+                .as_bytes()
+                .ct_eq(bearer_token.as_bytes())
+                .into(),
+            _ => false,
+        };
+        is_token_valid
+            || ip.is_some_and(|ip| {
+                self.allowed_networks
+                    .iter()
+                    .any(|network| network.contains(&ip))
+            })
+    }
+}
+
+/// Value of `X-Frame-Options` header, `frame-ancestors` directive of default
+/// `Content-Security-Policy` is set accordingly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameOptions {
+    #[default]
+    Deny,
+    SameOrigin,
+}
+
+impl FrameOptions {
+    pub fn header_value(&self) -> &'static str {
+        match self {
+            Self::Deny => "DENY",
+            Self::SameOrigin => "SAMEORIGIN",
+        }
+    }
+
+    pub fn frame_ancestors(&self) -> &'static str {
+        match self {
+            Self::Deny => "'none'",
+            Self::SameOrigin => "'self'",
+        }
+    }
+}
+
+/// Security headers added to every response, see
+/// [`app::security_headers::SecurityHeadersFairing`]. Default `content_security_policy` allows
+/// resources only from site itself and from asset and media base URLs, custom policy can use
+/// `{nonce}` placeholder to allow inline scripts of pages.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecurityHeadersConfig {
+    #[serde(default)]
+    pub content_security_policy: Option<String>,
+    #[serde(default)]
+    pub frame_options: FrameOptions,
+    #[serde(default = "default_referrer_policy")]
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            content_security_policy: None,
+            frame_options: FrameOptions::default(),
+            referrer_policy: default_referrer_policy(),
+        }
+    }
+}
+
+fn default_referrer_policy() -> String {
+    "strict-origin-when-cross-origin".to_string()
+}
+
+/// Only local clients are allowed by default.
+fn default_metrics_allowed_networks() -> Vec<IpNet> {
+    ["127.0.0.0/8", "::1/128"]
+        .iter()
+        .map(|network| network.parse().expect("network should be valid"))
+        .collect()
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UploadConfig {
     pub max_file_size: u64,
+    pub max_avatar_size: u64,
+    pub default_storage_quota_bytes: u64,
     pub storage: UploadStorage,
     pub max_upload_time: Duration,
+    /// Run storage cleanup in background with this interval, if set.
+    #[serde(default)]
+    pub cleanup_interval: Option<Duration>,
+    /// Maximum number of posts in one batch creation request.
+    #[serde(default = "default_max_post_batch_size")]
+    pub max_post_batch_size: u64,
+    /// Maximum total size of files in downloaded post archive.
+    #[serde(default = "default_max_post_archive_size")]
+    pub max_post_archive_size: u64,
+    /// Origins of other sites which can send upload requests, site itself is always allowed.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Show signed file URLs with this lifetime on pages of age-restricted posts, files of such
+    /// posts are served by media route only with valid signed URL. Proxy which serves media
+    /// itself should check access with `/media-access` route in this case.
+    #[serde(default)]
+    pub signed_url_lifetime: Option<Duration>,
+}
+
+fn default_max_post_batch_size() -> u64 {
+    100
+}
+
+fn default_max_post_archive_size() -> u64 {
+    16 * 1024 * 1024 * 1024
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -106,22 +869,88 @@ pub enum UploadStorage {
 }
 
 pub async fn run(rocket: Rocket<Build>, config: Config) -> Result<(), error::Error> {
+    let problems = config.validate();
+    for problem in problems.iter() {
+        problem.log();
+    }
+    if problems.iter().any(ConfigProblem::is_fatal) {
+        return Err(error::Error::Misc("invalid config".to_string()));
+    }
+
     let asset_cache =
         load_cache_manifest::<AssetFilterCustomError>(&config.asset_cache_manifest_path).unwrap();
 
     let pool = get_pool(&config).await?;
+    let cleanup_pool = pool.clone();
+    let cleanup_upload_config = config.upload_config.clone();
 
     let asset_context = AssetContext {
         asset_cache,
         base_url: config.asset_base_url.clone(),
+        registration_mode: config.registration_mode,
+        footer_pages: config.footer_pages.clone(),
+    };
+
+    let mailer = config.email_config.mailer.get_mailer()?;
+    let webhook_client = config.webhook_config.get_client()?;
+
+    let auth_config = AuthConfig {
+        cookie_security: config
+            .auth_config
+            .cookie_security
+            .clone()
+            .for_profile(rocket.figment().profile()),
+        ..config.auth_config
     };
 
+    let media_base_url = match &config.upload_config.storage {
+        UploadStorage::FileSystem { base_url, .. } => base_url.clone(),
+    };
+
+    let signed_token_key = SignedTokenKey::from_secret_key(
+        rocket
+            .figment()
+            .extract_inner::<String>("secret_key")
+            .ok()
+            .as_deref(),
+    );
+
     let rocket = rocket
-        .attach(csrf_lib::Fairing::default())
+        .attach(csrf_lib::Fairing::new(
+            csrf_lib::CsrfConfig::default()
+                .with_cookie_security(auth_config.cookie_security.clone())
+                .with_legacy_tokens(auth_config.accept_legacy_csrf_tokens),
+        ))
+        .attach(auth::PasswordChangeFairing::default())
+        .attach(auth::RecentAuthFairing::default())
+        .attach(app::shutdown::UploadShutdownFairing::default())
+        .attach(app::rate_limit::RateLimitFairing::new(
+            config.rate_limit_config,
+        ))
+        .attach(app::webhooks::WebhookFairing::new(
+            config.webhook_config,
+            webhook_client,
+        ))
+        .attach(app::telegram::TelegramFairing::new(config.telegram_config))
+        .attach(app::metrics::MetricsFairing::new(config.metrics_config))
+        // Frame options are set by security headers fairing, embedded posts can not be framed
+        // if default shield adds `X-Frame-Options` to them.
+        .attach(Shield::default().disable::<Frame>())
+        .attach(app::security_headers::SecurityHeadersFairing::new(
+            config.security_headers_config,
+            &[&config.asset_base_url, &media_base_url],
+        ))
         .manage(pool)
         .manage(asset_context)
         .manage(config.pagination_config)
-        .manage(config.upload_config.clone());
+        .manage(auth_config)
+        .manage(config.password_hash_config)
+        .manage(config.email_config)
+        .manage(config.registration_mode)
+        .manage(signed_token_key.clone())
+        .manage(mailer)
+        .manage(config.upload_config.clone())
+        .manage(StorageProbeCache::default());
 
     let rocket = if config.serve_assets {
         let rocket = rocket.mount(
@@ -133,7 +962,15 @@ pub async fn run(rocket: Rocket<Build>, config: Config) -> Result<(), error::Err
                 private_path: _,
                 public_path,
                 base_url,
-            } => rocket.mount(base_url, FileServer::from(public_path)),
+            } => {
+                let file_server = ConditionalFileServer::new(public_path);
+                let file_server = if config.upload_config.signed_url_lifetime.is_some() {
+                    file_server.with_signed_token_key(signed_token_key)
+                } else {
+                    file_server
+                };
+                rocket.mount(base_url, file_server)
+            }
         }
     } else {
         rocket
@@ -143,16 +980,44 @@ pub async fn run(rocket: Rocket<Build>, config: Config) -> Result<(), error::Err
 
     info!("Rocket configured");
 
+    let rocket = rocket.ignite().await?;
+
+    if let Some(cleanup_interval) = cleanup_upload_config.cleanup_interval {
+        tokio::spawn(run_cleanup_scheduler(
+            cleanup_pool,
+            cleanup_upload_config.storage,
+            cleanup_upload_config.max_upload_time,
+            cleanup_interval,
+            rocket.shutdown(),
+        ));
+    }
+
+    // Shutdown fairings (including upload one) are run before `launch` returns.
     let _ = rocket.launch().await?;
+    info!("Rocket shut down");
 
     Ok(())
 }
 
 fn mount_views(rocket: Rocket<Build>) -> Rocket<Build> {
+    let rocket = rocket
+        .register("/api", catchers![error::api_default_catcher])
+        .register(
+            "/",
+            catchers![
+                app::views::forbidden_catcher,
+                app::views::not_found_catcher,
+                app::views::unprocessable_entity_catcher,
+                app::views::unavailable_for_legal_reasons_catcher,
+                app::views::internal_server_error_catcher,
+            ],
+        );
     rocket.mount(
         "/",
         routes![
             app::views::index_get,
+            app::views::locale_set_get,
+            app::views::theme_set_get,
             app::views::registration_get,
             app::views::registration_post,
             app::views::login_get,
@@ -161,10 +1026,42 @@ fn mount_views(rocket: Rocket<Build>) -> Rocket<Build> {
             app::views::logout_post,
             app::views::change_password_get,
             app::views::change_password_post,
+            app::views::confirm_password_get,
+            app::views::confirm_password_post,
+            app::views::forgot_password_get,
+            app::views::forgot_password_post,
+            app::views::forgot_password_sent_get,
+            app::views::reset_password_get,
+            app::views::reset_password_post,
+            app::views::profile_edit_get,
+            app::views::profile_edit_post,
+            app::views::avatar_edit_get,
+            app::views::account_delete_get,
+            app::views::account_delete_post,
+            app::views::verify_email_get,
+            app::views::sessions_list_get,
+            app::views::session_revoke_post,
+            app::views::sessions_revoke_other_post,
+            app::views::api_tokens_list_get,
+            app::views::api_docs_get,
+            app::views::api_token_add_get,
+            app::views::api_token_add_post,
+            app::views::api_token_revoke_post,
             app::views::user_detail_get,
             app::views::user_edit_get,
             app::views::user_edit_post,
+            app::views::user_delete_get,
+            app::views::user_delete_post,
+            app::views::user_impersonate_get,
+            app::views::user_impersonate_post,
+            app::views::impersonation_stop_get,
+            app::views::impersonation_stop_post,
             app::views::ban_reasons_list_get,
+            app::views::invites_list_get,
+            app::views::invite_revoke_post,
+            app::views::invite_link_get,
+            app::views::invite_generate_get,
+            app::views::invite_generate_post,
             app::views::invite_add_get,
             app::views::invite_add_post,
             app::views::invite_remove_get,
@@ -173,8 +1070,21 @@ fn mount_views(rocket: Rocket<Build>) -> Rocket<Build> {
             app::views::ban_reason_add_post,
             app::views::ban_reason_edit_get,
             app::views::ban_reason_edit_post,
+            app::views::ban_reason_remove_get,
+            app::views::ban_reason_remove_post,
             app::views::posts_list_get,
+            app::views::posts_pinned_list_get,
             app::views::post_detail_get,
+            app::views::post_short_link_get,
+            app::views::post_by_slug_get,
+            app::views::post_embed_get,
+            app::views::post_download_zip_get,
+            app::views::feed_atom_get,
+            app::views::feed_rss_get,
+            app::views::sitemap_get,
+            app::views::sitemap_users_get,
+            app::views::sitemap_posts_get,
+            app::views::metrics_get,
             app::views::post_add_get,
             app::views::post_ban_get,
             app::views::post_ban_post,
@@ -183,12 +1093,68 @@ fn mount_views(rocket: Rocket<Build>) -> Rocket<Build> {
             app::views::post_edit_get,
             app::views::posts_search_get,
             app::views::users_list_get,
+            app::views::users_bulk_post,
+            app::views::user_invite_add_post,
+            app::views::uploader_request_get,
+            app::views::uploader_request_post,
+            app::views::uploader_requests_list_get,
+            app::views::audit_log_get,
+            app::views::uploader_request_approve_post,
+            app::views::uploader_request_reject_post,
+            app::views::upload_ban_by_hash_get,
+            app::views::upload_ban_by_hash_post,
+            app::views::post_quarantine_get,
+            app::views::post_quarantine_post,
+            app::views::post_ban_appeal_get,
+            app::views::post_ban_appeal_post,
+            app::views::admin_dashboard_get,
+            app::views::ban_appeals_list_get,
+            app::views::ban_appeal_approve_post,
+            app::views::ban_appeal_reject_post,
+            app::views::webhooks_list_get,
+            app::views::webhook_add_get,
+            app::views::webhook_add_post,
+            app::views::webhook_toggle_post,
+            app::views::webhook_remove_post,
+            app::views::webhook_deliveries_get,
+            app::views::content_ratings_list_get,
+            app::views::content_rating_add_get,
+            app::views::content_rating_add_post,
+            app::views::content_rating_edit_get,
+            app::views::content_rating_edit_post,
+            app::views::static_page_get,
+            app::views::static_pages_list_get,
+            app::views::static_page_add_get,
+            app::views::static_page_add_post,
+            app::views::static_page_edit_get,
+            app::views::static_page_edit_post,
+            app::views::static_page_remove_get,
+            app::views::static_page_remove_post,
+            app::views::post_content_rating_get,
+            app::views::post_content_rating_post,
             app::api::post_add_post,
+            app::api::post_add_batch_post,
+            app::api::posts_list_api_get,
+            app::api::post_detail_api_get,
             app::api::post_edit_post,
             app::api::upload_add_post,
+            app::api::avatar_upload_add_post,
             app::api::upload_upload_by_chunk_put,
             app::api::upload_finalize_post,
             app::api::upload_hide_post,
+            app::api::upload_preview_get,
+            app::api::tus_options,
+            app::api::tus_create_post,
+            app::api::tus_head,
+            app::api::tus_patch,
+            app::api::ban_reason_get,
+            app::api::healthz_get,
+            app::api::readyz_get,
+            app::api::media_access_get,
+            app::api::rate_limited_get,
+            app::api::openapi_get,
+            app::api::csrf_token_get,
+            app::api::oembed_get,
         ],
     )
 }
@@ -210,57 +1176,584 @@ pub async fn run_add_user(
             is_admin,
             is_active: true,
             birth_date: None,
+            email: None,
         },
+        &config.password_hash_config,
         &pool,
     )
     .await?
     {
-        Some(()) => info!("User successfully created"),
+        Some(()) => {
+            set_user_must_change_password(&username, true, &pool).await?;
+            info!("User successfully created")
+        }
         None => log::error!("Username {} already exists", username),
     }
 
     Ok(())
 }
 
-pub async fn run_cleanup_storage_with_pool(
+const LIST_USERS_PAGE_SIZE: u64 = 100;
+
+/// List users sorted by username, optionally filtered by username substring and status.
+pub async fn run_list_users_with_pool(
     pool: &PgPool,
-    storage: &UploadStorage,
-    page_size: u64,
-    max_age: Duration,
-) -> Result<(), error::Error> {
+    filter: Option<&str>,
+    status: Option<UserStatus>,
+) -> Result<Vec<User>, error::Error> {
     let mut stream = Box::pin(iterate_pages(
-        page_size,
+        LIST_USERS_PAGE_SIZE,
         Box::pin(async |page_params| {
-            list_old_in_progress_uploads_and_set_hiding(pool, page_params, max_age).await
+            list_users_with_pagination(pool, page_params, UserSort::Username, status).await
         }),
     ));
+    let mut users = Vec::new();
     while let Some(page) = stream.next().await {
-        let items = page?.items;
-        for upload in items.iter() {
+        users.extend(
+            page?
+                .items
+                .into_iter()
+                .filter(|user| filter.is_none_or(|filter| user.username.contains(filter))),
+        );
+    }
+
+    Ok(users)
+}
+
+pub async fn run_list_users(
+    config: Config,
+    filter: Option<String>,
+    status: Option<UserStatus>,
+    json: bool,
+) -> Result<(), error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let users = run_list_users_with_pool(&pool, filter.as_deref(), status).await?;
+    if !json {
+        println!(
+            "{:<32} {:<10} {:<12} created at",
+            "username", "status", "birth date"
+        );
+    }
+    for user in users {
+        let birth_date = user
+            .birth_date
+            .map(|birth_date| birth_date.date().to_string());
+        let created_at = user.created_at.format(&Rfc3339)?;
+        let status = UserStatus::from(user.clone()).get_option();
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "username": user.username,
+                    "status": status,
+                    "birth_date": birth_date,
+                    "created_at": created_at,
+                })
+            );
+        } else {
+            println!(
+                "{:<32} {:<10} {:<12} {}",
+                user.username,
+                status,
+                birth_date.as_deref().unwrap_or("-"),
+                created_at
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Set password for existing user, or random one (returned, user must change it after login) if
+/// `password` is `None`. All user sessions are revoked. Returns `None` if user does not exist.
+pub async fn run_set_password_with_pool(
+    pool: &PgPool,
+    password_hash_config: &PasswordHashConfig,
+    username: &str,
+    password: Option<String>,
+) -> Result<Option<String>, error::Error> {
+    if username == DELETED_USERNAME || try_get_user(username, pool).await?.is_none() {
+        return Ok(None);
+    }
+
+    let (password, must_change_password) = match password {
+        Some(password) => (password, false),
+        None => (generate_random_password(), true),
+    };
+    change_user_password(
+        username,
+        &password,
+        must_change_password,
+        password_hash_config,
+        pool,
+    )
+    .await?;
+    remove_user_sessions(username, None, pool).await?;
+
+    Ok(Some(password))
+}
+
+/// Returns `false` if user does not exist.
+pub async fn run_set_password(
+    config: Config,
+    username: String,
+    password: Option<String>,
+) -> Result<bool, error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let is_random = password.is_none();
+    match run_set_password_with_pool(&pool, &config.password_hash_config, &username, password)
+        .await?
+    {
+        Some(password) => {
+            if is_random {
+                println!("{}", password);
+            }
+            info!("Password successfully changed");
+            Ok(true)
+        }
+        None => {
+            log::error!("User {} does not exist", username);
+            Ok(false)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CleanupResult {
+    pub hidden_upload_count: u64,
+    pub cleared_ban_count: u64,
+}
+
+/// Hide stale unfinished uploads and clear expired post bans. Run is recorded in database.
+pub async fn run_cleanup_storage_with_pool(
+    pool: &PgPool,
+    storage: &UploadStorage,
+    page_size: u64,
+    max_age: Duration,
+) -> Result<CleanupResult, error::Error> {
+    // Pages are iterated without `iterate_pages`: future of async closure is not `Send`, and this
+    // function is run by scheduler task.
+    let mut hidden_upload_count = 0;
+    let mut page_id = 0;
+    loop {
+        let page_params = PageParams {
+            page_id: Some(page_id),
+            page_size,
+            overflow_behavior: PageOverflowBehavior::Error,
+        };
+        let page =
+            match list_old_in_progress_uploads_and_set_hiding(pool, page_params, max_age).await {
+                Ok(page) => page,
+                Err(error::Error::PageDoesNotExist) => break,
+                Err(err) => return Err(err),
+            };
+        for upload in page.items.iter() {
             info!("Cleaning up file {}", upload.id);
             unpublish_file(upload.id, upload.extension.as_deref(), storage).await?;
         }
-        set_uploads_hidden(pool, items.iter().map(|upload| upload.id).collect()).await?;
+        set_uploads_hidden(pool, page.items.iter().map(|upload| upload.id).collect()).await?;
+        hidden_upload_count += page.items.len() as u64;
+        if (page_id + 1) >= page.page_count {
+            break;
+        }
+        page_id += 1;
     }
 
-    Ok(())
+    let cleared_ban_count = clear_expired_post_bans(pool).await?;
+    if cleared_ban_count > 0 {
+        info!("Cleared {} expired post bans", cleared_ban_count);
+    }
+
+    let removed_idempotency_key_count =
+        remove_expired_idempotency_keys(OffsetDateTime::now_utc() - IDEMPOTENCY_KEY_LIFETIME, pool)
+            .await?;
+    if removed_idempotency_key_count > 0 {
+        info!(
+            "Removed {} expired idempotency keys",
+            removed_idempotency_key_count
+        );
+    }
+
+    let removed_session_count = remove_expired_sessions(pool).await?;
+    if removed_session_count > 0 {
+        info!("Removed {} expired sessions", removed_session_count);
+    }
+
+    add_cleanup_run(hidden_upload_count, cleared_ban_count, pool).await?;
+    METRICS.cleanup_runs.inc();
+    METRICS.cleanup_removed_files.inc_by(hidden_upload_count);
+
+    Ok(CleanupResult {
+        hidden_upload_count,
+        cleared_ban_count,
+    })
+}
+
+const CLEANUP_SCHEDULER_PAGE_SIZE: u64 = 100;
+
+/// Run storage cleanup every `interval` (with random delay up to tenth of interval) until Rocket
+/// is shut down. Failed runs are logged and retried on next tick.
+pub async fn run_cleanup_scheduler(
+    pool: PgPool,
+    storage: UploadStorage,
+    max_upload_time: Duration,
+    interval: Duration,
+    mut shutdown: Shutdown,
+) {
+    info!("Starting cleanup scheduler with interval {:?}", interval);
+    loop {
+        let jitter = interval.mul_f64(thread_rng().gen_range(0.0..0.1));
+        tokio::select! {
+            _ = &mut shutdown => {
+                info!("Stopping cleanup scheduler");
+                return;
+            }
+            _ = sleep(interval + jitter) => {}
+        }
+
+        match run_cleanup_storage_with_pool(
+            &pool,
+            &storage,
+            CLEANUP_SCHEDULER_PAGE_SIZE,
+            max_upload_time,
+        )
+        .await
+        {
+            Ok(result) => info!(
+                "Scheduled cleanup finished: {} uploads hidden, {} post bans cleared",
+                result.hidden_upload_count, result.cleared_ban_count
+            ),
+            Err(err) => log::error!("Scheduled cleanup failed: {:?}", err),
+        }
+    }
 }
 
 pub async fn run_cleanup_storage(config: Config, page_size: u64) -> Result<(), error::Error> {
     let pool = &get_pool(&config).await?;
     let storage = &config.upload_config.storage;
 
-    run_cleanup_storage_with_pool(
+    let result = run_cleanup_storage_with_pool(
         pool,
         storage,
         page_size,
         config.upload_config.max_upload_time,
     )
     .await?;
+    info!(
+        "Cleanup finished: {} uploads hidden, {} post bans cleared",
+        result.hidden_upload_count, result.cleared_ban_count
+    );
+
+    Ok(())
+}
+
+/// Published upload which file is missing or has size different from declared in database.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StorageDiscrepancy {
+    pub upload_id: i64,
+    pub declared_size: i64,
+    pub actual_size: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StatsReport {
+    pub stats: StorageStats,
+    pub disk_usage: StorageDiskUsage,
+    pub discrepancies: Vec<StorageDiscrepancy>,
+}
+
+const STATS_PAGE_SIZE: u64 = 100;
+
+pub async fn run_stats_with_pool(
+    pool: &PgPool,
+    storage: &UploadStorage,
+) -> Result<StatsReport, error::Error> {
+    let stats = get_storage_stats(pool).await?;
+    let disk_usage = get_storage_disk_usage(storage).await?;
+
+    let mut stream = Box::pin(iterate_pages(
+        STATS_PAGE_SIZE,
+        Box::pin(async |page_params| {
+            list_published_uploads_with_pagination(pool, page_params).await
+        }),
+    ));
+    let mut discrepancies = Vec::new();
+    while let Some(page) = stream.next().await {
+        for upload in page?.items {
+            let actual_size =
+                get_public_file_size(upload.id, upload.extension.as_deref(), storage).await?;
+            if actual_size != Some(upload.size as u64) {
+                discrepancies.push(StorageDiscrepancy {
+                    upload_id: upload.id,
+                    declared_size: upload.size,
+                    actual_size,
+                });
+            }
+        }
+    }
+
+    Ok(StatsReport {
+        stats,
+        disk_usage,
+        discrepancies,
+    })
+}
+
+pub async fn run_stats(config: Config, json: bool) -> Result<(), error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let report = run_stats_with_pool(&pool, &config.upload_config.storage).await?;
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    let stats = &report.stats;
+    println!("users: {}", stats.user_count());
+    println!("  banned: {}", stats.banned_user_count);
+    println!("  user: {}", stats.regular_user_count);
+    println!("  uploader: {}", stats.uploader_count);
+    println!("  admin: {}", stats.admin_count);
+    println!("posts: {}", stats.post_count());
+    println!("  visible: {}", stats.visible_post_count);
+    println!("  hidden: {}", stats.hidden_post_count);
+    println!("  quarantined: {}", stats.quarantined_post_count);
+    println!("  banned: {}", stats.banned_post_count);
+    println!(
+        "uploads: {} ({} bytes)",
+        stats.upload_count(),
+        stats.total_upload_size()
+    );
+    for item in stats.uploads.iter() {
+        println!(
+            "  {:?}: {} ({} bytes)",
+            item.file_status, item.count, item.total_size
+        );
+    }
+    println!("disk usage:");
+    println!("  private: {} bytes", report.disk_usage.private_size);
+    println!("  public: {} bytes", report.disk_usage.public_size);
+    if !report.discrepancies.is_empty() {
+        log::warn!(
+            "{} published uploads do not match their files",
+            report.discrepancies.len()
+        );
+    }
+    for discrepancy in report.discrepancies.iter() {
+        match discrepancy.actual_size {
+            Some(actual_size) => log::warn!(
+                "Upload {} has size {} bytes, but its file has {} bytes",
+                discrepancy.upload_id,
+                discrepancy.declared_size,
+                actual_size
+            ),
+            None => log::warn!("File of upload {} is missing", discrepancy.upload_id),
+        }
+    }
 
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostBanCheckError {
+    PostDoesNotExist,
+    BanReasonDoesNotExist,
+}
+
+/// Ban post on behalf of deleted user placeholder, optionally hiding its published files.
+/// Returns banned post.
+pub async fn run_ban_post_with_pool(
+    pool: &PgPool,
+    storage: &UploadStorage,
+    id: i64,
+    reason_id: Option<String>,
+    text: Option<String>,
+    hide_files: bool,
+) -> Result<Result<Post, PostBanCheckError>, error::Error> {
+    let Some(post) = try_get_post(id, pool, &Authentication::system()).await? else {
+        return Ok(Err(PostBanCheckError::PostDoesNotExist));
+    };
+    if let Some(reason_id) = &reason_id {
+        if try_get_ban_reason(reason_id, pool).await?.is_none() {
+            return Ok(Err(PostBanCheckError::BanReasonDoesNotExist));
+        }
+    }
+
+    if try_ban_post_check_exists(id, reason_id, text, None, DELETED_USERNAME, pool)
+        .await?
+        .is_none()
+    {
+        return Ok(Err(PostBanCheckError::PostDoesNotExist));
+    }
+
+    if hide_files {
+        for upload in post.uploads.iter() {
+            if try_set_upload_status_check_exists(
+                upload.id,
+                UploadStatus::Hiding,
+                Some(DELETED_USERNAME),
+                pool,
+            )
+            .await?
+            .is_none()
+            {
+                continue;
+            }
+            info!("Hiding file {}", upload.id);
+            unpublish_file(upload.id, upload.extension.as_deref(), storage).await?;
+            try_set_upload_status(upload.id, UploadStatus::Hidden, pool).await?;
+        }
+    }
+
+    Ok(Ok(post))
+}
+
+/// Returns `false` if post or ban reason does not exist.
+pub async fn run_ban_post(
+    config: Config,
+    id: i64,
+    reason_id: Option<String>,
+    text: Option<String>,
+    hide_files: bool,
+) -> Result<bool, error::Error> {
+    let pool = get_pool(&config).await?;
+
+    match run_ban_post_with_pool(
+        &pool,
+        &config.upload_config.storage,
+        id,
+        reason_id,
+        text,
+        hide_files,
+    )
+    .await?
+    {
+        Ok(post) => {
+            println!("{}", post.title);
+            info!("Post {} successfully banned", id);
+            Ok(true)
+        }
+        Err(PostBanCheckError::PostDoesNotExist) => {
+            log::error!("Post {} does not exist", id);
+            Ok(false)
+        }
+        Err(PostBanCheckError::BanReasonDoesNotExist) => {
+            log::error!("Ban reason does not exist");
+            Ok(false)
+        }
+    }
+}
+
+/// Unban post on behalf of deleted user placeholder. Returns unbanned post, or `None` if it does
+/// not exist.
+pub async fn run_unban_post_with_pool(
+    pool: &PgPool,
+    id: i64,
+) -> Result<Option<Post>, error::Error> {
+    let Some(post) = try_get_post(id, pool, &Authentication::system()).await? else {
+        return Ok(None);
+    };
+
+    Ok(try_unban_post_check_exists(id, DELETED_USERNAME, pool)
+        .await?
+        .map(|()| post))
+}
+
+/// Returns `false` if post does not exist.
+pub async fn run_unban_post(config: Config, id: i64) -> Result<bool, error::Error> {
+    let pool = get_pool(&config).await?;
+
+    match run_unban_post_with_pool(&pool, id).await? {
+        Some(post) => {
+            println!("{}", post.title);
+            info!("Post {} successfully unbanned", id);
+            Ok(true)
+        }
+        None => {
+            log::error!("Post {} does not exist", id);
+            Ok(false)
+        }
+    }
+}
+
+const BACKUP_PAGE_SIZE: u64 = 100;
+
+pub async fn run_export_posts(config: Config, output: PathBuf) -> Result<(), error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let exported_count = app::backup::export_posts(
+        &output,
+        BACKUP_PAGE_SIZE,
+        &config.upload_config.storage,
+        &pool,
+    )
+    .await?;
+    info!("Exported {} posts", exported_count);
+
+    Ok(())
+}
+
+/// Returns `false` if author does not exist.
+pub async fn run_import_posts(
+    config: Config,
+    input: PathBuf,
+    author: String,
+) -> Result<bool, error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let author = match try_get_user(&author, &pool).await? {
+        Some(author) if author.username != DELETED_USERNAME => author,
+        _ => {
+            log::error!("User {} does not exist", author);
+            return Ok(false);
+        }
+    };
+    let imported_count = app::backup::import_posts(
+        &input,
+        &author,
+        BACKUP_PAGE_SIZE,
+        &config.upload_config.storage,
+        &pool,
+    )
+    .await?;
+    info!("Imported {} posts", imported_count);
+
+    Ok(true)
+}
+
+pub async fn run_generate_invites(
+    config: Config,
+    count: u32,
+    expires_in: Option<Duration>,
+    max_uses: i32,
+) -> Result<(), error::Error> {
+    let pool = get_pool(&config).await?;
+
+    let expires_at = expires_in.map(|expires_in| OffsetDateTime::now_utc() + expires_in);
+    let invite_codes = add_random_invites(count, None, expires_at, max_uses, &pool).await?;
+    info!("Generated {} invite codes", invite_codes.len());
+    for invite_code in invite_codes {
+        println!("{}", invite_code);
+    }
+
+    Ok(())
+}
+
+/// Print config problems. Returns `false` if any of them is fatal.
+pub fn run_check_config(config: Config) -> bool {
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("Config is valid");
+    }
+    for problem in problems.iter() {
+        println!("{}", problem);
+    }
+
+    !problems.iter().any(ConfigProblem::is_fatal)
+}
+
 pub fn run_pack_with_paths(
     asset_manifest_path: &Path,
     asset_cache_manifest_path: &Path,
@@ -320,18 +1813,28 @@ pub fn main() {
     let rocket = rocket::build();
     let figment = rocket.figment();
 
-    let config: Config = figment.extract().unwrap();
+    let config: Config = match figment.extract() {
+        Ok(config) => config,
+        Err(err) => {
+            for error in err {
+                log::error!("Config error: {}", error);
+            }
+            std::process::exit(1);
+        }
+    };
 
     match opts.subcmd {
         CLISubcommand::Run => {
-            Runtime::new()
-                .unwrap()
-                .block_on(run(rocket, config))
-                .unwrap();
+            rocket::execute(run(rocket, config)).unwrap();
         }
         CLISubcommand::Pack => {
             run_pack(config).unwrap();
         }
+        CLISubcommand::CheckConfig => {
+            if !run_check_config(config) {
+                std::process::exit(1);
+            }
+        }
         CLISubcommand::AddUser {
             username,
             is_uploader,
@@ -349,11 +1852,89 @@ pub fn main() {
                 ))
                 .unwrap();
         }
+        CLISubcommand::ListUsers {
+            filter,
+            status,
+            json,
+        } => {
+            Runtime::new()
+                .unwrap()
+                .block_on(run_list_users(config, filter, status, json))
+                .unwrap();
+        }
+        CLISubcommand::SetPassword { username, random } => {
+            let password = if random {
+                None
+            } else {
+                Some(prompt_password("Type new password: ").unwrap())
+            };
+            let user_exists = Runtime::new()
+                .unwrap()
+                .block_on(run_set_password(config, username, password))
+                .unwrap();
+            if !user_exists {
+                std::process::exit(1);
+            }
+        }
         CLISubcommand::CleanupStorage { page_size } => {
             Runtime::new()
                 .unwrap()
                 .block_on(run_cleanup_storage(config, page_size))
                 .unwrap();
         }
+        CLISubcommand::Stats { json } => {
+            Runtime::new()
+                .unwrap()
+                .block_on(run_stats(config, json))
+                .unwrap();
+        }
+        CLISubcommand::BanPost {
+            id,
+            reason_id,
+            text,
+            hide_files,
+        } => {
+            let is_banned = Runtime::new()
+                .unwrap()
+                .block_on(run_ban_post(config, id, reason_id, text, hide_files))
+                .unwrap();
+            if !is_banned {
+                std::process::exit(1);
+            }
+        }
+        CLISubcommand::UnbanPost { id } => {
+            let is_unbanned = Runtime::new()
+                .unwrap()
+                .block_on(run_unban_post(config, id))
+                .unwrap();
+            if !is_unbanned {
+                std::process::exit(1);
+            }
+        }
+        CLISubcommand::ExportPosts { output } => {
+            Runtime::new()
+                .unwrap()
+                .block_on(run_export_posts(config, output))
+                .unwrap();
+        }
+        CLISubcommand::ImportPosts { input, author } => {
+            let author_exists = Runtime::new()
+                .unwrap()
+                .block_on(run_import_posts(config, input, author))
+                .unwrap();
+            if !author_exists {
+                std::process::exit(1);
+            }
+        }
+        CLISubcommand::GenerateInvites {
+            count,
+            expires_in,
+            max_uses,
+        } => {
+            Runtime::new()
+                .unwrap()
+                .block_on(run_generate_invites(config, count, expires_in, max_uses))
+                .unwrap();
+        }
     }
 }
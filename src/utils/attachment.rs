@@ -0,0 +1,45 @@
+use std::io::Cursor;
+
+use askama_rocket::Responder;
+use rocket::{
+    http::{ContentType, Header},
+    response, Request, Response,
+};
+use tokio::io::DuplexStream;
+
+pub struct CsvAttachment {
+    pub filename: String,
+    pub body: String,
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for CsvAttachment {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'o> {
+        Response::build()
+            .header(ContentType::CSV)
+            .header(Header::new(
+                "content-disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .sized_body(self.body.len(), Cursor::new(self.body))
+            .ok()
+    }
+}
+
+/// ZIP archive which is sent while it is written to `reader`.
+pub struct ZipAttachment {
+    pub filename: String,
+    pub reader: DuplexStream,
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for ZipAttachment {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'o> {
+        Response::build()
+            .header(ContentType::ZIP)
+            .header(Header::new(
+                "content-disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .streamed_body(self.reader)
+            .ok()
+    }
+}
@@ -1,10 +1,20 @@
+use std::borrow::Cow;
+
 use html_escape::{encode_quoted_attribute, encode_text};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::locale::Locale;
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Breadcrumb {
     pub page_name: String,
     pub page_url: Option<String>,
+    /// If set, `page_name` is message key in locale catalog.
+    #[serde(default)]
+    pub is_message: bool,
+    /// Values of `{name}` placeholders in message.
+    #[serde(default)]
+    pub message_args: Vec<(String, String)>,
 }
 
 impl Breadcrumb {
@@ -12,6 +22,8 @@ impl Breadcrumb {
         Self {
             page_name,
             page_url: Some(page_url),
+            is_message: false,
+            message_args: Vec::new(),
         }
     }
 
@@ -19,19 +31,66 @@ impl Breadcrumb {
         Self {
             page_name,
             page_url: None,
+            is_message: false,
+            message_args: Vec::new(),
+        }
+    }
+
+    pub fn message_with_url(key: &str, page_url: String) -> Self {
+        Self {
+            page_name: key.to_string(),
+            page_url: Some(page_url),
+            is_message: true,
+            message_args: Vec::new(),
+        }
+    }
+
+    pub fn message_without_url(key: &str) -> Self {
+        Self {
+            page_name: key.to_string(),
+            page_url: None,
+            is_message: true,
+            message_args: Vec::new(),
+        }
+    }
+
+    pub fn message_with_args_without_url(key: &str, args: &[(&str, &str)]) -> Self {
+        Self {
+            page_name: key.to_string(),
+            page_url: None,
+            is_message: true,
+            message_args: args
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    pub fn name(&self, locale: &Locale) -> Cow<'_, str> {
+        if !self.is_message {
+            Cow::Borrowed(&self.page_name)
+        } else if self.message_args.is_empty() {
+            Cow::Borrowed(locale.translate(&self.page_name))
+        } else {
+            let args: Vec<_> = self
+                .message_args
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect();
+            Cow::Owned(locale.translate_with_args(&self.page_name, &args))
         }
     }
 
-    pub fn render(&self) -> String {
+    pub fn render(&self, locale: &Locale) -> String {
         match &self.page_url {
             Some(page_url_real) => {
                 "<a href=\"".to_string()
                     + &encode_quoted_attribute(&page_url_real)
                     + "\">"
-                    + &encode_text(&self.page_name)
+                    + &encode_text(&self.name(locale))
                     + "</a>"
             }
-            None => encode_text(&self.page_name).to_string(),
+            None => encode_text(&self.name(locale)).to_string(),
         }
     }
 }
@@ -0,0 +1,134 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use rocket::{
+    http::{Header, Method, Status},
+    response::{self, Responder},
+    Request, Response,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const ETAG_HEADER: &str = "ETag";
+const LAST_MODIFIED_HEADER: &str = "Last-Modified";
+const IF_NONE_MATCH_HEADER: &str = "If-None-Match";
+const IF_MODIFIED_SINCE_HEADER: &str = "If-Modified-Since";
+
+/// Strong entity tag built from hash of everything which response content depends on.
+pub fn make_etag<T: Serialize>(value: &T) -> Result<String, crate::error::Error> {
+    Ok(format!(
+        "\"{}\"",
+        BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(serde_json::to_vec(value)?))
+    ))
+}
+
+/// Check `If-None-Match` header value with weak comparison, as required for this header.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// HTTP dates have precision of seconds, so modification times are compared in seconds.
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Responder which is replaced with `304 Not Modified` if client already has current version of
+/// response, checked with `If-None-Match` against `etag` or, if it is not sent, with
+/// `If-Modified-Since` against `last_modified`. Successful responses get `ETag` and
+/// `Last-Modified` headers.
+pub struct Conditional<R> {
+    pub inner: R,
+    pub etag: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl<R> Conditional<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    pub fn with_etag(self, etag: String) -> Self {
+        Self {
+            etag: Some(etag),
+            ..self
+        }
+    }
+
+    pub fn with_last_modified(self, last_modified: SystemTime) -> Self {
+        Self {
+            last_modified: Some(last_modified),
+            ..self
+        }
+    }
+
+    fn is_not_modified(&self, request: &Request<'_>) -> bool {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            return false;
+        }
+
+        if let Some(if_none_match) = request.headers().get_one(IF_NONE_MATCH_HEADER) {
+            return self
+                .etag
+                .as_deref()
+                .is_some_and(|etag| etag_matches(if_none_match, etag));
+        }
+
+        match (
+            request.headers().get_one(IF_MODIFIED_SINCE_HEADER),
+            self.last_modified,
+        ) {
+            (Some(if_modified_since), Some(last_modified)) => {
+                match httpdate::parse_http_date(if_modified_since) {
+                    Ok(since) => unix_seconds(last_modified) <= unix_seconds(since),
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn get_validator_headers(&self) -> Vec<Header<'static>> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(Header::new(ETAG_HEADER, etag.clone()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.push(Header::new(
+                LAST_MODIFIED_HEADER,
+                httpdate::fmt_http_date(last_modified),
+            ));
+        }
+        headers
+    }
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Conditional<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let validator_headers = self.get_validator_headers();
+
+        let mut response = if self.is_not_modified(request) {
+            Response::build().status(Status::NotModified).finalize()
+        } else {
+            let response = self.inner.respond_to(request)?;
+            if !response.status().class().is_success() {
+                return Ok(response);
+            }
+            response
+        };
+        for header in validator_headers {
+            response.set_header(header);
+        }
+        Ok(response)
+    }
+}
@@ -10,7 +10,10 @@ use rocket::{
     Data, Request,
 };
 
-use crate::utils::csrf_lib::{CsrfToken, VerificationFailure};
+use crate::{
+    auth::get_api_token_scope,
+    utils::csrf_lib::{CsrfToken, VerificationFailure},
+};
 
 pub const COOKIE_NAME: &str = "csrf_token";
 
@@ -88,6 +91,10 @@ pub trait CheckCSRF {
     fn check_csrf(&self, token: &CsrfToken) -> Result<(), VerificationFailure>;
 }
 
+/// CSRF check for API requests. Request authenticated with API token is accepted without check
+/// (browser does not send API token by itself, so CSRF is not possible), `X-CSRF-Token` header is
+/// ignored then, even if it is invalid. Otherwise `X-CSRF-Token` header must contain token for
+/// CSRF cookie, it can be taken from page or from `GET /api/csrf`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct HeaderCSRF {}
 
@@ -97,6 +104,10 @@ impl<'r> FromRequest<'r> for HeaderCSRF {
 
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
         *req.local_cache_async(async {
+            // API token takes precedence over cookies.
+            if get_api_token_scope(req).await.is_some() {
+                return request::Outcome::Success(Self {});
+            }
             let csrf: request::Outcome<CsrfToken, _> = req.guard().await;
             match csrf {
                 request::Outcome::Success(csrf_token) => {
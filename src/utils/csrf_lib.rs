@@ -1,19 +1,26 @@
-use base64::{prelude::BASE64_STANDARD_NO_PAD, Engine};
-use bcrypt::{hash, verify};
+use base64::{
+    prelude::{BASE64_STANDARD_NO_PAD, BASE64_URL_SAFE_NO_PAD},
+    Engine,
+};
+use hmac::{Hmac, Mac};
 use rand::{distributions::Standard, Rng};
 use rocket::{
     async_trait,
     fairing::{self, Fairing as RocketFairing, Info, Kind},
-    http::{Cookie, Status},
+    http::{Cookie, CookieJar, Status},
     request::{FromRequest, Outcome},
     time::{Duration, OffsetDateTime},
     Data, Request, Rocket, State,
 };
+use sha2::{Digest, Sha256};
 use std::borrow::Cow;
 
+use crate::CookieSecurity;
+
 // taken from https://github.com/kotovalexarian/rocket_csrf
 
-const BCRYPT_COST: u32 = 8;
+/// Prefix of tokens made by previous bcrypt-based scheme.
+const LEGACY_TOKEN_PREFIX: &str = "$2";
 
 #[derive(Debug, Clone)]
 pub struct CsrfConfig {
@@ -23,13 +30,30 @@ pub struct CsrfConfig {
     cookie_name: Cow<'static, str>,
     /// CSRF Token character length
     cookie_len: usize,
+    /// Cookie is re-issued if it expires earlier than this
+    refresh_before: Duration,
+    /// Accept bcrypt hashes of cookie value made before tokens were signed with HMAC
+    accept_legacy_tokens: bool,
+    /// Cookie name prefix and attributes
+    cookie_security: CookieSecurity,
 }
 
 pub struct Fairing {
     config: CsrfConfig,
 }
 
-pub struct CsrfToken(pub String);
+/// Key for HMAC of cookie value, derived from Rocket secret key.
+#[derive(Clone)]
+pub struct CsrfKey {
+    key: [u8; 32],
+}
+
+pub struct CsrfToken {
+    /// Cookie value, it is same for all forms until cookie expires.
+    pub value: String,
+    key: CsrfKey,
+    accept_legacy_tokens: bool,
+}
 
 pub struct VerificationFailure;
 
@@ -43,9 +67,12 @@ impl Default for CsrfConfig {
     fn default() -> Self {
         Self {
             // Set to 6hour for default in Database Session stores.
-            lifespan: Duration::days(1), // TODO: refresh
+            lifespan: Duration::days(1),
             cookie_name: "csrf_token".into(),
             cookie_len: 32,
+            refresh_before: Duration::hours(6),
+            accept_legacy_tokens: false,
+            cookie_security: CookieSecurity::default(),
         }
     }
 }
@@ -78,15 +105,87 @@ impl CsrfConfig {
         self.cookie_len = length;
         self
     }
+
+    /// Set how long before expiry cookie is re-issued, keep this below lifetime.
+    ///
+    pub fn with_refresh_before(mut self, time: Duration) -> Self {
+        self.refresh_before = time;
+        self
+    }
+}
+
+impl CsrfConfig {
+    /// Set CSRF cookie name prefix and attributes.
+    ///
+    pub fn with_cookie_security(mut self, cookie_security: CookieSecurity) -> Self {
+        self.cookie_security = cookie_security;
+        self
+    }
+
+    /// Set whether tokens made by previous bcrypt-based scheme are accepted.
+    ///
+    pub fn with_legacy_tokens(mut self, accept_legacy_tokens: bool) -> Self {
+        self.accept_legacy_tokens = accept_legacy_tokens;
+        self
+    }
+}
+
+impl CsrfKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: Sha256::new()
+                .chain_update(b"csrf:")
+                .chain_update(secret)
+                .finalize()
+                .into(),
+        }
+    }
+
+    /// Use configured secret key or random key if it is not set (tokens will not survive restart
+    /// then).
+    pub fn from_secret_key(secret_key: Option<&str>) -> Self {
+        match secret_key {
+            Some(secret_key) => Self::new(secret_key.as_bytes()),
+            None => {
+                let secret: Vec<u8> = rand::thread_rng().sample_iter(Standard).take(32).collect();
+                Self::new(&secret)
+            }
+        }
+    }
+
+    fn mac(&self, value: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(value.as_bytes());
+        mac
+    }
 }
 
 impl CsrfToken {
+    pub fn new(value: String, key: CsrfKey, accept_legacy_tokens: bool) -> Self {
+        Self {
+            value,
+            key,
+            accept_legacy_tokens,
+        }
+    }
+
+    /// Token for forms and `X-CSRF-Token` header: HMAC-SHA256 of cookie value. It is much cheaper
+    /// than password hash, and cookie value is random, so slow hash gives nothing.
     pub fn authenticity_token(&self) -> String {
-        hash(&self.0, BCRYPT_COST).unwrap()
+        BASE64_URL_SAFE_NO_PAD.encode(self.key.mac(&self.value).finalize().into_bytes())
     }
 
     pub fn verify(&self, form_authenticity_token: &str) -> Result<(), VerificationFailure> {
-        if verify(&self.0, form_authenticity_token).unwrap_or(false) {
+        let is_valid = if form_authenticity_token.starts_with(LEGACY_TOKEN_PREFIX) {
+            self.accept_legacy_tokens
+                && bcrypt::verify(&self.value, form_authenticity_token).unwrap_or(false)
+        } else {
+            BASE64_URL_SAFE_NO_PAD
+                .decode(form_authenticity_token)
+                .is_ok_and(|signature| self.key.mac(&self.value).verify_slice(&signature).is_ok())
+        };
+        if is_valid {
             Ok(())
         } else {
             Err(VerificationFailure {})
@@ -94,6 +193,79 @@ impl CsrfToken {
     }
 }
 
+/// CSRF token from cookie, cookie value is `token.issued_at` (`issued_at` is Unix timestamp).
+/// Cookies made before issue time was added have no `issued_at`, they are refreshed on next
+/// request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SessionCsrfToken {
+    value: Vec<u8>,
+    issued_at: Option<OffsetDateTime>,
+}
+
+impl SessionCsrfToken {
+    fn generate(config: &CsrfConfig) -> Self {
+        Self {
+            value: rand::thread_rng()
+                .sample_iter(Standard)
+                .take(config.cookie_len)
+                .collect(),
+            issued_at: None,
+        }
+    }
+
+    fn parse(cookie_value: &str) -> Option<Self> {
+        let (value, issued_at) = match cookie_value.split_once('.') {
+            Some((value, issued_at)) => (
+                value,
+                Some(OffsetDateTime::from_unix_timestamp(issued_at.parse().ok()?).ok()?),
+            ),
+            None => (cookie_value, None),
+        };
+        Some(Self {
+            value: BASE64_STANDARD_NO_PAD.decode(value).ok()?,
+            issued_at,
+        })
+    }
+
+    fn is_valid(&self, config: &CsrfConfig, now: OffsetDateTime) -> bool {
+        self.value.len() >= config.cookie_len
+            && self
+                .issued_at
+                .is_none_or(|issued_at| issued_at + config.lifespan > now)
+    }
+
+    fn needs_refresh(&self, config: &CsrfConfig, now: OffsetDateTime) -> bool {
+        self.issued_at
+            .is_none_or(|issued_at| issued_at + config.lifespan - config.refresh_before <= now)
+    }
+
+    /// Set cookie with this token, issued at `now`.
+    fn add_cookie(&mut self, cookies: &CookieJar<'_>, config: &CsrfConfig, now: OffsetDateTime) {
+        self.issued_at = Some(now);
+        let cookie_value = format!(
+            "{}.{}",
+            BASE64_STANDARD_NO_PAD.encode(&self.value),
+            now.unix_timestamp()
+        );
+        cookies.add_private(
+            config.cookie_security.apply(
+                Cookie::build((config.cookie_name.clone(), cookie_value))
+                    .expires(now + config.lifespan),
+            ),
+        );
+    }
+}
+
+/// Token for current request, it is selected by fairing (see [`Fairing::on_request`]).
+struct RequestCsrfToken(Option<SessionCsrfToken>);
+
+/// Replace CSRF token with new one. It should be done when user logs in, so that token known
+/// before login can not be used with new session. Forms on current page keep old token, so this
+/// should be followed by redirect.
+pub fn rotate_csrf_token(cookies: &CookieJar<'_>, config: &CsrfConfig) {
+    SessionCsrfToken::generate(config).add_cookie(cookies, config, OffsetDateTime::now_utc());
+}
+
 #[async_trait]
 impl RocketFairing for Fairing {
     fn info(&self) -> Info {
@@ -104,28 +276,36 @@ impl RocketFairing for Fairing {
     }
 
     async fn on_ignite(&self, rocket: Rocket<rocket::Build>) -> fairing::Result {
-        Ok(rocket.manage(self.config.clone()))
+        let key = CsrfKey::from_secret_key(
+            rocket
+                .figment()
+                .extract_inner::<String>("secret_key")
+                .ok()
+                .as_deref(),
+        );
+        Ok(rocket.manage(self.config.clone()).manage(key))
     }
 
+    /// Keep valid token, re-issue cookie with same token if it is close to expiry (so that forms
+    /// which are already open still work) or issue new token.
     async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
         let config = request.guard::<&State<CsrfConfig>>().await.unwrap();
+        let now = OffsetDateTime::now_utc();
 
-        if request.valid_csrf_token_from_session(config).is_some() {
-            return;
+        let mut token = match request
+            .cookies()
+            .get_private(&config.cookie_security.cookie_name(&config.cookie_name))
+            .and_then(|cookie| SessionCsrfToken::parse(cookie.value()))
+            .filter(|token| token.is_valid(config, now))
+        {
+            Some(token) => token,
+            None => SessionCsrfToken::generate(config),
+        };
+        if token.needs_refresh(config, now) {
+            token.add_cookie(request.cookies(), config, now);
         }
 
-        let values: Vec<u8> = rand::thread_rng()
-            .sample_iter(Standard)
-            .take(config.cookie_len)
-            .collect();
-
-        let encoded = BASE64_STANDARD_NO_PAD.encode(&values[..]);
-
-        let expires = OffsetDateTime::now_utc() + config.lifespan;
-
-        request
-            .cookies()
-            .add_private(Cookie::build((config.cookie_name.clone(), encoded)).expires(expires));
+        request.local_cache(|| RequestCsrfToken(Some(token)));
     }
 }
 
@@ -135,34 +315,15 @@ impl<'r> FromRequest<'r> for CsrfToken {
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
         let config = request.guard::<&State<CsrfConfig>>().await.unwrap();
+        let key = request.guard::<&State<CsrfKey>>().await.unwrap();
 
-        match request.valid_csrf_token_from_session(config) {
+        match &request.local_cache(|| RequestCsrfToken(None)).0 {
             None => Outcome::Error((Status::Forbidden, ())),
-            Some(token) => Outcome::Success(Self(BASE64_STANDARD_NO_PAD.encode(token))),
+            Some(token) => Outcome::Success(Self::new(
+                BASE64_STANDARD_NO_PAD.encode(&token.value),
+                key.inner().clone(),
+                config.accept_legacy_tokens,
+            )),
         }
     }
 }
-
-trait RequestCsrf {
-    fn valid_csrf_token_from_session(&self, config: &CsrfConfig) -> Option<Vec<u8>> {
-        self.csrf_token_from_session(config).and_then(|raw| {
-            if raw.len() >= config.cookie_len {
-                Some(raw)
-            } else {
-                None
-            }
-        })
-    }
-
-    fn csrf_token_from_session(&self, config: &CsrfConfig) -> Option<Vec<u8>>;
-}
-
-impl RequestCsrf for Request<'_> {
-    fn csrf_token_from_session(&self, config: &CsrfConfig) -> Option<Vec<u8>> {
-        let cookies = self.cookies();
-        cookies
-            .get_private(&config.cookie_name)
-            .or_else(|| cookies.get_pending(&config.cookie_name))
-            .and_then(|cookie| BASE64_STANDARD_NO_PAD.decode(cookie.value()).ok())
-    }
-}
@@ -0,0 +1,35 @@
+use std::io::Cursor;
+
+use askama::Template;
+use askama_rocket::Responder;
+use rocket::{
+    http::{ContentType, Header, Status},
+    response, Request, Response,
+};
+
+/// Time during which clients and proxies may reuse feed without requesting it again.
+pub const FEED_CACHE_MAX_AGE: u64 = 10 * 60;
+
+/// Feed rendered with template, which is sent with its own content type instead of one derived
+/// from template extension.
+pub struct FeedResponse<T: Template> {
+    pub template: T,
+    pub content_type: ContentType,
+}
+
+impl<'r, 'o: 'r, T: Template> Responder<'r, 'o> for FeedResponse<T> {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'o> {
+        let response = self
+            .template
+            .render()
+            .map_err(|_| Status::InternalServerError)?;
+        Response::build()
+            .header(self.content_type)
+            .header(Header::new(
+                "Cache-Control",
+                format!("public, max-age={}", FEED_CACHE_MAX_AGE),
+            ))
+            .sized_body(response.len(), Cursor::new(response))
+            .ok()
+    }
+}
@@ -0,0 +1,321 @@
+use std::{io::SeekFrom, path::PathBuf};
+
+use rocket::{
+    async_trait,
+    http::{
+        uri::{fmt::Path, Origin, Segments},
+        ContentType, Header, Method, Status,
+    },
+    request::{self, FromRequest},
+    response::{self, Responder},
+    route::{self, Handler},
+    Data, Request, Response, Route,
+};
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use crate::{
+    app::{db::is_upload_age_restricted, storage::parse_file_name},
+    utils::{
+        conditional::Conditional,
+        signed_token::{SignedTokenKey, FILE_TOKEN_PARAMETER},
+    },
+};
+
+const RANGE_HEADER: &str = "Range";
+const IF_RANGE_HEADER: &str = "If-Range";
+const ORIGINAL_URI_HEADER: &str = "X-Original-URI";
+
+/// Part of file requested with `Range` header, only single byte ranges are supported, other
+/// requests get full file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    Full,
+    Partial { start: u64, end: u64 },
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    pub fn parse(header: &str, file_size: u64) -> Self {
+        let range = match header.trim().strip_prefix("bytes=") {
+            Some(range) if !range.contains(',') => range,
+            _ => return Self::Full,
+        };
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => return Self::Full,
+        };
+
+        if start.is_empty() {
+            return match end.parse::<u64>() {
+                Ok(0) => Self::Unsatisfiable,
+                Ok(_) if file_size == 0 => Self::Unsatisfiable,
+                Ok(suffix_length) => Self::Partial {
+                    start: file_size.saturating_sub(suffix_length),
+                    end: file_size - 1,
+                },
+                Err(_) => Self::Full,
+            };
+        }
+
+        let start = match start.parse::<u64>() {
+            Ok(start) => start,
+            Err(_) => return Self::Full,
+        };
+        let end = if end.is_empty() {
+            u64::MAX
+        } else {
+            match end.parse::<u64>() {
+                Ok(end) if end >= start => end,
+                _ => return Self::Full,
+            }
+        };
+        if start >= file_size {
+            Self::Unsatisfiable
+        } else {
+            Self::Partial {
+                start,
+                end: end.min(file_size - 1),
+            }
+        }
+    }
+}
+
+/// Check if file can be shown by browser on site origin. Only images (except SVG, which can
+/// contain scripts), video and audio are shown, other files (like uploaded HTML pages) are
+/// downloaded.
+pub fn is_inline_content_type(content_type: &ContentType) -> bool {
+    (content_type.top() == "image" && *content_type != ContentType::SVG)
+        || content_type.top() == "video"
+        || content_type.top() == "audio"
+}
+
+/// File response with support of `Range` requests, file should be already positioned at start
+/// of range.
+pub struct RangedFile {
+    pub file: File,
+    pub file_size: u64,
+    pub range: ByteRange,
+    pub content_type: Option<ContentType>,
+}
+
+impl<'r> Responder<'r, 'static> for RangedFile {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response.raw_header("Accept-Ranges", "bytes");
+        if !self
+            .content_type
+            .as_ref()
+            .is_some_and(is_inline_content_type)
+        {
+            response.raw_header("Content-Disposition", "attachment");
+        }
+        if let Some(content_type) = self.content_type {
+            response.header(content_type);
+        }
+        match self.range {
+            ByteRange::Full => response.sized_body(self.file_size as usize, self.file),
+            ByteRange::Partial { start, end } => response
+                .status(Status::PartialContent)
+                .header(Header::new(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, end, self.file_size),
+                ))
+                .streamed_body(self.file.take(end - start + 1)),
+            ByteRange::Unsatisfiable => {
+                response
+                    .status(Status::RangeNotSatisfiable)
+                    .header(Header::new(
+                        "Content-Range",
+                        format!("bytes */{}", self.file_size),
+                    ))
+            }
+        };
+        response.ok()
+    }
+}
+
+/// Check if file is upload of age-restricted post, which is served only with signed URL. Files
+/// are refused if database can not be checked.
+async fn is_token_required(file_name: &str, pool: Option<&Pool<Postgres>>) -> bool {
+    let upload_id = match parse_file_name(file_name) {
+        Some(upload_id) => upload_id,
+        None => return false,
+    };
+    match pool {
+        Some(pool) => is_upload_age_restricted(upload_id, pool)
+            .await
+            .unwrap_or(true),
+        None => true,
+    }
+}
+
+/// Check token of signed URL (see [`crate::app::storage::get_signed_file_url`]). Files of
+/// age-restricted posts are served only with valid token, other files are served without it.
+pub async fn is_file_access_allowed(
+    signed_token_key: &SignedTokenKey,
+    token: Option<&str>,
+    file_name: &str,
+    pool: Option<&Pool<Postgres>>,
+) -> bool {
+    match token {
+        Some(token) => signed_token_key
+            .verify_file_name(token, file_name, OffsetDateTime::now_utc())
+            .is_ok(),
+        None => !is_token_required(file_name, pool).await,
+    }
+}
+
+/// URI of request which is checked by proxy with subrequest, it is passed in `X-Original-URI`
+/// header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OriginalUri(pub Origin<'static>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for OriginalUri {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req
+            .headers()
+            .get_one(ORIGINAL_URI_HEADER)
+            .and_then(|header| Origin::parse_owned(header.to_string()).ok())
+        {
+            Some(uri) => request::Outcome::Success(Self(uri)),
+            None => request::Outcome::Error((Status::BadRequest, ())),
+        }
+    }
+}
+
+/// Replacement of [`rocket::fs::FileServer`] which sends `Last-Modified` header with file
+/// modification time, answers `If-Modified-Since` requests with `304 Not Modified` and supports
+/// `Range` requests. If signed token key is set, tokens of signed URLs are checked and files of
+/// age-restricted posts are served only with them.
+#[derive(Clone, Debug)]
+pub struct ConditionalFileServer {
+    root: PathBuf,
+    rank: isize,
+    signed_token_key: Option<SignedTokenKey>,
+}
+
+impl ConditionalFileServer {
+    const DEFAULT_RANK: isize = 10;
+
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            root: path.into(),
+            rank: Self::DEFAULT_RANK,
+            signed_token_key: None,
+        }
+    }
+
+    pub fn with_signed_token_key(mut self, signed_token_key: SignedTokenKey) -> Self {
+        self.signed_token_key = Some(signed_token_key);
+        self
+    }
+
+    /// Check access to file with [`is_file_access_allowed`] if signed token key is set.
+    async fn is_access_allowed(
+        &self,
+        request: &Request<'_>,
+        relative_path: &std::path::Path,
+    ) -> bool {
+        let signed_token_key = match &self.signed_token_key {
+            Some(signed_token_key) => signed_token_key,
+            None => return true,
+        };
+        let file_name = match relative_path.to_str() {
+            Some(file_name) => file_name,
+            None => return false,
+        };
+        let token = match request.query_value::<&str>(FILE_TOKEN_PARAMETER) {
+            Some(Ok(token)) => Some(token),
+            Some(Err(_)) => return false,
+            None => None,
+        };
+        is_file_access_allowed(
+            signed_token_key,
+            token,
+            file_name,
+            request.rocket().state::<Pool<Postgres>>(),
+        )
+        .await
+    }
+
+    async fn open(
+        &self,
+        request: &Request<'_>,
+        path: PathBuf,
+    ) -> std::io::Result<Option<Conditional<RangedFile>>> {
+        let mut file = File::open(&path).await?;
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return Ok(None);
+        }
+        let file_size = metadata.len();
+        let last_modified = metadata.modified().ok();
+
+        let if_range_matches = match (request.headers().get_one(IF_RANGE_HEADER), last_modified) {
+            (None, _) => true,
+            (Some(if_range), Some(last_modified)) => {
+                if_range.trim() == httpdate::fmt_http_date(last_modified)
+            }
+            (Some(_), None) => false,
+        };
+        let range = match request.headers().get_one(RANGE_HEADER) {
+            Some(range) if if_range_matches => ByteRange::parse(range, file_size),
+            _ => ByteRange::Full,
+        };
+        if let ByteRange::Partial { start, .. } = range {
+            file.seek(SeekFrom::Start(start)).await?;
+        }
+
+        let ranged_file = RangedFile {
+            file,
+            file_size,
+            range,
+            content_type: path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(ContentType::from_extension),
+        };
+        Ok(Some(match last_modified {
+            Some(last_modified) => Conditional::new(ranged_file).with_last_modified(last_modified),
+            None => Conditional::new(ranged_file),
+        }))
+    }
+}
+
+#[async_trait]
+impl Handler for ConditionalFileServer {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        let relative_path = request
+            .segments::<Segments<'_, Path>>(0..)
+            .ok()
+            .and_then(|segments| segments.to_path_buf(false).ok());
+        let relative_path = match relative_path {
+            Some(relative_path) => relative_path,
+            None => return route::Outcome::forward(data, Status::NotFound),
+        };
+        if !self.is_access_allowed(request, &relative_path).await {
+            return route::Outcome::Error(Status::Forbidden);
+        }
+
+        match self.open(request, self.root.join(relative_path)).await {
+            Ok(Some(response)) => route::Outcome::from(request, response),
+            Ok(None) | Err(_) => route::Outcome::forward(data, Status::NotFound),
+        }
+    }
+}
+
+impl From<ConditionalFileServer> for Vec<Route> {
+    fn from(server: ConditionalFileServer) -> Self {
+        let mut route = Route::ranked(server.rank, Method::Get, "/<path..>", server.clone());
+        route.name = Some(format!("ConditionalFileServer: {}", server.root.display()).into());
+        vec![route]
+    }
+}
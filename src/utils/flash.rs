@@ -0,0 +1,121 @@
+use std::fmt::{Display, Formatter};
+
+use rocket::{
+    async_trait,
+    http::{Cookie, CookieJar, Status},
+    request::{self, FromRequest},
+    time::Duration,
+    Request,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::locale::Locale;
+
+pub const COOKIE_NAME: &str = "flash_message";
+
+/// Flash message cookie expires if it was not shown in this time (for example, if redirect was
+/// not followed).
+const COOKIE_LIFETIME: Duration = Duration::minutes(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLevel {
+    Success,
+    Info,
+    Error,
+}
+
+impl FlashLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Info => "info",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl Display for FlashLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One-time message shown on the next page (usually redirect target) in base template.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashMessage {
+    pub level: FlashLevel,
+    /// Message key in locale catalog.
+    pub message: String,
+    /// Values of `{name}` placeholders in message.
+    #[serde(default)]
+    pub args: Vec<(String, String)>,
+}
+
+impl FlashMessage {
+    pub fn new(level: FlashLevel, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Self::new(FlashLevel::Success, message)
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(FlashLevel::Info, message)
+    }
+
+    pub fn with_arg(mut self, name: &str, value: impl ToString) -> Self {
+        self.args.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn text(&self, locale: &Locale) -> String {
+        let args: Vec<_> = self
+            .args
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        locale.translate_with_args(&self.message, &args)
+    }
+}
+
+/// Store one-time message to show on the next page. Message set earlier in the same request is
+/// replaced.
+pub fn set_flash_message(cookies: &CookieJar<'_>, message: FlashMessage) {
+    cookies.add_private(
+        Cookie::build((
+            COOKIE_NAME,
+            serde_json::to_string(&message).unwrap_or_default(),
+        ))
+        .path("/")
+        .max_age(COOKIE_LIFETIME),
+    );
+}
+
+/// Get one-time message and remove it, so it will be shown only once.
+pub fn take_flash_message(cookies: &CookieJar<'_>) -> Option<FlashMessage> {
+    let value = cookies
+        .get_private(COOKIE_NAME)
+        .map(|cookie| cookie.value().to_string())?;
+    cookies.remove_private(Cookie::build(COOKIE_NAME).path("/"));
+
+    serde_json::from_str(&value).ok()
+}
+
+/// Guard which takes flash message from cookies, use it as `Option<FlashMessage>`.
+#[async_trait]
+impl<'r> FromRequest<'r> for FlashMessage {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match take_flash_message(req.cookies()) {
+            Some(flash_message) => request::Outcome::Success(flash_message),
+            None => request::Outcome::Forward(Status::NotFound),
+        }
+    }
+}
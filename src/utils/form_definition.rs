@@ -1,15 +1,19 @@
-use std::borrow::Cow;
+use std::collections::HashMap;
 
 use html_escape::{encode_quoted_attribute, encode_text};
 use serde::{Deserialize, Serialize};
 use time::Date;
 use validator::{ValidationError, ValidationErrors};
 
+use crate::utils::locale::Locale;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum FieldData {
     Checkbox(bool),
     Radio(Vec<(String, String)>, Option<String>),
+    Select(Vec<(String, String)>, Option<String>),
+    MultiSelect(Vec<(String, String)>, Vec<String>),
     Date(Option<Date>),
     Number(Option<f64>),
     EMail(Option<String>),
@@ -19,6 +23,138 @@ pub enum FieldData {
     TextArea(Option<String>),
     Password(Option<String>),
     Url(Option<String>),
+    File {
+        accept: Option<String>,
+        multiple: bool,
+        target: FileUploadTarget,
+    },
+}
+
+/// Settings of TypeScript uploader for file input. Files are sent with upload API instead of form,
+/// so they are rendered as data attributes of input.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileUploadTarget {
+    /// Post which files are attached to, `None` for avatar.
+    pub post_id: Option<i64>,
+    pub csrf_token: String,
+    pub max_size: u64,
+}
+
+/// Escape value of attribute in double quotes. Carriage return is escaped too, because HTML parser
+/// replaces it with line feed.
+fn escape_attribute(value: &str) -> String {
+    encode_quoted_attribute(value).replace('\r', "&#13;")
+}
+
+/// Escape text content of element, see [`escape_attribute`].
+fn escape_text(text: &str) -> String {
+    encode_text(text).replace('\r', "&#13;")
+}
+
+/// Builder of HTML element. Attribute values and text are always escaped, so that they are kept as
+/// is after parsing and can not add other elements.
+struct HtmlElement {
+    tag: &'static str,
+    start: String,
+    content: Option<String>,
+}
+
+impl HtmlElement {
+    fn new(tag: &'static str) -> Self {
+        Self {
+            tag,
+            start: "<".to_string() + tag,
+            content: None,
+        }
+    }
+
+    fn attribute(mut self, name: &str, value: &str) -> Self {
+        self.start += &(" ".to_string() + name + "=\"" + &escape_attribute(value) + "\"");
+        self
+    }
+
+    fn optional_attribute(self, name: &str, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => self.attribute(name, value),
+            None => self,
+        }
+    }
+
+    /// Add boolean attribute if `is_set` is `true`.
+    fn flag(mut self, name: &str, is_set: bool) -> Self {
+        if is_set {
+            self.start += &(" ".to_string() + name);
+        }
+        self
+    }
+
+    fn text(self, text: &str) -> Self {
+        self.html(&escape_text(text))
+    }
+
+    /// Add content which is already HTML.
+    fn html(mut self, html: &str) -> Self {
+        self.content.get_or_insert_with(String::new).push_str(html);
+        self
+    }
+
+    /// Render element, element without content is rendered as void element.
+    fn render(self) -> String {
+        match self.content {
+            Some(content) => self.start + ">" + &content + "</" + self.tag + ">",
+            None => self.start + " />",
+        }
+    }
+}
+
+impl FileUploadTarget {
+    fn add_data_attributes(&self, element: HtmlElement) -> HtmlElement {
+        element
+            .attribute("data-max-size", &self.max_size.to_string())
+            .optional_attribute(
+                "data-post-id",
+                self.post_id.map(|post_id| post_id.to_string()).as_deref(),
+            )
+            .attribute("data-csrf-token", &self.csrf_token)
+    }
+}
+
+/// Constraints which are rendered as HTML attributes of input, so that browser can check them
+/// before form is sent. `min` and `max` are limits of value for number fields and limits of
+/// length for text fields.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldConstraints {
+    pub is_required: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+    pub pattern: Option<String>,
+}
+
+impl FieldConstraints {
+    fn add_attributes(&self, element: HtmlElement, field_type: &FieldData) -> HtmlElement {
+        let element = element.flag("required", self.is_required);
+        let (min_name, max_name) = match field_type {
+            FieldData::Number(_) => ("min", "max"),
+            FieldData::EMail(_)
+            | FieldData::Telephone(_)
+            | FieldData::Text(_)
+            | FieldData::TextArea(_)
+            | FieldData::Password(_)
+            | FieldData::Url(_) => ("minlength", "maxlength"),
+            _ => return element,
+        };
+        [
+            (min_name, self.min),
+            (max_name, self.max),
+            ("step", self.step),
+        ]
+        .into_iter()
+        .fold(element, |element, (name, value)| {
+            element.optional_attribute(name, value.map(|value| value.to_string()).as_deref())
+        })
+        .optional_attribute("pattern", self.pattern.as_deref())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -27,358 +163,312 @@ pub struct FieldDefinition {
     pub verbose_name: String,
     pub field_type: FieldData,
     pub errors: Vec<ValidationError>,
+    pub constraints: FieldConstraints,
+    /// Message key of text shown under input.
+    pub help: Option<String>,
+    /// Message key of text shown in empty input.
+    pub placeholder: Option<String>,
+}
+
+/// Render `option` elements of `select`.
+fn render_select_options(
+    options: &[(String, String)],
+    is_selected: impl Fn(&String) -> bool,
+    locale: Locale,
+) -> String {
+    options
+        .iter()
+        .map(|(option_name, option_verbose_name)| {
+            HtmlElement::new("option")
+                .attribute("value", option_name)
+                .flag("selected", is_selected(option_name))
+                .text(locale.translate(option_verbose_name))
+                .render()
+        })
+        .collect()
+}
+
+/// Render list of translated error messages, it is empty if there are no errors.
+fn render_error_list(errors: &[ValidationError], locale: Locale) -> String {
+    if errors.is_empty() {
+        "".to_string()
+    } else {
+        let mut result = "<ul>".to_string();
+        for error in errors.iter() {
+            result += &HtmlElement::new("li")
+                .text(&locale.translate_validation_error(error))
+                .render();
+        }
+        result += "</ul>";
+        result
+    }
 }
 
 impl FieldDefinition {
-    fn render_errors(&self) -> String {
-        if self.errors.is_empty() {
-            "".to_string()
-        } else {
-            let mut result = "<ul>".to_string();
-            for error in self.errors.iter() {
-                let error_message = error.message.clone();
-                result += &("<li>".to_string()
-                    + &encode_text(
-                        error_message
-                            .unwrap_or_else(|| Cow::Owned("Error".to_string()))
-                            .as_ref(),
-                    )
-                    + "</li>");
+    fn render_errors(&self, locale: Locale) -> String {
+        render_error_list(&self.errors, locale)
+    }
+
+    /// Add id, name, constraints and placeholder to input element.
+    fn control(&self, element: HtmlElement, id: &str, locale: Locale) -> HtmlElement {
+        let element = element.attribute("id", id).attribute("name", &self.name);
+        let element = self.constraints.add_attributes(element, &self.field_type);
+        let has_placeholder = matches!(
+            self.field_type,
+            FieldData::Number(_)
+                | FieldData::EMail(_)
+                | FieldData::Telephone(_)
+                | FieldData::Text(_)
+                | FieldData::TextArea(_)
+                | FieldData::Password(_)
+                | FieldData::Url(_)
+        );
+        element.optional_attribute(
+            "placeholder",
+            self.placeholder
+                .as_deref()
+                .filter(|_| has_placeholder)
+                .map(|placeholder| locale.translate(placeholder)),
+        )
+    }
+
+    fn input(&self, input_type: &str, id: &str, locale: Locale) -> HtmlElement {
+        self.control(
+            HtmlElement::new("input").attribute("type", input_type),
+            id,
+            locale,
+        )
+    }
+
+    fn render_help(&self, locale: Locale) -> String {
+        match &self.help {
+            Some(help) => {
+                // Help of file input can mention size limit.
+                let max_size_kib = match &self.field_type {
+                    FieldData::File { target, .. } => (target.max_size / 1024).to_string(),
+                    _ => "".to_string(),
+                };
+                HtmlElement::new("small")
+                    .attribute("class", "form-field-help")
+                    .text(&locale.translate_with_args(help, &[("max_size_kib", &max_size_kib)]))
+                    .render()
             }
-            result += "</ul>";
-            result
+            None => "".to_string(),
         }
     }
 
-    pub fn render(&self) -> String {
-        let name_escaped = &encode_quoted_attribute(&self.name);
-        let verbose_name_escaped = &encode_text(&self.verbose_name);
-        match self.field_type.clone() {
-            FieldData::Checkbox(true) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"checkbox\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" checked /></td></tr>\n"
-            }
-            FieldData::Checkbox(false) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"checkbox\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
+    /// Render table row with label, errors, control and help.
+    fn render_row(&self, id: &str, control: &str, locale: Locale) -> String {
+        "<tr><th>".to_string()
+            + &HtmlElement::new("label")
+                .attribute("for", id)
+                .text(locale.translate(&self.verbose_name))
+                .render()
+            + "</th><td>"
+            + &self.render_errors(locale)
+            + control
+            + &self.render_help(locale)
+            + "</td></tr>\n"
+    }
+
+    /// HTML id of input, prefix is used to make ids unique when several forms are on one page.
+    pub fn get_html_id(&self, id_prefix: Option<&str>) -> String {
+        match id_prefix {
+            Some(id_prefix) => format!("input-{}-{}", id_prefix, self.name),
+            None => format!("input-{}", self.name),
+        }
+    }
+
+    pub fn render(&self, locale: Locale, id_prefix: Option<&str>) -> String {
+        let id = &self.get_html_id(id_prefix);
+        let text_input = |input_type: &str, value: &Option<String>| {
+            self.input(input_type, id, locale)
+                .optional_attribute("value", value.as_deref())
+                .render()
+        };
+        let control = match &self.field_type {
+            FieldData::Hidden(value) => {
+                return "<tr hidden><td></td><td>".to_string()
+                    + &text_input("hidden", value)
+                    + &self.render_help(locale)
+                    + "</td></tr>\n";
             }
             FieldData::Radio(options, selected_option) => {
-                let options_str: String = options
+                let options_html: String = options
                     .iter()
                     .map(|(option_name, option_verbose_name)| {
-                        let option_name_escaped = encode_quoted_attribute(option_name);
-                        "<div><input type=\"radio\" id=\"input-".to_string()
-                            + name_escaped
-                            + "-"
-                            + &option_name_escaped
-                            + "\""
-                            + {
-                                if selected_option.as_ref() == Some(option_name) {
-                                    " checked"
-                                } else {
-                                    ""
-                                }
-                            }
-                            + " name=\""
-                            + name_escaped
-                            + "\" value=\""
-                            + &option_name_escaped
-                            + "\" /> <label for=\"input-"
-                            + name_escaped
-                            + "-"
-                            + &option_name_escaped
-                            + "\">"
-                            + &encode_text(option_verbose_name)
-                            + "</label></div>"
+                        let option_id = &(id.to_string() + "-" + option_name);
+                        "<div>".to_string()
+                            + &self
+                                .input("radio", option_id, locale)
+                                .flag("checked", selected_option.as_ref() == Some(option_name))
+                                .attribute("value", option_name)
+                                .render()
+                            + " "
+                            + &HtmlElement::new("label")
+                                .attribute("for", option_id)
+                                .text(locale.translate(option_verbose_name))
+                                .render()
+                            + "</div>"
                     })
                     .collect();
-
-                "<tr><th>".to_string()
-                    + verbose_name_escaped
-                    + "</th><td><fieldset>"
-                    + &self.render_errors()
-                    + &options_str
-                    + "</fieldset></td></tr>\n"
-            }
-            FieldData::Number(Some(number)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"number\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &number.to_string()
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Number(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"number\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Date(Some(date)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"date\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &date.to_string()
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Date(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"date\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Hidden(None) => {
-                "<tr style=\"display: none\"><td></td><td><input type=\"hidden\" id=\"input-"
-                    .to_string()
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Hidden(Some(hidden)) => {
-                "<tr style=\"display: none\"><td></td><td><input type=\"hidden\" id=\"input-"
-                    .to_string()
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&hidden)
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::EMail(Some(email)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"email\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&email)
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::EMail(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"email\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Telephone(Some(telephone)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"tel\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&telephone)
-                    + "\" /></td></tr>\n"
+                return "<tr><th>".to_string()
+                    + &escape_text(locale.translate(&self.verbose_name))
+                    + "</th><td>"
+                    + &HtmlElement::new("fieldset")
+                        .html(&self.render_errors(locale))
+                        .html(&options_html)
+                        .render()
+                    + &self.render_help(locale)
+                    + "</td></tr>\n";
             }
-            FieldData::Telephone(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"tel\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Text(Some(text)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"text\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&text)
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Text(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"text\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::TextArea(Some(text)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<textarea rows=10 id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\">"
-                    + &encode_text(&text)
-                    + "</textarea></td></tr>\n"
-            }
-            FieldData::TextArea(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<textarea rows=10 id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\"></textarea></td></tr>\n"
-            }
-            FieldData::Password(Some(password)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"password\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&password)
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Password(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"password\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Url(Some(url)) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"url\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" value=\""
-                    + &encode_text(&url)
-                    + "\" /></td></tr>\n"
-            }
-            FieldData::Url(None) => {
-                "<tr><th><label for=\"input-".to_string()
-                    + name_escaped
-                    + "\">"
-                    + verbose_name_escaped
-                    + "</label></th><td>"
-                    + &self.render_errors()
-                    + "<input type=\"url\" id=\"input-"
-                    + name_escaped
-                    + "\" name=\""
-                    + name_escaped
-                    + "\" /></td></tr>\n"
-            }
-        }
+            FieldData::Checkbox(is_checked) => self
+                .input("checkbox", id, locale)
+                .flag("checked", *is_checked)
+                .render(),
+            FieldData::Select(options, selected_option) => self
+                .control(HtmlElement::new("select"), id, locale)
+                .html(&render_select_options(
+                    options,
+                    |option_name| selected_option.as_ref() == Some(option_name),
+                    locale,
+                ))
+                .render(),
+            FieldData::MultiSelect(options, selected_options) => self
+                .control(HtmlElement::new("select"), id, locale)
+                .flag("multiple", true)
+                .html(&render_select_options(
+                    options,
+                    |option_name| selected_options.contains(option_name),
+                    locale,
+                ))
+                .render(),
+            FieldData::File {
+                accept,
+                multiple,
+                target,
+            } => target
+                .add_data_attributes(
+                    self.input("file", id, locale)
+                        .optional_attribute("accept", accept.as_deref())
+                        .flag("multiple", *multiple),
+                )
+                .render(),
+            FieldData::Date(value) => self
+                .input("date", id, locale)
+                .optional_attribute("value", value.map(|date| date.to_string()).as_deref())
+                .render(),
+            FieldData::Number(value) => self
+                .input("number", id, locale)
+                .optional_attribute("value", value.map(|number| number.to_string()).as_deref())
+                .render(),
+            FieldData::EMail(value) => text_input("email", value),
+            FieldData::Telephone(value) => text_input("tel", value),
+            FieldData::Text(value) => text_input("text", value),
+            FieldData::Password(value) => text_input("password", value),
+            FieldData::Url(value) => text_input("url", value),
+            // Parser drops line feed right after start tag, so it is added before text to keep
+            // text which starts with line feed.
+            FieldData::TextArea(value) => self
+                .control(HtmlElement::new("textarea"), id, locale)
+                .attribute("rows", "10")
+                .html("\n")
+                .text(value.as_deref().unwrap_or_default())
+                .render(),
+        };
+        self.render_row(id, &control, locale)
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct FormDefinition {
+    /// Fields which do not belong to any group.
     pub fields: Vec<FieldDefinition>,
+    /// Named groups of fields, they are shown after ungrouped fields in order of their first field.
+    pub groups: Vec<(String, Vec<FieldDefinition>)>,
+    /// Errors which do not belong to any field, they are shown above fields.
+    pub form_errors: Vec<ValidationError>,
     pub submit_name: Option<String>,
+    /// Prefix of input ids, derived forms use name of form struct.
+    pub id_prefix: Option<String>,
+}
+
+/// Get errors with names that do not match any field, including errors of struct-level validation
+/// (validator stores them with `__all__` name). Errors are ordered by name.
+pub fn get_form_errors<'a>(
+    field_errors: &HashMap<&'static str, &Vec<ValidationError>>,
+    fields: impl IntoIterator<Item = &'a FieldDefinition>,
+) -> Vec<ValidationError> {
+    let field_names: Vec<&str> = fields
+        .into_iter()
+        .map(|field| field.name.as_str())
+        .collect();
+    let mut names: Vec<&str> = field_errors
+        .keys()
+        .copied()
+        .filter(|name| !field_names.contains(name))
+        .collect();
+    names.sort_unstable();
+    names
+        .into_iter()
+        .flat_map(|name| field_errors[name].iter().cloned())
+        .collect()
 }
 
 impl FormDefinition {
-    pub fn render(&self) -> String {
-        let mut result =
-            "<div class=\"table-wrapper\">\n<table class=\"table-detail\">\n".to_string();
-        for field in self.fields.iter() {
-            result += &field.render();
+    /// Iterate over ungrouped fields and then over fields of groups.
+    pub fn all_fields(&self) -> impl Iterator<Item = &FieldDefinition> {
+        self.fields
+            .iter()
+            .chain(self.groups.iter().flat_map(|(_, fields)| fields.iter()))
+    }
+
+    /// Keep only fields matching predicate, groups left without fields are removed.
+    pub fn retain_fields(&mut self, predicate: impl Fn(&FieldDefinition) -> bool) {
+        self.fields.retain(&predicate);
+        for (_, fields) in self.groups.iter_mut() {
+            fields.retain(&predicate);
+        }
+        self.groups.retain(|(_, fields)| !fields.is_empty());
+    }
+
+    /// Render rows of fields only, for templates which add them to their own table. Groups are
+    /// not marked, their fields follow ungrouped fields.
+    pub fn render_fields(&self, locale: &Locale) -> String {
+        self.all_fields()
+            .map(|field| field.render(*locale, self.id_prefix.as_deref()))
+            .collect()
+    }
+
+    pub fn render(&self, locale: &Locale) -> String {
+        let mut result = String::new();
+        if !self.form_errors.is_empty() {
+            result += &("<div class=\"form-errors\" role=\"alert\">".to_string()
+                + &render_error_list(&self.form_errors, *locale)
+                + "</div>\n");
+        }
+        result += "<div class=\"table-wrapper\">\n<table class=\"table-detail\">\n";
+        result += &self
+            .fields
+            .iter()
+            .map(|field| field.render(*locale, self.id_prefix.as_deref()))
+            .collect::<String>();
+        if !self.groups.is_empty() {
+            result += "</table>\n</div>\n";
+            for (group_name, fields) in self.groups.iter() {
+                result += &("<fieldset class=\"form-group\">\n<legend>".to_string()
+                    + &escape_text(locale.translate(group_name))
+                    + "</legend>\n<div class=\"table-wrapper\">\n<table class=\"table-detail\">\n"
+                    + &fields
+                        .iter()
+                        .map(|field| field.render(*locale, self.id_prefix.as_deref()))
+                        .collect::<String>()
+                    + "</table>\n</div>\n</fieldset>\n");
+            }
+            result += "<div class=\"table-wrapper\">\n<table class=\"table-detail\">\n";
         }
         if let Some(submit_name) = self.submit_name.clone() {
-            let submit_name_escaped = &encode_text(&submit_name);
+            let submit_name_escaped = &escape_text(locale.translate(&submit_name));
             result += &("<tr><td></td><td><button>".to_string()
                 + submit_name_escaped
                 + "</button></td></tr>\n");
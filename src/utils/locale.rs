@@ -0,0 +1,218 @@
+use std::{borrow::Cow, collections::HashMap, convert::Infallible, fmt};
+
+use lazy_static::lazy_static;
+use rocket::{
+    async_trait,
+    http::{
+        impl_from_uri_param_identity,
+        uri::fmt::{Formatter, Path, UriDisplay},
+        Cookie, CookieJar,
+    },
+    request::{self, FromParam, FromRequest},
+    time::Duration,
+    Request,
+};
+use serde::{Deserialize, Serialize};
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+pub const COOKIE_NAME: &str = "locale";
+
+const COOKIE_LIFETIME: Duration = Duration::days(365);
+
+lazy_static! {
+    static ref CATALOG_RU: HashMap<&'static str, &'static str> =
+        parse_catalog(include_str!("../locales/ru.txt"));
+    static ref CATALOG_EN: HashMap<&'static str, &'static str> =
+        parse_catalog(include_str!("../locales/en.txt"));
+}
+
+/// Parse message catalog: one `key = value` pair per line, empty lines and lines starting with `#`
+/// are skipped.
+fn parse_catalog(source: &'static str) -> HashMap<&'static str, &'static str> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// Language of user interface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Ru,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Self; 2] = [Self::Ru, Self::En];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Ru => "ru",
+            Self::En => "en",
+        }
+    }
+
+    pub fn html_lang(&self) -> &'static str {
+        match self {
+            Self::Ru => "ru-RU",
+            Self::En => "en",
+        }
+    }
+
+    /// Name of language in this language, for locale switcher.
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Self::Ru => "русский",
+            Self::En => "English",
+        }
+    }
+
+    /// Get locale by language tag (like `en` or `en-GB`), only primary subtag is used.
+    pub fn from_language_tag(tag: &str) -> Option<Self> {
+        let primary_subtag = tag.trim().split('-').next()?;
+        Self::ALL
+            .into_iter()
+            .find(|locale| primary_subtag.eq_ignore_ascii_case(locale.code()))
+    }
+
+    /// Get supported locale with highest quality from `Accept-Language` header value.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut best: Option<(Self, f32)> = None;
+        for item in header.split(',') {
+            let mut parts = item.split(';');
+            let locale = match parts.next().and_then(Self::from_language_tag) {
+                Some(locale) => locale,
+                None => continue,
+            };
+            let quality = parts
+                .find_map(|part| part.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.trim().parse().ok())
+                .unwrap_or(0.0);
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((locale, quality));
+            }
+        }
+        best.map(|(locale, _)| locale)
+    }
+
+    /// Get locale from cookie set by user, or from `Accept-Language` header, or default one.
+    pub fn resolve(req: &Request<'_>) -> Self {
+        req.cookies()
+            .get(COOKIE_NAME)
+            .and_then(|cookie| Self::from_language_tag(cookie.value()))
+            .or_else(|| {
+                req.headers()
+                    .get_one("Accept-Language")
+                    .and_then(Self::from_accept_language)
+            })
+            .unwrap_or_default()
+    }
+
+    fn catalog(&self) -> &'static HashMap<&'static str, &'static str> {
+        match self {
+            Self::Ru => &CATALOG_RU,
+            Self::En => &CATALOG_EN,
+        }
+    }
+
+    /// Get message by key, falling back to default locale catalog. If key is not found, it is
+    /// returned as is, so text which is not a message key (like user data or strings which are
+    /// not moved to catalog yet) is shown unchanged.
+    pub fn translate<'a>(&self, key: &'a str) -> &'a str {
+        self.catalog()
+            .get(key)
+            .or_else(|| Self::default().catalog().get(key))
+            .copied()
+            .unwrap_or(key)
+    }
+
+    /// Get message by key and replace `{name}` placeholders with values.
+    pub fn translate_with_args(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.translate(key).to_string(), |message, (name, value)| {
+                message.replace(&format!("{{{}}}", name), value)
+            })
+    }
+
+    /// Get message of validation error translated with its parameters as placeholder values.
+    pub fn translate_validation_error(&self, error: &ValidationError) -> String {
+        let params: Vec<(String, String)> = error
+            .params
+            .iter()
+            .map(|(name, value)| {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                (name.to_string(), value)
+            })
+            .collect();
+        let args: Vec<(&str, &str)> = params
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        self.translate_with_args(error.message.as_deref().unwrap_or("Error"), &args)
+    }
+
+    /// Replace message keys of validation errors with translated messages, for API responses.
+    pub fn translate_validation_errors(&self, errors: &mut ValidationErrors) {
+        for kind in errors.errors_mut().values_mut() {
+            match kind {
+                ValidationErrorsKind::Field(errors) => {
+                    for error in errors.iter_mut() {
+                        if error.message.is_some() {
+                            error.message =
+                                Some(Cow::Owned(self.translate_validation_error(error)));
+                        }
+                    }
+                }
+                ValidationErrorsKind::Struct(errors) => self.translate_validation_errors(errors),
+                ValidationErrorsKind::List(errors) => {
+                    for errors in errors.values_mut() {
+                        self.translate_validation_errors(errors);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn set_locale_cookie(cookies: &CookieJar<'_>, locale: Locale) {
+    cookies.add(
+        Cookie::build((COOKIE_NAME, locale.code()))
+            .path("/")
+            .max_age(COOKIE_LIFETIME),
+    );
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Locale {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(Self::resolve(req))
+    }
+}
+
+impl<'a> FromParam<'a> for Locale {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code() == param)
+            .ok_or(param)
+    }
+}
+
+impl UriDisplay<Path> for Locale {
+    fn fmt(&self, f: &mut Formatter<'_, Path>) -> fmt::Result {
+        f.write_raw(self.code())
+    }
+}
+
+impl_from_uri_param_identity!([Path] Locale);
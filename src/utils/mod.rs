@@ -3,16 +3,31 @@ use std::path::Path;
 use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
 use tokio::fs::remove_file;
 
+pub mod attachment;
 pub mod breadcrumbs;
+pub mod conditional;
 pub mod content_range;
 pub mod csrf;
 pub mod csrf_lib;
+pub mod feed;
+pub mod file_server;
+pub mod flash;
 pub mod form_definition;
 pub mod form_extra_validation;
 pub mod iter_group;
+pub mod locale;
+pub mod origin;
 pub mod page_stream;
 pub mod pagination;
+pub mod post_layout;
+pub mod redirect;
+pub mod secret;
+pub mod signed_token;
+pub mod slug;
 pub mod template_with_status;
+pub mod theme;
+pub mod timezone;
+pub mod tus;
 pub mod url_query;
 
 pub fn date_to_offset_date_time(date: Date) -> OffsetDateTime {
@@ -0,0 +1,87 @@
+use rocket::{
+    async_trait,
+    http::Status,
+    request::{self, FromRequest},
+    Request,
+};
+
+use crate::{auth::get_api_token_scope, EmailConfig, UploadConfig};
+
+/// Get origin (`scheme://host[:port]`) of absolute URL in lowercase, `None` is returned for
+/// relative URLs and for `null` origin.
+pub fn get_url_origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if scheme.is_empty() || authority.is_empty() {
+        return None;
+    }
+    Some(format!("{}://{}", scheme, authority).to_ascii_lowercase())
+}
+
+/// Check if request origin is same as origin of site or is in `allowed_origins`. Origin is same if
+/// its host is the one from `Host` header or if it is origin of `site_url`.
+pub fn is_origin_allowed(
+    origin: &str,
+    host: Option<&str>,
+    site_url: &str,
+    allowed_origins: &[String],
+) -> bool {
+    let origin = match get_url_origin(origin) {
+        Some(origin) => origin,
+        None => return false,
+    };
+    let is_same_host = host.is_some_and(|host| {
+        origin
+            .split_once("://")
+            .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host.trim()))
+    });
+    is_same_host
+        || get_url_origin(site_url).as_deref() == Some(origin.as_str())
+        || allowed_origins.iter().any(|allowed_origin| {
+            get_url_origin(allowed_origin).as_deref() == Some(origin.as_str())
+        })
+}
+
+/// Check of `Origin` header (or of `Referer` header, if there is no `Origin`) for upload requests,
+/// see [`is_origin_allowed`], allowed origins are set in [`UploadConfig`]. Requests without both
+/// headers are not sent by browsers, so they are accepted. Requests authenticated with API token
+/// are accepted without check, like in [`crate::utils::csrf::HeaderCSRF`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllowedOrigin {}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for AllowedOrigin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if get_api_token_scope(req).await.is_some() {
+            return request::Outcome::Success(Self {});
+        }
+        let headers = req.headers();
+        let origin = match headers
+            .get_one("Origin")
+            .or_else(|| headers.get_one("Referer"))
+        {
+            Some(origin) => origin,
+            None => return request::Outcome::Success(Self {}),
+        };
+        let (email_config, upload_config) = match (
+            req.rocket().state::<EmailConfig>(),
+            req.rocket().state::<UploadConfig>(),
+        ) {
+            (Some(email_config), Some(upload_config)) => (email_config, upload_config),
+            _ => return request::Outcome::Error((Status::InternalServerError, ())),
+        };
+
+        if is_origin_allowed(
+            origin,
+            headers.get_one("Host"),
+            &email_config.site_url,
+            &upload_config.allowed_origins,
+        ) {
+            request::Outcome::Success(Self {})
+        } else {
+            request::Outcome::Error((Status::Forbidden, ()))
+        }
+    }
+}
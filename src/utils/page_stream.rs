@@ -3,7 +3,7 @@ use std::{ops::AsyncFn, pin::Pin};
 use async_stream::try_stream;
 use tokio_stream::Stream;
 
-use crate::utils::pagination::{Page, PageParams};
+use crate::utils::pagination::{Page, PageOverflowBehavior, PageParams};
 
 pub fn iterate_pages<T, Fun>(
     page_size: u64,
@@ -18,6 +18,7 @@ where
             let page_params = PageParams {
                 page_id: Some(page_id),
                 page_size,
+                overflow_behavior: PageOverflowBehavior::Error,
             };
             match (page_func)(page_params).await {
                 Ok(page) => {
@@ -2,10 +2,18 @@ use log::debug;
 
 use crate::PaginationConfig;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PageOverflowBehavior {
+    #[default]
+    Error,
+    Clamp,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PageParams {
     pub page_id: Option<u64>,
     pub page_size: u64,
+    pub overflow_behavior: PageOverflowBehavior,
 }
 
 impl PageParams {
@@ -27,6 +35,9 @@ impl PageParams {
 
         let page_id = match self.page_id {
             Some(page_id) if page_id < total_page_count => Ok(page_id),
+            Some(_) if self.overflow_behavior == PageOverflowBehavior::Clamp => {
+                Ok(total_page_count.saturating_sub(1))
+            }
             Some(_) => Err(crate::error::Error::PageDoesNotExist),
             None if total_page_count > 0 => Ok(total_page_count - 1),
             _ => Ok(0),
@@ -68,10 +79,29 @@ pub struct Page<T> {
 }
 
 impl<T> Page<T> {
+    pub fn first_item_index(&self) -> u64 {
+        debug_assert!(self.items.len() as u64 <= self.page_size);
+        if self.items.is_empty() {
+            0
+        } else {
+            self.page_id * self.page_size + 1
+        }
+    }
+
+    pub fn last_item_index(&self) -> u64 {
+        debug_assert!(self.items.len() as u64 <= self.page_size);
+        if self.items.is_empty() {
+            0
+        } else {
+            self.page_id * self.page_size + self.items.len() as u64
+        }
+    }
+
     pub fn map<F, Y>(&self, func: F) -> Page<Y>
     where
         F: Fn(&T) -> Y,
     {
+        debug_assert!(self.items.len() as u64 <= self.page_size);
         Page {
             items: self.items.iter().map(func).collect(),
             page_id: self.page_id,
@@ -0,0 +1,84 @@
+use std::fmt;
+
+use rocket::{
+    http::{
+        impl_from_uri_param_identity,
+        uri::fmt::{Formatter, Query, UriDisplay},
+        Cookie, CookieJar,
+    },
+    time::Duration,
+    FromFormField,
+};
+
+pub const COOKIE_NAME: &str = "post_list_layout";
+
+const COOKIE_LIFETIME: Duration = Duration::days(365);
+
+/// Layout of post lists: text list or grid of cards with thumbnails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromFormField)]
+pub enum PostListLayout {
+    #[default]
+    #[field(value = "list")]
+    List,
+    #[field(value = "grid")]
+    Grid,
+}
+
+impl PostListLayout {
+    pub const ALL: [Self; 2] = [Self::List, Self::Grid];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Grid => "grid",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.code() == code)
+    }
+
+    /// Message key of layout name.
+    pub fn get_verbose_name(&self) -> &'static str {
+        match self {
+            Self::List => "post_layout.list",
+            Self::Grid => "post_layout.grid",
+        }
+    }
+
+    /// Get layout from cookie, or default one.
+    pub fn from_cookies(cookies: &CookieJar<'_>) -> Self {
+        cookies
+            .get(COOKIE_NAME)
+            .and_then(|cookie| Self::from_code(cookie.value()))
+            .unwrap_or_default()
+    }
+
+    /// Layout from query parameter is preferred and remembered in cookie, otherwise layout is read
+    /// from cookie.
+    pub fn resolve(layout: Option<Self>, cookies: &CookieJar<'_>) -> Self {
+        match layout {
+            Some(layout) => {
+                set_post_list_layout_cookie(cookies, layout);
+                layout
+            }
+            None => Self::from_cookies(cookies),
+        }
+    }
+}
+
+pub fn set_post_list_layout_cookie(cookies: &CookieJar<'_>, layout: PostListLayout) {
+    cookies.add(
+        Cookie::build((COOKIE_NAME, layout.code()))
+            .path("/")
+            .max_age(COOKIE_LIFETIME),
+    );
+}
+
+impl UriDisplay<Query> for PostListLayout {
+    fn fmt(&self, f: &mut Formatter<'_, Query>) -> fmt::Result {
+        f.write_value(self.code())
+    }
+}
+
+impl_from_uri_param_identity!([Query] PostListLayout);
@@ -0,0 +1,13 @@
+/// Get redirect target from `next` parameter if it is path on this site. Paths starting with `//`
+/// or containing backslashes and control characters are rejected, because browsers can treat them
+/// as links to other hosts.
+pub fn get_local_redirect_path(next: &str) -> Option<&str> {
+    if next.starts_with('/')
+        && !next.starts_with("//")
+        && !next.chars().any(|c| c == '\\' || c.is_control())
+    {
+        Some(next)
+    } else {
+        None
+    }
+}
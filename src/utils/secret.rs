@@ -0,0 +1,86 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    ops::Deref,
+};
+
+use rocket::{
+    async_trait,
+    form::{self, DataField, FromFormField, ValueField},
+};
+use serde::{Deserialize, Serialize};
+use validator::HasLen;
+
+/// Text shown instead of value of [`Secret`] in `Debug` and `Display` output.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Value which must not get into logs: it is (de)serialized and parsed from forms like wrapped
+/// value, but is shown as [`REDACTED`] when formatted. Value is available with [`Secret::expose`]
+/// or by dereference.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> Display for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Length validation of `validator` checks wrapped value.
+impl<'a, T> HasLen for &'a Secret<T>
+where
+    &'a T: HasLen,
+{
+    fn length(&self) -> u64 {
+        (&self.0).length()
+    }
+}
+
+#[async_trait]
+impl<'v, T: FromFormField<'v>> FromFormField<'v> for Secret<T> {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        T::from_value(field).map(Self)
+    }
+
+    async fn from_data(field: DataField<'v, '_>) -> form::Result<'v, Self> {
+        T::from_data(field).await.map(Self)
+    }
+
+    fn default() -> Option<Self> {
+        T::default().map(Self)
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt::{self, Debug, Formatter};
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::{distributions::Standard, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+/// Name of query parameter with token in signed file URLs.
+pub const FILE_TOKEN_PARAMETER: &str = "token";
+
+/// Prefix of payload of file tokens, so that other tokens (like invite tokens) can not be used as
+/// file tokens.
+const FILE_TOKEN_PAYLOAD_PREFIX: &str = "file:";
+
+/// Prefix of payload of invite tokens, so that file tokens can not be used as invite tokens.
+const INVITE_TOKEN_PAYLOAD_PREFIX: &str = "invite:";
+
+/// Key for signing short tokens (like invite links), derived from Rocket secret key.
+#[derive(Clone)]
+pub struct SignedTokenKey {
+    key: [u8; 32],
+}
+
+impl Debug for SignedTokenKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignedTokenKey").finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignedTokenError {
+    Malformed,
+    InvalidSignature,
+    Expired,
+}
+
+impl SignedTokenKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: Sha256::digest(secret).into(),
+        }
+    }
+
+    /// Use configured secret key or random key if it is not set (tokens will not survive restart
+    /// then).
+    pub fn from_secret_key(secret_key: Option<&str>) -> Self {
+        match secret_key {
+            Some(secret_key) => Self::new(secret_key.as_bytes()),
+            None => {
+                let secret: Vec<u8> = thread_rng().sample_iter(Standard).take(32).collect();
+                Self::new(&secret)
+            }
+        }
+    }
+
+    fn mac(&self, message: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(message.as_bytes());
+        mac
+    }
+
+    /// Sign payload, token format is `payload.expires_at.signature`.
+    pub fn sign(&self, payload: &str, expires_at: OffsetDateTime) -> String {
+        let message = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(payload),
+            expires_at.unix_timestamp()
+        );
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(self.mac(&message).finalize().into_bytes());
+        format!("{}.{}", message, signature)
+    }
+
+    /// Check signature and expiry and return payload.
+    pub fn verify(&self, token: &str, now: OffsetDateTime) -> Result<String, SignedTokenError> {
+        let (message, signature) = token.rsplit_once('.').ok_or(SignedTokenError::Malformed)?;
+        let (payload, expires_at) = message.split_once('.').ok_or(SignedTokenError::Malformed)?;
+
+        let signature = BASE64_URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| SignedTokenError::Malformed)?;
+        if self.mac(message).verify_slice(&signature).is_err() {
+            return Err(SignedTokenError::InvalidSignature);
+        }
+
+        let expires_at: i64 = expires_at
+            .parse()
+            .map_err(|_| SignedTokenError::Malformed)?;
+        if now.unix_timestamp() >= expires_at {
+            return Err(SignedTokenError::Expired);
+        }
+
+        let payload = BASE64_URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| SignedTokenError::Malformed)?;
+        String::from_utf8(payload).map_err(|_| SignedTokenError::Malformed)
+    }
+
+    /// Sign invite code for invite link.
+    pub fn sign_invite_code(&self, invite_code: &str, expires_at: OffsetDateTime) -> String {
+        self.sign(
+            &format!("{}{}", INVITE_TOKEN_PAYLOAD_PREFIX, invite_code),
+            expires_at,
+        )
+    }
+
+    /// Check token from invite link and return invite code.
+    pub fn verify_invite_code(
+        &self,
+        token: &str,
+        now: OffsetDateTime,
+    ) -> Result<String, SignedTokenError> {
+        let payload = self.verify(token, now)?;
+        payload
+            .strip_prefix(INVITE_TOKEN_PAYLOAD_PREFIX)
+            .map(str::to_string)
+            .ok_or(SignedTokenError::InvalidSignature)
+    }
+
+    /// Sign name of file in storage for direct download URL.
+    pub fn sign_file_name(&self, file_name: &str, expires_at: OffsetDateTime) -> String {
+        self.sign(
+            &format!("{}{}", FILE_TOKEN_PAYLOAD_PREFIX, file_name),
+            expires_at,
+        )
+    }
+
+    /// Check token from direct download URL, token must be signed for this file.
+    pub fn verify_file_name(
+        &self,
+        token: &str,
+        file_name: &str,
+        now: OffsetDateTime,
+    ) -> Result<(), SignedTokenError> {
+        let payload = self.verify(token, now)?;
+        if payload.strip_prefix(FILE_TOKEN_PAYLOAD_PREFIX) == Some(file_name) {
+            Ok(())
+        } else {
+            Err(SignedTokenError::InvalidSignature)
+        }
+    }
+}
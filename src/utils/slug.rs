@@ -0,0 +1,104 @@
+/// Maximum length of generated slug without numeric suffix.
+pub const MAX_SLUG_LENGTH: usize = 80;
+
+/// Get Latin transliteration of lowercase Cyrillic letter.
+fn transliterate_cyrillic(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'е' | 'э' => "e",
+        'ё' => "yo",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'ї' => "yi",
+        'й' | 'ы' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ў' => "w",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    })
+}
+
+/// Make URL slug from text: Cyrillic letters are transliterated, other letters and digits are
+/// kept if they are ASCII, everything else separates words with `-`. Slug is cut to
+/// [`MAX_SLUG_LENGTH`] at word boundary if possible. Result is empty if text has no usable
+/// characters.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    let mut has_separator = false;
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let part = if c.is_ascii_alphanumeric() {
+            Some(c.to_string())
+        } else {
+            transliterate_cyrillic(c).map(str::to_string)
+        };
+        match part {
+            Some(part) => {
+                if part.is_empty() {
+                    continue;
+                }
+                if has_separator && !slug.is_empty() {
+                    slug.push('-');
+                }
+                has_separator = false;
+                slug.push_str(&part);
+            }
+            None => has_separator = true,
+        }
+    }
+
+    if slug.len() > MAX_SLUG_LENGTH {
+        // Slug is ASCII, so it can be cut at any byte.
+        let cut_slug = &slug[..MAX_SLUG_LENGTH];
+        let cut_slug = match cut_slug.rfind('-') {
+            Some(pos) if slug.as_bytes()[MAX_SLUG_LENGTH] != b'-' && pos > 0 => &cut_slug[..pos],
+            _ => cut_slug.trim_end_matches('-'),
+        };
+        slug = cut_slug.to_string();
+    }
+    slug
+}
+
+/// Check if `slug` is `base_slug` itself or `base_slug` with numeric suffix.
+pub fn is_slug_variant(slug: &str, base_slug: &str) -> bool {
+    match slug.strip_prefix(base_slug) {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix('-')
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|c| c.is_ascii_digit())),
+        None => false,
+    }
+}
+
+/// Get first of `base_slug`, `base_slug-2`, `base_slug-3`... which is not taken.
+pub fn make_unique_slug(base_slug: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(base_slug) {
+        return base_slug.to_string();
+    }
+    (2u64..)
+        .map(|number| format!("{}-{}", base_slug, number))
+        .find(|slug| !is_taken(slug))
+        .expect("some suffix should be free")
+}
@@ -0,0 +1,110 @@
+use std::{convert::Infallible, fmt};
+
+use rocket::{
+    async_trait,
+    http::{
+        impl_from_uri_param_identity,
+        uri::fmt::{Formatter, Path, UriDisplay},
+        Cookie, CookieJar,
+    },
+    request::{self, FromParam, FromRequest},
+    time::Duration,
+    FromFormField, Request,
+};
+use serde::{Deserialize, Serialize};
+
+pub const COOKIE_NAME: &str = "theme";
+
+const COOKIE_LIFETIME: Duration = Duration::days(365);
+
+/// Color theme of user interface. It is emitted as `theme-*` class of `<body>`.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, FromFormField,
+)]
+#[sqlx(type_name = "user_theme")]
+#[sqlx(rename_all = "UPPERCASE")]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    #[field(value = "light")]
+    Light,
+    #[field(value = "dark")]
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Self; 2] = [Self::Light, Self::Dark];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.code() == code)
+    }
+
+    /// Message key of theme name.
+    pub fn get_verbose_name(self) -> &'static str {
+        match self {
+            Self::Light => "theme.light",
+            Self::Dark => "theme.dark",
+        }
+    }
+
+    pub fn get_options() -> Vec<(String, String)> {
+        Self::ALL
+            .into_iter()
+            .map(|theme| (theme.get_option(), theme.get_verbose_name().to_string()))
+            .collect()
+    }
+
+    pub fn get_option(self) -> String {
+        self.code().to_string()
+    }
+
+    /// Get theme from cookie, or default one.
+    pub fn from_cookies(cookies: &CookieJar<'_>) -> Self {
+        cookies
+            .get(COOKIE_NAME)
+            .and_then(|cookie| Self::from_code(cookie.value()))
+            .unwrap_or_default()
+    }
+}
+
+pub fn set_theme_cookie(cookies: &CookieJar<'_>, theme: Theme) {
+    cookies.add(
+        Cookie::build((COOKIE_NAME, theme.code()))
+            .path("/")
+            .max_age(COOKIE_LIFETIME),
+    );
+}
+
+/// Theme is read from cookie only: for logged in users it is copied from user row when they log
+/// in or change it.
+#[async_trait]
+impl<'r> FromRequest<'r> for Theme {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(Self::from_cookies(req.cookies()))
+    }
+}
+
+impl<'a> FromParam<'a> for Theme {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        Self::from_code(param).ok_or(param)
+    }
+}
+
+impl UriDisplay<Path> for Theme {
+    fn fmt(&self, f: &mut Formatter<'_, Path>) -> fmt::Result {
+        f.write_raw(self.code())
+    }
+}
+
+impl_from_uri_param_identity!([Path] Theme);
@@ -0,0 +1,46 @@
+use rocket::{
+    http::{Cookie, CookieJar},
+    time::{Duration, UtcOffset},
+};
+
+pub const COOKIE_NAME: &str = "timezone_offset";
+
+const COOKIE_LIFETIME: Duration = Duration::days(365);
+
+/// Range of real timezone offsets from UTC, in minutes.
+pub const MIN_TIMEZONE_OFFSET: i32 = -12 * 60;
+pub const MAX_TIMEZONE_OFFSET: i32 = 14 * 60;
+
+/// Timezone is fixed offset from UTC in minutes: daylight saving time is not tracked, user changes
+/// offset when it starts or ends.
+pub fn is_timezone_offset_valid(offset: i32) -> bool {
+    (MIN_TIMEZONE_OFFSET..=MAX_TIMEZONE_OFFSET).contains(&offset)
+}
+
+/// Get offset from cookie, or `None` if it is absent or invalid.
+pub fn timezone_offset_from_cookies(cookies: &CookieJar<'_>) -> Option<i32> {
+    cookies
+        .get(COOKIE_NAME)
+        .and_then(|cookie| cookie.value().parse().ok())
+        .filter(|offset| is_timezone_offset_valid(*offset))
+}
+
+/// Store offset in cookie, `None` removes it, so that dates are shown in UTC.
+pub fn set_timezone_offset_cookie(cookies: &CookieJar<'_>, offset: Option<i32>) {
+    match offset {
+        Some(offset) => cookies.add(
+            Cookie::build((COOKIE_NAME, offset.to_string()))
+                .path("/")
+                .max_age(COOKIE_LIFETIME),
+        ),
+        None => cookies.remove(Cookie::build(COOKIE_NAME).path("/")),
+    }
+}
+
+/// Convert offset in minutes to [`UtcOffset`], invalid offset is replaced with UTC.
+pub fn get_utc_offset(offset: i32) -> UtcOffset {
+    if !is_timezone_offset_valid(offset) {
+        return UtcOffset::UTC;
+    }
+    UtcOffset::from_whole_seconds(offset * 60).unwrap_or(UtcOffset::UTC)
+}
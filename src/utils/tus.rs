@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use askama_rocket::Responder;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use rocket::{
+    async_trait,
+    http::{Header, Status},
+    request::{self, FromRequest},
+    response, Request, Response,
+};
+
+/// Supported version of tus resumable upload protocol, see <https://tus.io/protocols/resumable-upload>.
+pub const TUS_VERSION: &str = "1.0.0";
+pub const TUS_EXTENSIONS: &str = "creation";
+pub const TUS_PATCH_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+/// Parse `Upload-Metadata` header: comma-separated pairs of key and Base64-encoded value, value may
+/// be omitted. Returns `None` if header is malformed.
+pub fn parse_tus_upload_metadata(header: &str) -> Option<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+    for pair in header.split(',') {
+        let mut parts = pair.split_whitespace();
+        let key = match parts.next() {
+            Some(key) => key,
+            None => continue,
+        };
+        let value = match parts.next() {
+            Some(value) => String::from_utf8(BASE64_STANDARD.decode(value).ok()?).ok()?,
+            None => String::new(),
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        metadata.insert(key.to_string(), value);
+    }
+    Some(metadata)
+}
+
+/// Headers of tus request. Missing headers are `None`, so that routes can respond with statuses
+/// required by protocol.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TusHeaders {
+    pub version: Option<String>,
+    pub upload_length: Option<u64>,
+    pub upload_offset: Option<u64>,
+    pub upload_metadata: HashMap<String, String>,
+    pub content_type: Option<String>,
+}
+
+impl TusHeaders {
+    pub fn is_version_supported(&self) -> bool {
+        self.version.as_deref() == Some(TUS_VERSION)
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for TusHeaders {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let headers = req.headers();
+        let parse_number = |name: &str| -> Result<Option<u64>, ()> {
+            headers
+                .get_one(name)
+                .map(|value| value.trim().parse().map_err(|_| ()))
+                .transpose()
+        };
+        let (upload_length, upload_offset) =
+            match (parse_number("Upload-Length"), parse_number("Upload-Offset")) {
+                (Ok(upload_length), Ok(upload_offset)) => (upload_length, upload_offset),
+                _ => return request::Outcome::Error((Status::BadRequest, ())),
+            };
+        let upload_metadata = match headers.get_one("Upload-Metadata") {
+            Some(header) => match parse_tus_upload_metadata(header) {
+                Some(upload_metadata) => upload_metadata,
+                None => return request::Outcome::Error((Status::BadRequest, ())),
+            },
+            None => HashMap::new(),
+        };
+
+        request::Outcome::Success(Self {
+            version: headers.get_one("Tus-Resumable").map(str::to_string),
+            upload_length,
+            upload_offset,
+            upload_metadata,
+            content_type: headers.get_one("Content-Type").map(str::to_string),
+        })
+    }
+}
+
+/// Response without body, `Tus-Resumable` header is always added.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TusResponse {
+    pub status: Status,
+    pub headers: Vec<(&'static str, String)>,
+}
+
+impl TusResponse {
+    pub fn new(status: Status) -> Self {
+        Self {
+            status,
+            headers: vec![],
+        }
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+
+    /// Response to request with unsupported protocol version.
+    pub fn version_not_supported() -> Self {
+        Self::new(Status::PreconditionFailed).header("Tus-Version", TUS_VERSION)
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for TusResponse {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = Response::build();
+        response
+            .status(self.status)
+            .header(Header::new("Tus-Resumable", TUS_VERSION));
+        for (name, value) in self.headers {
+            response.header(Header::new(name, value));
+        }
+        response.ok()
+    }
+}
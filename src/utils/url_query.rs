@@ -27,6 +27,10 @@ impl UrlQuery {
     pub fn contains_key(&mut self, key: &String) {
         self.multimap.contains_key(key);
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.multimap.flat_iter()
+    }
 }
 
 impl FromIterator<(String, String)> for UrlQuery {
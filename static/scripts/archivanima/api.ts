@@ -1,6 +1,6 @@
 /// <amd-module name='archivanima/api'/>
 
-import { RequestError, ajaxPost, ajaxPostJSON, ajaxPut } from 'archivanima/ajax';
+import { RequestError, ajaxGet, ajaxPost, ajaxPostJSON, ajaxPut } from 'archivanima/ajax';
 import { Either, getRight, isRight, left, mapLeft, right, unwrapEitherOrThrow, unwrapOrThrow } from 'archivanima/utils';
 
 function getFileExtension(
@@ -18,16 +18,37 @@ export async function uploadFile(
     file: File, chunkSize: number, postId: number,
     onProgress: (id: number, uploadedSize: number, totalSize: number) => void
 ): Promise<Either<number, RequestError>> {
-    const extension = getFileExtension(file.name);
-
-    const result = await ajaxPostJSON(
+    return uploadFileTo(
         '/api/uploads/add',
         {
             size: file.size,
-            extension: extension,
+            extension: getFileExtension(file.name),
             post_id: postId
-        }
+        },
+        file, chunkSize, onProgress
     );
+}
+
+export async function uploadAvatar(
+    file: File, chunkSize: number,
+    onProgress: (id: number, uploadedSize: number, totalSize: number) => void
+): Promise<Either<number, RequestError>> {
+    return uploadFileTo(
+        '/api/users/me/avatar/add',
+        {
+            size: file.size,
+            extension: getFileExtension(file.name)
+        },
+        file, chunkSize, onProgress
+    );
+}
+
+async function uploadFileTo(
+    addUrl: string, addRequest: { [s: string]: unknown },
+    file: File, chunkSize: number,
+    onProgress: (id: number, uploadedSize: number, totalSize: number) => void
+): Promise<Either<number, RequestError>> {
+    const result = await ajaxPostJSON(addUrl, addRequest);
     if (isRight(result)) {
         return right(unwrapOrThrow(getRight(result)));
     }
@@ -86,6 +107,7 @@ export interface PostResult {
 
 export async function addPost(
     title: string, description: string, is_hidden: boolean, is_pinned: boolean | null, minAge: number | null,
+    contentRatingId: string | null,
 ): Promise<Either<PostResult, RequestError>> {
     const result = await ajaxPostJSON(
         '/api/posts/add',
@@ -94,7 +116,8 @@ export async function addPost(
             description: description,
             is_hidden: is_hidden,
             is_pinned: is_pinned,
-            min_age: minAge
+            min_age: minAge,
+            content_rating_id: contentRatingId
         }
     );
     return mapLeft(
@@ -111,7 +134,7 @@ export async function addPost(
 
 export async function editPost(
     id: number, title: string | null, description: string | null, is_hidden: boolean | null, is_pinned: boolean | null,
-    minAge: number | null
+    minAge: number | null, contentRatingId: string | null
 ): Promise<Either<void, RequestError>> {
     return mapLeft(
         await ajaxPostJSON(
@@ -121,9 +144,32 @@ export async function editPost(
                 description: description,
                 is_hidden: is_hidden,
                 is_pinned: is_pinned,
-                min_age: minAge
+                min_age: minAge,
+                content_rating_id: contentRatingId
             }
         ),
         () => { }
     );
 }
+
+export interface BanReasonResult {
+    id: string;
+    description: string | null;
+    defaultText: string | null;
+}
+
+export async function getBanReason(
+    id: string
+): Promise<Either<BanReasonResult, RequestError>> {
+    return mapLeft(
+        await ajaxGet(`/api/ban-reasons/by-id/${encodeURIComponent(id)}`),
+        (response) => {
+            const typedResponse = <{ [s: string]: unknown }>(response.body);
+            return {
+                id: <string>typedResponse['id'],
+                description: <string | null>typedResponse['description'],
+                defaultText: <string | null>typedResponse['default_text'],
+            };
+        }
+    );
+}
@@ -0,0 +1,57 @@
+/// <amd-module name='archivanima/avatar_edit'/>
+
+import { uploadAvatar } from 'archivanima/api';
+import { unwrapEitherOrThrow } from 'archivanima/utils';
+
+export class AvatarEditForm {
+    form: HTMLFormElement;
+    button: HTMLButtonElement;
+    fileField: HTMLInputElement;
+    progressCell: HTMLElement;
+    chunkSize: number;
+    maxSize: number;
+
+    constructor(form: HTMLFormElement, chunkSize: number) {
+        this.form = form;
+        this.button = <HTMLButtonElement>form.querySelector('button#button-upload');
+        this.fileField = <HTMLInputElement>form.querySelector('input[name="file"]');
+        this.progressCell = <HTMLElement>form.querySelector('#cell-progress');
+        this.chunkSize = chunkSize;
+        this.maxSize = Number.parseInt(<string>this.fileField.dataset.maxSize);
+
+        this.form.addEventListener('submit', (event: Event) => this.onFormSubmit(event));
+        this.button.disabled = false;
+    }
+
+    private async onFormSubmit(event: Event) {
+        event.preventDefault();
+        if ((this.fileField.files === null) || (this.fileField.files.length === 0)) {
+            return;
+        }
+
+        const file = this.fileField.files[0];
+        if (file.size > this.maxSize) {
+            this.progressCell.textContent = 'файл слишком большой';
+            return;
+        }
+
+        this.fileField.disabled = true;
+        this.button.disabled = true;
+
+        const progressBar = document.createElement('progress');
+        progressBar.value = 0;
+        progressBar.max = 1;
+        this.progressCell.replaceChildren(progressBar);
+
+        unwrapEitherOrThrow(await uploadAvatar(
+            file, this.chunkSize,
+            (id, uploadedSize, totalSize) => {
+                console.log(`Upload ID ${id}, progress ${uploadedSize} / ${totalSize}`);
+                progressBar.value = uploadedSize;
+                progressBar.max = totalSize;
+            }
+        ));
+
+        document.location.reload();
+    }
+}
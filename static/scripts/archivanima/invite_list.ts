@@ -0,0 +1,24 @@
+/// <amd-module name='archivanima/invite_list'/>
+
+export class InviteList {
+    table: HTMLTableElement;
+
+    constructor(table: HTMLTableElement) {
+        this.table = table;
+
+        this.table.querySelectorAll<HTMLButtonElement>('button.button-copy-invite').forEach(
+            (button) => button.addEventListener('click', () => this.onCopyButtonClick(button))
+        );
+    }
+
+    private async onCopyButtonClick(button: HTMLButtonElement) {
+        const code = <string>button.dataset.code;
+        try {
+            await navigator.clipboard.writeText(code);
+            button.textContent = 'скопировано';
+        } catch (error) {
+            console.error(error);
+            button.textContent = code;
+        }
+    }
+}
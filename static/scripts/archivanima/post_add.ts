@@ -11,6 +11,7 @@ export class PostAddForm {
     hiddenField: HTMLInputElement;
     pinnedField: HTMLInputElement;
     minAgeField: HTMLInputElement;
+    contentRatingField: HTMLSelectElement;
     fileField: HTMLInputElement;
     progressCell: HTMLElement;
     chunkSize: number;
@@ -23,6 +24,7 @@ export class PostAddForm {
         this.descriptionField = <HTMLInputElement>form.querySelector('textarea#input-description');
         this.hiddenField = <HTMLInputElement>form.querySelector('input#input-hidden');
         this.minAgeField = <HTMLInputElement>form.querySelector('input#input-min_age');
+        this.contentRatingField = <HTMLSelectElement>form.querySelector('select#input-content_rating_id');
         this.fileField = <HTMLInputElement>form.querySelector('input#input-file');
         this.progressCell = <HTMLElement>form.querySelector('#cell-progress');
         this.chunkSize = chunkSize;
@@ -68,11 +70,12 @@ export class PostAddForm {
         const isHidden = this.hiddenField.checked;
         const isPinned = this.pinnedField.checked;
         const minAge = this.minAgeField.valueAsNumber;
+        const contentRatingId = this.contentRatingField.value;
         const mustHideAndUnhide = !isHidden && (this.fileField.files.length > 0);
 
         const postResult = unwrapEitherOrThrow(await addPost(
             title, description, mustHideAndUnhide ? true : isHidden, isPinned,
-            Number.isNaN(minAge) ? null : minAge
+            Number.isNaN(minAge) ? null : minAge, contentRatingId === '' ? null : contentRatingId
         ));
 
         const files = Array.from(this.fileField.files);
@@ -93,7 +96,7 @@ export class PostAddForm {
         if (mustHideAndUnhide) {
             unwrapEitherOrThrow(await editPost(
                 postResult.id, title, description, false, isPinned,
-                Number.isNaN(minAge) ? null : minAge
+                Number.isNaN(minAge) ? null : minAge, contentRatingId === '' ? null : contentRatingId
             ));
         }
 
@@ -0,0 +1,29 @@
+/// <amd-module name='archivanima/post_ban'/>
+
+import { getBanReason } from 'archivanima/api';
+import { unwrapEitherOrThrow } from 'archivanima/utils';
+
+export class PostBanForm {
+    form: HTMLFormElement;
+    textField: HTMLInputElement;
+
+    constructor(form: HTMLFormElement) {
+        this.form = form;
+        this.textField = <HTMLInputElement>form.querySelector('input#input-ban_reason_text');
+
+        this.form.querySelectorAll<HTMLInputElement>('input[type="radio"][name="ban_reason_id"]').forEach(
+            (radio) => radio.addEventListener('change', () => this.onReasonChange(radio))
+        );
+    }
+
+    private async onReasonChange(radio: HTMLInputElement) {
+        if (!radio.checked) {
+            return;
+        }
+
+        const banReason = unwrapEitherOrThrow(await getBanReason(radio.value));
+        if (banReason.defaultText !== null) {
+            this.textField.value = banReason.defaultText;
+        }
+    }
+}
@@ -13,6 +13,7 @@ export class PostEditForm {
     hiddenField: HTMLInputElement;
     pinnedField: HTMLInputElement;
     minAgeField: HTMLInputElement;
+    contentRatingField: HTMLSelectElement;
     fileField: HTMLInputElement;
     progressCell: HTMLElement;
     uploadItemElements: HTMLElement[];
@@ -28,6 +29,7 @@ export class PostEditForm {
         this.hiddenField = <HTMLInputElement>form.querySelector('input#input-hidden');
         this.pinnedField = <HTMLInputElement>form.querySelector('input#input-pinned');
         this.minAgeField = <HTMLInputElement>form.querySelector('input#input-min_age');
+        this.contentRatingField = <HTMLSelectElement>form.querySelector('select#input-content_rating_id');
         this.fileField = <HTMLInputElement>form.querySelector('input#input-file');
         this.progressCell = <HTMLElement>form.querySelector('#cell-progress');
         this.uploadItemElements = Array.from(form.querySelectorAll('.upload-item'));
@@ -99,10 +101,11 @@ export class PostEditForm {
         const isPinned = this.pinnedField.checked;
         const isHidden = this.hiddenField.checked;
         const minAge = this.minAgeField.valueAsNumber;
+        const contentRatingId = this.contentRatingField.value;
 
         unwrapEitherOrThrow(await editPost(
             this.id, title, description, isHidden, isPinned,
-            Number.isNaN(minAge) ? null : minAge
+            Number.isNaN(minAge) ? null : minAge, contentRatingId === '' ? null : contentRatingId
         ));
 
         for (let fileId of Array.from(this.removedFiles)) {
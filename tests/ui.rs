@@ -0,0 +1,6 @@
+/// Check that misuse of derive macros is reported as compile errors.
+#[test]
+fn ui() {
+    let test_cases = trybuild::TestCases::new();
+    test_cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,12 @@
+use archivanima_macros::RawForm;
+
+struct IdField;
+
+#[derive(RawForm)]
+struct Form {
+    #[extra_validated(UserIdSet)]
+    #[extra_validated(PostIdSet)]
+    user: IdField,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use archivanima_macros::RawForm;
+
+struct IdField;
+
+#[derive(RawForm)]
+struct Form {
+    #[extra_validated(UserIdSet, loader: get_id_set)]
+    user: IdField,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use archivanima_macros::RawForm;
+
+#[derive(RawForm)]
+struct Form {
+    #[extra_validated(UserIdSet)]
+    user: String,
+}
+
+fn main() {}